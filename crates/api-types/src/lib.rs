@@ -0,0 +1,142 @@
+//! Typed model of the JSON served by the backend, for client crates.
+//!
+//! Mirrors the proof format OpenVM serializes (`VersionedVmStarkProof`), so
+//! clients can deserialize `/api/prove` responses without depending on the
+//! OpenVM SDK.
+
+use serde::{Deserialize, Serialize};
+
+/// Guest inputs, for clients that run or prove guests themselves.
+pub use guest_protocol::{ExecutionMode, GuestInput, INPUT_VERSION};
+
+/// An OpenVM STARK proof, as `stark_proof_json` in `/api/prove` responses.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StarkProofJson {
+    /// Proof format version, e.g. `v2.0`.
+    pub version: String,
+    /// Encoded aggregation proof.
+    #[serde(with = "hex_bytes")]
+    pub proof: Vec<u8>,
+    /// Encoded Merkle proof of the user public values the guest revealed.
+    #[serde(with = "hex_bytes")]
+    pub user_pvs_proof: Vec<u8>,
+    /// Encoded deferral Merkle proofs, for guests that defer proofs.
+    #[serde(default, with = "hex_bytes::option")]
+    pub deferral_merkle_proofs: Option<Vec<u8>>,
+}
+
+/// Bytes as lowercase hex strings, as OpenVM writes them.
+mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        hex::decode(hex).map_err(serde::de::Error::custom)
+    }
+
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(
+            bytes: &Option<Vec<u8>>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match bytes {
+                Some(bytes) => super::serialize(bytes, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Vec<u8>>, D::Error> {
+            Option::<String>::deserialize(deserializer)?
+                .map(|hex| hex::decode(hex).map_err(serde::de::Error::custom))
+                .transpose()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn openvm_proof_json() -> serde_json::Value {
+        json!({
+            "version": "v2.0",
+            "proof": "00010203fdfeff",
+            "user_pvs_proof": "2a000000",
+            "deferral_merkle_proofs": null,
+        })
+    }
+
+    #[test]
+    fn round_trips_openvm_proof_json() {
+        let proof: StarkProofJson = serde_json::from_value(openvm_proof_json()).unwrap();
+        assert_eq!(proof.version, "v2.0");
+        assert_eq!(proof.proof, [0x00, 0x01, 0x02, 0x03, 0xfd, 0xfe, 0xff]);
+        assert_eq!(proof.user_pvs_proof, [0x2a, 0, 0, 0]);
+        assert_eq!(proof.deferral_merkle_proofs, None);
+        assert_eq!(serde_json::to_value(&proof).unwrap(), openvm_proof_json());
+    }
+
+    #[test]
+    fn round_trips_deferral_merkle_proofs() {
+        let mut json = openvm_proof_json();
+        json["deferral_merkle_proofs"] = json!("beef");
+        let proof: StarkProofJson = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(proof.deferral_merkle_proofs, Some(vec![0xbe, 0xef]));
+        assert_eq!(serde_json::to_value(&proof).unwrap(), json);
+    }
+
+    #[test]
+    fn rejects_invalid_hex() {
+        let mut json = openvm_proof_json();
+        json["proof"] = json!("0xzz");
+        assert!(serde_json::from_value::<StarkProofJson>(json).is_err());
+    }
+
+    #[test]
+    fn round_trips_guest_inputs() {
+        let inputs = [
+            GuestInput::program(&[0x01, 0x00, 0x00], false),
+            GuestInput::program(&[0x01, 0x00, 0x00], true),
+            GuestInput::program_with_args(&[0x01, 0x00, 0x00], &[vec![0x01], vec![]], false),
+            GuestInput::aggregation(&[0; 32], &[[0x2a; 32]]),
+            GuestInput::build_info(1),
+        ];
+        for input in inputs {
+            assert_eq!(GuestInput::decode(&input.encode()), Some(input));
+        }
+    }
+
+    #[test]
+    fn encodes_guest_inputs_with_their_version() {
+        let bytes = GuestInput::program(&[0xab], true).encode();
+        // Version, then the program, the mode and no arguments.
+        let items: [&[u8]; 4] = [
+            &[INPUT_VERSION],
+            &[0, 0, 0, 1, 0xab],
+            &[0, 0, 0, 1, 1],
+            &[0, 0, 0, 4, 0, 0, 0, 0],
+        ];
+        assert_eq!(bytes, items.concat());
+        assert_eq!(GuestInput::decode(&bytes[..bytes.len() - 1]), None);
+
+        let mut other_version = bytes.clone();
+        other_version[0] = INPUT_VERSION + 1;
+        assert_eq!(GuestInput::decode(&other_version), None);
+    }
+
+    #[test]
+    fn rejects_missing_fields() {
+        let mut json = openvm_proof_json();
+        json.as_object_mut().unwrap().remove("user_pvs_proof");
+        assert!(serde_json::from_value::<StarkProofJson>(json).is_err());
+    }
+}
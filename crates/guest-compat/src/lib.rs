@@ -0,0 +1,136 @@
+//! Roll-forward compatibility fixtures for the guest protocol.
+//!
+//! Each file in `fixtures/` freezes the conformance cases of one protocol
+//! version, [`INPUT_VERSION`] and [`COMMITMENT_VERSION`]: the
+//! [`GuestInput::encode`] bytes of every input, and the public values guests
+//! reveal for it. Recorded fixtures are never edited. The tests of this crate
+//! check that the protocol still decodes and reproduces the fixtures of its
+//! current versions, and that verifiers still read them. A change to the
+//! input layout or to a commitment therefore fails CI until the matching
+//! version is bumped and its fixtures are recorded with
+//! `GUEST_COMPAT_BLESS=1 cargo test -p guest-compat`.
+//!
+//! The fixtures of older versions check roll-forward: their inputs still
+//! decode, as the current inputs of the same cases, and their public values
+//! still verify with [`guest_protocol::v1`]. `input-v1-commitment-v1.json`
+//! was recorded from the protocol before statements and arguments.
+//!
+//! `crates/zkvms/openvm/core/tests/conformance.rs` runs the fixtures of the
+//! current versions through the built OpenVM guest.
+
+use std::path::{Path, PathBuf};
+
+use guest_protocol::conformance::Case;
+use guest_protocol::{COMMITMENT_VERSION, GuestInput, INPUT_VERSION, Status};
+use serde::{Deserialize, Serialize};
+
+/// The conformance cases of one protocol version.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixtureSet {
+    pub input_version: u8,
+    pub commitment_version: u32,
+    pub cases: Vec<Fixture>,
+}
+
+/// One conformance case, serialized.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub name: String,
+    /// [`GuestInput::encode`] of the input, as hex.
+    pub input_hex: String,
+    /// Label of the [`Status`] the guest reveals, `None` if it must abort or
+    /// for commitment versions without a status word.
+    pub status: Option<String>,
+    /// The public values the guest reveals, as hex, `None` if it must abort.
+    pub public_values_hex: Option<String>,
+    /// The rendered result a successful run commits to, for versions whose
+    /// public values only verify given it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+}
+
+impl Fixture {
+    pub fn from_case(case: &Case) -> Self {
+        Self {
+            name: case.name.to_string(),
+            input_hex: hex::encode(case.input.encode()),
+            status: case
+                .public_values
+                .and_then(|public_values| Status::from_public_values(&public_values))
+                .map(|status| status.label().to_string()),
+            public_values_hex: case.public_values.map(hex::encode),
+            result: None,
+        }
+    }
+
+    /// The input, `None` if the current protocol cannot decode it.
+    pub fn input(&self) -> Option<GuestInput> {
+        GuestInput::decode(&hex::decode(&self.input_hex).ok()?)
+    }
+
+    pub fn public_values(&self) -> Option<Vec<u8>> {
+        hex::decode(self.public_values_hex.as_deref()?).ok()
+    }
+}
+
+impl FixtureSet {
+    /// The conformance cases of the current protocol versions.
+    pub fn current() -> Self {
+        Self {
+            input_version: INPUT_VERSION,
+            commitment_version: COMMITMENT_VERSION,
+            cases: guest_protocol::conformance::cases()
+                .iter()
+                .map(Fixture::from_case)
+                .collect(),
+        }
+    }
+
+    /// Whether the set was recorded with the current protocol versions.
+    pub fn is_current(&self) -> bool {
+        self.input_version == INPUT_VERSION && self.commitment_version == COMMITMENT_VERSION
+    }
+
+    /// Where the set is recorded.
+    pub fn path(&self) -> PathBuf {
+        fixtures_dir().join(format!(
+            "input-v{}-commitment-v{}.json",
+            self.input_version, self.commitment_version
+        ))
+    }
+
+    /// Record the set at [`FixtureSet::path`].
+    pub fn write(&self) -> std::io::Result<()> {
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+        std::fs::write(self.path(), json)
+    }
+}
+
+pub fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures")
+}
+
+/// Every recorded fixture set, in file name order.
+pub fn recorded() -> std::io::Result<Vec<FixtureSet>> {
+    let mut paths = std::fs::read_dir(fixtures_dir())?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.retain(|path| path.extension().is_some_and(|ext| ext == "json"));
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| {
+            let set: FixtureSet = serde_json::from_slice(&std::fs::read(path)?)?;
+            if set.path() != *path {
+                return Err(std::io::Error::other(format!(
+                    "{} holds the fixtures of input v{} and commitment v{}",
+                    path.display(),
+                    set.input_version,
+                    set.commitment_version
+                )));
+            }
+            Ok(set)
+        })
+        .collect()
+}
@@ -0,0 +1,199 @@
+//! Checks the recorded fixtures against the current guest protocol.
+//!
+//! Run with `GUEST_COMPAT_BLESS=1` to record the fixtures of new protocol
+//! versions, or new conformance cases of the current versions. Recorded
+//! fixtures are never rewritten.
+
+use guest_compat::{Fixture, FixtureSet, recorded};
+use guest_protocol::{
+    COMMITMENT_VERSION, ExecutionMode, INPUT_VERSION, ProgramHasher, Status, decode_aggregation,
+    merkle_root, success_commitment, v1,
+};
+
+fn bless() -> bool {
+    std::env::var_os("GUEST_COMPAT_BLESS").is_some_and(|value| value == "1")
+}
+
+fn current_fixtures() -> Vec<Fixture> {
+    recorded()
+        .expect("recorded fixtures")
+        .into_iter()
+        .filter(FixtureSet::is_current)
+        .flat_map(|set| set.cases)
+        .collect()
+}
+
+#[test]
+fn current_versions_are_recorded() {
+    let current = FixtureSet::current();
+    let Some(mut set) = recorded()
+        .expect("recorded fixtures")
+        .into_iter()
+        .find(FixtureSet::is_current)
+    else {
+        assert!(
+            bless(),
+            "No fixtures for input v{} and commitment v{}: record them with \
+             `GUEST_COMPAT_BLESS=1 cargo test -p guest-compat`",
+            INPUT_VERSION,
+            COMMITMENT_VERSION
+        );
+        current.write().expect("record fixtures");
+        return;
+    };
+
+    let unrecorded: Vec<Fixture> = current
+        .cases
+        .into_iter()
+        .filter(|case| !set.cases.iter().any(|fixture| fixture.name == case.name))
+        .collect();
+    if unrecorded.is_empty() {
+        return;
+    }
+    assert!(
+        bless(),
+        "Conformance cases {:?} are not recorded: record them with \
+         `GUEST_COMPAT_BLESS=1 cargo test -p guest-compat`",
+        unrecorded.iter().map(|case| &case.name).collect::<Vec<_>>()
+    );
+    set.cases.extend(unrecorded);
+    set.write().expect("record fixtures");
+}
+
+#[test]
+fn current_protocol_reproduces_its_fixtures() {
+    let cases = FixtureSet::current().cases;
+    let mut mismatches = Vec::new();
+    for fixture in current_fixtures() {
+        let Some(input) = fixture.input() else {
+            mismatches.push(format!("{}: the input no longer decodes", fixture.name));
+            continue;
+        };
+        if hex::encode(input.encode()) != fixture.input_hex {
+            mismatches.push(format!("{}: the input encodes differently", fixture.name));
+        }
+        match cases.iter().find(|case| case.name == fixture.name) {
+            Some(case) if *case == fixture => {}
+            Some(case) => mismatches.push(format!(
+                "{}: recorded {:?}, the protocol now gives {:?}",
+                fixture.name, fixture, case
+            )),
+            None => mismatches.push(format!(
+                "{}: the conformance case was removed",
+                fixture.name
+            )),
+        }
+    }
+    assert!(
+        mismatches.is_empty(),
+        "The protocol changed without a version bump; bump INPUT_VERSION or \
+         COMMITMENT_VERSION and record new fixtures:\n{}",
+        mismatches.join("\n")
+    );
+}
+
+#[test]
+fn verifiers_read_current_fixtures() {
+    for fixture in current_fixtures() {
+        let (Some(status), Some(public_values)) = (&fixture.status, fixture.public_values()) else {
+            continue;
+        };
+        let revealed = Status::from_public_values(&public_values);
+        assert_eq!(
+            revealed.map(Status::label),
+            Some(status.as_str()),
+            "{}",
+            fixture.name
+        );
+
+        // A proven failure names its kind to whoever knows the program.
+        let input = fixture.input().expect("current input");
+        if input.mode == ExecutionMode::ProveFailures && revealed != Some(Status::Ok) {
+            let kind = ProgramHasher::with_program(&input.payload)
+                .with_args(&input.args)
+                .committed_failure(&public_values);
+            assert_eq!(kind.map(Status::of_failure), revealed, "{}", fixture.name);
+        }
+    }
+}
+
+#[test]
+fn older_inputs_decode_as_current_inputs() {
+    let current = FixtureSet::current().cases;
+    for set in recorded().expect("recorded fixtures") {
+        if set.input_version == INPUT_VERSION {
+            continue;
+        }
+        for fixture in &set.cases {
+            let input = fixture.input().unwrap_or_else(|| {
+                panic!(
+                    "{} of input v{} no longer decodes",
+                    fixture.name, set.input_version
+                )
+            });
+            // So the current guest runs it as the current case.
+            if let Some(case) = current.iter().find(|case| case.name == fixture.name) {
+                assert_eq!(Some(input), case.input(), "{}", fixture.name);
+            }
+        }
+    }
+}
+
+#[test]
+fn older_public_values_still_verify() {
+    let current = FixtureSet::current().cases;
+    for set in recorded().expect("recorded fixtures") {
+        match set.commitment_version {
+            COMMITMENT_VERSION => continue,
+            1 => {}
+            version => panic!("No verifier for the fixtures of commitment v{version}"),
+        }
+        for fixture in &set.cases {
+            let Some(public_values) = fixture.public_values() else {
+                continue;
+            };
+            let input = fixture.input().expect("older input");
+            let payload = input.payload.as_slice();
+            let expected = match input.mode {
+                ExecutionMode::Strict | ExecutionMode::ProveFailures => {
+                    if v1::committed_failure(payload, &public_values).is_some() {
+                        continue;
+                    }
+                    let result = fixture.result.as_deref().unwrap_or_else(|| {
+                        panic!("{}: neither a failure nor a recorded result", fixture.name)
+                    });
+                    // The same outcome is what the current protocol commits to.
+                    if let Some(case) = current.iter().find(|case| case.name == fixture.name) {
+                        assert_eq!(
+                            case.public_values(),
+                            Some(success_commitment(payload, result).to_vec()),
+                            "{}",
+                            fixture.name
+                        );
+                    }
+                    v1::success_commitment(payload, result)
+                }
+                ExecutionMode::Aggregate => {
+                    let (previous, commitments) =
+                        decode_aggregation(payload).expect("aggregation batch");
+                    let root = merkle_root(&commitments);
+                    v1::epoch_commitment(&previous, &root, commitments.len() as u64)
+                }
+                ExecutionMode::SelfTest => {
+                    let cases: Vec<[u8; 32]> = set
+                        .cases
+                        .iter()
+                        .filter(|case| {
+                            case.input()
+                                .is_some_and(|i| i.mode != ExecutionMode::SelfTest)
+                        })
+                        .filter_map(|case| case.public_values()?.try_into().ok())
+                        .collect();
+                    v1::self_test_commitment(&cases)
+                }
+                ExecutionMode::BuildInfo => panic!("{}: no build info check", fixture.name),
+            };
+            assert_eq!(public_values, expected, "{}", fixture.name);
+        }
+    }
+}
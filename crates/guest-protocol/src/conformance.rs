@@ -0,0 +1,270 @@
+//! Conformance suite for guests implementing this protocol.
+//!
+//! Proofs from different zkVM backends are interchangeable at the claim level
+//! only if their guests reveal the same public values for the same input.
+//! [`cases`] lists inputs with the public values every guest must reveal for
+//! them (or, for strict failures, must refuse to reveal), pinned as test
+//! vectors so a change to a commitment format cannot go unnoticed. Each
+//! backend runs them against its own guest with [`check`]; see
+//! `crates/zkvms/openvm/core/tests/conformance.rs`.
+//!
+//! Successful results are committed as rendered by the evaluator, so the
+//! vectors also pin the `uplc-turbo` rendering (`Integer(42)`), and each
+//! vector opens with the [`crate::Status`] word of its case.
+
+use crate::GuestInput;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+/// One input and what the guest must do with it.
+#[derive(Clone, Debug)]
+pub struct Case {
+    pub name: &'static str,
+    pub input: GuestInput,
+    /// The public values the guest reveals, or `None` if it must abort.
+    pub public_values: Option<[u8; 32]>,
+}
+
+/// A case the guest did not conform to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    pub case: &'static str,
+    pub expected: Option<[u8; 32]>,
+    /// What the guest revealed, or why it failed.
+    pub actual: Result<Vec<u8>, String>,
+}
+
+/// `(con integer 42)`
+const ANSWER: &[u8] = &[0x01, 0x00, 0x00, 0x48, 0x15, 0x01];
+/// `[(builtin addInteger) (con integer 40) (con integer 2)]`
+const ADD: &[u8] = &[0x01, 0x00, 0x00, 0x33, 0x70, 0x09, 0x02, 0x82, 0x40, 0x09];
+/// `(error)`
+const ERROR: &[u8] = &[0x01, 0x00, 0x00, 0x61];
+/// `(lam x x)`
+const IDENTITY: &[u8] = &[0x01, 0x00, 0x00, 0x20, 0x01, 0x01];
+/// Not a flat program.
+const MALFORMED: &[u8] = &[0xff];
+/// `(lam x (con integer 42))`
+const CONSTANT_FUNCTION: &[u8] = &[0x01, 0x00, 0x00, 0x24, 0x81, 0x51];
+/// The CBOR of `PlutusData` integer 1.
+const DATA_ONE: &[u8] = &[0x01];
+/// Not CBOR.
+const MALFORMED_DATA: &[u8] = &[0xff];
+
+/// Every conformance case.
+pub fn cases() -> Vec<Case> {
+    let commitments = [[0x01; 32], [0x02; 32], [0x03; 32]];
+    vec![
+        Case {
+            name: "constant",
+            input: GuestInput::program(ANSWER, false),
+            public_values: Some(hex32(
+                "00000000bba9cf9b0a352012130cc4486e0ab8cfa1890d74048310fa9a63878b",
+            )),
+        },
+        Case {
+            name: "builtin_application",
+            input: GuestInput::program(ADD, false),
+            public_values: Some(hex32(
+                "00000000e7f2c94db708663bf7a2be1d642caada99aaadef740b19299ceebe08",
+            )),
+        },
+        Case {
+            name: "success_with_prove_failures",
+            input: GuestInput::program(ANSWER, true),
+            public_values: Some(hex32(
+                "00000000bba9cf9b0a352012130cc4486e0ab8cfa1890d74048310fa9a63878b",
+            )),
+        },
+        Case {
+            name: "strict_failure",
+            input: GuestInput::program(ERROR, false),
+            public_values: None,
+        },
+        Case {
+            name: "evaluation_failure",
+            input: GuestInput::program(ERROR, true),
+            public_values: Some(hex32(
+                "00000001164475b58f2551e39b9419874d58446815d66b0ca70a9fc5e956df64",
+            )),
+        },
+        Case {
+            name: "non_constant_result",
+            input: GuestInput::program(IDENTITY, true),
+            public_values: Some(hex32(
+                "0000000198cb7270e06ec4d504e5a101643bd9987921f7d95556a31f6c2d6f7b",
+            )),
+        },
+        Case {
+            name: "decode_failure",
+            input: GuestInput::program(MALFORMED, true),
+            public_values: Some(hex32(
+                "00000003b4c22d892db7c0e030b923b6e5cc64b790bd83d829ec738748f58ba1",
+            )),
+        },
+        Case {
+            name: "applied_to_argument",
+            input: GuestInput::program_with_args(CONSTANT_FUNCTION, &[DATA_ONE.to_vec()], false),
+            public_values: Some(hex32(
+                "00000000448b404b87b6bb28d3a6b5c7d748503de9edc6074dc1259d0a6ec932",
+            )),
+        },
+        Case {
+            name: "malformed_argument",
+            input: GuestInput::program_with_args(
+                CONSTANT_FUNCTION,
+                &[MALFORMED_DATA.to_vec()],
+                true,
+            ),
+            public_values: Some(hex32(
+                "00000003491a1f2d0a6c9602158a6bdf0b755f12351653f3e3339ed0a38a6aa6",
+            )),
+        },
+        Case {
+            name: "oversized_arguments",
+            input: GuestInput::program_with_args(
+                CONSTANT_FUNCTION,
+                &[vec![0; crate::MAX_ARGS_BYTES + 1]],
+                false,
+            ),
+            public_values: Some(hex32(
+                "00000005c07d2847238910259298a3d0fb23d89d921c9ab803ccb733ce9ef87b",
+            )),
+        },
+        Case {
+            name: "first_epoch",
+            input: GuestInput::aggregation(&[0; 32], &commitments),
+            public_values: Some(hex32(
+                "00000000be194a272ca4c686bda2665abb629eee05e4c058ee40eb99c10be0bf",
+            )),
+        },
+        Case {
+            name: "chained_epoch",
+            input: GuestInput::aggregation(&[0xaa; 32], &commitments[..1]),
+            public_values: Some(hex32(
+                "000000009984501bd5155d8b7f76122eae9c7ea159defd217544a9eb25706ac0",
+            )),
+        },
+        Case {
+            name: "self_test",
+            input: GuestInput::self_test(),
+            public_values: Some(hex32(
+                "00000000c9de2f392763a077dea0e5fbb0b523f514eea40033d2f43eac052009",
+            )),
+        },
+    ]
+}
+
+/// The cases a guest runs in its self-test: those it must reveal public
+/// values for, as a guest cannot recover from aborting, except the self-test
+/// itself.
+pub fn self_test_cases() -> Vec<Case> {
+    cases()
+        .into_iter()
+        .filter(|case| {
+            case.public_values.is_some() && case.input.mode != crate::ExecutionMode::SelfTest
+        })
+        .collect()
+}
+
+/// Run every case through `execute`, which runs a guest on an input and
+/// returns its public values. Returns the cases the guest got wrong.
+pub fn check<E: Display>(
+    mut execute: impl FnMut(&GuestInput) -> Result<Vec<u8>, E>,
+) -> Vec<Mismatch> {
+    cases()
+        .into_iter()
+        .filter_map(|case| {
+            let actual = execute(&case.input).map_err(|e| e.to_string());
+            let conforms = match (&actual, &case.public_values) {
+                (Ok(actual), Some(expected)) => actual == expected,
+                (Err(_), None) => true,
+                _ => false,
+            };
+            (!conforms).then_some(Mismatch {
+                case: case.name,
+                expected: case.public_values,
+                actual,
+            })
+        })
+        .collect()
+}
+
+const fn hex32(hex: &str) -> [u8; 32] {
+    const fn nibble(c: u8) -> u8 {
+        match c {
+            b'0'..=b'9' => c - b'0',
+            b'a'..=b'f' => c - b'a' + 10,
+            _ => panic!("invalid hex digit"),
+        }
+    }
+    let hex = hex.as_bytes();
+    assert!(hex.len() == 64, "expected 32 bytes of hex");
+    let mut bytes = [0; 32];
+    let mut i = 0;
+    while i < 32 {
+        bytes[i] = nibble(hex[2 * i]) << 4 | nibble(hex[2 * i + 1]);
+        i += 1;
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ErrorKind, ProgramHasher, epoch_commitment, merkle_root};
+
+    /// The reference guest: what the protocol says a guest reveals, given the
+    /// outcome of evaluating each program.
+    fn reference(input: &GuestInput) -> Result<Vec<u8>, &'static str> {
+        use crate::ExecutionMode::*;
+        let payload = input.payload.as_slice();
+        let hasher = ProgramHasher::with_program(payload).with_args(&input.args);
+        if matches!(input.mode, Strict | ProveFailures)
+            && crate::exceeds_limits(payload, &input.args)
+        {
+            return Ok(crate::rejection_commitment(payload, &input.args).to_vec());
+        }
+        let failure = |kind| match input.mode {
+            ProveFailures => Ok(hasher.failure_commitment(kind).to_vec()),
+            _ => Err("evaluation failed"),
+        };
+        match (input.mode, payload) {
+            (SelfTest, _) => {
+                let public_values: Vec<[u8; 32]> = self_test_cases()
+                    .iter()
+                    .map(|case| reference(&case.input).unwrap().try_into().unwrap())
+                    .collect();
+                Ok(crate::self_test_commitment(&public_values).to_vec())
+            }
+            (Aggregate, _) => {
+                let (previous, commitments) = crate::decode_aggregation(payload).unwrap();
+                let root = merkle_root(&commitments);
+                Ok(epoch_commitment(&previous, &root, commitments.len() as u64).to_vec())
+            }
+            (_, ANSWER | ADD) => Ok(hasher.success_commitment("Integer(42)").to_vec()),
+            (_, CONSTANT_FUNCTION) if input.args == [DATA_ONE] => {
+                Ok(hasher.success_commitment("Integer(42)").to_vec())
+            }
+            (_, CONSTANT_FUNCTION) => failure(ErrorKind::Decode),
+            (_, ERROR) => failure(ErrorKind::Evaluation),
+            (_, IDENTITY) => failure(ErrorKind::NonConstantResult),
+            (_, MALFORMED) => failure(ErrorKind::Decode),
+            _ => Err("unexpected input"),
+        }
+    }
+
+    #[test]
+    fn vectors_follow_the_protocol() {
+        assert_eq!(check(reference), vec![]);
+    }
+
+    #[test]
+    fn reports_nonconforming_guests() {
+        let mismatches = check(|_: &GuestInput| Ok::<_, &str>(vec![0; 32]));
+        assert_eq!(mismatches.len(), cases().len());
+        assert!(mismatches.iter().any(|m| m.case == "strict_failure"));
+    }
+}
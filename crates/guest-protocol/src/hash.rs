@@ -0,0 +1,182 @@
+//! Hash functions that commitments can be computed with.
+//!
+//! The guests commit with SHA-256, the [`Sha256Hash`] backend. The same
+//! statements and Merkle trees can be committed to with any
+//! [`CommitmentHash`]:
+//!
+//! - [`Blake2b256`], feature `blake2b`: Cardano's own hash, so ledger
+//!   scripts can recompute commitments with a builtin.
+//! - [`Poseidon2BabyBear`], feature `poseidon2`: digests are BabyBear field
+//!   elements, cheap to open inside a recursive STARK circuit.
+
+use sha2::{Digest, Sha256};
+
+/// An incremental hash with 32-byte digests.
+pub trait CommitmentHash: Default {
+    /// Name of the hash function, e.g. for metadata.
+    const NAME: &'static str;
+
+    fn update(&mut self, bytes: &[u8]);
+
+    fn finalize(self) -> [u8; 32];
+
+    /// Digest of `bytes` in one call.
+    fn digest(bytes: &[u8]) -> [u8; 32] {
+        let mut hasher = Self::default();
+        hasher.update(bytes);
+        hasher.finalize()
+    }
+}
+
+/// SHA-256, the hash the guests commit with.
+#[derive(Clone, Default)]
+pub struct Sha256Hash(Sha256);
+
+impl CommitmentHash for Sha256Hash {
+    const NAME: &'static str = "sha256";
+
+    fn update(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finalize(self) -> [u8; 32] {
+        self.0.finalize().into()
+    }
+}
+
+/// Blake2b with 32-byte digests, as used by the Cardano ledger.
+#[cfg(feature = "blake2b")]
+#[derive(Clone, Default)]
+pub struct Blake2b256(blake2::Blake2b<blake2::digest::consts::U32>);
+
+#[cfg(feature = "blake2b")]
+impl CommitmentHash for Blake2b256 {
+    const NAME: &'static str = "blake2b-256";
+
+    fn update(&mut self, bytes: &[u8]) {
+        blake2::Digest::update(&mut self.0, bytes);
+    }
+
+    fn finalize(self) -> [u8; 32] {
+        blake2::Digest::finalize(self.0).into()
+    }
+}
+
+#[cfg(feature = "poseidon2")]
+pub use poseidon2::Poseidon2BabyBear;
+
+#[cfg(feature = "poseidon2")]
+mod poseidon2 {
+    use super::CommitmentHash;
+    use alloc::vec::Vec;
+    use openvm_poseidon2_air::{Poseidon2Config, p3_symmetric::Permutation};
+    use openvm_stark_backend::p3_field::{PrimeCharacteristicRing, PrimeField32};
+    use openvm_stark_sdk::p3_baby_bear::{BabyBear, Poseidon2BabyBear as Permutation16};
+
+    /// Field elements absorbed per permutation; the other 8 of the 16 are
+    /// the capacity.
+    const RATE: usize = 8;
+    /// Bytes packed into each field element, so every packing is below the
+    /// BabyBear modulus.
+    const BYTES_PER_ELEMENT: usize = 3;
+
+    /// Poseidon2 over BabyBear with the OpenVM VM's width-16 permutation and
+    /// round constants, as a sponge with rate 8.
+    ///
+    /// Bytes are padded with a single `0x01` and zeroes, packed three per
+    /// field element little-endian, and absorbed 8 elements at a time. The
+    /// digest is the 8 elements of the rate after the last permutation, each
+    /// as a u32 little-endian; [`Poseidon2BabyBear::finalize_elements`]
+    /// returns them as field elements.
+    #[derive(Clone, Default)]
+    pub struct Poseidon2BabyBear {
+        bytes: Vec<u8>,
+    }
+
+    impl Poseidon2BabyBear {
+        /// The digest as field elements, for circuits that consume it
+        /// without unpacking bytes.
+        pub fn finalize_elements(mut self) -> [BabyBear; RATE] {
+            let config = Poseidon2Config::<BabyBear>::default();
+            let (external_constants, internal_constants) =
+                config.constants.to_external_internal_constants();
+            let permutation = Permutation16::<16>::new(external_constants, internal_constants);
+
+            self.bytes.push(0x01);
+            let padded_len = self.bytes.len().next_multiple_of(BYTES_PER_ELEMENT * RATE);
+            self.bytes.resize(padded_len, 0);
+
+            let mut state = [BabyBear::ZERO; 16];
+            for block in self.bytes.chunks_exact(BYTES_PER_ELEMENT * RATE) {
+                for (lane, packed) in state.iter_mut().zip(block.chunks_exact(BYTES_PER_ELEMENT)) {
+                    let value = packed
+                        .iter()
+                        .rev()
+                        .fold(0u32, |value, byte| (value << 8) | u32::from(*byte));
+                    *lane += BabyBear::from_u32(value);
+                }
+                state = permutation.permute(state);
+            }
+            let mut digest = [BabyBear::ZERO; RATE];
+            digest.copy_from_slice(&state[..RATE]);
+            digest
+        }
+    }
+
+    impl CommitmentHash for Poseidon2BabyBear {
+        const NAME: &'static str = "poseidon2-babybear";
+
+        fn update(&mut self, bytes: &[u8]) {
+            self.bytes.extend_from_slice(bytes);
+        }
+
+        fn finalize(self) -> [u8; 32] {
+            let mut digest = [0; 32];
+            for (bytes, element) in digest.chunks_exact_mut(4).zip(self.finalize_elements()) {
+                bytes.copy_from_slice(&element.as_canonical_u32().to_le_bytes());
+            }
+            digest
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256_backend_is_sha256() {
+        let mut hasher = Sha256Hash::default();
+        hasher.update(b"a");
+        hasher.update(b"bc");
+        assert_eq!(hasher.finalize(), Sha256Hash::digest(b"abc"));
+        assert_eq!(
+            Sha256Hash::digest(b"abc")[..4],
+            [0xba, 0x78, 0x16, 0xbf],
+            "SHA-256 of \"abc\""
+        );
+    }
+
+    #[cfg(feature = "blake2b")]
+    #[test]
+    fn blake2b_backend_is_blake2b_256() {
+        assert_eq!(
+            Blake2b256::digest(b"")[..4],
+            [0x0e, 0x57, 0x51, 0xc0],
+            "Blake2b-256 of the empty string"
+        );
+    }
+
+    #[cfg(feature = "poseidon2")]
+    #[test]
+    fn poseidon2_padding_tells_trailing_zeroes_apart() {
+        assert_ne!(
+            Poseidon2BabyBear::digest(b"ab"),
+            Poseidon2BabyBear::digest(b"ab\0")
+        );
+        assert_ne!(
+            Poseidon2BabyBear::digest(b""),
+            Poseidon2BabyBear::digest(&[0; 24])
+        );
+    }
+}
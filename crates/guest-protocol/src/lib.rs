@@ -0,0 +1,777 @@
+//! Contract shared by the zkVM guests and their hosts: how guest inputs are
+//! laid out and how evaluation outcomes are committed to as public values.
+//!
+//! Every run reveals 32 bytes of public values: a [`Status`] word, then the
+//! first 28 bytes of a digest of what the run attests, see [`public_values`].
+#![no_std]
+
+extern crate alloc;
+
+pub mod conformance;
+pub mod hash;
+pub mod statement;
+pub mod v1;
+
+pub use hash::{CommitmentHash, Sha256Hash};
+pub use statement::Statement;
+
+use alloc::vec::Vec;
+use sha2::{Digest, Sha256};
+
+/// Version of this crate, part of the guest's build info.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Version of the commitment formats below. Bumped whenever a guest would
+/// commit to a different digest for the same program and outcome.
+pub const COMMITMENT_VERSION: u32 = 3;
+
+/// Length of the [`Status`] word that opens the public values.
+pub const STATUS_LEN: usize = 4;
+
+/// Prefix of encoded statements, see [`Statement::encode`].
+pub const STATEMENT_TAG: &[u8] = b"statement:";
+
+/// Prefix of epoch commitments, see [`epoch_commitment`].
+pub const EPOCH_TAG: &[u8] = b"epoch:";
+
+/// Prefix of recursion bridge commitments, see [`bridge_commitment`].
+pub const BRIDGE_TAG: &[u8] = b"bridge:";
+
+/// Prefix of self-test commitments, see [`self_test_commitment`].
+pub const SELF_TEST_TAG: &[u8] = b"self-test:";
+
+/// Prefix of rejection commitments, see [`rejection_commitment`].
+pub const REJECTED_TAG: &[u8] = b"rejected:";
+
+/// Largest program, in bytes, a guest evaluates: the `uplc` crate's default
+/// decode limit. Larger programs are rejected, see [`exceeds_limits`].
+pub const MAX_PROGRAM_BYTES: usize = 256 * 1024;
+
+/// Largest total size, in bytes, of the arguments a guest applies a program
+/// to. Larger arguments are rejected, see [`exceeds_limits`].
+pub const MAX_ARGS_BYTES: usize = 64 * 1024;
+
+/// What the guest does with its payload: the entrypoint it runs, and for
+/// programs, how it treats evaluation failures.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ExecutionMode {
+    /// Evaluation failures abort the guest, so only successful runs can be proven.
+    #[default]
+    Strict = 0,
+    /// Evaluation failures are committed to like any other outcome.
+    ProveFailures = 1,
+    /// The input is an aggregation batch rather than a program: the guest
+    /// commits to the batch's epoch commitment, see [`decode_aggregation`].
+    Aggregate = 2,
+    /// The input is a build info query rather than a program: the guest
+    /// reveals one chunk of its build info, see [`build_info_chunk`].
+    BuildInfo = 3,
+    /// The payload is empty: the guest runs the conformance cases it can
+    /// check by itself and reveals their [`self_test_commitment`], see
+    /// [`conformance::self_test_cases`].
+    SelfTest = 4,
+}
+
+impl ExecutionMode {
+    /// Decode the mode input written by the host. An empty input means `Strict`.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [] | [0] => Some(Self::Strict),
+            [1] => Some(Self::ProveFailures),
+            [2] => Some(Self::Aggregate),
+            [3] => Some(Self::BuildInfo),
+            [4] => Some(Self::SelfTest),
+            _ => None,
+        }
+    }
+
+    pub fn encode(self) -> [u8; 1] {
+        [self as u8]
+    }
+
+    /// Name of the guest entrypoint the mode dispatches to.
+    pub fn entrypoint(self) -> &'static str {
+        match self {
+            Self::Strict | Self::ProveFailures => "evaluate",
+            Self::Aggregate => "batch",
+            Self::BuildInfo => "version-query",
+            Self::SelfTest => "self-test",
+        }
+    }
+}
+
+/// Version of the [`GuestInput::encode`] layout.
+pub const INPUT_VERSION: u8 = 2;
+
+/// Everything one guest run reads, in order: the payload (a program, an
+/// aggregation batch, a build info query, or nothing for a self-test), the
+/// execution mode, then the arguments the program is applied to.
+///
+/// Hosts write each of [`GuestInput::items`] with one `write_bytes`, which the
+/// guest reads back with one `read_vec`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GuestInput {
+    pub payload: Vec<u8>,
+    pub mode: ExecutionMode,
+    /// CBOR-encoded `PlutusData` the program is applied to, in order, e.g. a
+    /// datum, a redeemer and a script context. Only programs take arguments.
+    pub args: Vec<Vec<u8>>,
+}
+
+impl GuestInput {
+    /// Evaluate a flat-encoded program, committing to failures too if
+    /// `prove_failures` is set.
+    pub fn program(program_bytes: &[u8], prove_failures: bool) -> Self {
+        Self::program_with_args(program_bytes, &[], prove_failures)
+    }
+
+    /// Evaluate a flat-encoded program applied to CBOR-encoded `PlutusData`
+    /// `args`. The commitment covers each argument's [`argument_hash`].
+    pub fn program_with_args(program_bytes: &[u8], args: &[Vec<u8>], prove_failures: bool) -> Self {
+        Self {
+            payload: program_bytes.to_vec(),
+            mode: if prove_failures {
+                ExecutionMode::ProveFailures
+            } else {
+                ExecutionMode::Strict
+            },
+            args: args.to_vec(),
+        }
+    }
+
+    /// Start building the input of a program applied to named arguments, see
+    /// [`GuestInputBuilder`].
+    pub fn builder(program_bytes: &[u8]) -> GuestInputBuilder {
+        GuestInputBuilder::new(program_bytes)
+    }
+
+    /// Chain an epoch's commitments, see [`encode_aggregation`].
+    pub fn aggregation(previous: &[u8; 32], commitments: &[[u8; 32]]) -> Self {
+        Self {
+            payload: encode_aggregation(previous, commitments),
+            mode: ExecutionMode::Aggregate,
+            args: Vec::new(),
+        }
+    }
+
+    /// Reveal chunk `index` of the guest's build info, see [`build_info_chunk`].
+    pub fn build_info(index: u32) -> Self {
+        Self {
+            payload: encode_build_info_query(index).to_vec(),
+            mode: ExecutionMode::BuildInfo,
+            args: Vec::new(),
+        }
+    }
+
+    /// Run the guest's self-test, see [`ExecutionMode::SelfTest`].
+    pub fn self_test() -> Self {
+        Self {
+            payload: Vec::new(),
+            mode: ExecutionMode::SelfTest,
+            args: Vec::new(),
+        }
+    }
+
+    /// The inputs in the order the guest reads them.
+    pub fn items(&self) -> [Vec<u8>; 3] {
+        [
+            self.payload.clone(),
+            self.mode.encode().to_vec(),
+            encode_args(&self.args),
+        ]
+    }
+
+    /// Self-describing encoding for handing inputs between processes:
+    /// [`INPUT_VERSION`], then each item as its length (u32 big-endian)
+    /// followed by its bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.push(INPUT_VERSION);
+        for item in self.items() {
+            bytes.extend_from_slice(&(item.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(&item);
+        }
+        bytes
+    }
+
+    /// Decode [`GuestInput::encode`] output. Inputs of [`INPUT_VERSION`] 1,
+    /// which had no arguments item, decode with no arguments. `None` for
+    /// another version or a malformed input.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let (&version, mut rest) = bytes.split_first()?;
+        if version != 1 && version != INPUT_VERSION {
+            return None;
+        }
+        let mut next_item = || {
+            let (len, tail) = rest.split_first_chunk::<4>()?;
+            let len = u32::from_be_bytes(*len) as usize;
+            let (item, tail) = tail.split_at_checked(len)?;
+            rest = tail;
+            Some(item)
+        };
+        let payload = next_item()?.to_vec();
+        let mode = ExecutionMode::decode(next_item()?)?;
+        let args = match version {
+            1 => Vec::new(),
+            _ => decode_args(next_item()?)?,
+        };
+        rest.is_empty().then_some(Self {
+            payload,
+            mode,
+            args,
+        })
+    }
+}
+
+/// Builds a [`GuestInput`] for a validator from its named arguments, so
+/// callers don't have to know the order the program takes them in.
+///
+/// The arguments are applied as datum, redeemer, then script context,
+/// whichever order they were given in, skipping those that were not, e.g.
+/// only the script context for a Plutus V3 validator.
+#[derive(Clone, Debug, Default)]
+pub struct GuestInputBuilder {
+    program: Vec<u8>,
+    datum: Option<Vec<u8>>,
+    redeemer: Option<Vec<u8>>,
+    script_context: Option<Vec<u8>>,
+    prove_failures: bool,
+}
+
+impl GuestInputBuilder {
+    pub fn new(program_bytes: &[u8]) -> Self {
+        Self {
+            program: program_bytes.to_vec(),
+            ..Self::default()
+        }
+    }
+
+    /// CBOR-encoded `PlutusData` datum.
+    pub fn datum(mut self, cbor: &[u8]) -> Self {
+        self.datum = Some(cbor.to_vec());
+        self
+    }
+
+    /// CBOR-encoded `PlutusData` redeemer.
+    pub fn redeemer(mut self, cbor: &[u8]) -> Self {
+        self.redeemer = Some(cbor.to_vec());
+        self
+    }
+
+    /// CBOR-encoded `PlutusData` script context.
+    pub fn script_context(mut self, cbor: &[u8]) -> Self {
+        self.script_context = Some(cbor.to_vec());
+        self
+    }
+
+    /// Commit to evaluation failures too, see [`ExecutionMode::ProveFailures`].
+    pub fn prove_failures(mut self, prove_failures: bool) -> Self {
+        self.prove_failures = prove_failures;
+        self
+    }
+
+    pub fn build(self) -> GuestInput {
+        let args: Vec<Vec<u8>> = [self.datum, self.redeemer, self.script_context]
+            .into_iter()
+            .flatten()
+            .collect();
+        GuestInput::program_with_args(&self.program, &args, self.prove_failures)
+    }
+}
+
+/// The arguments item of a [`GuestInput`]: their count, then each argument,
+/// as u32 big-endian lengths followed by bytes.
+pub fn encode_args(args: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&(args.len() as u32).to_be_bytes());
+    for arg in args {
+        bytes.extend_from_slice(&(arg.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(arg);
+    }
+    bytes
+}
+
+/// Decode [`encode_args`] output. `None` unless `bytes` is exactly that.
+pub fn decode_args(bytes: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let (count, mut rest) = bytes.split_first_chunk::<4>()?;
+    let args = (0..u32::from_be_bytes(*count))
+        .map(|_| {
+            let (len, tail) = rest.split_first_chunk::<4>()?;
+            let (arg, tail) = tail.split_at_checked(u32::from_be_bytes(*len) as usize)?;
+            rest = tail;
+            Some(arg.to_vec())
+        })
+        .collect::<Option<Vec<_>>>()?;
+    rest.is_empty().then_some(args)
+}
+
+/// SHA256 of an argument's CBOR encoding, as given: how a [`Statement`]
+/// refers to it. Verifiers recompute it from the datum, redeemer or context
+/// they were handed.
+pub fn argument_hash(cbor: &[u8]) -> [u8; 32] {
+    Sha256::digest(cbor).into()
+}
+
+/// Category of evaluation failure committed to in `ProveFailures` mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The program bytes are not a valid flat-encoded program.
+    Decode,
+    /// The machine reported an error while evaluating the program.
+    Evaluation,
+    /// Evaluation ran out of execution budget.
+    BudgetExceeded,
+    /// Evaluation finished but did not produce a constant.
+    NonConstantResult,
+}
+
+impl ErrorKind {
+    pub const ALL: [Self; 4] = [
+        Self::Decode,
+        Self::Evaluation,
+        Self::BudgetExceeded,
+        Self::NonConstantResult,
+    ];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Decode => "decode",
+            Self::Evaluation => "evaluation",
+            Self::BudgetExceeded => "budget_exceeded",
+            Self::NonConstantResult => "non_constant_result",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.label() == label)
+    }
+}
+
+/// How a guest run ended, revealed in every mode as the first [`STATUS_LEN`]
+/// bytes of its public values (u32 big-endian), so a verifier can tell a
+/// proof of a failure from a proof of a success without knowing the program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum Status {
+    /// A program evaluated to a constant, or another entrypoint did its job.
+    Ok = 0,
+    /// Evaluation failed or did not produce a constant.
+    EvalError = 1,
+    /// Evaluation ran out of execution budget.
+    BudgetExceeded = 2,
+    /// The program bytes are not a valid flat-encoded program.
+    DecodeError = 3,
+    /// The guest itself failed. Reserved: guests abort on internal errors, so
+    /// no proof carries it yet.
+    Internal = 4,
+    /// The guest refused the program or its arguments as larger than
+    /// [`MAX_PROGRAM_BYTES`] or [`MAX_ARGS_BYTES`], without evaluating them.
+    TooLarge = 5,
+}
+
+impl Status {
+    pub const ALL: [Self; 6] = [
+        Self::Ok,
+        Self::EvalError,
+        Self::BudgetExceeded,
+        Self::DecodeError,
+        Self::Internal,
+        Self::TooLarge,
+    ];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::EvalError => "eval_error",
+            Self::BudgetExceeded => "budget_exceeded",
+            Self::DecodeError => "decode_error",
+            Self::Internal => "internal",
+            Self::TooLarge => "too_large",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|status| status.label() == label)
+    }
+
+    /// The status committed for a failure of `kind`.
+    pub const fn of_failure(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::Decode => Self::DecodeError,
+            ErrorKind::Evaluation | ErrorKind::NonConstantResult => Self::EvalError,
+            ErrorKind::BudgetExceeded => Self::BudgetExceeded,
+        }
+    }
+
+    pub const fn word(self) -> [u8; STATUS_LEN] {
+        (self as u32).to_be_bytes()
+    }
+
+    pub fn from_word(word: [u8; STATUS_LEN]) -> Option<Self> {
+        Self::ALL.into_iter().find(|status| status.word() == word)
+    }
+
+    /// The status a guest revealed at the start of `public_values`. `None`
+    /// for public values too short to hold one or an unknown word, e.g. from
+    /// a guest older than [`COMMITMENT_VERSION`] 3.
+    pub fn from_public_values(public_values: &[u8]) -> Option<Self> {
+        Self::from_word(*public_values.first_chunk()?)
+    }
+}
+
+/// The public values a guest reveals: `status`'s word, then the first 28
+/// bytes of `digest`.
+pub fn public_values(status: Status, digest: &[u8; 32]) -> [u8; 32] {
+    let mut values = [0; 32];
+    values[..STATUS_LEN].copy_from_slice(&status.word());
+    values[STATUS_LEN..].copy_from_slice(&digest[..32 - STATUS_LEN]);
+    values
+}
+
+/// SHA256 state over a program, fed in chunks as it arrives, and the
+/// hashes of the arguments it is applied to.
+///
+/// Every statement about a run holds the program's hash, so it is computed
+/// once for all candidate commitments; multi-MB programs are then hashed once
+/// per check rather than once per error kind.
+#[derive(Clone, Default)]
+pub struct ProgramHasher {
+    program: Sha256,
+    args: Vec<[u8; 32]>,
+}
+
+impl ProgramHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hasher over a whole program.
+    pub fn with_program(program_bytes: &[u8]) -> Self {
+        let mut hasher = Self::new();
+        hasher.update(program_bytes);
+        hasher
+    }
+
+    /// Append the next chunk of the program.
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.program.update(chunk);
+    }
+
+    /// Commit to the program applied to CBOR-encoded `args`, in order.
+    pub fn with_args(mut self, args: &[Vec<u8>]) -> Self {
+        self.args = args.iter().map(|arg| argument_hash(arg)).collect();
+        self
+    }
+
+    /// SHA256 of the program fed so far.
+    pub fn program_hash(&self) -> [u8; 32] {
+        self.program.clone().finalize().into()
+    }
+
+    /// The statement that the program evaluated to `result`.
+    pub fn success(&self, result: &str) -> Statement {
+        Statement::success(self.program_hash(), result).with_args(self.args.clone())
+    }
+
+    /// The statement that the program failed with `kind`.
+    pub fn failure(&self, kind: ErrorKind) -> Statement {
+        Statement::failure(self.program_hash(), kind).with_args(self.args.clone())
+    }
+
+    /// See [`success_commitment`].
+    pub fn success_commitment(&self, result: &str) -> [u8; 32] {
+        self.success(result).commitment()
+    }
+
+    /// See [`failure_commitment`].
+    pub fn failure_commitment(&self, kind: ErrorKind) -> [u8; 32] {
+        self.failure(kind).commitment()
+    }
+
+    /// See [`committed_failure`].
+    pub fn committed_failure(&self, commitment: &[u8]) -> Option<ErrorKind> {
+        let program = self.program_hash();
+        ErrorKind::ALL.into_iter().find(|kind| {
+            Statement::failure(program, *kind)
+                .with_args(self.args.clone())
+                .commitment()
+                .as_slice()
+                == commitment
+        })
+    }
+}
+
+/// The commitment to a successful run: [`Status::Ok`] and the SHA256 of the
+/// [`Statement`] that the program evaluated to `result`.
+pub fn success_commitment(program_bytes: &[u8], result: &str) -> [u8; 32] {
+    ProgramHasher::with_program(program_bytes).success_commitment(result)
+}
+
+/// The commitment to a failed run: [`Status::of_failure`] and the SHA256 of
+/// the [`Statement`] that the program failed with `kind`.
+pub fn failure_commitment(program_bytes: &[u8], kind: ErrorKind) -> [u8; 32] {
+    ProgramHasher::with_program(program_bytes).failure_commitment(kind)
+}
+
+/// Recover the failure kind a guest committed to, if `commitment` is a
+/// failure commitment for `program_bytes`.
+pub fn committed_failure(program_bytes: &[u8], commitment: &[u8]) -> Option<ErrorKind> {
+    ProgramHasher::with_program(program_bytes).committed_failure(commitment)
+}
+
+/// Whether a guest must reject `program_bytes` applied to `args` instead of
+/// evaluating them: the program is larger than [`MAX_PROGRAM_BYTES`], or the
+/// arguments together larger than [`MAX_ARGS_BYTES`].
+pub fn exceeds_limits(program_bytes: &[u8], args: &[Vec<u8>]) -> bool {
+    program_bytes.len() > MAX_PROGRAM_BYTES
+        || args.iter().map(Vec::len).sum::<usize>() > MAX_ARGS_BYTES
+}
+
+/// The commitment to refusing an input that [`exceeds_limits`]:
+/// [`Status::TooLarge`] and SHA256("rejected:" || program length || total
+/// argument length || SHA256(program) || argument count || each
+/// [`argument_hash`]), lengths as u64 and the count as u32, big-endian.
+///
+/// Guests reveal it in every program mode, so a server can prove it refused
+/// an input and a client gets the same answer at the boundary every time.
+pub fn rejection_commitment(program_bytes: &[u8], args: &[Vec<u8>]) -> [u8; 32] {
+    let args_len = args.iter().map(Vec::len).sum::<usize>();
+    let mut hasher = Sha256::new();
+    hasher.update(REJECTED_TAG);
+    hasher.update((program_bytes.len() as u64).to_be_bytes());
+    hasher.update((args_len as u64).to_be_bytes());
+    hasher.update(Sha256::digest(program_bytes));
+    hasher.update((args.len() as u32).to_be_bytes());
+    for arg in args {
+        hasher.update(argument_hash(arg));
+    }
+    public_values(Status::TooLarge, &hasher.finalize().into())
+}
+
+/// Input of an `Aggregate` run: the previous epoch commitment followed by the
+/// commitments proven during the epoch, 32 bytes each.
+pub fn encode_aggregation(previous: &[u8; 32], commitments: &[[u8; 32]]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(32 * (commitments.len() + 1));
+    bytes.extend_from_slice(previous);
+    for commitment in commitments {
+        bytes.extend_from_slice(commitment);
+    }
+    bytes
+}
+
+/// Split an aggregation batch into the previous epoch commitment and the
+/// epoch's commitments. `None` unless it holds at least one commitment.
+pub fn decode_aggregation(bytes: &[u8]) -> Option<([u8; 32], Vec<[u8; 32]>)> {
+    if bytes.len() < 64 || bytes.len() % 32 != 0 {
+        return None;
+    }
+    let mut chunks = bytes
+        .chunks_exact(32)
+        .map(|chunk| <[u8; 32]>::try_from(chunk).expect("chunks are 32 bytes"));
+    let previous = chunks.next()?;
+    Some((previous, chunks.collect()))
+}
+
+/// SHA256(0x00 || commitment): a Merkle leaf.
+pub fn merkle_leaf(commitment: &[u8; 32]) -> [u8; 32] {
+    merkle_leaf_with::<Sha256Hash>(commitment)
+}
+
+/// [`merkle_leaf`] with `H` instead of SHA256.
+pub fn merkle_leaf_with<H: CommitmentHash>(commitment: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = H::default();
+    hasher.update(&[0x00]);
+    hasher.update(commitment);
+    hasher.finalize()
+}
+
+/// SHA256(0x01 || left || right): a Merkle inner node. The prefixes keep a
+/// leaf from ever being read as an inner node.
+pub fn merkle_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    merkle_node_with::<Sha256Hash>(left, right)
+}
+
+/// [`merkle_node`] with `H` instead of SHA256.
+pub fn merkle_node_with<H: CommitmentHash>(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = H::default();
+    hasher.update(&[0x01]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize()
+}
+
+/// Hash a tree level pairwise; an unpaired last node is promoted as is.
+fn next_level<H: CommitmentHash>(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => merkle_node_with::<H>(left, right),
+            [single] => *single,
+            _ => unreachable!("chunks(2) yields one or two nodes"),
+        })
+        .collect()
+}
+
+/// Merkle root of `commitments`, in order. All zeroes for an empty list.
+pub fn merkle_root(commitments: &[[u8; 32]]) -> [u8; 32] {
+    merkle_root_with::<Sha256Hash>(commitments)
+}
+
+/// [`merkle_root`] with `H` instead of SHA256.
+pub fn merkle_root_with<H: CommitmentHash>(commitments: &[[u8; 32]]) -> [u8; 32] {
+    let mut level: Vec<_> = commitments.iter().map(merkle_leaf_with::<H>).collect();
+    if level.is_empty() {
+        return [0; 32];
+    }
+    while level.len() > 1 {
+        level = next_level::<H>(&level);
+    }
+    level[0]
+}
+
+/// One step of a Merkle inclusion path, from the leaf up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MerkleStep {
+    pub sibling: [u8; 32],
+    /// Whether the sibling is hashed on the left of the running node.
+    pub sibling_is_left: bool,
+}
+
+/// Inclusion path of `commitments[index]` under [`merkle_root`], or `None` if
+/// `index` is out of range. Levels where the node is promoted add no step.
+pub fn merkle_path(commitments: &[[u8; 32]], mut index: usize) -> Option<Vec<MerkleStep>> {
+    if index >= commitments.len() {
+        return None;
+    }
+    let mut level: Vec<_> = commitments.iter().map(merkle_leaf).collect();
+    let mut path = Vec::new();
+    while level.len() > 1 {
+        let sibling = index ^ 1;
+        if let Some(node) = level.get(sibling) {
+            path.push(MerkleStep {
+                sibling: *node,
+                sibling_is_left: sibling < index,
+            });
+        }
+        level = next_level::<Sha256Hash>(&level);
+        index /= 2;
+    }
+    Some(path)
+}
+
+/// Check that `path` leads from `commitment` to `root`.
+pub fn verify_merkle_path(commitment: &[u8; 32], path: &[MerkleStep], root: &[u8; 32]) -> bool {
+    let node = path.iter().fold(merkle_leaf(commitment), |node, step| {
+        if step.sibling_is_left {
+            merkle_node(&step.sibling, &node)
+        } else {
+            merkle_node(&node, &step.sibling)
+        }
+    });
+    node == *root
+}
+
+/// [`Status::Ok`] and SHA256("epoch:" || previous || root || count as u64
+/// big-endian): the commitment to an epoch, chained to the previous one. The
+/// first epoch chains to all zeroes.
+pub fn epoch_commitment(previous: &[u8; 32], root: &[u8; 32], count: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(EPOCH_TAG);
+    hasher.update(previous);
+    hasher.update(root);
+    hasher.update(count.to_be_bytes());
+    public_values(Status::Ok, &hasher.finalize().into())
+}
+
+/// [`Status::Ok`] and SHA256("bridge:" || SHA256(agg_vk) || SHA256(baseline)
+/// || user_public_values): the commitment of the recursion bridge guest to "a proof verifying under
+/// `agg_vk` and `baseline` revealed `user_public_values`". `baseline` is the
+/// verifier's canonical baseline encoding.
+pub fn bridge_commitment(agg_vk: &[u8], baseline: &[u8], user_public_values: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(BRIDGE_TAG);
+    hasher.update(Sha256::digest(agg_vk));
+    hasher.update(Sha256::digest(baseline));
+    hasher.update(user_public_values);
+    public_values(Status::Ok, &hasher.finalize().into())
+}
+
+/// Commitment revealed by a self-test: [`Status::Ok`] and
+/// SHA256([`SELF_TEST_TAG`] || each case's public values, in order).
+pub fn self_test_commitment(public_values: &[[u8; 32]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(SELF_TEST_TAG);
+    for values in public_values {
+        hasher.update(values);
+    }
+    crate::public_values(Status::Ok, &hasher.finalize().into())
+}
+
+/// Input of a `BuildInfo` query for chunk `index`.
+pub fn encode_build_info_query(index: u32) -> [u8; 4] {
+    index.to_be_bytes()
+}
+
+pub fn decode_build_info_query(bytes: &[u8]) -> Option<u32> {
+    bytes.try_into().ok().map(u32::from_be_bytes)
+}
+
+/// Bytes of build info revealed per [`build_info_chunk`].
+pub const BUILD_INFO_CHUNK_LEN: usize = 32 - STATUS_LEN;
+
+/// [`Status::Ok`] and chunk `index` of the guest's build info, zero-padded:
+/// public values are 32 bytes, so hosts query chunks until one holds a zero
+/// byte after the status word. Build info is text of `key=value` pairs
+/// separated by `;`, e.g. `evaluator=uplc-turbo;openvm-guest=0.1.0`.
+pub fn build_info_chunk(info: &[u8], index: u32) -> [u8; 32] {
+    let mut chunk = [0u8; BUILD_INFO_CHUNK_LEN];
+    let start = (index as usize)
+        .saturating_mul(BUILD_INFO_CHUNK_LEN)
+        .min(info.len());
+    let bytes = &info[start..info.len().min(start + BUILD_INFO_CHUNK_LEN)];
+    chunk[..bytes.len()].copy_from_slice(bytes);
+    let mut values = [0; 32];
+    values[..STATUS_LEN].copy_from_slice(&Status::Ok.word());
+    values[STATUS_LEN..].copy_from_slice(&chunk);
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn builder_applies_named_arguments_in_order() {
+        let input = GuestInput::builder(&[1, 2])
+            .script_context(&[3])
+            .datum(&[1])
+            .redeemer(&[2])
+            .prove_failures(true)
+            .build();
+        assert_eq!(
+            input,
+            GuestInput::program_with_args(&[1, 2], &[vec![1], vec![2], vec![3]], true)
+        );
+        assert_eq!(GuestInput::decode(&input.encode()), Some(input));
+
+        let v3 = GuestInput::builder(&[1, 2]).script_context(&[3]).build();
+        assert_eq!(v3.args, [vec![3]]);
+        assert_eq!(v3.mode, ExecutionMode::Strict);
+    }
+
+    #[test]
+    fn decodes_inputs_without_arguments_of_version_1() {
+        // `(con integer 42)` with prove failures, as encoded at version 1.
+        let v1 = [1, 0, 0, 0, 6, 1, 0, 0, 0x48, 0x15, 1, 0, 0, 0, 1, 1];
+        let input = GuestInput::decode(&v1).expect("version 1 input");
+        assert_eq!(input, GuestInput::program(&v1[5..11], true));
+        assert_eq!(input.encode()[0], INPUT_VERSION);
+
+        // Version 1 had no arguments item.
+        let mut with_args = v1.to_vec();
+        with_args.extend_from_slice(&[0, 0, 0, 4, 0, 0, 0, 0]);
+        assert_eq!(GuestInput::decode(&with_args), None);
+    }
+}
@@ -0,0 +1,323 @@
+//! The claim a program commitment attests, as a structured statement:
+//! "program P applied to args A evaluated to R under cost model C within
+//! budget B".
+//!
+//! Guests commit to SHA256 of the statement's canonical [`Statement::encode`]
+//! form, after the [`Status`] of its outcome, rather than to the program and
+//! result concatenated, so every part of the claim is delimited and new parts
+//! cannot be confused with a result.
+//! Verifiers rebuild the statement from what they were told, or
+//! [`Statement::decode`] it from a bundle, and show it with its `Display`.
+
+use crate::hash::{CommitmentHash, Sha256Hash};
+use crate::{ErrorKind, STATEMENT_TAG, Status};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Cost model the program was evaluated under.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CostModel {
+    /// The evaluator's built-in cost model.
+    #[default]
+    Default,
+    /// SHA256 of the cost model parameters.
+    Hash([u8; 32]),
+}
+
+/// Execution budget the program was evaluated within.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Budget {
+    /// The evaluator's default budget.
+    #[default]
+    Default,
+    Limit {
+        cpu: u64,
+        mem: u64,
+    },
+}
+
+/// What evaluation produced.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The rendered result constant, e.g. `Integer(42)`.
+    Result(String),
+    Failure(ErrorKind),
+}
+
+/// A claim about one evaluation. The guests evaluate under the default cost
+/// model and budget, see [`Statement::success`] and [`Statement::failure`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Statement {
+    /// SHA256 of the flat-encoded program.
+    pub program: [u8; 32],
+    /// [`crate::argument_hash`] of each argument the program is applied to,
+    /// in order.
+    pub args: Vec<[u8; 32]>,
+    pub cost_model: CostModel,
+    pub budget: Budget,
+    pub outcome: Outcome,
+}
+
+impl Statement {
+    /// The program with SHA256 `program` evaluated to `result`.
+    pub fn success(program: [u8; 32], result: &str) -> Self {
+        Self::new(program, Outcome::Result(result.into()))
+    }
+
+    /// The program with SHA256 `program` failed with `kind`.
+    pub fn failure(program: [u8; 32], kind: ErrorKind) -> Self {
+        Self::new(program, Outcome::Failure(kind))
+    }
+
+    /// The same claim about the program applied to the arguments with
+    /// hashes `args`.
+    pub fn with_args(mut self, args: Vec<[u8; 32]>) -> Self {
+        self.args = args;
+        self
+    }
+
+    fn new(program: [u8; 32], outcome: Outcome) -> Self {
+        Self {
+            program,
+            args: Vec::new(),
+            cost_model: CostModel::Default,
+            budget: Budget::Default,
+            outcome,
+        }
+    }
+
+    /// Canonical encoding: [`STATEMENT_TAG`], the program hash, the argument
+    /// count and each argument hash, the cost model, the budget, then the
+    /// outcome. Counts and lengths are u32 and budgets u64, big-endian; each
+    /// variant is introduced by a tag byte, 0 for the default.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(STATEMENT_TAG);
+        bytes.extend_from_slice(&self.program);
+        bytes.extend_from_slice(&(self.args.len() as u32).to_be_bytes());
+        for arg in &self.args {
+            bytes.extend_from_slice(arg);
+        }
+        match self.cost_model {
+            CostModel::Default => bytes.push(0),
+            CostModel::Hash(hash) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&hash);
+            }
+        }
+        match self.budget {
+            Budget::Default => bytes.push(0),
+            Budget::Limit { cpu, mem } => {
+                bytes.push(1);
+                bytes.extend_from_slice(&cpu.to_be_bytes());
+                bytes.extend_from_slice(&mem.to_be_bytes());
+            }
+        }
+        match &self.outcome {
+            Outcome::Result(result) => {
+                bytes.push(0);
+                put_bytes(&mut bytes, result.as_bytes());
+            }
+            Outcome::Failure(kind) => {
+                bytes.push(1);
+                put_bytes(&mut bytes, kind.label().as_bytes());
+            }
+        }
+        bytes
+    }
+
+    /// Decode [`Statement::encode`] output. `None` unless `bytes` is exactly
+    /// one canonically encoded statement.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader(bytes.strip_prefix(STATEMENT_TAG)?);
+        let program = *reader.array::<32>()?;
+        let count = u32::from_be_bytes(*reader.array()?);
+        let args = (0..count)
+            .map(|_| reader.array::<32>().copied())
+            .collect::<Option<Vec<_>>>()?;
+        let cost_model = match reader.tag()? {
+            0 => CostModel::Default,
+            1 => CostModel::Hash(*reader.array()?),
+            _ => return None,
+        };
+        let budget = match reader.tag()? {
+            0 => Budget::Default,
+            1 => Budget::Limit {
+                cpu: u64::from_be_bytes(*reader.array()?),
+                mem: u64::from_be_bytes(*reader.array()?),
+            },
+            _ => return None,
+        };
+        let outcome = match reader.tag()? {
+            0 => Outcome::Result(String::from(core::str::from_utf8(reader.bytes()?).ok()?)),
+            1 => Outcome::Failure(ErrorKind::from_label(
+                core::str::from_utf8(reader.bytes()?).ok()?,
+            )?),
+            _ => return None,
+        };
+        reader.0.is_empty().then_some(Self {
+            program,
+            args,
+            cost_model,
+            budget,
+            outcome,
+        })
+    }
+
+    /// The status word of the outcome.
+    pub fn status(&self) -> Status {
+        match self.outcome {
+            Outcome::Result(_) => Status::Ok,
+            Outcome::Failure(kind) => Status::of_failure(kind),
+        }
+    }
+
+    /// The public values a guest reveals for it: its [`Statement::status`]
+    /// and the SHA256 of the encoding, see [`crate::public_values`].
+    pub fn commitment(&self) -> [u8; 32] {
+        self.commitment_with::<Sha256Hash>()
+    }
+
+    /// [`Statement::commitment`], hashing the encoding with `H` instead.
+    pub fn commitment_with<H: CommitmentHash>(&self) -> [u8; 32] {
+        crate::public_values(self.status(), &H::digest(&self.encode()))
+    }
+}
+
+fn put_bytes(bytes: &mut Vec<u8>, value: &[u8]) {
+    bytes.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(value);
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn array<const N: usize>(&mut self) -> Option<&'a [u8; N]> {
+        let (value, rest) = self.0.split_first_chunk::<N>()?;
+        self.0 = rest;
+        Some(value)
+    }
+
+    fn tag(&mut self) -> Option<u8> {
+        self.array::<1>().map(|[tag]| *tag)
+    }
+
+    fn bytes(&mut self) -> Option<&'a [u8]> {
+        let len = u32::from_be_bytes(*self.array()?) as usize;
+        let (value, rest) = self.0.split_at_checked(len)?;
+        self.0 = rest;
+        Some(value)
+    }
+}
+
+fn write_hex(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    bytes.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+}
+
+/// E.g. `program 1a2b… with no args evaluated to Integer(42) under the
+/// default cost model within the default budget`.
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("program ")?;
+        write_hex(f, &self.program)?;
+        match self.args.as_slice() {
+            [] => f.write_str(" with no args")?,
+            args => {
+                f.write_str(" applied to ")?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write_hex(f, arg)?;
+                }
+            }
+        }
+        match &self.outcome {
+            Outcome::Result(result) => write!(f, " evaluated to {}", result)?,
+            Outcome::Failure(kind) => write!(f, " failed with {}", kind.label())?,
+        }
+        match self.cost_model {
+            CostModel::Default => f.write_str(" under the default cost model")?,
+            CostModel::Hash(hash) => {
+                f.write_str(" under cost model ")?;
+                write_hex(f, &hash)?;
+            }
+        }
+        match self.budget {
+            Budget::Default => f.write_str(" within the default budget"),
+            Budget::Limit { cpu, mem } => write!(f, " within cpu {} mem {}", cpu, mem),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+    use sha2::{Digest, Sha256};
+
+    #[test]
+    fn round_trips_every_part() {
+        let statements = [
+            Statement::success([1; 32], "Integer(42)"),
+            Statement::failure([2; 32], ErrorKind::BudgetExceeded),
+            Statement {
+                program: [3; 32],
+                args: vec![[5; 32], [6; 32]],
+                cost_model: CostModel::Hash([4; 32]),
+                budget: Budget::Limit { cpu: 10, mem: 20 },
+                outcome: Outcome::Result("Bool(true)".into()),
+            },
+        ];
+        for statement in statements {
+            assert_eq!(Statement::decode(&statement.encode()), Some(statement));
+        }
+    }
+
+    #[test]
+    fn rejects_trailing_and_truncated_bytes() {
+        let bytes = Statement::success([1; 32], "Integer(42)").encode();
+        assert_eq!(Statement::decode(&[bytes.as_slice(), &[0]].concat()), None);
+        assert_eq!(Statement::decode(&bytes[..bytes.len() - 1]), None);
+    }
+
+    #[test]
+    fn commitments_open_with_the_outcome_status() {
+        let statements = [
+            (Statement::success([1; 32], "Integer(42)"), Status::Ok),
+            (
+                Statement::failure([1; 32], ErrorKind::NonConstantResult),
+                Status::EvalError,
+            ),
+            (
+                Statement::failure([1; 32], ErrorKind::Decode),
+                Status::DecodeError,
+            ),
+        ];
+        for (statement, status) in statements {
+            let commitment = statement.commitment();
+            assert_eq!(Status::from_public_values(&commitment), Some(status));
+            assert_eq!(
+                commitment[crate::STATUS_LEN..],
+                Sha256::digest(statement.encode())[..32 - crate::STATUS_LEN]
+            );
+        }
+        assert_eq!(Status::from_public_values(&[0, 0, 0, 9]), None);
+        assert_eq!(Status::from_public_values(&[0, 0]), None);
+    }
+
+    #[test]
+    fn displays_the_claim() {
+        let statement = Statement::failure([0xab; 32], ErrorKind::Evaluation);
+        assert_eq!(
+            statement.to_string(),
+            alloc::format!(
+                "program {} with no args failed with evaluation under the default cost \
+                 model within the default budget",
+                "ab".repeat(32)
+            )
+        );
+    }
+}
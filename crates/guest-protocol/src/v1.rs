@@ -0,0 +1,60 @@
+//! Commitments of guests at [`COMMITMENT_VERSION`](crate::COMMITMENT_VERSION)
+//! 1, so proofs made by those guests can still be checked.
+//!
+//! Version 1 public values are a bare 32-byte SHA256 digest, without a
+//! [`Status`](crate::Status) word or a [`Statement`](crate::Statement): a
+//! run commits to its program bytes followed by its rendered result, or by
+//! `"error:"` and the failure kind. Epochs and self-tests commit as today,
+//! without the status word.
+
+use sha2::{Digest, Sha256};
+
+use crate::{EPOCH_TAG, ErrorKind, SELF_TEST_TAG};
+
+/// Prefix separating failure commitments from result commitments.
+pub const FAILURE_TAG: &[u8] = b"error:";
+
+/// SHA256(program_bytes || result): the commitment to a successful run.
+pub fn success_commitment(program_bytes: &[u8], result: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(program_bytes);
+    hasher.update(result.as_bytes());
+    hasher.finalize().into()
+}
+
+/// SHA256(program_bytes || "error:" || kind): the commitment to a failed run.
+pub fn failure_commitment(program_bytes: &[u8], kind: ErrorKind) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(program_bytes);
+    hasher.update(FAILURE_TAG);
+    hasher.update(kind.label().as_bytes());
+    hasher.finalize().into()
+}
+
+/// Recover the failure kind a guest committed to, if `commitment` is a
+/// version 1 failure commitment for `program_bytes`.
+pub fn committed_failure(program_bytes: &[u8], commitment: &[u8]) -> Option<ErrorKind> {
+    ErrorKind::ALL
+        .into_iter()
+        .find(|kind| failure_commitment(program_bytes, *kind).as_slice() == commitment)
+}
+
+/// SHA256("epoch:" || previous || root || count as u64 big-endian).
+pub fn epoch_commitment(previous: &[u8; 32], root: &[u8; 32], count: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(EPOCH_TAG);
+    hasher.update(previous);
+    hasher.update(root);
+    hasher.update(count.to_be_bytes());
+    hasher.finalize().into()
+}
+
+/// SHA256("self-test:" || each case's version 1 public values, in order).
+pub fn self_test_commitment(public_values: &[[u8; 32]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(SELF_TEST_TAG);
+    for values in public_values {
+        hasher.update(values);
+    }
+    hasher.finalize().into()
+}
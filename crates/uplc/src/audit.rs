@@ -0,0 +1,140 @@
+//! Determinism audit for the evaluators, enabled by the `determinism-audit`
+//! feature.
+//!
+//! Guest commitments hash the formatted evaluation result, so any run-to-run
+//! variation (e.g. iteration order of a hash-based container while building or
+//! printing a value) makes them irreproducible. This crate keeps no hash-based
+//! containers of its own; the ones that matter live inside the evaluator
+//! backends, so the audit evaluates every program twice and asserts that both
+//! runs agree.
+
+use crate::UplcEvaluator;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use anyhow::Result;
+use core::fmt::Display;
+
+/// Evaluator wrapper that panics when two evaluations of the same program
+/// disagree on their result or error.
+pub struct DeterminismAudit {
+    inner: Box<dyn UplcEvaluator>,
+}
+
+impl DeterminismAudit {
+    pub fn new(inner: Box<dyn UplcEvaluator>) -> Self {
+        Self { inner }
+    }
+}
+
+/// Render an evaluation outcome the way it ends up in the commitment.
+fn render(outcome: &Result<Box<dyn Display>>) -> Result<String, String> {
+    match outcome {
+        Ok(value) => Ok(value.to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+impl UplcEvaluator for DeterminismAudit {
+    fn evaluate_program_bytes_with_args(
+        &self,
+        program_bytes: &[u8],
+        args: &[Vec<u8>],
+    ) -> Result<Box<dyn Display>> {
+        let first = self
+            .inner
+            .evaluate_program_bytes_with_args(program_bytes, args);
+        let second = self
+            .inner
+            .evaluate_program_bytes_with_args(program_bytes, args);
+        assert_eq!(
+            render(&first),
+            render(&second),
+            "{} evaluated the same program differently; commitments would not be reproducible",
+            self.inner.version()
+        );
+        first
+    }
+
+    fn version(&self) -> &str {
+        self.inner.version()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    /// `(con integer 42)`
+    const ANSWER: [u8; 6] = [0x01, 0x00, 0x00, 0x48, 0x15, 0x01];
+    /// `(error)`
+    const ERROR: [u8; 4] = [0x01, 0x00, 0x00, 0x61];
+
+    /// Evaluates every program to the number of earlier evaluations.
+    #[derive(Default)]
+    struct Counting {
+        calls: Cell<u32>,
+    }
+
+    impl UplcEvaluator for Counting {
+        fn evaluate_program_bytes_with_args(
+            &self,
+            _program_bytes: &[u8],
+            _args: &[Vec<u8>],
+        ) -> Result<Box<dyn Display>> {
+            let calls = self.calls.get();
+            self.calls.set(calls + 1);
+            Ok(Box::new(calls))
+        }
+
+        fn version(&self) -> &str {
+            "counting"
+        }
+    }
+
+    /// Fails every evaluation the same way.
+    struct Failing;
+
+    impl UplcEvaluator for Failing {
+        fn evaluate_program_bytes_with_args(
+            &self,
+            _program_bytes: &[u8],
+            _args: &[Vec<u8>],
+        ) -> Result<Box<dyn Display>> {
+            Err(crate::UplcError::EvaluationError("always".to_string()).into())
+        }
+
+        fn version(&self) -> &str {
+            "failing"
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "counting evaluated the same program differently")]
+    fn panics_when_runs_disagree() {
+        let audit = DeterminismAudit::new(Box::new(Counting::default()));
+        let _ = audit.evaluate_program_bytes(&ANSWER);
+    }
+
+    #[test]
+    fn returns_agreeing_failures() {
+        let audit = DeterminismAudit::new(Box::new(Failing));
+        let outcome = render(&audit.evaluate_program_bytes(&ANSWER));
+        assert_eq!(outcome, Err("Evaluation error: always".to_string()));
+        assert_eq!(audit.version(), "failing");
+    }
+
+    #[test]
+    fn audits_the_default_evaluator() {
+        let evaluator = crate::get_evaluator();
+        let answer = render(&evaluator.evaluate_program_bytes(&ANSWER));
+        assert!(answer.is_ok(), "{:?}", answer);
+        for program in [&ANSWER[..], &ERROR[..]] {
+            assert_eq!(
+                render(&evaluator.evaluate_program_bytes(program)),
+                render(&crate::backend_evaluator().evaluate_program_bytes(program))
+            );
+        }
+    }
+}
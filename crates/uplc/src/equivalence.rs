@@ -0,0 +1,233 @@
+//! Structural equality of programs.
+//!
+//! Two encodings of the same script can differ in bytes: on-chain and
+//! blueprint copies are wrapped in one or two CBOR bytestrings, while the
+//! playground compiles to raw flat, and flat padding is not canonical. Both
+//! programs are unwrapped and decoded to De Bruijn form, which erases binder
+//! names, then compared term by term.
+
+use crate::UplcError;
+use crate::limits::DecodeLimits;
+use alloc::format;
+use alloc::vec;
+
+#[cfg(all(feature = "uplc-turbo", not(feature = "uplc-turbo-riscv")))]
+use uplc_turbo;
+#[cfg(feature = "uplc-turbo-riscv")]
+use uplc_turbo_riscv as uplc_turbo;
+
+use uplc_turbo::{arena::Arena, binder::DeBruijn, flat, program::Program, term::Term};
+
+/// Strip CBOR bytestring wrappers (definite length only) around a flat
+/// program. Flat programs start with their major version, which never reads
+/// as a complete CBOR bytestring, so raw flat is returned unchanged.
+pub fn unwrap_cbor(mut bytes: &[u8]) -> &[u8] {
+    loop {
+        let (header, len) = match bytes.first().copied() {
+            Some(byte @ 0x40..=0x57) => (1, usize::from(byte - 0x40)),
+            Some(0x58) if bytes.len() >= 2 => (2, usize::from(bytes[1])),
+            Some(0x59) if bytes.len() >= 3 => {
+                (3, usize::from(u16::from_be_bytes([bytes[1], bytes[2]])))
+            }
+            Some(0x5a) if bytes.len() >= 5 => (
+                5,
+                u32::from_be_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]) as usize,
+            ),
+            _ => return bytes,
+        };
+        if bytes.len() - header != len {
+            return bytes;
+        }
+        bytes = &bytes[header..];
+    }
+}
+
+fn decode<'a>(
+    arena: &'a Arena,
+    bytes: &[u8],
+    limits: &DecodeLimits,
+) -> Result<&'a Program<'a, DeBruijn>, UplcError> {
+    let flat_bytes = unwrap_cbor(bytes);
+    limits.check_flat(flat_bytes)?;
+    let program: &Program<DeBruijn> = flat::decode(arena, flat_bytes)
+        .map_err(|e| UplcError::DecodeError(format!("Program decode error: {:?}", e)))?;
+    Ok(program)
+}
+
+/// Whether two hex-encoded programs, raw flat or CBOR-wrapped, are the same
+/// program modulo binder names and encoding.
+pub fn programs_equal(program_a_hex: &str, program_b_hex: &str) -> Result<bool, UplcError> {
+    programs_equal_bytes(
+        &crate::impls::decode_hex(program_a_hex)?,
+        &crate::impls::decode_hex(program_b_hex)?,
+    )
+}
+
+/// [`programs_equal`] on raw bytes.
+pub fn programs_equal_bytes(program_a: &[u8], program_b: &[u8]) -> Result<bool, UplcError> {
+    let limits = DecodeLimits::DEFAULT;
+    let arena = Arena::new();
+    let a = decode(&arena, program_a, &limits)?;
+    let b = decode(&arena, program_b, &limits)?;
+    if a.version != b.version {
+        return Ok(false);
+    }
+
+    // Explicit stack, as the programs may nest up to `max_depth`.
+    let mut pending = vec![(a.term, b.term)];
+    while let Some((a, b)) = pending.pop() {
+        match (a, b) {
+            (Term::Var(a), Term::Var(b)) => {
+                if a != b {
+                    return Ok(false);
+                }
+            }
+            // De Bruijn binders carry no name worth comparing.
+            (Term::Lambda { body: a, .. }, Term::Lambda { body: b, .. }) => {
+                pending.push((*a, *b));
+            }
+            (
+                Term::Apply {
+                    function: fa,
+                    argument: aa,
+                },
+                Term::Apply {
+                    function: fb,
+                    argument: ab,
+                },
+            ) => {
+                pending.push((*fa, *fb));
+                pending.push((*aa, *ab));
+            }
+            (Term::Delay(a), Term::Delay(b)) | (Term::Force(a), Term::Force(b)) => {
+                pending.push((*a, *b));
+            }
+            (
+                Term::Case {
+                    constr: ca,
+                    branches: ba,
+                },
+                Term::Case {
+                    constr: cb,
+                    branches: bb,
+                },
+            ) => {
+                if ba.len() != bb.len() {
+                    return Ok(false);
+                }
+                pending.push((*ca, *cb));
+                pending.extend(ba.iter().zip(bb.iter()).map(|(a, b)| (*a, *b)));
+            }
+            (
+                Term::Constr {
+                    tag: ta,
+                    fields: fa,
+                },
+                Term::Constr {
+                    tag: tb,
+                    fields: fb,
+                },
+            ) => {
+                if ta != tb || fa.len() != fb.len() {
+                    return Ok(false);
+                }
+                pending.extend(fa.iter().zip(fb.iter()).map(|(a, b)| (*a, *b)));
+            }
+            (Term::Constant(a), Term::Constant(b)) => {
+                if a != b {
+                    return Ok(false);
+                }
+            }
+            (Term::Builtin(a), Term::Builtin(b)) => {
+                if a != b {
+                    return Ok(false);
+                }
+            }
+            (Term::Error, Term::Error) => {}
+            _ => return Ok(false),
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    /// `(con integer 42)`
+    const ANSWER: [u8; 6] = [0x01, 0x00, 0x00, 0x48, 0x15, 0x01];
+
+    /// `bytes` in a definite-length CBOR bytestring.
+    fn wrap(bytes: &[u8]) -> Vec<u8> {
+        let mut wrapped = match bytes.len() {
+            len @ 0..=23 => vec![0x40 + len as u8],
+            len => vec![0x58, len as u8],
+        };
+        wrapped.extend_from_slice(bytes);
+        wrapped
+    }
+
+    #[test]
+    fn unwraps_cbor_bytestrings() {
+        assert_eq!(unwrap_cbor(&ANSWER), ANSWER);
+        assert_eq!(unwrap_cbor(&wrap(&ANSWER)), ANSWER);
+        assert_eq!(unwrap_cbor(&wrap(&wrap(&ANSWER))), ANSWER);
+        // A length that does not cover the rest is not a wrapper.
+        let mut truncated = wrap(&ANSWER);
+        truncated.pop();
+        assert_eq!(unwrap_cbor(&truncated), truncated);
+    }
+
+    #[test]
+    fn equates_encodings_of_the_same_program() {
+        let once = wrap(&ANSWER);
+        let twice = wrap(&once);
+        for (a, b) in [
+            (&ANSWER[..], &once[..]),
+            (&ANSWER[..], &twice[..]),
+            (&once[..], &twice[..]),
+        ] {
+            assert!(programs_equal_bytes(a, b).unwrap());
+            assert!(programs_equal_bytes(b, a).unwrap());
+        }
+        assert!(programs_equal(&hex::encode(ANSWER), &hex::encode(&twice)).unwrap());
+
+        // The same term, its filler a byte longer.
+        let padded = [0x01, 0x00, 0x00, 0x48, 0x15, 0x00, 0x01];
+        assert!(programs_equal_bytes(&ANSWER, &padded).unwrap());
+    }
+
+    #[test]
+    fn tells_different_programs_apart() {
+        // `(con integer 43)`
+        let other_constant = [0x01, 0x00, 0x00, 0x48, 0x15, 0x81];
+        assert!(!programs_equal_bytes(&ANSWER, &other_constant).unwrap());
+
+        // `[(builtin addInteger) (con integer 40) (con integer 2)]`
+        let add = [0x01, 0x00, 0x00, 0x33, 0x70, 0x09, 0x02, 0x82, 0x40, 0x09];
+        // `[(builtin subtractInteger) (con integer 40) (con integer 2)]`
+        let subtract = [0x01, 0x00, 0x00, 0x33, 0x70, 0x29, 0x02, 0x82, 0x40, 0x09];
+        assert!(programs_equal_bytes(&add, &add).unwrap());
+        assert!(!programs_equal_bytes(&add, &subtract).unwrap());
+
+        // `(case (constr 0) (con integer 0))`, version 1.1.0
+        let one_branch = [0x01, 0x01, 0x00, 0x98, 0x00, 0x52, 0x00, 0x01];
+        // `(case (constr 0) (con integer 0) (con integer 1))`
+        let two_branches = [
+            0x01, 0x01, 0x00, 0x98, 0x00, 0x52, 0x00, 0x0a, 0x40, 0x04, 0x01,
+        ];
+        assert!(programs_equal_bytes(&one_branch, &one_branch).unwrap());
+        assert!(!programs_equal_bytes(&one_branch, &two_branches).unwrap());
+    }
+
+    #[test]
+    fn rejects_undecodable_programs() {
+        let truncated = [0x01, 0x00, 0x00, 0x33];
+        assert!(matches!(
+            programs_equal_bytes(&ANSWER, &truncated),
+            Err(UplcError::DecodeError(_))
+        ));
+        assert!(programs_equal("not hex", &hex::encode(ANSWER)).is_err());
+    }
+}
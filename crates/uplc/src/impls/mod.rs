@@ -11,12 +11,20 @@ pub use uplc_turbo::UplcTurboEvaluator;
 pub use self::uplc_aiken::UplcAikenEvaluator;
 
 use crate::UplcError;
+use crate::limits::DecodeLimits;
 use alloc::format;
 use alloc::string::String;
 use alloc::vec::Vec;
 
-/// Decode a hex-encoded UPLC program into raw bytes.
-pub fn decode_program_hex(program_hex: &str) -> Result<Vec<u8>, UplcError> {
+/// Decode a hex-encoded UPLC program into raw bytes, rejecting programs
+/// larger than `limits` allow.
+pub fn decode_program_hex(program_hex: &str, limits: &DecodeLimits) -> Result<Vec<u8>, UplcError> {
+    let program_bytes = decode_hex(program_hex)?;
+    limits.check_program_size(&program_bytes)?;
+    Ok(program_bytes)
+}
+
+pub(crate) fn decode_hex(program_hex: &str) -> Result<Vec<u8>, UplcError> {
     hex::decode(program_hex.trim())
         .map_err(|e| UplcError::DecodeError(format!("Hex decode error: {}", e)))
 }
@@ -1,5 +1,7 @@
 #[cfg(feature = "uplc-aiken")]
-use super::{decode_program_hex, make_result};
+use super::make_result;
+#[cfg(feature = "uplc-aiken")]
+use crate::limits::DecodeLimits;
 #[cfg(feature = "uplc-aiken")]
 use crate::{UplcError, UplcEvaluator};
 #[cfg(feature = "uplc-aiken")]
@@ -7,36 +9,60 @@ use alloc::boxed::Box;
 #[cfg(feature = "uplc-aiken")]
 use alloc::format;
 #[cfg(feature = "uplc-aiken")]
+use alloc::vec::Vec;
+#[cfg(feature = "uplc-aiken")]
 use uplc_aiken::ast::{DeBruijn, Program, Term};
 #[cfg(feature = "uplc-aiken")]
+use uplc_aiken::machine::Error as MachineError;
+#[cfg(feature = "uplc-aiken")]
 use uplc_aiken::machine::cost_model::ExBudget;
 
 #[cfg(feature = "uplc-aiken")]
 #[derive(Default)]
-pub struct UplcAikenEvaluator;
+pub struct UplcAikenEvaluator {
+    limits: DecodeLimits,
+}
 
 #[cfg(feature = "uplc-aiken")]
 impl UplcAikenEvaluator {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Evaluator enforcing `limits` instead of [`DecodeLimits::DEFAULT`].
+    pub fn with_limits(limits: DecodeLimits) -> Self {
+        Self { limits }
     }
 }
 
 #[cfg(feature = "uplc-aiken")]
 impl UplcEvaluator for UplcAikenEvaluator {
-    fn evaluate_program(&self, program_hex: &str) -> anyhow::Result<Box<dyn core::fmt::Display>> {
-        let program_bytes = decode_program_hex(program_hex)?;
+    fn evaluate_program_bytes_with_args(
+        &self,
+        program_bytes: &[u8],
+        args: &[Vec<u8>],
+    ) -> anyhow::Result<Box<dyn core::fmt::Display>> {
+        self.limits.check_flat(program_bytes)?;
+        for arg in args {
+            self.limits.check_data(arg)?;
+        }
 
-        let program = Program::<DeBruijn>::from_flat(&program_bytes)
+        let mut program = Program::<DeBruijn>::from_flat(program_bytes)
             .map_err(|e| UplcError::DecodeError(format!("Program decode error: {:?}", e)))?;
+        for arg in args {
+            let data = uplc_aiken::plutus_data(arg)
+                .map_err(|e| UplcError::DecodeError(format!("Argument decode error: {:?}", e)))?;
+            program = program.apply_data(data);
+        }
 
         let budget = ExBudget::default();
         let eval_result = program.eval(budget);
         let cost = eval_result.cost();
 
-        let result_term = eval_result
-            .result()
-            .map_err(|e| UplcError::EvaluationError(format!("Evaluation error: {:?}", e)))?;
+        let result_term = eval_result.result().map_err(|e| match &e {
+            MachineError::OutOfExError(_) => UplcError::BudgetExceeded(format!("{:?}", e)),
+            _ => UplcError::EvaluationError(format!("Evaluation error: {:?}", e)),
+        })?;
 
         let result_constant = match &result_term {
             Term::Constant(c) => c,
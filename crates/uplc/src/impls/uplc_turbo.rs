@@ -1,37 +1,62 @@
-use super::{decode_program_hex, make_result};
+use super::make_result;
+use crate::limits::DecodeLimits;
 use crate::{UplcError, UplcEvaluator};
 use alloc::boxed::Box;
 use alloc::format;
+use alloc::vec::Vec;
 
 #[cfg(all(feature = "uplc-turbo", not(feature = "uplc-turbo-riscv")))]
 use uplc_turbo;
 #[cfg(feature = "uplc-turbo-riscv")]
 use uplc_turbo_riscv as uplc_turbo;
 
-use uplc_turbo::{arena::Arena, binder::DeBruijn, flat};
+use uplc_turbo::{
+    arena::Arena, binder::DeBruijn, data::PlutusData, flat, machine::MachineError, term::Term,
+};
 #[derive(Default)]
-pub struct UplcTurboEvaluator;
+pub struct UplcTurboEvaluator {
+    limits: DecodeLimits,
+}
 
 impl UplcTurboEvaluator {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Evaluator enforcing `limits` instead of [`DecodeLimits::DEFAULT`].
+    pub fn with_limits(limits: DecodeLimits) -> Self {
+        Self { limits }
     }
 }
 
 impl UplcEvaluator for UplcTurboEvaluator {
-    fn evaluate_program(&self, program_hex: &str) -> anyhow::Result<Box<dyn core::fmt::Display>> {
-        let program_bytes = decode_program_hex(program_hex)?;
+    fn evaluate_program_bytes_with_args(
+        &self,
+        program_bytes: &[u8],
+        args: &[Vec<u8>],
+    ) -> anyhow::Result<Box<dyn core::fmt::Display>> {
+        self.limits.check_flat(program_bytes)?;
+        for arg in args {
+            self.limits.check_data(arg)?;
+        }
 
         let arena = Arena::new();
 
-        let program: &uplc_turbo::program::Program<DeBruijn> = flat::decode(&arena, &program_bytes)
-            .map_err(|e| UplcError::DecodeError(format!("Program decode error: {:?}", e)))?;
+        let mut program: &uplc_turbo::program::Program<DeBruijn> =
+            flat::decode(&arena, program_bytes)
+                .map_err(|e| UplcError::DecodeError(format!("Program decode error: {:?}", e)))?;
+        for arg in args {
+            let data = PlutusData::from_cbor(&arena, arg)
+                .map_err(|e| UplcError::DecodeError(format!("Argument decode error: {:?}", e)))?;
+            program = program.apply(&arena, Term::data(&arena, data));
+        }
 
         let eval_result = program.eval(&arena);
 
-        let result_term = eval_result
-            .term
-            .map_err(|e| UplcError::EvaluationError(format!("Evaluation error: {:?}", e)))?;
+        let result_term = eval_result.term.map_err(|e| match &e {
+            MachineError::OutOfExError(_) => UplcError::BudgetExceeded(format!("{:?}", e)),
+            _ => UplcError::EvaluationError(format!("Evaluation error: {:?}", e)),
+        })?;
 
         let result_constant = match &result_term {
             uplc_turbo::term::Term::Constant(c) => c,
@@ -2,18 +2,46 @@
 
 extern crate alloc;
 
+#[cfg(feature = "determinism-audit")]
+pub mod audit;
+#[cfg(any(feature = "uplc-turbo", feature = "uplc-turbo-riscv"))]
+pub mod equivalence;
 pub mod impls;
+pub mod limits;
 
 use alloc::boxed::Box;
 use alloc::string::String;
+use alloc::vec::Vec;
 use anyhow::Result;
 use core::fmt::Display;
 
+/// Version of this crate, part of the guest's build info.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
 /// Abstraction for UPLC evaluation functionality
 pub trait UplcEvaluator {
-    /// Evaluate a UPLC program given as hex-encoded bytes
+    /// Evaluate a flat-encoded UPLC program applied to CBOR-encoded
+    /// `PlutusData` arguments, in order (e.g. datum, redeemer, script
+    /// context). Arguments that are not valid CBOR fail with
+    /// [`UplcError::DecodeError`].
     /// Returns the result as a displayable value
-    fn evaluate_program(&self, program_hex: &str) -> Result<Box<dyn Display>>;
+    fn evaluate_program_bytes_with_args(
+        &self,
+        program_bytes: &[u8],
+        args: &[Vec<u8>],
+    ) -> Result<Box<dyn Display>>;
+
+    /// Evaluate a flat-encoded UPLC program
+    /// Returns the result as a displayable value
+    fn evaluate_program_bytes(&self, program_bytes: &[u8]) -> Result<Box<dyn Display>> {
+        self.evaluate_program_bytes_with_args(program_bytes, &[])
+    }
+
+    /// Evaluate a UPLC program given as hex-encoded bytes
+    fn evaluate_program(&self, program_hex: &str) -> Result<Box<dyn Display>> {
+        let program_bytes = impls::decode_hex(program_hex)?;
+        self.evaluate_program_bytes(&program_bytes)
+    }
 
     fn version(&self) -> &str;
 }
@@ -25,6 +53,8 @@ pub enum UplcError {
     DecodeError(String),
     #[error("Evaluation error: {0}")]
     EvaluationError(String),
+    #[error("Budget exceeded: {0}")]
+    BudgetExceeded(String),
     #[error("Result conversion error: {0}")]
     ResultError(String),
 }
@@ -42,6 +72,25 @@ impl Display for EvaluationResult {
 }
 
 pub fn get_evaluator() -> Box<dyn UplcEvaluator> {
+    audited(backend_evaluator())
+}
+
+/// `evaluator`, checking every evaluation for run-to-run differences when
+/// the `determinism-audit` feature is on, for callers that pick their
+/// evaluators themselves, e.g. differential tests.
+pub fn audited(evaluator: Box<dyn UplcEvaluator>) -> Box<dyn UplcEvaluator> {
+    #[cfg(feature = "determinism-audit")]
+    {
+        Box::new(audit::DeterminismAudit::new(evaluator))
+    }
+
+    #[cfg(not(feature = "determinism-audit"))]
+    {
+        evaluator
+    }
+}
+
+fn backend_evaluator() -> Box<dyn UplcEvaluator> {
     #[cfg(any(feature = "uplc-turbo", feature = "uplc-turbo-riscv"))]
     {
         Box::new(impls::UplcTurboEvaluator::new())
@@ -0,0 +1,622 @@
+//! Structural limits on flat-encoded programs.
+//!
+//! The cost model only bounds evaluation, so a crafted flat encoding can
+//! allocate or recurse arbitrarily while it is being decoded. The decoders
+//! are third-party, recurse on nested terms, constants and `Data`, and have
+//! no limits. [`DecodeLimits::check_flat`] therefore scans the encoding
+//! before any decoder sees it: without allocating per term and with explicit
+//! stacks, it counts terms and measures the nesting depth of terms,
+//! constants and `Data`, and the size of every constant, nested values
+//! included.
+
+use crate::UplcError;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Limits enforced on every program before it is decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Maximum size of the flat-encoded program.
+    pub max_program_bytes: usize,
+    /// Maximum number of term nodes.
+    pub max_terms: usize,
+    /// Maximum nesting depth of terms, the root being at depth 1. Values
+    /// nested in a constant, and `Data` nested in its CBOR, count as deeper
+    /// levels.
+    pub max_depth: usize,
+    /// Maximum size of a single constant: the bytes of its integers,
+    /// bytestrings, strings and `Data`, plus one per value, nested values
+    /// included.
+    pub max_constant_bytes: usize,
+}
+
+impl DecodeLimits {
+    /// Limits applied by the evaluators, including the zkVM guest. Well
+    /// above the largest scripts seen on mainnet.
+    pub const DEFAULT: Self = Self {
+        max_program_bytes: 256 * 1024,
+        max_terms: 1_000_000,
+        max_depth: 10_000,
+        max_constant_bytes: 64 * 1024,
+    };
+
+    /// Check the flat encoding's size.
+    pub fn check_program_size(&self, program_bytes: &[u8]) -> Result<(), UplcError> {
+        if program_bytes.len() > self.max_program_bytes {
+            return Err(UplcError::DecodeError(format!(
+                "Program is {} bytes, limit is {}",
+                program_bytes.len(),
+                self.max_program_bytes
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check the flat encoding's size and shape; call before decoding it.
+    ///
+    /// Encodings too malformed to scan are rejected too, as the decoder
+    /// would reject them.
+    pub fn check_flat(&self, program_bytes: &[u8]) -> Result<(), UplcError> {
+        self.scan(program_bytes).map_err(Into::into)
+    }
+
+    /// [`DecodeLimits::check_flat`], but accepting encodings too malformed
+    /// to scan, for callers that report decode failures separately. The scan
+    /// reads the encoding in the decoder's order, so the decoder stops at
+    /// the same point, before exceeding a limit.
+    pub fn check_flat_limits(&self, program_bytes: &[u8]) -> Result<(), UplcError> {
+        match self.scan(program_bytes) {
+            Err(Rejection::Limit(error)) => Err(error),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check CBOR-encoded `PlutusData`, e.g. an argument, for nesting deeper
+    /// than [`DecodeLimits::max_depth`].
+    pub fn check_data(&self, cbor: &[u8]) -> Result<(), UplcError> {
+        cbor_depth(cbor)
+            .and_then(|depth| self.check_depth(depth))
+            .map_err(Into::into)
+    }
+
+    fn scan(&self, program_bytes: &[u8]) -> Result<(), Rejection> {
+        self.check_program_size(program_bytes)
+            .map_err(Rejection::Limit)?;
+        let mut bits = Bits::new(program_bytes);
+        for _ in 0..3 {
+            bits.natural()?;
+        }
+
+        let mut terms = 0;
+        let mut pending = vec![Pending::Term(1)];
+        while let Some(next) = pending.pop() {
+            let depth = match next {
+                Pending::Term(depth) => depth,
+                Pending::Terms(depth) => {
+                    if bits.bit()? {
+                        pending.push(Pending::Terms(depth));
+                        pending.push(Pending::Term(depth));
+                    }
+                    continue;
+                }
+            };
+            terms += 1;
+            if terms > self.max_terms {
+                return Err(Rejection::limit(format!(
+                    "Program has more than {} terms",
+                    self.max_terms
+                )));
+            }
+            self.check_depth(depth)?;
+
+            match bits.bits(4)? {
+                // Var
+                0 => {
+                    bits.natural()?;
+                }
+                // Delay, Lambda, Force
+                1 | 2 | 5 => pending.push(Pending::Term(depth + 1)),
+                // Apply: the function, then the argument
+                3 => {
+                    pending.push(Pending::Term(depth + 1));
+                    pending.push(Pending::Term(depth + 1));
+                }
+                4 => self.check_constant(&mut bits, depth)?,
+                // Error
+                6 => {}
+                // Builtin
+                7 => {
+                    bits.bits(7)?;
+                }
+                // Constr: the tag, then the fields
+                8 => {
+                    bits.natural()?;
+                    pending.push(Pending::Terms(depth + 1));
+                }
+                // Case: the scrutinee, then the branches
+                9 => {
+                    pending.push(Pending::Terms(depth + 1));
+                    pending.push(Pending::Term(depth + 1));
+                }
+                tag => {
+                    return Err(Rejection::malformed(format!("Unknown term tag {}", tag)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_depth(&self, depth: usize) -> Result<(), Rejection> {
+        if depth > self.max_depth {
+            return Err(Rejection::limit(format!(
+                "Program nests terms deeper than {}",
+                self.max_depth
+            )));
+        }
+        Ok(())
+    }
+
+    /// Scan the constant of a term at `depth`: its type, then its value.
+    fn check_constant(&self, bits: &mut Bits<'_>, depth: usize) -> Result<(), Rejection> {
+        let mut tags = Vec::new();
+        while bits.bit()? {
+            tags.push(bits.bits(4)?);
+        }
+        let (ends, type_depth) = parse_type(&tags)
+            .ok_or_else(|| Rejection::malformed(format!("Invalid constant type {:?}", tags)))?;
+        self.check_depth(depth + type_depth - 1)?;
+
+        let mut size = 0;
+        let mut values = vec![Value::One(0)];
+        while let Some(value) = values.pop() {
+            let index = match value {
+                Value::One(index) => index,
+                Value::Many(index) => {
+                    if bits.bit()? {
+                        values.push(Value::Many(index));
+                        values.push(Value::One(index));
+                    }
+                    continue;
+                }
+            };
+            size += 1;
+            match tags[index] {
+                // Integer
+                0 => size += bits.natural()?,
+                // ByteString, String
+                1 | 2 => size += bits.bytes(None)?,
+                // Unit
+                3 => {}
+                // Bool
+                4 => {
+                    bits.bit()?;
+                }
+                // Data, as CBOR
+                8 => {
+                    let mut cbor = Vec::new();
+                    size += bits.bytes(Some(&mut cbor))?;
+                    self.check_depth(depth + type_depth - 1 + cbor_depth(&cbor)?)?;
+                }
+                // List, then its element type
+                _ if tags[index + 1] == 5 => values.push(Value::Many(index + 2)),
+                // Pair, then its two element types
+                _ => {
+                    values.push(Value::One(ends[index + 3]));
+                    values.push(Value::One(index + 3));
+                }
+            }
+            if size > self.max_constant_bytes {
+                return Err(Rejection::limit(format!(
+                    "Constant is more than {} bytes",
+                    self.max_constant_bytes
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Why a scan stopped.
+enum Rejection {
+    /// The program exceeds a limit.
+    Limit(UplcError),
+    /// The encoding is malformed, so the decoder rejects it too.
+    Malformed(UplcError),
+}
+
+impl Rejection {
+    fn limit(message: String) -> Self {
+        Self::Limit(UplcError::DecodeError(message))
+    }
+
+    fn malformed(message: String) -> Self {
+        Self::Malformed(UplcError::DecodeError(message))
+    }
+}
+
+impl From<Rejection> for UplcError {
+    fn from(rejection: Rejection) -> Self {
+        match rejection {
+            Rejection::Limit(error) | Rejection::Malformed(error) => error,
+        }
+    }
+}
+
+/// What [`DecodeLimits::check_flat`] reads next.
+enum Pending {
+    /// A term at this depth.
+    Term(usize),
+    /// A list of terms at this depth: a 1 bit before each, a 0 bit after.
+    Terms(usize),
+}
+
+/// What [`DecodeLimits::check_constant`] reads next, by the index of its
+/// type in the type tags.
+enum Value {
+    One(usize),
+    /// List elements: a 1 bit before each, a 0 bit after.
+    Many(usize),
+}
+
+/// Check a constant's type tags, in prefix order: `7 5 a` is a list of `a`
+/// and `7 7 6 a b` a pair. Returns the index after each type, by its first
+/// index, and the type's nesting depth. `None` for a malformed type, or one
+/// without a flat encoding, such as BLS12-381 elements.
+fn parse_type(tags: &[u8]) -> Option<(Vec<usize>, usize)> {
+    let mut ends = vec![0; tags.len()];
+    // Lists and pairs being read: their first index, and the element types
+    // still to read.
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    let mut depth = 0;
+    let mut index = 0;
+    loop {
+        let start = index;
+        let elements = match tags.get(index..)? {
+            [7, 5, ..] => 1,
+            [7, 7, 6, ..] => 2,
+            [0..=4 | 8, ..] => 0,
+            _ => return None,
+        };
+        index += elements + 1;
+        if elements > 0 {
+            open.push((start, elements));
+            continue;
+        }
+        ends[start] = index;
+        depth = depth.max(open.len() + 1);
+        loop {
+            let Some((start, elements)) = open.last_mut() else {
+                return (index == tags.len()).then_some((ends, depth));
+            };
+            *elements -= 1;
+            if *elements > 0 {
+                break;
+            }
+            ends[*start] = index;
+            open.pop();
+        }
+    }
+}
+
+/// Nesting depth of one CBOR item, an atom being at depth 1, without
+/// recursing.
+fn cbor_depth(cbor: &[u8]) -> Result<usize, Rejection> {
+    let malformed = || Rejection::malformed("Malformed Data CBOR".into());
+    // Per open array, map or tag: the items still to read, `None` until a
+    // break for indefinite lengths.
+    let mut open: Vec<Option<u64>> = Vec::new();
+    let mut depth = 0;
+    let mut rest = cbor;
+    loop {
+        let (&initial, tail) = rest.split_first().ok_or_else(malformed)?;
+        rest = tail;
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        if initial == 0xff {
+            // A break closes the innermost indefinite-length item.
+            if open.pop().ok_or_else(malformed)?.is_some() {
+                return Err(malformed());
+            }
+        } else {
+            let argument = match info {
+                0..=23 => Some(info as u64),
+                24..=27 => {
+                    let len = 1 << (info - 24);
+                    let (bytes, tail) = rest.split_at_checked(len).ok_or_else(malformed)?;
+                    rest = tail;
+                    Some(bytes.iter().fold(0, |n, byte| (n << 8) | *byte as u64))
+                }
+                31 if (2..=5).contains(&major) => None,
+                _ => return Err(malformed()),
+            };
+            let items = match (major, argument) {
+                (0 | 1 | 7, _) | (2 | 3, Some(0)) => Some(0),
+                (2 | 3, Some(len)) => {
+                    let len = usize::try_from(len).map_err(|_| malformed())?;
+                    rest = rest.get(len..).ok_or_else(malformed)?;
+                    Some(0)
+                }
+                // Indefinite-length strings are chunks up to a break.
+                (2..=5, None) => None,
+                (4, Some(len)) => Some(len),
+                (5, Some(len)) => Some(len.checked_mul(2).ok_or_else(malformed)?),
+                (6, _) => Some(1),
+                _ => return Err(malformed()),
+            };
+            depth = depth.max(open.len() + 1);
+            if items != Some(0) {
+                open.push(items);
+                continue;
+            }
+        }
+        // An item ended: count it against its container.
+        loop {
+            match open.last_mut() {
+                None => return rest.is_empty().then_some(depth).ok_or_else(malformed),
+                Some(None) => break,
+                Some(Some(items)) => {
+                    *items -= 1;
+                    if *items > 0 {
+                        break;
+                    }
+                    open.pop();
+                }
+            }
+        }
+    }
+}
+
+/// A flat encoding, read most significant bit first.
+struct Bits<'a> {
+    bytes: &'a [u8],
+    /// Position in bits.
+    position: usize,
+}
+
+impl<'a> Bits<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn truncated() -> Rejection {
+        Rejection::malformed("Program is truncated".into())
+    }
+
+    fn bit(&mut self) -> Result<bool, Rejection> {
+        let byte = self
+            .bytes
+            .get(self.position / 8)
+            .ok_or_else(Self::truncated)?;
+        let bit = (byte >> (7 - self.position % 8)) & 1 == 1;
+        self.position += 1;
+        Ok(bit)
+    }
+
+    /// The next `count` bits, at most 8.
+    fn bits(&mut self, count: usize) -> Result<u8, Rejection> {
+        let mut value = 0;
+        for _ in 0..count {
+            value = (value << 1) | self.bit()? as u8;
+        }
+        Ok(value)
+    }
+
+    /// Skip a natural: 7-bit groups, each after a bit set unless it is the
+    /// last. Returns the number of groups.
+    fn natural(&mut self) -> Result<usize, Rejection> {
+        let mut groups = 1;
+        while self.bits(8)? & 0x80 != 0 {
+            groups += 1;
+        }
+        Ok(groups)
+    }
+
+    /// Skip a bytestring: filler up to a byte boundary, then chunks of at
+    /// most 255 bytes, each after its length, up to an empty one. Returns
+    /// its length, and appends it to `out` if given.
+    fn bytes(&mut self, mut out: Option<&mut Vec<u8>>) -> Result<usize, Rejection> {
+        while !self.bit()? {}
+        if !self.position.is_multiple_of(8) {
+            return Err(Rejection::malformed("Misaligned bytestring".into()));
+        }
+        let mut len = 0;
+        loop {
+            let start = self.position / 8;
+            let chunk_len = *self.bytes.get(start).ok_or_else(Self::truncated)? as usize;
+            let chunk = self
+                .bytes
+                .get(start + 1..start + 1 + chunk_len)
+                .ok_or_else(Self::truncated)?;
+            self.position += 8 * (1 + chunk_len);
+            if chunk_len == 0 {
+                return Ok(len);
+            }
+            len += chunk_len;
+            if let Some(out) = out.as_deref_mut() {
+                out.extend_from_slice(chunk);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes a flat encoding, most significant bit first.
+    #[derive(Default)]
+    struct Writer {
+        bytes: Vec<u8>,
+        bits: usize,
+    }
+
+    impl Writer {
+        /// A program of version 1.0.0, its term still to write.
+        fn program() -> Self {
+            let mut writer = Self::default();
+            writer.write(1, 8).write(0, 8).write(0, 8);
+            writer
+        }
+
+        fn write(&mut self, value: u8, count: usize) -> &mut Self {
+            for i in (0..count).rev() {
+                if self.bits.is_multiple_of(8) {
+                    self.bytes.push(0);
+                }
+                let bit = (value >> i) & 1;
+                *self.bytes.last_mut().unwrap() |= bit << (7 - self.bits % 8);
+                self.bits += 1;
+            }
+            self
+        }
+
+        /// A bytestring after its filler, in one chunk.
+        fn bytestring(&mut self, bytes: &[u8]) -> &mut Self {
+            while self.bits % 8 != 7 {
+                self.write(0, 1);
+            }
+            self.write(1, 1).write(bytes.len() as u8, 8);
+            for byte in bytes {
+                self.write(*byte, 8);
+            }
+            self.write(0, 8)
+        }
+
+        /// The filler ending the program.
+        fn finish(&mut self) -> Vec<u8> {
+            while self.bits % 8 != 7 {
+                self.write(0, 1);
+            }
+            self.write(1, 1);
+            core::mem::take(&mut self.bytes)
+        }
+    }
+
+    /// A constant term of type `tags`, its value still to write.
+    fn constant(writer: &mut Writer, tags: &[u8]) {
+        writer.write(4, 4);
+        for tag in tags {
+            writer.write(1, 1).write(*tag, 4);
+        }
+        writer.write(0, 1);
+    }
+
+    fn decode_error(result: Result<(), UplcError>) -> alloc::string::String {
+        match result {
+            Err(UplcError::DecodeError(message)) => message,
+            other => panic!("expected a decode error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn accepts_well_formed_programs() {
+        let limits = DecodeLimits::DEFAULT;
+        // `(con integer 42)`
+        limits
+            .check_flat(&[0x01, 0x00, 0x00, 0x48, 0x15, 0x01])
+            .unwrap();
+        // `[(builtin addInteger) (con integer 40) (con integer 2)]`
+        let add = [0x01, 0x00, 0x00, 0x33, 0x70, 0x09, 0x02, 0x82, 0x40, 0x09];
+        limits.check_flat(&add).unwrap();
+        // `(lam x x)`
+        limits
+            .check_flat(&[0x01, 0x00, 0x00, 0x20, 0x01, 0x01])
+            .unwrap();
+
+        // `(con (list (pair integer bytestring)) [(1, #aa), (2, #bb)])`
+        let mut writer = Writer::program();
+        constant(&mut writer, &[7, 5, 7, 7, 6, 0, 1]);
+        for (integer, byte) in [(2, 0xaa), (4, 0xbb)] {
+            writer.write(1, 1).write(integer, 8).bytestring(&[byte]);
+        }
+        writer.write(0, 1);
+        limits.check_flat(&writer.finish()).unwrap();
+    }
+
+    #[test]
+    fn rejects_deep_nesting_without_recursing() {
+        let limits = DecodeLimits::DEFAULT;
+        let nested = |levels: usize| {
+            let mut writer = Writer::program();
+            for _ in 0..levels {
+                // Delay
+                writer.write(1, 4);
+            }
+            // Error
+            writer.write(6, 4).finish()
+        };
+        limits.check_flat(&nested(limits.max_depth - 1)).unwrap();
+        let message = decode_error(limits.check_flat(&nested(limits.max_depth)));
+        assert!(message.contains("deeper than"), "{}", message);
+        // Far deeper than a recursive walk could take.
+        let deep = nested(400_000);
+        decode_error(limits.check_flat(&deep));
+        // Reported before the decoder would fail on a truncated tail.
+        decode_error(limits.check_flat_limits(&deep[..10_000]));
+    }
+
+    #[test]
+    fn counts_nested_values_against_the_constant_limit() {
+        let limits = DecodeLimits {
+            max_constant_bytes: 100,
+            ..DecodeLimits::DEFAULT
+        };
+        // `(con (list bytestring) [...])`, each element under the limit.
+        let list = |elements: usize| {
+            let mut writer = Writer::program();
+            constant(&mut writer, &[7, 5, 1]);
+            for _ in 0..elements {
+                writer.write(1, 1).bytestring(&[0; 20]);
+            }
+            writer.write(0, 1).finish()
+        };
+        limits.check_flat(&list(4)).unwrap();
+        let message = decode_error(limits.check_flat(&list(5)));
+        assert!(message.contains("Constant"), "{}", message);
+    }
+
+    #[test]
+    fn rejects_deeply_nested_data() {
+        let limits = DecodeLimits {
+            max_depth: 100,
+            ..DecodeLimits::DEFAULT
+        };
+        // `(con data (List [(List [... (I 0)])]))`
+        let data = |levels: usize| {
+            let mut cbor = vec![0x81; levels];
+            cbor.push(0x00);
+            let mut writer = Writer::program();
+            constant(&mut writer, &[8]);
+            writer.bytestring(&cbor).finish()
+        };
+        limits.check_flat(&data(98)).unwrap();
+        decode_error(limits.check_flat(&data(100)));
+
+        assert!(limits.check_data(&[0x9f, 0x00, 0xff]).is_ok());
+        let mut deep = vec![0x81; 200];
+        deep.push(0x00);
+        decode_error(limits.check_data(&deep));
+        decode_error(limits.check_data(&[0x82, 0x00]));
+    }
+
+    #[test]
+    fn rejects_truncated_and_unencodable_programs() {
+        let limits = DecodeLimits::DEFAULT;
+        let truncated = [0x01, 0x00, 0x00, 0x33];
+        decode_error(limits.check_flat(&truncated));
+        // Left to the decoder, which rejects it first.
+        limits.check_flat_limits(&truncated).unwrap();
+
+        // BLS12-381 elements have no flat encoding.
+        let mut writer = Writer::program();
+        constant(&mut writer, &[9]);
+        decode_error(limits.check_flat(&writer.finish()));
+    }
+}
@@ -0,0 +1,29 @@
+//! Recursion bridge: verify an OpenVM STARK proof inside the guest.
+//!
+//! Runs the same `verify_stark` logic as the browser verifier over a proof,
+//! its verification baseline, and an aggregation VK supplied by the host, and
+//! reveals `guest_protocol::bridge_commitment` of what the proof attests. A
+//! proof of this guest stands in for the inner proof, so proofs from other
+//! servers or older guest versions can be composed under one VK.
+//!
+//! Experimental: in-VM STARK verification takes far more cycles than UPLC
+//! evaluation.
+
+openvm::entry!(main);
+
+pub fn main() {
+    // Inputs, in order: proof JSON, bitcode-serialized agg VK, baseline JSON
+    let proof_json = String::from_utf8(openvm::io::read_vec()).expect("Proof is not UTF-8");
+    let agg_vk = openvm::io::read_vec();
+    let baseline_json = String::from_utf8(openvm::io::read_vec()).expect("Baseline is not UTF-8");
+
+    // Only proofs that verify can be bridged
+    let claim = openvm_wasm_verifier::verify_stark_claim(&proof_json, &agg_vk, &baseline_json)
+        .unwrap_or_else(|e| panic!("Inner proof rejected: {}", e));
+
+    openvm::io::reveal_bytes32(guest_protocol::bridge_commitment(
+        &agg_vk,
+        &claim.baseline,
+        &claim.user_public_values,
+    ));
+}
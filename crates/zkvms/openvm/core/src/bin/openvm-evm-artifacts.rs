@@ -1,13 +1,13 @@
 use std::{env, fs, io::BufReader, path::PathBuf};
 
 use eyre::{eyre, Context, Result};
-use openvm_prover::{load_agg_pk, load_app_pk, load_config, load_exe};
+use openvm_prover::{load_agg_pk, load_app_pk, load_config, load_exe, make_stdin, ExecutionMode};
 use openvm_sdk::{
     config::AggregationSystemParams,
     fs::{read_object_from_file, write_object_to_file, write_to_file_json},
     keygen::{Halo2ProvingKey, RootProvingKey},
     types::EvmProof,
-    Sdk, StdIn,
+    Sdk,
 };
 use serde::{Deserialize, Serialize};
 use snark_verifier_sdk::snark_verifier::{
@@ -130,12 +130,6 @@ fn parse_args() -> Result<Args> {
     Ok(args)
 }
 
-fn make_stdin(program_bytes: &[u8]) -> StdIn {
-    let mut stdin = StdIn::default();
-    stdin.write_bytes(program_bytes);
-    stdin
-}
-
 fn main() -> Result<()> {
     let args = parse_args()?;
 
@@ -193,7 +187,7 @@ fn main() -> Result<()> {
     }
 
     let evm_proof = sdk
-        .prove_evm(exe, make_stdin(&program_bytes), &[])
+        .prove_evm(exe, make_stdin(&program_bytes, ExecutionMode::Strict), &[])
         .wrap_err("failed to generate OpenVM EVM Halo2/KZG proof")?;
     write_to_file_json(&args.proof_json, &evm_proof)
         .with_context(|| format!("failed to write proof JSON: {}", args.proof_json.display()))?;
@@ -0,0 +1,287 @@
+//! [`ProverBuilder`]: a [`Prover`] from artifact paths or values already in
+//! memory, instead of calling each `load_*` function by hand.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use eyre::{Result, WrapErr};
+use openvm_sdk::fs::read_object_from_file;
+use serde::de::DeserializeOwned;
+
+use openvm_sdk::Sdk;
+
+use crate::{
+    default_agg_params, load_config, verify_artifact, verify_artifact_strict, AggPk, AppPk, Config,
+    Exe, Prover,
+};
+
+/// An artifact to load from a file, or one the caller already holds.
+enum Artifact<T> {
+    Path(PathBuf),
+    Value(T),
+}
+
+/// How loaded artifacts are checked against their checksum files.
+#[derive(Clone, Copy)]
+enum Checksums {
+    Skip,
+    /// [`verify_artifact`]
+    Verify,
+    /// [`verify_artifact_strict`]
+    Require,
+}
+
+impl<T: DeserializeOwned> Artifact<T> {
+    fn load(self, what: &str, checksums: Checksums) -> Result<T> {
+        match self {
+            Self::Value(value) => Ok(value),
+            Self::Path(path) => load_artifact(&path, what, checksums),
+        }
+    }
+}
+
+fn load_artifact<T: DeserializeOwned>(path: &Path, what: &str, checksums: Checksums) -> Result<T> {
+    match checksums {
+        Checksums::Skip => {}
+        Checksums::Verify => verify_artifact(path)?,
+        Checksums::Require => verify_artifact_strict(path)?,
+    }
+    read_object_from_file(path)
+        .wrap_err_with(|| format!("Failed to load {}: {}", what, path.display()))
+}
+
+/// Configures a [`Prover`]: where its config, executable and keys come
+/// from, and how it runs.
+///
+/// The config and executable are required. Without keys the SDK generates
+/// them on first use, which takes minutes and tens of GB, so leave them out
+/// only in tests and development. The app key alone is enough to execute
+/// with metering and prove app-level proofs; aggregated proofs then fail
+/// rather than generating the aggregation key. Every key given is held once,
+/// by the SDK the [`Prover`] shares across its clones.
+pub struct ProverBuilder {
+    config: Option<Artifact<Config>>,
+    exe: Option<Artifact<Arc<Exe>>>,
+    app_pk: Option<Artifact<AppPk>>,
+    agg_pk: Option<Artifact<AggPk>>,
+    #[cfg(feature = "evm-prove")]
+    root_pk: Option<Artifact<crate::RootPk>>,
+    #[cfg(feature = "evm-prove")]
+    halo2_pk: Option<Artifact<crate::Halo2Pk>>,
+    threads: Option<usize>,
+    verify_checksums: bool,
+    require_checksums: bool,
+    metering: bool,
+    determinism_check: bool,
+}
+
+impl Default for ProverBuilder {
+    fn default() -> Self {
+        Self {
+            config: None,
+            exe: None,
+            app_pk: None,
+            agg_pk: None,
+            #[cfg(feature = "evm-prove")]
+            root_pk: None,
+            #[cfg(feature = "evm-prove")]
+            halo2_pk: None,
+            threads: None,
+            verify_checksums: true,
+            require_checksums: false,
+            metering: true,
+            determinism_check: false,
+        }
+    }
+}
+
+impl ProverBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the config from an `openvm.toml`, see [`load_config`].
+    pub fn config_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config = Some(Artifact::Path(path.into()));
+        self
+    }
+
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(Artifact::Value(config));
+        self
+    }
+
+    /// Load the guest executable from a `.vmexe`.
+    pub fn exe_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.exe = Some(Artifact::Path(path.into()));
+        self
+    }
+
+    pub fn exe(mut self, exe: Arc<Exe>) -> Self {
+        self.exe = Some(Artifact::Value(exe));
+        self
+    }
+
+    /// Load the app proving key from `app.pk`.
+    pub fn app_pk_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.app_pk = Some(Artifact::Path(path.into()));
+        self
+    }
+
+    pub fn app_pk(mut self, app_pk: AppPk) -> Self {
+        self.app_pk = Some(Artifact::Value(app_pk));
+        self
+    }
+
+    /// Load the aggregation proving key from `agg_stark.pk`.
+    pub fn agg_pk_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.agg_pk = Some(Artifact::Path(path.into()));
+        self
+    }
+
+    pub fn agg_pk(mut self, agg_pk: AggPk) -> Self {
+        self.agg_pk = Some(Artifact::Value(agg_pk));
+        self
+    }
+
+    /// Load the root verifier proving key, which the Halo2 key wraps.
+    #[cfg(feature = "evm-prove")]
+    pub fn root_pk_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.root_pk = Some(Artifact::Path(path.into()));
+        self
+    }
+
+    #[cfg(feature = "evm-prove")]
+    pub fn root_pk(mut self, root_pk: crate::RootPk) -> Self {
+        self.root_pk = Some(Artifact::Value(root_pk));
+        self
+    }
+
+    /// Load the Halo2 proving key, for [`Prover::prove_evm`]. It is tied to
+    /// the root key it was generated from, so it needs that root key too.
+    #[cfg(feature = "evm-prove")]
+    pub fn halo2_pk_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.halo2_pk = Some(Artifact::Path(path.into()));
+        self
+    }
+
+    #[cfg(feature = "evm-prove")]
+    pub fn halo2_pk(mut self, halo2_pk: crate::Halo2Pk) -> Self {
+        self.halo2_pk = Some(Artifact::Value(halo2_pk));
+        self
+    }
+
+    /// Execute and prove on a dedicated pool of `threads` threads instead of
+    /// the global rayon pool, e.g. to leave cores to other services.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Whether to check loaded artifacts against their checksum files, see
+    /// [`verify_artifact`]. On by default; hashing multi-GB keys adds
+    /// seconds to startup.
+    pub fn verify_checksums(mut self, verify_checksums: bool) -> Self {
+        self.verify_checksums = verify_checksums;
+        self
+    }
+
+    /// Whether an artifact without a checksum file fails to load, see
+    /// [`verify_artifact_strict`], rather than loading with a warning. Off by
+    /// default, for artifacts written before checksums were recorded. Has no
+    /// effect with [`Self::verify_checksums`] off.
+    pub fn require_checksums(mut self, require_checksums: bool) -> Self {
+        self.require_checksums = require_checksums;
+        self
+    }
+
+    /// Whether [`Prover::execute`] and its variants meter the run, reporting
+    /// its cycles, segments and trace heights. On by default; unmetered runs
+    /// are faster and report only the public values.
+    pub fn metering(mut self, metering: bool) -> Self {
+        self.metering = metering;
+        self
+    }
+
+    /// Re-execute the guest after every STARK proof and fail the proof if
+    /// the run reveals other public values, see
+    /// [`Prover::check_determinism`]. Off by default.
+    pub fn determinism_check(mut self, determinism_check: bool) -> Self {
+        self.determinism_check = determinism_check;
+        self
+    }
+
+    /// Load whatever was given as a path and build the [`Prover`].
+    pub fn build(self) -> Result<Prover> {
+        let verify = match (self.verify_checksums, self.require_checksums) {
+            (false, _) => Checksums::Skip,
+            (true, false) => Checksums::Verify,
+            (true, true) => Checksums::Require,
+        };
+        let config = match self.config {
+            Some(Artifact::Path(path)) => load_config(&path)?,
+            Some(Artifact::Value(config)) => config,
+            None => eyre::bail!("The prover needs a config"),
+        };
+        let exe = match self.exe {
+            Some(Artifact::Path(path)) => Arc::new(load_artifact(&path, "vmexe", verify)?),
+            Some(Artifact::Value(exe)) => exe,
+            None => eyre::bail!("The prover needs a guest executable"),
+        };
+        let aggregation = self.agg_pk.is_some() || self.app_pk.is_none();
+        let sdk = match (self.app_pk, self.agg_pk) {
+            (Some(app_pk), Some(agg_pk)) => Sdk::builder()
+                .app_pk(app_pk.load("app proving key", verify)?)
+                .agg_pk(agg_pk.load("agg proving key", verify)?),
+            (Some(app_pk), None) => Sdk::builder().app_pk(app_pk.load("app proving key", verify)?),
+            (None, None) => Sdk::builder()
+                .app_config(config.clone())
+                .agg_params(default_agg_params()),
+            (None, Some(_)) => {
+                eyre::bail!("The aggregation proving key needs the app proving key")
+            }
+        };
+        #[cfg(feature = "evm-prove")]
+        let halo2 = self.halo2_pk.is_some();
+        #[cfg(feature = "evm-prove")]
+        let sdk = {
+            if halo2 && self.root_pk.is_none() {
+                eyre::bail!(
+                    "The Halo2 proving key needs the root proving key it was generated from"
+                );
+            }
+            let mut sdk = sdk;
+            if let Some(root_pk) = self.root_pk {
+                sdk = sdk.root_pk(root_pk.load("root proving key", verify)?);
+            }
+            if let Some(halo2_pk) = self.halo2_pk {
+                sdk = sdk.halo2_pk(halo2_pk.load("Halo2 proving key", verify)?);
+            }
+            sdk
+        };
+        let sdk = sdk
+            .build()
+            .wrap_err("Failed to initialize the OpenVM SDK")?;
+        let pool = self
+            .threads
+            .map(|threads| {
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .wrap_err("Failed to start the prover thread pool")
+            })
+            .transpose()?;
+
+        Ok(Prover {
+            config: Arc::new(config),
+            exe,
+            sdk: Arc::new(sdk),
+            pool: pool.map(Arc::new),
+            aggregation,
+            metering: self.metering,
+            determinism_check: self.determinism_check,
+            #[cfg(feature = "evm-prove")]
+            halo2,
+        })
+    }
+}
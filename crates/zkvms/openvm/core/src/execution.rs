@@ -0,0 +1,71 @@
+//! Cycle counts and trace heights of a guest run, measured while executing.
+
+use serde::{Deserialize, Serialize};
+
+/// What a guest run costs, to estimate the cost of proving it before
+/// committing to a proof.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ExecutionReport {
+    /// User public values revealed by the guest, as [`crate::execute`]
+    /// returns them.
+    #[serde(skip)]
+    pub public_values: Vec<u8>,
+    /// Instructions executed, one cycle each.
+    pub total_cycles: u64,
+    /// Continuation segments the run is split into, one app proof each.
+    pub segments: usize,
+    /// Trace height of each chip, summed over the segments, tallest first.
+    /// Chips the run never used are left out.
+    pub trace_heights: Vec<ChipTraceHeight>,
+}
+
+/// Rows a chip contributes to the traces of a run.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChipTraceHeight {
+    /// The chip's AIR name, as in the app proving key.
+    pub chip: String,
+    pub height: u64,
+}
+
+impl ExecutionReport {
+    /// The report of a run revealing `public_values`, from the instruction
+    /// count and per-AIR trace heights of each segment. `air_names` are
+    /// indexed like the heights.
+    pub(crate) fn new<'a>(
+        public_values: Vec<u8>,
+        segments: impl IntoIterator<Item = (u64, &'a [u32])>,
+        air_names: &[String],
+    ) -> Self {
+        let mut total_cycles = 0;
+        let mut segment_count = 0;
+        let mut heights = vec![0u64; air_names.len()];
+        for (instructions, trace_heights) in segments {
+            total_cycles += instructions;
+            segment_count += 1;
+            for (total, height) in heights.iter_mut().zip(trace_heights) {
+                *total += u64::from(*height);
+            }
+        }
+        let mut trace_heights: Vec<_> = air_names
+            .iter()
+            .zip(heights)
+            .filter(|(_, height)| *height > 0)
+            .map(|(chip, height)| ChipTraceHeight {
+                chip: chip.clone(),
+                height,
+            })
+            .collect();
+        trace_heights.sort_by(|a, b| b.height.cmp(&a.height).then_with(|| a.chip.cmp(&b.chip)));
+        Self {
+            public_values,
+            total_cycles,
+            segments: segment_count,
+            trace_heights,
+        }
+    }
+
+    /// Total rows over every chip's trace, a rough measure of proving cost.
+    pub fn total_trace_height(&self) -> u64 {
+        self.trace_heights.iter().map(|chip| chip.height).sum()
+    }
+}
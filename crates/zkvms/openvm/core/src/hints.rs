@@ -0,0 +1,122 @@
+//! [`KvHints`]: inputs a guest loads by key when it needs them, instead of
+//! reading every input up front with `read_vec`.
+
+use std::collections::BTreeMap;
+
+use openvm_sdk::StdIn;
+
+/// Values for the VM's key-value hint store, next to the [`StdIn`] stream.
+///
+/// A guest loads the value of a key into its hint stream with
+/// `openvm::io::hint_load_by_key` and then reads it like any other input, so
+/// a multi-megabyte script or reference inputs the run may never touch
+/// don't have to be written through `read_vec` up front. Values given to
+/// [`KvHints::insert`] are passed to the VM as they are, so must already be
+/// in the encoding `hint_load_by_key` expects; [`KvHints::insert_bytes`]
+/// encodes bytes the guest then reads with `read_vec`.
+///
+/// The store is not part of the public values: a guest that loads a hint
+/// must commit to what it read, or the proof says nothing about it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KvHints(BTreeMap<Vec<u8>, Vec<u8>>);
+
+impl KvHints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store `value` under `key`, replacing any earlier value.
+    pub fn insert(&mut self, key: impl Into<Vec<u8>>, value: impl Into<Vec<u8>>) {
+        self.0.insert(key.into(), value.into());
+    }
+
+    /// Store `bytes` under `key` as a single input, which a guest reads with
+    /// `read_vec` after loading the key.
+    ///
+    /// `hint_load_by_key` expects a little-endian `u32` count of inputs,
+    /// then each input as its `u32` length in words followed by its words;
+    /// [`StdIn`] writes bytes one per word.
+    pub fn insert_bytes(&mut self, key: impl Into<Vec<u8>>, bytes: &[u8]) {
+        self.insert(key, encode_inputs(&[bytes]));
+    }
+
+    /// [`KvHints::insert`], for chaining.
+    pub fn with(mut self, key: impl Into<Vec<u8>>, value: impl Into<Vec<u8>>) -> Self {
+        self.insert(key, value);
+        self
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        self.0.get(key).map(Vec::as_slice)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Add every hint to `stdin`'s key-value store.
+    pub fn add_to(&self, stdin: &mut StdIn) {
+        for (key, value) in &self.0 {
+            stdin.add_key_value(key.clone(), value.clone());
+        }
+    }
+}
+
+/// Inputs in the hint stream encoding of `hint_load_by_key`, see
+/// [`KvHints::insert_bytes`].
+fn encode_inputs(inputs: &[&[u8]]) -> Vec<u8> {
+    let words = inputs.iter().map(|input| 1 + input.len()).sum::<usize>();
+    let mut encoded = Vec::with_capacity(4 * (1 + words));
+    encoded.extend_from_slice(&(inputs.len() as u32).to_le_bytes());
+    for input in inputs {
+        encoded.extend_from_slice(&(input.len() as u32).to_le_bytes());
+        for byte in *input {
+            encoded.extend_from_slice(&u32::from(*byte).to_le_bytes());
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_bytes_as_one_input() {
+        let hints = KvHints::new().with("raw", [7]);
+        let mut encoded = hints.clone();
+        encoded.insert_bytes("script", &[0x01, 0xff]);
+
+        assert_eq!(hints.get(b"raw"), Some(&[7][..]));
+        assert_eq!(
+            encoded.get(b"script"),
+            Some(&[1, 0, 0, 0, 2, 0, 0, 0, 0x01, 0, 0, 0, 0xff, 0, 0, 0][..])
+        );
+        assert_eq!(encoded.len(), 2);
+    }
+
+    #[test]
+    fn adds_every_hint_to_the_key_value_store() {
+        let mut hints = KvHints::new().with("raw", [7]);
+        hints.insert_bytes("script", &[0x01, 0xff]);
+
+        let mut stdin = StdIn::default();
+        hints.add_to(&mut stdin);
+
+        assert_eq!(stdin.kv_store.len(), 2);
+        assert_eq!(
+            stdin.kv_store.get(b"raw".as_slice()).map(Vec::as_slice),
+            hints.get(b"raw")
+        );
+        assert_eq!(
+            stdin.kv_store.get(b"script".as_slice()).map(Vec::as_slice),
+            hints.get(b"script")
+        );
+        // Hints go to the store only, not the input stream.
+        assert!(stdin.buffer.is_empty());
+    }
+}
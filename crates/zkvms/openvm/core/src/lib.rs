@@ -1,25 +1,52 @@
 //! Host-side OpenVM SDK wrapper for building, executing, proving, and key
 //! generation — replaces `cargo openvm` CLI commands with direct Rust API calls.
 
-use std::path::Path;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use eyre::{Result, WrapErr};
-use openvm_circuit::arch::instructions::exe::VmExe;
+#[cfg(feature = "build")]
+use openvm_build::{GuestOptions, TargetFilter};
+use openvm_circuit::arch::{instructions::exe::VmExe, ContinuationVmProof};
 use openvm_continuations::CommitBytes;
 use openvm_sdk::config::{AggregationSystemParams, AppConfig};
 use openvm_sdk::fs::{read_object_from_file, write_object_to_file};
+use openvm_sdk::keygen::{AggProvingKey, AppProvingKey, AppVerifyingKey};
 #[cfg(feature = "evm-prove")]
-use openvm_sdk::keygen::Halo2ProvingKey;
-use openvm_sdk::keygen::{AggProvingKey, AppProvingKey};
+use openvm_sdk::keygen::{Halo2ProvingKey, RootProvingKey};
 #[cfg(feature = "evm-prove")]
 use openvm_sdk::types::EvmProof;
 use openvm_sdk::types::{VerificationBaselineJson, VersionedVmStarkProof};
 use openvm_sdk::{Sdk, StdIn};
 use openvm_sdk_config::SdkVmConfig;
-use openvm_stark_backend::{keygen::types::MultiStarkVerifyingKey, SystemParams};
+use openvm_stark_backend::{
+    keygen::types::MultiStarkVerifyingKey, p3_field::PrimeField32, SystemParams,
+};
 use openvm_stark_sdk::config::{app_params_with_100_bits_security, MAX_APP_LOG_STACKED_HEIGHT};
+use openvm_transpiler::{elf::Elf, openvm_platform::memory::MEM_SIZE};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use telemetry::{process_cpu_secs, timed, RssSampler};
+
+mod builder;
+mod execution;
+mod hints;
+mod registry;
+mod tasks;
+mod telemetry;
+mod worker;
+
+pub use builder::ProverBuilder;
+pub use execution::{ChipTraceHeight, ExecutionReport};
+pub use hints::KvHints;
+pub use registry::GuestRegistry;
+pub use tasks::{ProveProgress, Stage, Task, TimedOut, WorkerPool};
+pub use telemetry::ProveTelemetry;
+pub use worker::{serve_worker, WorkerJob, WorkerProcess};
 
 // Re-export crates used by downstream consumers (e.g. the web backend).
+pub use guest_protocol::{ExecutionMode, GuestInput, GuestInputBuilder, Status};
 pub use openvm_circuit;
 pub use openvm_sdk;
 pub use openvm_sdk::types::VerificationBaselineJson as StarkVerificationBaselineJson;
@@ -30,12 +57,18 @@ pub type F = openvm_sdk::F;
 pub type Config = AppConfig<SdkVmConfig>;
 pub type Exe = VmExe<F>;
 pub type AppPk = AppProvingKey<SdkVmConfig>;
+pub type AppVk = AppVerifyingKey;
+/// App-level (pre-aggregation) proof: one STARK proof per continuation segment.
+pub type AppProof = ContinuationVmProof<openvm_sdk::SC>;
 pub type AggPk = AggProvingKey;
 pub type AggVk = MultiStarkVerifyingKey<openvm_sdk::SC>;
 #[cfg(feature = "evm-prove")]
+pub type RootPk = RootProvingKey;
+#[cfg(feature = "evm-prove")]
 pub type Halo2Pk = Halo2ProvingKey;
 
 /// Result of a STARK proof generation.
+#[derive(Serialize, Deserialize)]
 pub struct StarkProveResult {
     /// Serialized STARK proof as JSON (for sending to client).
     pub proof_json: serde_json::Value,
@@ -47,6 +80,18 @@ pub struct StarkProveResult {
     pub app_exe_commit: String,
     /// App VM commit hex string.
     pub app_vm_commit: String,
+    /// User public values the proof commits to (the guest's revealed bytes).
+    pub user_public_values: Vec<u8>,
+    /// Time and memory spent generating the proof.
+    pub telemetry: ProveTelemetry,
+}
+
+impl StarkProveResult {
+    /// How the proven run ended, from the status word opening the public
+    /// values. `None` for guests older than `COMMITMENT_VERSION` 3.
+    pub fn status(&self) -> Option<Status> {
+        Status::from_public_values(&self.user_public_values)
+    }
 }
 
 fn default_app_system_params() -> SystemParams {
@@ -77,6 +122,132 @@ pub fn openvm_version() -> &'static str {
     openvm_sdk::OPENVM_VERSION
 }
 
+/// Path of the checksum file written next to an artifact (`<artifact>.sha256`),
+/// in `sha256sum` format.
+pub fn checksum_path(artifact_path: &Path) -> PathBuf {
+    let mut path = artifact_path.as_os_str().to_owned();
+    path.push(".sha256");
+    PathBuf::from(path)
+}
+
+fn file_sha256(path: &Path) -> Result<String> {
+    let mut file =
+        std::fs::File::open(path).wrap_err_with(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1 << 20];
+    loop {
+        let read = file
+            .read(&mut buf)
+            .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Check an artifact against its checksum file before deserializing it, so a
+/// truncated or half-written file fails with a clear error rather than deep
+/// inside the SDK's deserializer.
+///
+/// Artifacts written before checksums were recorded have no checksum file and
+/// are accepted with a warning; [`verify_artifact_strict`] rejects them.
+pub fn verify_artifact(path: &Path) -> Result<()> {
+    check_artifact(path, false)
+}
+
+/// [`verify_artifact`], failing for an artifact without a checksum file, e.g.
+/// one whose copy lost its sidecar.
+pub fn verify_artifact_strict(path: &Path) -> Result<()> {
+    check_artifact(path, true)
+}
+
+fn check_artifact(path: &Path, require_checksum: bool) -> Result<()> {
+    let len = std::fs::metadata(path)
+        .wrap_err_with(|| format!("Failed to stat {}", path.display()))?
+        .len();
+    if len == 0 {
+        eyre::bail!("{} is empty; regenerate it", path.display());
+    }
+
+    let sidecar = checksum_path(path);
+    let expected = match std::fs::read_to_string(&sidecar) {
+        Ok(contents) => contents
+            .split_whitespace()
+            .next()
+            .map(str::to_ascii_lowercase)
+            .ok_or_else(|| eyre::eyre!("Checksum file {} is empty", sidecar.display()))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && require_checksum => {
+            eyre::bail!(
+                "No checksum file for {} at {}; regenerate the artifact or copy its checksum file",
+                path.display(),
+                sidecar.display()
+            );
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            tracing::warn!(
+                "No checksum file for {}; skipping integrity check",
+                path.display()
+            );
+            return Ok(());
+        }
+        Err(e) => return Err(e).wrap_err_with(|| format!("Failed to read {}", sidecar.display())),
+    };
+
+    let actual = file_sha256(path)?;
+    if actual != expected {
+        eyre::bail!(
+            "{} ({} bytes) does not match its recorded checksum: the file is truncated, partially written, or corrupted; regenerate it",
+            path.display(),
+            len
+        );
+    }
+    Ok(())
+}
+
+fn read_artifact<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    verify_artifact(path)?;
+    read_object_from_file(path)
+}
+
+/// `path` with `.partial` appended, where it is written before being
+/// renamed into place.
+fn partial_path(path: &Path) -> PathBuf {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    PathBuf::from(partial)
+}
+
+/// Write an artifact and its checksum file.
+///
+/// Both are written to temporary files and renamed into place, the checksum
+/// file first: an interrupted write never leaves a partial file under the
+/// final name, nor an artifact without its checksum file.
+fn write_artifact<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let partial = partial_path(path);
+    write_object_to_file(&partial, value)?;
+    write_checksum(path, &file_sha256(&partial)?)?;
+    std::fs::rename(&partial, path)?;
+    Ok(())
+}
+
+/// Write the checksum file of the artifact at `artifact_path`, whose SHA256
+/// is `sha256` as hex, see [`checksum_path`]. Write it before moving the
+/// artifact into place.
+pub fn write_checksum(artifact_path: &Path, sha256: &str) -> Result<()> {
+    let file_name = artifact_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let sidecar = checksum_path(artifact_path);
+    let partial = partial_path(&sidecar);
+    std::fs::write(&partial, format!("{}  {}\n", sha256, file_name))
+        .wrap_err_with(|| format!("Failed to write {}", partial.display()))?;
+    std::fs::rename(&partial, &sidecar)
+        .wrap_err_with(|| format!("Failed to write {}", sidecar.display()))
+}
+
 /// Load an `AppConfig` from an `openvm.toml` file.
 pub fn load_config(config_path: &Path) -> Result<AppConfig<SdkVmConfig>> {
     let toml_str = std::fs::read_to_string(config_path)
@@ -87,25 +258,31 @@ pub fn load_config(config_path: &Path) -> Result<AppConfig<SdkVmConfig>> {
 
 /// Load a pre-built guest executable (`.vmexe`) from disk.
 pub fn load_exe(vmexe_path: &Path) -> Result<VmExe<F>> {
-    read_object_from_file(vmexe_path)
+    read_artifact(vmexe_path)
         .wrap_err_with(|| format!("Failed to load vmexe: {}", vmexe_path.display()))
 }
 
 /// Load app proving key from disk.
 pub fn load_app_pk(pk_path: &Path) -> Result<AppProvingKey<SdkVmConfig>> {
-    read_object_from_file(pk_path)
+    read_artifact(pk_path)
         .wrap_err_with(|| format!("Failed to load app proving key: {}", pk_path.display()))
 }
 
+/// Load app verifying key from disk (written next to `app.pk` by `generate_app_pk`).
+pub fn load_app_vk(vk_path: &Path) -> Result<AppVerifyingKey> {
+    read_artifact(vk_path)
+        .wrap_err_with(|| format!("Failed to load app verifying key: {}", vk_path.display()))
+}
+
 /// Load aggregation proving key from disk.
 pub fn load_agg_pk(agg_pk_path: &Path) -> Result<AggProvingKey> {
-    read_object_from_file(agg_pk_path)
+    read_artifact(agg_pk_path)
         .wrap_err_with(|| format!("Failed to load agg proving key: {}", agg_pk_path.display()))
 }
 
 /// Load aggregation verifying key from disk.
 pub fn load_agg_vk(agg_vk_path: &Path) -> Result<AggVk> {
-    read_object_from_file(agg_vk_path).wrap_err_with(|| {
+    read_artifact(agg_vk_path).wrap_err_with(|| {
         format!(
             "Failed to load agg verifying key: {}",
             agg_vk_path.display()
@@ -113,70 +290,201 @@ pub fn load_agg_vk(agg_vk_path: &Path) -> Result<AggVk> {
     })
 }
 
+/// Guest target to build when the package has several.
+#[derive(Clone, Debug)]
+pub enum BuildTarget {
+    Bin(String),
+    Example(String),
+}
+
+/// Options for `build_guest`, mirroring the `cargo openvm build` flags.
+#[derive(Clone, Debug)]
+pub struct BuildOptions {
+    /// Cargo features enabled on the guest crate (e.g. `uplc-aiken`).
+    pub features: Vec<String>,
+    /// Cargo profile, also naming the output directory under `target/openvm`.
+    pub profile: String,
+    /// Target to build; `None` builds the package's only binary.
+    pub target: Option<BuildTarget>,
+    /// Extra flags passed to rustc when compiling the guest.
+    pub rustflags: Vec<String>,
+}
+
+impl Default for BuildOptions {
+    fn default() -> Self {
+        Self {
+            features: Vec::new(),
+            profile: "release".to_string(),
+            target: None,
+            rustflags: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "build")]
+impl BuildOptions {
+    fn guest_options(&self) -> GuestOptions {
+        GuestOptions::default()
+            .with_features(self.features.clone())
+            .with_profile(self.profile.clone())
+            .with_rustc_flags(self.rustflags.clone())
+    }
+
+    fn target_filter(&self) -> Option<TargetFilter> {
+        self.target.as_ref().map(|target| match target {
+            BuildTarget::Bin(name) => TargetFilter {
+                name: name.clone(),
+                kind: "bin".to_string(),
+            },
+            BuildTarget::Example(name) => TargetFilter {
+                name: name.clone(),
+                kind: "example".to_string(),
+            },
+        })
+    }
+}
+
 /// Build the guest crate → ELF → VmExe, equivalent to `cargo openvm build`.
 ///
-/// This cross-compiles the guest to riscv32im and transpiles the ELF to a VmExe.
-pub fn build_guest(manifest_path: &Path, config_path: &Path, target_dir: &Path) -> Result<()> {
+/// This cross-compiles the guest to riscv32im and transpiles the ELF to a VmExe,
+/// written to `target/openvm/<profile>/openvm-guest.vmexe`.
+#[cfg(feature = "build")]
+pub fn build_guest(
+    manifest_path: &Path,
+    config_path: &Path,
+    target_dir: &Path,
+    options: &BuildOptions,
+) -> Result<()> {
     let config = load_config(config_path)?;
     let sdk = sdk_from_config(config)?;
 
-    let guest_opts = Default::default();
     let pkg_dir = manifest_path
         .parent()
         .ok_or_else(|| eyre::eyre!("Invalid manifest path"))?;
-    let target_filter = Default::default();
 
     let elf = sdk
-        .build(guest_opts, pkg_dir, &target_filter, None)
+        .build(
+            options.guest_options(),
+            pkg_dir,
+            &options.target_filter(),
+            None,
+        )
         .wrap_err("Failed to build guest ELF")?;
     let exe = sdk
         .convert_to_exe(elf)
         .wrap_err("Failed to convert ELF to VmExe")?;
 
-    let vmexe_dir = target_dir.join("openvm/release");
-    std::fs::create_dir_all(&vmexe_dir)?;
+    let vmexe_dir = target_dir.join("openvm").join(&options.profile);
+    write_exe(exe.as_ref(), &vmexe_dir)
+}
+
+/// Load a guest ELF built outside the SDK and transpile it to a VmExe.
+///
+/// Lets callers with their own build pipeline (reproducible builds, custom
+/// guests) skip `sdk.build`; the config must match the one the guest targets.
+pub fn load_elf_and_transpile(config_path: &Path, elf_path: &Path) -> Result<VmExe<F>> {
+    let config = load_config(config_path)?;
+    let sdk = sdk_from_config(config)?;
+
+    let elf_bytes = std::fs::read(elf_path)
+        .wrap_err_with(|| format!("Failed to read guest ELF: {}", elf_path.display()))?;
+    let elf = Elf::decode(&elf_bytes, MEM_SIZE as u32)
+        .wrap_err_with(|| format!("Failed to decode guest ELF: {}", elf_path.display()))?;
+    let exe = sdk
+        .convert_to_exe(elf)
+        .wrap_err("Failed to convert ELF to VmExe")?;
+    Ok(Arc::unwrap_or_clone(exe))
+}
+
+/// Transpile a precompiled guest ELF and write the VmExe where `build_guest`
+/// would, so the rest of the pipeline is unchanged.
+pub fn build_guest_from_elf(config_path: &Path, elf_path: &Path, target_dir: &Path) -> Result<()> {
+    let exe = load_elf_and_transpile(config_path, elf_path)?;
+    write_exe(&exe, &target_dir.join("openvm/release"))
+}
+
+fn write_exe(exe: &VmExe<F>, vmexe_dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(vmexe_dir)?;
     let vmexe_path = vmexe_dir.join("openvm-guest.vmexe");
-    write_object_to_file(&vmexe_path, exe.as_ref())
+    write_artifact(&vmexe_path, exe)
         .wrap_err_with(|| format!("Failed to write vmexe to {}", vmexe_path.display()))?;
 
     tracing::info!("Guest built: {}", vmexe_path.display());
     Ok(())
 }
 
-/// Generate app proving key, equivalent to `cargo openvm keygen`.
+/// Generate app proving + verifying key, equivalent to `cargo openvm keygen`.
 ///
-/// Note: the SDK also produces an `AppVerifyingKey` but we don't persist it —
-/// the client reconstructs the full VK from `agg_stark.vk` + per-program commits.
+/// The app verifying key (`app.vk`) only checks app-level proofs from
+/// `prove_app_only`; aggregated proofs are verified against `agg_stark.vk`.
+#[cfg(feature = "keygen")]
 pub fn generate_app_pk(config_path: &Path, target_dir: &Path) -> Result<()> {
     let config = load_config(config_path)?;
     let sdk = sdk_from_config(config)?;
 
-    let (app_pk, _app_vk) = sdk.app_keygen();
+    let (app_pk, app_vk) = sdk.app_keygen();
 
     let openvm_dir = target_dir.join("openvm");
     std::fs::create_dir_all(&openvm_dir)?;
 
     let pk_path = openvm_dir.join("app.pk");
-    write_object_to_file(&pk_path, &app_pk).wrap_err("Failed to write app.pk")?;
+    write_artifact(&pk_path, &app_pk).wrap_err("Failed to write app.pk")?;
+
+    let vk_path = openvm_dir.join("app.vk");
+    write_artifact(&vk_path, &app_vk).wrap_err("Failed to write app.vk")?;
 
-    tracing::info!("App proving key generated: {}", pk_path.display());
+    tracing::info!("App keys generated in {}", openvm_dir.display());
     Ok(())
 }
 
+/// Options for `generate_agg_keys`.
+#[derive(Clone, Debug, Default)]
+pub struct KeygenOptions {
+    /// Worker threads for keygen; `None` uses one per core. Keygen processes
+    /// circuits in parallel and peak memory grows with the number in flight,
+    /// so fewer threads trade time for memory. The keys do not depend on it.
+    pub threads: Option<usize>,
+}
+
+impl KeygenOptions {
+    /// Sequential keygen, for machines that run out of memory otherwise
+    /// (e.g. 32 GB). Expect it to take several times longer.
+    pub fn low_memory() -> Self {
+        Self { threads: Some(1) }
+    }
+}
+
 /// Generate aggregation proving key + verifying key, equivalent to `cargo openvm setup`.
-pub fn generate_agg_keys(config_path: &Path, openvm_home: &Path) -> Result<()> {
+#[cfg(feature = "keygen")]
+pub fn generate_agg_keys(
+    config_path: &Path,
+    openvm_home: &Path,
+    options: &KeygenOptions,
+) -> Result<()> {
     let config = load_config(config_path)?;
-    let sdk = sdk_from_config(config)?;
-
-    let (agg_pk, agg_vk) = sdk.agg_keygen();
+    // The SDK is dropped with its copy of the keys as soon as they are
+    // returned.
+    let keygen = move || -> Result<_> { Ok(sdk_from_config(config)?.agg_keygen()) };
+    let (agg_pk, agg_vk) = match options.threads {
+        Some(threads) => {
+            tracing::info!("Generating aggregation keys with {} thread(s)", threads);
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .wrap_err("Failed to start the keygen thread pool")?
+                .install(keygen)?
+        }
+        None => keygen()?,
+    };
 
     std::fs::create_dir_all(openvm_home)?;
 
     let pk_path = openvm_home.join("agg_stark.pk");
-    write_object_to_file(&pk_path, &agg_pk).wrap_err("Failed to write agg_stark.pk")?;
+    write_artifact(&pk_path, &agg_pk).wrap_err("Failed to write agg_stark.pk")?;
+    drop(agg_pk);
 
     let vk_path = openvm_home.join("agg_stark.vk");
-    write_object_to_file(&vk_path, &agg_vk).wrap_err("Failed to write agg_stark.vk")?;
+    write_artifact(&vk_path, &agg_vk).wrap_err("Failed to write agg_stark.vk")?;
 
     tracing::info!("Aggregation keys generated in {}", openvm_home.display());
     Ok(())
@@ -184,50 +492,233 @@ pub fn generate_agg_keys(config_path: &Path, openvm_home: &Path) -> Result<()> {
 
 /// Build StdIn from raw program bytes for the guest.
 ///
-/// The guest expects `openvm::io::read_vec()` to return the program bytes.
-fn make_stdin(program_bytes: &[u8]) -> StdIn {
+/// The guest expects `openvm::io::read_vec()` to return the program bytes,
+/// followed by the encoded execution mode and the (here empty) arguments.
+/// To apply the program to arguments, build a [`GuestInput`], e.g. with
+/// [`GuestInput::builder`], and pass it to [`guest_stdin`].
+pub fn make_stdin(program_bytes: &[u8], mode: ExecutionMode) -> StdIn {
+    guest_stdin(&GuestInput {
+        payload: program_bytes.to_vec(),
+        mode,
+        args: Vec::new(),
+    })
+}
+
+/// Build StdIn from a [`GuestInput`], one `read_vec` per item.
+pub fn guest_stdin(input: &GuestInput) -> StdIn {
+    let mut stdin = StdIn::default();
+    for item in input.items() {
+        stdin.write_bytes(&item);
+    }
+    stdin
+}
+
+/// [`guest_stdin`], with `hints` in the key-value store for the guest to
+/// load on demand, see [`KvHints`].
+pub fn guest_stdin_with_hints(input: &GuestInput, hints: &KvHints) -> StdIn {
+    let mut stdin = guest_stdin(input);
+    hints.add_to(&mut stdin);
+    stdin
+}
+
+/// Build StdIn for the experimental recursion bridge guest (`bridge/`): the
+/// inner proof JSON, the bitcode-serialized `agg_stark.vk`, and the
+/// verification baseline JSON.
+pub fn make_bridge_stdin(proof_json: &str, agg_vk_bytes: &[u8], baseline_json: &str) -> StdIn {
     let mut stdin = StdIn::default();
-    stdin.write_bytes(program_bytes);
+    stdin.write_bytes(proof_json.as_bytes());
+    stdin.write_bytes(agg_vk_bytes);
+    stdin.write_bytes(baseline_json.as_bytes());
     stdin
 }
 
+/// Execute the recursion bridge guest over an inner proof.
+///
+/// Returns its public values: `guest_protocol::bridge_commitment` of the
+/// inner proof's claim. Fails if the inner proof does not verify.
+pub fn execute_bridge(
+    config: &AppConfig<SdkVmConfig>,
+    exe: Arc<VmExe<F>>,
+    proof_json: &str,
+    agg_vk_bytes: &[u8],
+    baseline_json: &str,
+) -> Result<Vec<u8>> {
+    let stdin = make_bridge_stdin(proof_json, agg_vk_bytes, baseline_json);
+    let sdk = sdk_from_config(config.clone())?;
+    let output = sdk
+        .execute(exe, stdin)
+        .wrap_err("Bridge guest execution failed")?;
+    Ok(output)
+}
+
 /// Execute the guest without proof generation (fast).
 ///
 /// Returns the user public values (32-byte SHA256 commitment).
+/// With `ExecutionMode::ProveFailures`, a failing program still executes
+/// successfully and the commitment covers the failure kind instead.
 /// Equivalent to `cargo openvm run`.
+///
+/// Metering the run needs the app proving key, so only [`Prover::execute`]
+/// reports its cycles and trace heights.
 pub fn execute(
     config: &AppConfig<SdkVmConfig>,
-    exe: &VmExe<F>,
+    exe: Arc<VmExe<F>>,
     program_bytes: &[u8],
+    mode: ExecutionMode,
+) -> Result<Vec<u8>> {
+    execute_input(
+        config,
+        exe,
+        &GuestInput {
+            payload: program_bytes.to_vec(),
+            mode,
+            args: Vec::new(),
+        },
+    )
+}
+
+/// Execute the guest on any [`GuestInput`] and return its public values.
+pub fn execute_input(
+    config: &AppConfig<SdkVmConfig>,
+    exe: Arc<VmExe<F>>,
+    input: &GuestInput,
 ) -> Result<Vec<u8>> {
-    let stdin = make_stdin(program_bytes);
     let sdk = sdk_from_config(config.clone())?;
     let output = sdk
-        .execute(exe.clone(), stdin)
+        .execute(exe, guest_stdin(input))
         .wrap_err("Guest execution failed")?;
     Ok(output)
 }
 
+/// Execute `stdin` with metering, reporting the cycles, segments and trace
+/// heights of the run along with its public values.
+fn metered_execution(sdk: &Sdk, exe: Arc<VmExe<F>>, stdin: StdIn) -> Result<ExecutionReport> {
+    let (public_values, segments) = sdk
+        .execute_metered(exe, stdin)
+        .wrap_err("Guest execution failed")?;
+    let air_names: Vec<String> = sdk
+        .app_pk()
+        .app_vm_pk
+        .vm_pk
+        .per_air
+        .iter()
+        .map(|pk| pk.air_name.clone())
+        .collect();
+    Ok(ExecutionReport::new(
+        public_values,
+        segments
+            .iter()
+            .map(|segment| (segment.num_insns, segment.trace_heights.as_slice())),
+        &air_names,
+    ))
+}
+
+/// Most build info chunks read before giving up on finding its end.
+const MAX_BUILD_INFO_CHUNKS: u32 = 16;
+
+/// Query the guest's build info, e.g.
+/// `evaluator=uplc-turbo;openvm-guest=0.1.0;uplc=0.1.0;guest-protocol=0.1.0`.
+///
+/// Public values are 32 bytes, so the guest is executed once per chunk in
+/// `ExecutionMode::BuildInfo` until a chunk is zero-padded after its status
+/// word. Guests built before build info queries existed fail to execute.
+pub fn query_build_info(config: &AppConfig<SdkVmConfig>, exe: Arc<VmExe<F>>) -> Result<String> {
+    let sdk = sdk_from_config(config.clone())?;
+    let mut info = Vec::new();
+    for index in 0..MAX_BUILD_INFO_CHUNKS {
+        let public_values = sdk
+            .execute(exe.clone(), guest_stdin(&GuestInput::build_info(index)))
+            .wrap_err("Guest build info query failed")?;
+        let chunk = public_values
+            .get(guest_protocol::STATUS_LEN..)
+            .unwrap_or_default();
+        match chunk.iter().position(|&byte| byte == 0) {
+            Some(end) => {
+                info.extend_from_slice(&chunk[..end]);
+                return String::from_utf8(info).wrap_err("Guest build info is not UTF-8");
+            }
+            None => info.extend_from_slice(chunk),
+        }
+    }
+    eyre::bail!(
+        "Guest build info is longer than {} chunks",
+        MAX_BUILD_INFO_CHUNKS
+    )
+}
+
 /// Generate a STARK proof for the given program.
 ///
 /// Equivalent to `cargo openvm prove stark` + `cargo openvm commit`.
 /// Returns the proof JSON, commits, and public values in one call.
+///
+/// The keys are moved into the SDK when the caller hands over the last
+/// reference and cloned otherwise; to prove repeatedly, use a [`Prover`].
 pub fn prove_stark(
-    exe: &VmExe<F>,
-    app_pk: &AppProvingKey<SdkVmConfig>,
-    agg_pk: &AggProvingKey,
+    exe: Arc<VmExe<F>>,
+    app_pk: Arc<AppPk>,
+    agg_pk: Arc<AggPk>,
     program_bytes: &[u8],
+    mode: ExecutionMode,
 ) -> Result<StarkProveResult> {
-    let stdin = make_stdin(program_bytes);
+    let sdk = sdk_from_keys(Arc::unwrap_or_clone(app_pk), Arc::unwrap_or_clone(agg_pk))?;
+    stark_proof(&sdk, exe, make_stdin(program_bytes, mode), &|_: Stage| {})
+}
 
-    let sdk = sdk_from_keys(app_pk.clone(), agg_pk.clone())?;
-    let mut prover = sdk
-        .prover(exe.clone())
-        .wrap_err("Failed to create STARK prover")?;
-    let (proof, _) = prover
-        .prove(stdin, &[])
-        .wrap_err("STARK proof generation failed")?;
-    let baseline = prover.generate_baseline();
+/// Prove `stdin`, reporting each [`Stage`] to `progress` as it starts.
+///
+/// The app proof of every segment and its aggregation are separate SDK
+/// calls, so the telemetry times each, and the leaf proofs that start the
+/// aggregation are made one segment at a time, so `progress` hears of each.
+fn stark_proof(
+    sdk: &Sdk,
+    exe: Arc<VmExe<F>>,
+    stdin: StdIn,
+    progress: &dyn ProveProgress,
+) -> Result<StarkProveResult> {
+    let rss_sampler = RssSampler::start();
+    let cpu_started = process_cpu_secs();
+    progress.stage(Stage::AppProof);
+    let (prover, setup_secs) = timed(|| sdk.prover(exe));
+    let mut prover = prover.wrap_err("Failed to create STARK prover")?;
+    let (app_proof, app_proof_secs) = timed(|| prover.app_prover.prove(stdin));
+    let app_proof = app_proof.wrap_err("App proof generation failed")?;
+    let segments = app_proof.per_segment.len();
+    progress.stage(Stage::Aggregating);
+    let (proof, aggregation_secs) = timed(|| -> Result<_> {
+        let mut leaf_proofs = Vec::with_capacity(segments);
+        for index in 0..segments {
+            progress.segment(index, segments);
+            let leaf_proof = prover
+                .agg_prover
+                .generate_leaf_proof(&app_proof, index)
+                .wrap_err_with(|| format!("Leaf proof of segment {} failed", index))?;
+            leaf_proofs.push(leaf_proof);
+        }
+        prover
+            .agg_prover
+            .aggregate_leaf_proofs(leaf_proofs, &app_proof.user_public_values, &[])
+            .wrap_err("STARK aggregation failed")
+    });
+    let (proof, _) = proof?;
+    progress.stage(Stage::Finishing);
+    let user_public_values = proof
+        .user_pvs_proof
+        .public_values
+        .iter()
+        .map(|value| value.as_canonical_u32() as u8)
+        .collect();
+    let (baseline, baseline_secs) = timed(|| prover.generate_baseline());
+    let telemetry = ProveTelemetry {
+        setup_secs,
+        app_proof_secs,
+        aggregation_secs,
+        segments,
+        baseline_secs,
+        cpu_secs: cpu_started
+            .zip(process_cpu_secs())
+            .map(|(started, finished)| finished - started),
+        peak_rss_bytes: rss_sampler.finish(),
+    };
     let baseline_json = VerificationBaselineJson::from(baseline.clone());
     let app_exe_commit = commit_hex(baseline_json.app_exe_commit);
     let app_vm_commit = CommitBytes::from(prover.app_vm_commit());
@@ -237,6 +728,7 @@ pub fn prove_stark(
     let proof_version = versioned.version.clone();
     let proof_json =
         serde_json::to_value(&versioned).wrap_err("Failed to serialize proof to JSON")?;
+    progress.stage(Stage::Done);
 
     Ok(StarkProveResult {
         proof_json,
@@ -244,9 +736,328 @@ pub fn prove_stark(
         proof_version,
         app_exe_commit,
         app_vm_commit: commit_hex(app_vm_commit),
+        user_public_values,
+        telemetry,
     })
 }
 
+/// Long-lived proving handle for the guest executable and its keys.
+///
+/// The SDK is built from the keys once, including the root and Halo2 keys
+/// for EVM proofs, and the executable is shared behind an `Arc`, so serving
+/// a request does not copy multi-GB keys. Cloning a `Prover` is cheap.
+/// [`Prover::builder`] loads the artifacts from their paths.
+#[derive(Clone)]
+pub struct Prover {
+    config: Arc<Config>,
+    exe: Arc<Exe>,
+    sdk: Arc<Sdk>,
+    /// Threads to execute and prove on; `None` uses the global rayon pool.
+    pool: Option<Arc<rayon::ThreadPool>>,
+    /// Whether the prover can aggregate: `false` when it was given the app
+    /// key alone, see [`ProverBuilder`].
+    aggregation: bool,
+    /// Whether executions are metered, see [`ProverBuilder::metering`].
+    metering: bool,
+    /// Whether proofs are re-executed, see
+    /// [`ProverBuilder::determinism_check`].
+    determinism_check: bool,
+    /// Whether the SDK holds a Halo2 proving key, so EVM proofs never
+    /// generate one on the fly.
+    #[cfg(feature = "evm-prove")]
+    halo2: bool,
+}
+
+impl Prover {
+    pub fn new(config: Config, exe: Arc<Exe>, app_pk: AppPk, agg_pk: AggPk) -> Result<Self> {
+        ProverBuilder::new()
+            .config(config)
+            .exe(exe)
+            .app_pk(app_pk)
+            .agg_pk(agg_pk)
+            .verify_checksums(false)
+            .build()
+    }
+
+    pub fn builder() -> ProverBuilder {
+        ProverBuilder::new()
+    }
+
+    /// Run `work` on the prover's threads.
+    fn install<T: Send>(&self, work: impl FnOnce() -> T + Send) -> T {
+        match &self.pool {
+            Some(pool) => pool.install(work),
+            None => work(),
+        }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The shared executable; clone the `Arc` to pass it on without copying.
+    pub fn exe(&self) -> &Arc<Exe> {
+        &self.exe
+    }
+
+    /// A prover of another executable built for the same config, sharing
+    /// this prover's SDK and keys rather than copying them.
+    pub fn with_exe(&self, exe: Arc<Exe>) -> Self {
+        Self {
+            exe,
+            ..self.clone()
+        }
+    }
+
+    pub fn app_pk(&self) -> &AppPk {
+        self.sdk.app_pk()
+    }
+
+    pub fn agg_pk(&self) -> &AggPk {
+        self.sdk.agg_pk()
+    }
+
+    /// App execution and VM commits of the guest, as its proofs report
+    /// them, see [`compute_app_commit`].
+    pub fn app_commit(&self) -> Result<(String, String)> {
+        let prover = self
+            .sdk
+            .prover(self.exe.clone())
+            .wrap_err("Failed to create STARK prover")?;
+        let baseline = prover.generate_baseline();
+        let app_vm_commit = CommitBytes::from(prover.app_vm_commit());
+
+        Ok((
+            commit_hex(CommitBytes::from(baseline.app_exe_commit)),
+            commit_hex(app_vm_commit),
+        ))
+    }
+
+    /// Execute the guest without proof generation, see [`execute`], and
+    /// report the cycles and trace heights of the run, to estimate the cost
+    /// of proving it.
+    pub fn execute(&self, program_bytes: &[u8], mode: ExecutionMode) -> Result<ExecutionReport> {
+        self.execute_stdin(make_stdin(program_bytes, mode))
+    }
+
+    /// [`Prover::execute`] on any [`GuestInput`], e.g. a program applied to
+    /// arguments.
+    pub fn execute_input(&self, input: &GuestInput) -> Result<ExecutionReport> {
+        self.execute_stdin(guest_stdin(input))
+    }
+
+    /// Execute `stdin`, metered unless the builder turned metering off.
+    fn execute_stdin(&self, stdin: StdIn) -> Result<ExecutionReport> {
+        self.install(|| {
+            if self.metering {
+                return metered_execution(&self.sdk, self.exe.clone(), stdin);
+            }
+            let public_values = self
+                .sdk
+                .execute(self.exe.clone(), stdin)
+                .wrap_err("Guest execution failed")?;
+            Ok(ExecutionReport {
+                public_values,
+                ..ExecutionReport::default()
+            })
+        })
+    }
+
+    /// Prove the stdin `stdin` builds, then, if the builder asked for it,
+    /// re-execute it and check the run reveals what the proof commits to.
+    fn prove_stdin(
+        &self,
+        stdin: impl Fn() -> StdIn,
+        progress: &dyn ProveProgress,
+    ) -> Result<StarkProveResult> {
+        self.check_aggregation()?;
+        self.install(|| {
+            let result = stark_proof(&self.sdk, self.exe.clone(), stdin(), progress)?;
+            if self.determinism_check {
+                let output = self
+                    .sdk
+                    .execute(self.exe.clone(), stdin())
+                    .wrap_err("Guest execution failed")?;
+                check_public_values(&output, &result)?;
+            }
+            Ok(result)
+        })
+    }
+
+    fn check_aggregation(&self) -> Result<()> {
+        if !self.aggregation {
+            eyre::bail!("The prover was built without an aggregation proving key");
+        }
+        Ok(())
+    }
+
+    /// Generate a STARK proof of the guest run on any [`GuestInput`], e.g. a
+    /// program applied to arguments.
+    pub fn prove_stark_input(&self, input: &GuestInput) -> Result<StarkProveResult> {
+        self.prove_stdin(|| guest_stdin(input), &|_: Stage| {})
+    }
+
+    /// [`Prover::execute_input`], with key-value `hints` the guest can load
+    /// on demand.
+    pub fn execute_with_hints(
+        &self,
+        input: &GuestInput,
+        hints: &KvHints,
+    ) -> Result<ExecutionReport> {
+        self.execute_stdin(guest_stdin_with_hints(input, hints))
+    }
+
+    /// [`Prover::prove_stark_input`], with key-value `hints` the guest can
+    /// load on demand.
+    pub fn prove_stark_with_hints(
+        &self,
+        input: &GuestInput,
+        hints: &KvHints,
+    ) -> Result<StarkProveResult> {
+        self.prove_stdin(|| guest_stdin_with_hints(input, hints), &|_: Stage| {})
+    }
+
+    /// Generate a STARK proof for the given program, see [`prove_stark`].
+    pub fn prove_stark(
+        &self,
+        program_bytes: &[u8],
+        mode: ExecutionMode,
+    ) -> Result<StarkProveResult> {
+        self.prove_stark_with_progress(program_bytes, mode, &|_: Stage| {})
+    }
+
+    /// [`Prover::prove_stark`], reporting the `AppProof`, `Aggregating`,
+    /// `Finishing` and `Done` stages to `progress` as they start, and each
+    /// segment aggregated. A failed proof stops reporting at the stage that
+    /// failed.
+    pub fn prove_stark_with_progress(
+        &self,
+        program_bytes: &[u8],
+        mode: ExecutionMode,
+        progress: &dyn ProveProgress,
+    ) -> Result<StarkProveResult> {
+        self.prove_stdin(|| make_stdin(program_bytes, mode), progress)
+    }
+
+    /// [`Prover::execute`] on a thread of `pool`, as a [`Task`].
+    pub fn execute_async(
+        &self,
+        pool: &WorkerPool,
+        program_bytes: Vec<u8>,
+        mode: ExecutionMode,
+    ) -> Task<ExecutionReport> {
+        let prover = self.clone();
+        pool.spawn(move |stage| {
+            stage.enter(Stage::Executing);
+            prover.execute(&program_bytes, mode)
+        })
+    }
+
+    /// [`Prover::prove_stark`] on a thread of `pool`, as a [`Task`] whose
+    /// stage moves from `Queued` through `AppProof`, `Aggregating` and
+    /// `Finishing` to `Done`.
+    pub fn prove_stark_async(
+        &self,
+        pool: &WorkerPool,
+        program_bytes: Vec<u8>,
+        mode: ExecutionMode,
+    ) -> Task<StarkProveResult> {
+        let prover = self.clone();
+        pool.spawn(move |stage| {
+            prover.prove_stdin(|| make_stdin(&program_bytes, mode), &|next: Stage| {
+                stage.enter(next)
+            })
+        })
+    }
+
+    /// Generate only the app-level proof, see [`prove_app_only`].
+    pub fn prove_app(&self, program_bytes: &[u8], mode: ExecutionMode) -> Result<AppProof> {
+        self.install(|| {
+            let mut prover = self
+                .sdk
+                .app_prover(self.exe.clone())
+                .wrap_err("Failed to create app prover")?;
+            prover
+                .prove(make_stdin(program_bytes, mode))
+                .wrap_err("App proof generation failed")
+        })
+    }
+
+    /// Generate an EVM-verifiable proof with the root and Halo2 keys given
+    /// to the [`ProverBuilder`], see [`prove_evm`].
+    #[cfg(feature = "evm-prove")]
+    pub fn prove_evm(&self, program_bytes: &[u8], mode: ExecutionMode) -> Result<EvmProof> {
+        self.check_aggregation()?;
+        if !self.halo2 {
+            eyre::bail!("The prover was built without a Halo2 proving key");
+        }
+        self.install(|| {
+            self.sdk
+                .prove_evm(self.exe.clone(), make_stdin(program_bytes, mode), &[])
+                .wrap_err("EVM Halo2 proof generation failed")
+        })
+    }
+
+    /// Re-execute the guest and check that it reveals the public values the
+    /// proof commits to.
+    ///
+    /// A mismatch means guest evaluation is nondeterministic (e.g. iteration
+    /// order of a hash-based container), so the proof must not be served.
+    pub fn check_determinism(
+        &self,
+        program_bytes: &[u8],
+        mode: ExecutionMode,
+        result: &StarkProveResult,
+    ) -> Result<()> {
+        let output = self.execute(program_bytes, mode)?.public_values;
+        check_public_values(&output, result)
+    }
+}
+
+/// Fail unless a re-execution revealed `output`, the public values `result`
+/// commits to.
+fn check_public_values(output: &[u8], result: &StarkProveResult) -> Result<()> {
+    if output != result.user_public_values {
+        eyre::bail!(
+            "Nondeterministic guest: re-execution revealed {} but the proof commits to {}",
+            hex::encode(output),
+            hex::encode(&result.user_public_values)
+        );
+    }
+    Ok(())
+}
+
+/// Generate only the app-level proof, skipping aggregation.
+///
+/// Much faster than `prove_stark`, but the proof has one STARK per segment and
+/// is only checked by `verify_app_proof` against the app verifying key.
+pub fn prove_app_only(
+    exe: Arc<VmExe<F>>,
+    app_pk: &AppProvingKey<SdkVmConfig>,
+    program_bytes: &[u8],
+    mode: ExecutionMode,
+) -> Result<AppProof> {
+    let stdin = make_stdin(program_bytes, mode);
+
+    let sdk = Sdk::builder()
+        .app_pk(app_pk.clone())
+        .build()
+        .wrap_err("Failed to initialize app proving SDK")?;
+    let mut prover = sdk
+        .app_prover(exe)
+        .wrap_err("Failed to create app prover")?;
+    prover.prove(stdin).wrap_err("App proof generation failed")
+}
+
+/// Verify an app-level proof from `prove_app_only`.
+///
+/// Returns the user public values the guest revealed.
+pub fn verify_app_proof(app_vk: &AppVerifyingKey, proof: &AppProof) -> Result<Vec<u8>> {
+    let verified =
+        openvm_sdk::verify_app_proof(app_vk, proof).wrap_err("App proof verification failed")?;
+    Ok(verified.user_public_values)
+}
+
 /// Verify a STARK proof using the native OpenVM 2.0 verifier.
 pub fn verify_stark(
     agg_vk: &AggVk,
@@ -264,6 +1075,22 @@ pub fn verify_stark(
     Ok(())
 }
 
+/// User public values a STARK proof commits to, read from its JSON without
+/// verifying it.
+pub fn stark_proof_public_values(proof_json: &serde_json::Value) -> Result<Vec<u8>> {
+    let versioned: VersionedVmStarkProof = serde_json::from_value(proof_json.clone())
+        .wrap_err("Failed to deserialize versioned proof JSON")?;
+    let proof: openvm_sdk::types::VmStarkProof<openvm_sdk::SC> = versioned
+        .try_into()
+        .wrap_err("Failed to decode STARK proof")?;
+    Ok(proof
+        .user_pvs_proof
+        .public_values
+        .iter()
+        .map(|value| value.as_canonical_u32() as u8)
+        .collect())
+}
+
 // =============================================================================
 // EVM / Halo2 proving (behind `evm-prove` feature flag)
 // =============================================================================
@@ -272,7 +1099,7 @@ pub fn verify_stark(
 ///
 /// **Warning:** Requires >64 GB RAM and takes 10+ minutes.
 /// The resulting key is >10 GB serialized. Run once and persist.
-#[cfg(feature = "evm-prove")]
+#[cfg(all(feature = "evm-prove", feature = "keygen"))]
 pub fn generate_halo2_pk(
     config: &AppConfig<SdkVmConfig>,
     app_pk: &AppProvingKey<SdkVmConfig>,
@@ -286,16 +1113,19 @@ pub fn generate_halo2_pk(
 /// Generate an EVM-verifiable Halo2/KZG proof by wrapping a STARK proof.
 ///
 /// This runs the full pipeline: STARK prove → aggregation → root → Halo2 wrapper.
-/// Much slower than `prove_stark` and requires Halo2 keys.
+/// Much slower than `prove_stark` and requires Halo2 keys. The keys are
+/// copied into a fresh SDK on every call; to prove repeatedly, use
+/// [`Prover::prove_evm`].
 #[cfg(feature = "evm-prove")]
 pub fn prove_evm(
-    exe: &VmExe<F>,
+    exe: Arc<VmExe<F>>,
     app_pk: &AppProvingKey<SdkVmConfig>,
     agg_pk: &AggProvingKey,
     halo2_pk: &Halo2ProvingKey,
     program_bytes: &[u8],
+    mode: ExecutionMode,
 ) -> Result<EvmProof> {
-    let stdin = make_stdin(program_bytes);
+    let stdin = make_stdin(program_bytes, mode);
 
     let sdk = Sdk::builder()
         .app_pk(app_pk.clone())
@@ -305,7 +1135,7 @@ pub fn prove_evm(
         .wrap_err("Failed to initialize EVM proving SDK")?;
 
     let evm_proof = sdk
-        .prove_evm(exe.clone(), stdin, &[])
+        .prove_evm(exe, stdin, &[])
         .wrap_err("EVM Halo2 proof generation failed")?;
 
     Ok(evm_proof)
@@ -315,14 +1145,12 @@ pub fn prove_evm(
 ///
 /// Equivalent to `cargo openvm commit`.
 pub fn compute_app_commit(
-    exe: &VmExe<F>,
+    exe: Arc<VmExe<F>>,
     app_pk: &AppProvingKey<SdkVmConfig>,
     agg_pk: &AggProvingKey,
 ) -> Result<(String, String)> {
     let sdk = sdk_from_keys(app_pk.clone(), agg_pk.clone())?;
-    let prover = sdk
-        .prover(exe.clone())
-        .wrap_err("Failed to create STARK prover")?;
+    let prover = sdk.prover(exe).wrap_err("Failed to create STARK prover")?;
     let baseline = prover.generate_baseline();
     let app_vm_commit = CommitBytes::from(prover.app_vm_commit());
 
@@ -337,12 +1165,7 @@ pub mod evm_halo2_mcu {
     use std::{env, fs, io::BufReader, path::PathBuf};
 
     use eyre::{eyre, Context, Result};
-    use openvm_sdk::{
-        fs::read_object_from_file,
-        keygen::{Halo2ProvingKey, RootProvingKey},
-        types::EvmProof,
-        Sdk,
-    };
+    use openvm_sdk::{keygen::Halo2ProvingKey, types::EvmProof, Sdk};
     use serde::{Deserialize, Serialize};
     use snark_verifier_sdk::snark_verifier::{
         halo2_base::halo2_proofs::{
@@ -372,52 +1195,46 @@ pub mod evm_halo2_mcu {
         CircuitExt,
     };
 
-    use crate::{make_stdin, AggPk, AppPk, Exe};
+    use crate::{make_stdin, ExecutionMode, Prover, ProverBuilder};
 
     const BN254_BYTES: usize = 32;
     const NUM_ACCUMULATOR: usize = 12;
 
+    #[derive(Serialize, Deserialize)]
     pub struct McuHalo2Artifacts {
         pub proof: EvmProof,
         pub proof_json: serde_json::Value,
         pub native_verifier_key: Vec<u8>,
     }
 
-    pub fn prove_mcu_halo2(
-        exe: &Exe,
-        app_pk: &AppPk,
-        agg_pk: &AggPk,
-        program_bytes: &[u8],
-    ) -> Result<McuHalo2Artifacts> {
-        let mut builder = Sdk::builder().app_pk(app_pk.clone()).agg_pk(agg_pk.clone());
-
+    /// Add the root and Halo2 proving keys named by `OPENVM_ROOT_PK` (or
+    /// `MCU_EVM_ROOT_PK`) and `OPENVM_HALO2_PK` (or `MCU_EVM_HALO2_PK`) to
+    /// `builder`, so the [`Prover`] loads them once. Keys left unset are
+    /// generated by the prover's SDK on its first MCU proof, and kept.
+    pub fn with_configured_keys(mut builder: ProverBuilder) -> ProverBuilder {
         if let Some(root_pk_path) = configured_path(&["OPENVM_ROOT_PK", "MCU_EVM_ROOT_PK"]) {
-            let root_pk: RootProvingKey = read_object_from_file(&root_pk_path).with_context(|| {
-                format!(
-                    "failed to load root proving key: {}",
-                    root_pk_path.display()
-                )
-            })?;
-            builder = builder.root_pk(root_pk);
+            builder = builder.root_pk_path(root_pk_path);
         }
-
         if let Some(halo2_pk_path) = configured_path(&["OPENVM_HALO2_PK", "MCU_EVM_HALO2_PK"]) {
-            let halo2_pk: Halo2ProvingKey = read_object_from_file(&halo2_pk_path).with_context(|| {
-                format!(
-                    "failed to load Halo2 proving key: {}",
-                    halo2_pk_path.display()
-                )
-            })?;
-            builder = builder.halo2_pk(halo2_pk);
+            builder = builder.halo2_pk_path(halo2_pk_path);
         }
+        builder
+    }
 
-        let sdk = builder.build().wrap_err("failed to initialize OpenVM SDK")?;
-
-        let proof = sdk
-            .prove_evm(exe.clone(), make_stdin(program_bytes), &[])
+    /// Prove with the SDK the `prover` shares across requests, so its keys
+    /// are neither copied nor reloaded; see [`with_configured_keys`].
+    pub fn prove_mcu_halo2(
+        prover: &Prover,
+        program_bytes: &[u8],
+        mode: ExecutionMode,
+    ) -> Result<McuHalo2Artifacts> {
+        prover.check_aggregation()?;
+        let sdk = &prover.sdk;
+        let proof = prover
+            .install(|| sdk.prove_evm(prover.exe().clone(), make_stdin(program_bytes, mode), &[]))
             .wrap_err("failed to generate OpenVM Halo2/KZG proof")?;
 
-        verify_halo2_kzg_native(&sdk, proof.clone())
+        verify_halo2_kzg_native(sdk, proof.clone())
             .wrap_err("native Halo2/KZG verifier rejected generated proof")?;
 
         let proof_json =
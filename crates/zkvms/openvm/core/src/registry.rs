@@ -0,0 +1,187 @@
+//! [`GuestRegistry`]: several guests loaded side by side, e.g. alternative
+//! UPLC evaluators, each proved by name.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use eyre::Result;
+
+use crate::{Exe, ExecutionMode, ExecutionReport, Prover, StarkProveResult};
+
+/// Named [`Prover`]s, one per guest executable.
+///
+/// Each guest is proven with its own executable, so its proofs carry its own
+/// `app_exe_commit` and verifiers tell the guests apart by it. The proving
+/// keys depend on the VM config alone, so guests added with
+/// [`GuestRegistry::register_exe`] share the default guest's SDK and with it
+/// a single copy of the app and aggregation keys, whatever their number. A
+/// guest registered with its own [`Prover`] brings its own keys.
+#[derive(Clone)]
+pub struct GuestRegistry<G = Prover> {
+    guests: BTreeMap<String, G>,
+    /// The guest used when a caller names none, the first registered unless
+    /// set with [`GuestRegistry::set_default`].
+    default: Option<String>,
+}
+
+impl<G> Default for GuestRegistry<G> {
+    fn default() -> Self {
+        Self {
+            guests: BTreeMap::new(),
+            default: None,
+        }
+    }
+}
+
+impl<G> GuestRegistry<G> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `guest` under `name`. Names are unique: registering one twice
+    /// fails rather than replacing a guest callers may already rely on.
+    pub fn register(&mut self, name: impl Into<String>, guest: G) -> Result<()> {
+        let name = name.into();
+        if self.guests.contains_key(&name) {
+            eyre::bail!("A guest named {:?} is already registered", name);
+        }
+        self.default.get_or_insert_with(|| name.clone());
+        self.guests.insert(name, guest);
+        Ok(())
+    }
+
+    /// [`GuestRegistry::register`], for chaining.
+    pub fn with(mut self, name: impl Into<String>, guest: G) -> Result<Self> {
+        self.register(name, guest)?;
+        Ok(self)
+    }
+
+    /// Use the guest named `name` when a caller names none.
+    pub fn set_default(&mut self, name: &str) -> Result<()> {
+        self.guest(name)?;
+        self.default = Some(name.to_string());
+        Ok(())
+    }
+
+    /// The default guest and its name, `None` while the registry is empty.
+    pub fn default_guest(&self) -> Option<(&str, &G)> {
+        let name = self.default.as_deref()?;
+        Some((name, &self.guests[name]))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&G> {
+        self.guests.get(name)
+    }
+
+    /// The guest named `name`, or an error listing the registered names.
+    pub fn guest(&self, name: &str) -> Result<&G> {
+        self.get(name).ok_or_else(|| {
+            eyre::eyre!(
+                "No guest named {:?}; registered: {}",
+                name,
+                self.names().collect::<Vec<_>>().join(", ")
+            )
+        })
+    }
+
+    /// Registered names, in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.guests.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.guests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.guests.is_empty()
+    }
+}
+
+impl GuestRegistry<Prover> {
+    /// Add the executable `exe` under `name`, proven with the default
+    /// guest's config and keys, see [`Prover::with_exe`]. Fails while the
+    /// registry is empty.
+    pub fn register_exe(&mut self, name: impl Into<String>, exe: Arc<Exe>) -> Result<()> {
+        let (_, prover) = self
+            .default_guest()
+            .ok_or_else(|| eyre::eyre!("Register a guest with its keys first"))?;
+        let prover = prover.with_exe(exe);
+        self.register(name, prover)
+    }
+
+    /// [`Prover::execute`] with the guest named `name`.
+    pub fn execute_for(
+        &self,
+        name: &str,
+        program_bytes: &[u8],
+        mode: ExecutionMode,
+    ) -> Result<ExecutionReport> {
+        self.guest(name)?.execute(program_bytes, mode)
+    }
+
+    /// [`Prover::prove_stark`] with the guest named `name`.
+    pub fn prove_for(
+        &self,
+        name: &str,
+        program_bytes: &[u8],
+        mode: ExecutionMode,
+    ) -> Result<StarkProveResult> {
+        self.guest(name)?.prove_stark(program_bytes, mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> GuestRegistry<u32> {
+        GuestRegistry::new()
+            .with("uplc-turbo", 1)
+            .and_then(|registry| registry.with("uplc-aiken", 2))
+            .expect("distinct names")
+    }
+
+    #[test]
+    fn rejects_duplicate_names() {
+        let mut registry = registry();
+        assert!(registry.register("uplc-turbo", 3).is_err());
+        assert_eq!(registry.get("uplc-turbo"), Some(&1));
+        assert_eq!(registry.len(), 2);
+    }
+
+    #[test]
+    fn defaults_to_the_first_guest() {
+        let mut registry = registry();
+        assert_eq!(registry.default_guest(), Some(("uplc-turbo", &1)));
+
+        registry
+            .set_default("uplc-aiken")
+            .expect("registered guest");
+        assert_eq!(registry.default_guest(), Some(("uplc-aiken", &2)));
+
+        assert!(registry.set_default("plutus-core").is_err());
+        assert_eq!(registry.default_guest(), Some(("uplc-aiken", &2)));
+    }
+
+    #[test]
+    fn has_no_default_while_empty() {
+        let mut registry = GuestRegistry::<u32>::new();
+        assert!(registry.is_empty());
+        assert_eq!(registry.default_guest(), None);
+        assert!(registry.set_default("uplc-turbo").is_err());
+    }
+
+    #[test]
+    fn looks_guests_up_by_name() {
+        let registry = registry();
+        assert_eq!(registry.guest("uplc-aiken").ok(), Some(&2));
+        assert_eq!(
+            registry.names().collect::<Vec<_>>(),
+            ["uplc-aiken", "uplc-turbo"]
+        );
+
+        let error = registry.guest("plutus-core").unwrap_err().to_string();
+        assert!(error.contains("uplc-aiken, uplc-turbo"), "{}", error);
+    }
+}
@@ -0,0 +1,195 @@
+//! Guest work on dedicated threads, awaited as futures.
+//!
+//! Executing and proving block for seconds to minutes, so async services
+//! must keep them off their runtime's threads. A [`WorkerPool`] runs them on
+//! its own threads and hands back a [`Task`]: a future of the result that can
+//! also be polled for the [`Stage`] the work has reached.
+//!
+//! The pool's threads are plain OS threads rather than a rayon pool, so the
+//! prover's own parallelism still runs on the global rayon pool instead of
+//! being confined to the pool's threads.
+//!
+//! Work on these threads cannot be stopped early; to abort work that
+//! outlives a timeout, run it in a [`crate::WorkerProcess`] instead.
+
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+/// How far a [`Task`] has got.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum Stage {
+    /// Waiting for a free worker thread.
+    Queued = 0,
+    /// Executing the guest.
+    Executing = 1,
+    /// Proving each continuation segment of the run.
+    AppProof = 2,
+    /// Aggregating the segment proofs into a single STARK proof.
+    Aggregating = 3,
+    /// Generating the verification baseline and serializing the proof.
+    Finishing = 4,
+    /// The result is ready.
+    Done = 5,
+}
+
+impl Stage {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Queued,
+            1 => Self::Executing,
+            2 => Self::AppProof,
+            3 => Self::Aggregating,
+            4 => Self::Finishing,
+            _ => Self::Done,
+        }
+    }
+}
+
+/// Receives the [`Stage`]s of a proof as they start, e.g. to tell users
+/// whether a multi-minute proof is still proving or nearly done.
+pub trait ProveProgress: Sync {
+    fn stage(&self, stage: Stage);
+
+    /// Segment `index` (from 0) of `total` started within the current
+    /// stage. [`Stage::Aggregating`] reports each segment's leaf proof.
+    fn segment(&self, _index: usize, _total: usize) {}
+}
+
+impl<F: Fn(Stage) + Sync> ProveProgress for F {
+    fn stage(&self, stage: Stage) {
+        self(stage)
+    }
+}
+
+/// Where a running job reports its [`Stage`].
+pub(crate) struct StageReporter(Arc<AtomicU8>);
+
+impl StageReporter {
+    pub(crate) fn enter(&self, stage: Stage) {
+        self.0.store(stage as u8, Ordering::Relaxed);
+    }
+}
+
+struct Completion<T> {
+    result: Option<Result<T>>,
+    waker: Option<Waker>,
+}
+
+/// The pending result of work submitted to a [`WorkerPool`].
+///
+/// Dropping a task does not stop the work: a proof runs to completion once
+/// started, and its result is discarded.
+pub struct Task<T> {
+    stage: Arc<AtomicU8>,
+    completion: Arc<Mutex<Completion<T>>>,
+}
+
+impl<T> Task<T> {
+    pub fn stage(&self) -> Stage {
+        Stage::from_u8(self.stage.load(Ordering::Relaxed))
+    }
+}
+
+impl<T> Future for Task<T> {
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut completion = self.completion.lock().expect("task completion poisoned");
+        match completion.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                completion.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// The error of work that outlived its timeout, see [`crate::WorkerProcess`].
+/// Callers find it with `report.downcast_ref::<TimedOut>()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimedOut {
+    pub timeout: Duration,
+}
+
+impl std::fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Timed out after {:.1}s", self.timeout.as_secs_f64())
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Threads that run guest work submitted as [`Task`]s, first come first
+/// served. The threads exit once the pool is dropped and its queue drained.
+pub struct WorkerPool {
+    jobs: mpsc::Sender<Job>,
+}
+
+impl WorkerPool {
+    /// A pool of `threads` workers. Each proof already uses every core, so
+    /// one thread per proof that fits in memory at once is enough.
+    pub fn new(threads: usize) -> Result<Self> {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let queue = Arc::new(Mutex::new(queue));
+        for index in 0..threads.max(1) {
+            let queue = queue.clone();
+            std::thread::Builder::new()
+                .name(format!("openvm-worker-{}", index))
+                .spawn(move || loop {
+                    let job = queue.lock().expect("worker queue poisoned").recv();
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => break,
+                    }
+                })
+                .wrap_err("Failed to start a worker thread")?;
+        }
+        Ok(Self { jobs })
+    }
+
+    /// Run `work` on a worker thread. A panic in `work` fails the task
+    /// rather than the worker.
+    pub(crate) fn spawn<T: Send + 'static>(
+        &self,
+        work: impl FnOnce(&StageReporter) -> Result<T> + Send + 'static,
+    ) -> Task<T> {
+        let stage = Arc::new(AtomicU8::new(Stage::Queued as u8));
+        let completion = Arc::new(Mutex::new(Completion {
+            result: None,
+            waker: None,
+        }));
+        let task = Task {
+            stage: stage.clone(),
+            completion: completion.clone(),
+        };
+        let reporter = StageReporter(stage);
+        let job: Job = Box::new(move || {
+            let result = std::panic::catch_unwind(AssertUnwindSafe(|| work(&reporter)))
+                .unwrap_or_else(|_| Err(eyre::eyre!("Worker panicked")));
+            reporter.enter(Stage::Done);
+            let mut completion = completion.lock().expect("task completion poisoned");
+            completion.result = Some(result);
+            if let Some(waker) = completion.waker.take() {
+                waker.wake();
+            }
+        });
+        // Workers only exit once the pool, and with it this sender, is gone.
+        self.jobs
+            .send(job)
+            .expect("worker threads outlive their pool");
+        task
+    }
+}
@@ -0,0 +1,127 @@
+//! Time and memory measurements taken while generating a proof.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+const RSS_SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Clock ticks per second of `/proc/self/stat`'s CPU times (`USER_HZ`), which
+/// Linux fixes at 100 on every architecture the prover runs on.
+const CLOCK_TICKS_PER_SEC: f64 = 100.0;
+
+/// Per-phase resource usage of a STARK proof, for spotting performance
+/// regressions across OpenVM upgrades.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProveTelemetry {
+    /// Preparing the prover for the executable (committing the program).
+    pub setup_secs: f64,
+    /// Proving every continuation segment of the run (the app proof).
+    pub app_proof_secs: f64,
+    /// Aggregating the segment proofs into a single STARK proof.
+    pub aggregation_secs: f64,
+    /// Continuation segments the run was split into, one app proof each.
+    pub segments: usize,
+    /// Generating the verification baseline for the proof.
+    pub baseline_secs: f64,
+    /// CPU time used while proving, in seconds. Like `peak_rss_bytes`, this
+    /// is the whole process, so it is the proof's own only in a
+    /// [`crate::WorkerProcess`] worker, which proves one job at a time;
+    /// `None` where `/proc/self/stat` is unavailable.
+    pub cpu_secs: Option<f64>,
+    /// Peak resident set size sampled while proving, in bytes. This is the
+    /// whole process, so concurrent proofs inflate it; `None` where
+    /// `/proc/self/status` is unavailable.
+    pub peak_rss_bytes: Option<u64>,
+}
+
+/// Time a phase, returning its result and duration in seconds.
+pub(crate) fn timed<T>(phase: impl FnOnce() -> T) -> (T, f64) {
+    let started_at = Instant::now();
+    let value = phase();
+    (value, started_at.elapsed().as_secs_f64())
+}
+
+/// CPU time this process has used so far, in seconds.
+pub(crate) fn process_cpu_secs() -> Option<f64> {
+    parse_cpu_secs(&std::fs::read_to_string("/proc/self/stat").ok()?)
+}
+
+/// User plus system time from a `/proc/<pid>/stat` line. The command name
+/// may contain spaces, so fields are counted from the closing parenthesis.
+fn parse_cpu_secs(stat: &str) -> Option<f64> {
+    let mut fields = stat.rsplit_once(')')?.1.split_whitespace();
+    // utime and stime are the 14th and 15th fields, the 12th and 13th after
+    // the command name.
+    let utime: u64 = fields.nth(11)?.parse().ok()?;
+    let stime: u64 = fields.next()?.parse().ok()?;
+    Some((utime + stime) as f64 / CLOCK_TICKS_PER_SEC)
+}
+
+/// Current resident set size of this process, in bytes.
+fn current_rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kib = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+/// Samples the resident set size on a background thread until finished or
+/// dropped.
+pub(crate) struct RssSampler {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<Option<u64>>>,
+}
+
+impl RssSampler {
+    pub(crate) fn start() -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let handle = std::thread::spawn(move || {
+            let mut peak = current_rss_bytes();
+            while !thread_stop.load(Ordering::Relaxed) {
+                std::thread::sleep(RSS_SAMPLE_INTERVAL);
+                peak = peak.max(current_rss_bytes());
+            }
+            peak
+        });
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// Stop sampling and return the peak observed.
+    pub(crate) fn finish(mut self) -> Option<u64> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.handle.take()?.join().ok().flatten()
+    }
+}
+
+impl Drop for RssSampler {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cpu_time_from_proc_stat() {
+        let stat = "4242 (cardano (zkvms)) S 1 4242 4242 0 -1 4194560 \
+                    1000 0 0 0 250 50 0 0 20 0 8 0 100 0 0";
+        assert_eq!(parse_cpu_secs(stat), Some(3.0));
+        assert_eq!(parse_cpu_secs("4242 (truncated) S 1"), None);
+    }
+}
@@ -0,0 +1,398 @@
+//! Guest work in a child process, so a timeout aborts it.
+//!
+//! The SDK cannot interrupt execution or proving once started. A
+//! [`WorkerProcess`] therefore runs jobs in child processes and kills one
+//! when its job outlives its timeout, so the job's threads and memory are
+//! gone by the time the call returns. The child is any command that calls
+//! [`serve_worker`], e.g. the backend's `cardano-zkvms worker`.
+//!
+//! Loading the guest and its keys takes tens of GB and can take minutes, so
+//! a worker loads them once, then serves one job after another until it is
+//! killed or its parent goes away. A timeout starts once the job is sent to
+//! a worker that has loaded its keys, so it bounds the job alone. The cost
+//! is memory: every worker holds its own copy of the keys next to the
+//! parent's for as long as it lives, idle or not, and there is a worker per
+//! job that ran at the same time as others. A killed worker is replaced on
+//! the next job by a fresh one, which loads the keys again.
+//!
+//! A worker writes a ready line once loaded. The parent writes each
+//! [`WorkerJob`] to the worker's stdin as one JSON line, and the worker
+//! answers on stdout with a JSON line per [`Stage`] it enters and per
+//! segment it reports, and a last one with the result. Other lines on stdout
+//! are logged and skipped.
+
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use eyre::{Result, WrapErr};
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Deserialize, Serialize,
+};
+
+use crate::{
+    guest_stdin, ExecutionReport, GuestInput, ProveProgress, Prover, Stage, StarkProveResult,
+    TimedOut,
+};
+
+/// A job for a worker process.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "job", rename_all = "snake_case")]
+pub enum WorkerJob {
+    /// [`Prover::execute_input`].
+    Execute { input_hex: String },
+    /// [`Prover::prove_stark_input`], reporting its stages.
+    ProveStark { input_hex: String },
+    /// [`crate::evm_halo2_mcu::prove_mcu_halo2`] of the input's program.
+    #[cfg(feature = "evm-prove")]
+    ProveMcuHalo2 { input_hex: String },
+}
+
+impl WorkerJob {
+    /// The input the job runs, `None` if it does not decode.
+    pub fn input(&self) -> Option<GuestInput> {
+        let input_hex = match self {
+            Self::Execute { input_hex } | Self::ProveStark { input_hex } => input_hex,
+            #[cfg(feature = "evm-prove")]
+            Self::ProveMcuHalo2 { input_hex } => input_hex,
+        };
+        GuestInput::decode(&hex::decode(input_hex).ok()?)
+    }
+}
+
+/// A line the worker writes to stdout.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum WorkerMessage<T> {
+    /// The worker has loaded its prover and waits for jobs.
+    Ready,
+    Stage(Stage),
+    Segment {
+        index: usize,
+        total: usize,
+    },
+    Done(T),
+    Failed(String),
+}
+
+/// An execution as the worker reports it: [`ExecutionReport`] does not
+/// serialize its public values.
+#[derive(Serialize, Deserialize)]
+struct Executed {
+    public_values_hex: String,
+    report: ExecutionReport,
+}
+
+/// Runs jobs in long-lived child processes, killed once a job outlives its
+/// timeout. Clones share their workers.
+#[derive(Clone, Debug)]
+pub struct WorkerProcess {
+    program: PathBuf,
+    args: Vec<String>,
+    /// Workers between jobs, their keys loaded.
+    idle: Arc<Mutex<Vec<Worker>>>,
+}
+
+impl WorkerProcess {
+    /// Workers started as `program` with `args`, which must serve the job
+    /// with [`serve_worker`].
+    pub fn new(
+        program: impl Into<PathBuf>,
+        args: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self {
+            program: program.into(),
+            args: args.into_iter().map(Into::into).collect(),
+            idle: Arc::default(),
+        }
+    }
+
+    /// Start a worker and wait until it has loaded its keys, so the first
+    /// job does not wait for them.
+    pub fn warm(&self) -> Result<()> {
+        let worker = self.start()?;
+        self.idle
+            .lock()
+            .expect("idle workers lock poisoned")
+            .push(worker);
+        Ok(())
+    }
+
+    /// [`Prover::execute_input`] in a worker, failing with [`TimedOut`]
+    /// after `timeout`; `None` waits as long as it takes.
+    pub fn execute(
+        &self,
+        input: &GuestInput,
+        timeout: Option<Duration>,
+    ) -> Result<ExecutionReport> {
+        let job = WorkerJob::Execute {
+            input_hex: hex::encode(input.encode()),
+        };
+        let executed: Executed = self.run(&job, timeout, &|_: Stage| {})?;
+        let mut report = executed.report;
+        report.public_values =
+            hex::decode(executed.public_values_hex).wrap_err("Worker returned invalid hex")?;
+        Ok(report)
+    }
+
+    /// [`Prover::prove_stark_input`] in a worker, reporting its stages to
+    /// `progress` and failing with [`TimedOut`] after `timeout`.
+    pub fn prove_stark(
+        &self,
+        input: &GuestInput,
+        timeout: Option<Duration>,
+        progress: &dyn ProveProgress,
+    ) -> Result<StarkProveResult> {
+        let job = WorkerJob::ProveStark {
+            input_hex: hex::encode(input.encode()),
+        };
+        self.run(&job, timeout, progress)
+    }
+
+    /// [`crate::evm_halo2_mcu::prove_mcu_halo2`] in a worker, failing with
+    /// [`TimedOut`] after `timeout`.
+    #[cfg(feature = "evm-prove")]
+    pub fn prove_mcu_halo2(
+        &self,
+        input: &GuestInput,
+        timeout: Option<Duration>,
+    ) -> Result<crate::evm_halo2_mcu::McuHalo2Artifacts> {
+        let job = WorkerJob::ProveMcuHalo2 {
+            input_hex: hex::encode(input.encode()),
+        };
+        self.run(&job, timeout, &|_: Stage| {})
+    }
+
+    /// Run `job` in an idle worker, or a new one once it has loaded its
+    /// keys. Returns once the job is done, or once the worker has exited,
+    /// killed if it timed out.
+    fn run<T: DeserializeOwned>(
+        &self,
+        job: &WorkerJob,
+        timeout: Option<Duration>,
+        progress: &dyn ProveProgress,
+    ) -> Result<T> {
+        let mut request = serde_json::to_vec(job).wrap_err("Failed to serialize worker job")?;
+        request.push(b'\n');
+        let idle = self.idle.lock().expect("idle workers lock poisoned").pop();
+        let mut worker = match idle {
+            Some(mut worker) => match worker.send(&request) {
+                Ok(()) => worker,
+                // An idle worker that cannot take the job has died.
+                Err(_) => {
+                    worker.kill();
+                    self.start_with(&request)?
+                }
+            },
+            None => self.start_with(&request)?,
+        };
+
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let outcome = loop {
+            let line = match deadline {
+                Some(deadline) => worker
+                    .lines
+                    .recv_timeout(deadline.saturating_duration_since(Instant::now())),
+                None => worker
+                    .lines
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match line {
+                Ok(line) => match serde_json::from_str::<WorkerMessage<T>>(&line) {
+                    Ok(WorkerMessage::Stage(stage)) => progress.stage(stage),
+                    Ok(WorkerMessage::Segment { index, total }) => progress.segment(index, total),
+                    Ok(WorkerMessage::Done(value)) => break Some(Ok(value)),
+                    Ok(WorkerMessage::Failed(error)) => break Some(Err(eyre::eyre!(error))),
+                    Ok(WorkerMessage::Ready) | Err(_) => {
+                        tracing::debug!("Worker output: {}", line)
+                    }
+                },
+                Err(RecvTimeoutError::Timeout) => {
+                    // Killed rather than abandoned, so the job stops using
+                    // the memory and cores its caller accounts for.
+                    worker.kill();
+                    let timeout = timeout.expect("only a deadline times out");
+                    return Err(TimedOut { timeout }.into());
+                }
+                Err(RecvTimeoutError::Disconnected) => break None,
+            }
+        };
+        match outcome {
+            Some(result) => {
+                self.idle
+                    .lock()
+                    .expect("idle workers lock poisoned")
+                    .push(worker);
+                result
+            }
+            None => {
+                let status = worker
+                    .child
+                    .wait()
+                    .wrap_err("Failed to wait for the worker")?;
+                Err(eyre::eyre!("Worker exited with {} and no result", status))
+            }
+        }
+    }
+
+    /// A new worker, once it has loaded its keys, given `request`.
+    fn start_with(&self, request: &[u8]) -> Result<Worker> {
+        let mut worker = self.start()?;
+        if let Err(e) = worker.send(request) {
+            worker.kill();
+            return Err(e.wrap_err("Failed to send the job to a new worker"));
+        }
+        Ok(worker)
+    }
+
+    /// Start a worker and wait, without a deadline, until it has loaded its
+    /// keys.
+    fn start(&self) -> Result<Worker> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .wrap_err_with(|| format!("Failed to start worker {}", self.program.display()))?;
+        let stdin = child.stdin.take().expect("worker stdin is piped");
+        let stdout = child.stdout.take().expect("worker stdout is piped");
+        let (sender, lines) = mpsc::channel();
+        let reader = std::thread::Builder::new()
+            .name("openvm-worker-output".into())
+            .spawn(move || {
+                for line in BufReader::new(stdout).lines() {
+                    match line {
+                        Ok(line) if sender.send(line).is_ok() => {}
+                        _ => break,
+                    }
+                }
+            });
+        let mut worker = Worker {
+            child,
+            stdin,
+            lines,
+        };
+        if let Err(e) = reader {
+            worker.kill();
+            return Err(e).wrap_err("Failed to start a worker thread");
+        }
+
+        loop {
+            let Ok(line) = worker.lines.recv() else {
+                let status = worker
+                    .child
+                    .wait()
+                    .wrap_err("Failed to wait for the worker")?;
+                eyre::bail!("Worker exited with {} before it was ready", status);
+            };
+            match serde_json::from_str::<WorkerMessage<IgnoredAny>>(&line) {
+                Ok(WorkerMessage::Ready) => return Ok(worker),
+                Ok(WorkerMessage::Failed(error)) => {
+                    worker.kill();
+                    eyre::bail!("Worker failed to load: {}", error);
+                }
+                _ => tracing::debug!("Worker output: {}", line),
+            }
+        }
+    }
+}
+
+/// A worker process and its output lines.
+#[derive(Debug)]
+struct Worker {
+    child: Child,
+    stdin: ChildStdin,
+    lines: mpsc::Receiver<String>,
+}
+
+impl Worker {
+    /// Write one job line to the worker.
+    fn send(&mut self, request: &[u8]) -> Result<()> {
+        self.stdin
+            .write_all(request)
+            .and_then(|()| self.stdin.flush())
+            .wrap_err("Failed to send the worker job")
+    }
+
+    /// Kill the worker and wait for it to exit.
+    fn kill(mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Serve jobs in a worker process: load the prover once with `load`, say
+/// so on stdout, then run each job read from stdin until stdin closes,
+/// answering on stdout, see the module docs.
+pub fn serve_worker(load: impl FnOnce() -> Result<Prover>) -> Result<()> {
+    let prover = match load() {
+        Ok(prover) => prover,
+        Err(e) => {
+            send(&WorkerMessage::<()>::Failed(format!("{:#}", e)))?;
+            return Err(e);
+        }
+    };
+    send(&WorkerMessage::<()>::Ready)?;
+
+    for request in std::io::stdin().lock().lines() {
+        let request = request.wrap_err("Failed to read the worker job")?;
+        if let Err(e) = serve_job(&prover, &request) {
+            send(&WorkerMessage::<()>::Failed(format!("{:#}", e)))?;
+        }
+    }
+    Ok(())
+}
+
+/// Run the job in `request` and send its result.
+fn serve_job(prover: &Prover, request: &str) -> Result<()> {
+    let job: WorkerJob = serde_json::from_str(request).wrap_err("Invalid worker job")?;
+    let input = job
+        .input()
+        .ok_or_else(|| eyre::eyre!("The worker job's input does not decode"))?;
+
+    match job {
+        WorkerJob::Execute { .. } => prover.execute_input(&input).and_then(|report| {
+            send(&WorkerMessage::Done(Executed {
+                public_values_hex: hex::encode(&report.public_values),
+                report,
+            }))
+        }),
+        WorkerJob::ProveStark { .. } => prover
+            .prove_stdin(|| guest_stdin(&input), &SentProgress)
+            .and_then(|result| send(&WorkerMessage::Done(result))),
+        #[cfg(feature = "evm-prove")]
+        WorkerJob::ProveMcuHalo2 { .. } => {
+            crate::evm_halo2_mcu::prove_mcu_halo2(prover, &input.payload, input.mode)
+                .and_then(|artifacts| send(&WorkerMessage::Done(artifacts)))
+        }
+    }
+}
+
+/// Sends a proof's progress to the parent.
+struct SentProgress;
+
+// Progress is best effort; the result line reports failures.
+impl ProveProgress for SentProgress {
+    fn stage(&self, stage: Stage) {
+        let _ = send(&WorkerMessage::<()>::Stage(stage));
+    }
+
+    fn segment(&self, index: usize, total: usize) {
+        let _ = send(&WorkerMessage::<()>::Segment { index, total });
+    }
+}
+
+/// Write one message line to stdout.
+fn send<T: Serialize>(message: &WorkerMessage<T>) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    serde_json::to_writer(&mut stdout, message).wrap_err("Failed to write the worker output")?;
+    stdout.write_all(b"\n")?;
+    stdout.flush()?;
+    Ok(())
+}
@@ -0,0 +1,117 @@
+//! Runs the guest protocol conformance suite against the OpenVM guest.
+//!
+//! Needs a built guest, so it is ignored by default: run `make conformance`,
+//! or `cargo test -p openvm-prover --test conformance -- --ignored` after
+//! `cardano-zkvms setup`. `OPENVM_CONFIG` and `OPENVM_VMEXE` override where
+//! the guest's `openvm.toml` and vmexe are read from.
+//!
+//! It also runs the Aiken programs pinned by the `aiken-compat` corpus
+//! (`web/crates/aiken-wasm/tests/aiken-compat`), checking the guest reveals
+//! their golden commitments.
+//!
+//! And it runs the `guest-compat` fixtures of the protocol versions the guest
+//! implements (`crates/guest-compat/fixtures`), checking the guest still
+//! reveals what it revealed when they were recorded.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+fn artifact(var: &str, default: &str) -> PathBuf {
+    std::env::var_os(var).map(PathBuf::from).unwrap_or_else(|| {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("..")
+            .join(default)
+    })
+}
+
+/// Run an input in the built guest.
+fn guest() -> impl Fn(&guest_protocol::GuestInput) -> eyre::Result<Vec<u8>> {
+    let config = openvm_prover::load_config(&artifact("OPENVM_CONFIG", "openvm.toml"))
+        .expect("guest openvm.toml");
+    let exe = Arc::new(
+        openvm_prover::load_exe(&artifact(
+            "OPENVM_VMEXE",
+            "target/openvm/release/openvm-guest.vmexe",
+        ))
+        .expect("guest vmexe"),
+    );
+
+    move |input| openvm_prover::execute_input(&config, exe.clone(), input)
+}
+
+#[test]
+#[ignore = "needs a built guest"]
+fn openvm_guest_conforms() {
+    let mismatches = guest_protocol::conformance::check(guest());
+    assert!(mismatches.is_empty(), "{:#?}", mismatches);
+}
+
+#[test]
+#[ignore = "needs a built guest"]
+fn openvm_guest_commits_to_aiken_compat_goldens() {
+    let corpus = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../../../../web/crates/aiken-wasm/tests/aiken-compat");
+    let execute = guest();
+
+    let mut mismatches = Vec::new();
+    for entry in std::fs::read_dir(&corpus).expect("aiken-compat corpus") {
+        let path = entry.expect("corpus entry").path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let golden: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&path).expect("golden")).expect("golden JSON");
+        // Programs are pinned by `AIKEN_COMPAT_BLESS=1 cargo test --test aiken_compat`.
+        let (Some(program_hex), Some(commitment)) = (
+            golden["program_hex"].as_str(),
+            golden["commitment"].as_str(),
+        ) else {
+            continue;
+        };
+
+        let program = hex::decode(program_hex).expect("golden program hex");
+        let input = guest_protocol::GuestInput::program(&program, false);
+        let actual = execute(&input).map(hex::encode).map_err(|e| e.to_string());
+        if actual.as_deref() != Ok(commitment) {
+            mismatches.push((path.file_stem().unwrap().to_owned(), actual));
+        }
+    }
+    assert!(mismatches.is_empty(), "{:#?}", mismatches);
+}
+
+#[test]
+#[ignore = "needs a built guest"]
+fn openvm_guest_reproduces_compat_fixtures() {
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../../../guest-compat/fixtures")
+        .join(format!(
+            "input-v{}-commitment-v{}.json",
+            guest_protocol::INPUT_VERSION,
+            guest_protocol::COMMITMENT_VERSION
+        ));
+    let set: serde_json::Value = serde_json::from_slice(
+        &std::fs::read(&fixtures).expect("fixtures of the current versions"),
+    )
+    .expect("fixtures JSON");
+    let execute = guest();
+
+    let mut mismatches = Vec::new();
+    for fixture in set["cases"].as_array().expect("fixture cases") {
+        let input = hex::decode(fixture["input_hex"].as_str().expect("fixture input"))
+            .ok()
+            .and_then(|bytes| guest_protocol::GuestInput::decode(&bytes))
+            .expect("fixture input of the current version");
+        let expected = fixture["public_values_hex"].as_str();
+        let actual = execute(&input).map(hex::encode);
+        // Fixtures without public values are inputs the guest must abort on.
+        let conforms = match (&actual, expected) {
+            (Ok(actual), Some(expected)) => actual == expected,
+            (Err(_), None) => true,
+            _ => false,
+        };
+        if !conforms {
+            mismatches.push((fixture["name"].clone(), actual.map_err(|e| e.to_string())));
+        }
+    }
+    assert!(mismatches.is_empty(), "{:#?}", mismatches);
+}
@@ -0,0 +1,82 @@
+//! Execution-only OpenVM for the browser.
+//!
+//! Runs the exact guest vmexe the backend proves, without proof generation,
+//! so the playground's preview commitment is the one the server will prove
+//! rather than the output of a separate evaluator build. The vmexe and
+//! `openvm.toml` are served by the backend under `/data/`.
+
+use std::sync::Arc;
+
+use guest_protocol::{ExecutionMode, GuestInput};
+use openvm_circuit::arch::instructions::exe::VmExe;
+use openvm_sdk::config::{AggregationSystemParams, AppConfig};
+use openvm_sdk::{Sdk, StdIn, F};
+use openvm_sdk_config::SdkVmConfig;
+use openvm_stark_sdk::config::{app_params_with_100_bits_security, MAX_APP_LOG_STACKED_HEIGHT};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(start)]
+pub fn main() {
+    console_error_panic_hook::set_once();
+}
+
+/// Execute the guest on a program and return the revealed commitment.
+///
+/// # Arguments
+/// * `vmexe_bytes` - serialized guest `VmExe` (`/data/openvm-guest.vmexe`)
+/// * `config_toml` - the guest's `openvm.toml` (`/data/openvm.toml`)
+/// * `program_bytes` - flat-encoded UPLC program
+/// * `prove_failures` - commit to evaluation failures instead of aborting
+///
+/// # Returns
+/// * The hex-encoded public values (the 32-byte commitment), or an error if
+///   the guest aborts
+#[wasm_bindgen]
+pub fn execute_guest(
+    vmexe_bytes: &[u8],
+    config_toml: &str,
+    program_bytes: &[u8],
+    prove_failures: bool,
+) -> Result<String, JsValue> {
+    let mode = if prove_failures {
+        ExecutionMode::ProveFailures
+    } else {
+        ExecutionMode::Strict
+    };
+    execute_guest_native(vmexe_bytes, config_toml, program_bytes, mode)
+        .map(hex::encode)
+        .map_err(|error| JsValue::from_str(&error))
+}
+
+pub fn execute_guest_native(
+    vmexe_bytes: &[u8],
+    config_toml: &str,
+    program_bytes: &[u8],
+    mode: ExecutionMode,
+) -> Result<Vec<u8>, String> {
+    let exe: VmExe<F> = bitcode::deserialize(vmexe_bytes)
+        .map_err(|e| format!("Failed to deserialize vmexe: {}", e))?;
+    let vm_config = SdkVmConfig::from_toml(config_toml)
+        .map_err(|e| format!("Failed to parse openvm.toml: {}", e))?;
+    // Same parameters as the prover, so execution behaves identically.
+    let config = AppConfig::new(
+        vm_config,
+        app_params_with_100_bits_security(MAX_APP_LOG_STACKED_HEIGHT),
+    );
+    let sdk = Sdk::new(config, AggregationSystemParams::default())
+        .map_err(|e| format!("Failed to initialize OpenVM: {}", e))?;
+
+    // Same layout as `openvm_prover::guest_stdin`.
+    let mut stdin = StdIn::default();
+    let input = GuestInput {
+        payload: program_bytes.to_vec(),
+        mode,
+        args: Vec::new(),
+    };
+    for item in input.items() {
+        stdin.write_bytes(&item);
+    }
+
+    sdk.execute(Arc::new(exe), stdin)
+        .map_err(|e| format!("Guest execution failed: {}", e))
+}
@@ -0,0 +1,68 @@
+//! Heap usage tracking, enabled by the `heap-stats` feature.
+//!
+//! Wraps the system allocator to record how many bytes are live at most and
+//! how many were allocated in total. Programs close to the zkVM memory limits
+//! fail unpredictably, so the guest prints both after evaluation for
+//! developers to see how much headroom a program leaves.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+#[global_allocator]
+static ALLOCATOR: Tracking = Tracking {
+    live: Cell::new(0),
+    peak: Cell::new(0),
+    total: Cell::new(0),
+};
+
+struct Tracking {
+    live: Cell<usize>,
+    peak: Cell<usize>,
+    total: Cell<usize>,
+}
+
+// The guest runs on a single thread, and the target has no atomics.
+unsafe impl Sync for Tracking {}
+
+unsafe impl GlobalAlloc for Tracking {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            self.grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        self.live.set(self.live.get() - layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            self.live.set(self.live.get() - layout.size());
+            self.grow(new_size);
+        }
+        new_ptr
+    }
+}
+
+impl Tracking {
+    fn grow(&self, size: usize) {
+        let live = self.live.get() + size;
+        self.live.set(live);
+        self.peak.set(self.peak.get().max(live));
+        self.total.set(self.total.get() + size);
+    }
+}
+
+/// Print the heap usage so far to the host, as
+/// `heap: peak=<bytes> total=<bytes>`.
+pub fn report() {
+    openvm::io::println(format!(
+        "heap: peak={} total={}",
+        ALLOCATOR.peak.get(),
+        ALLOCATOR.total.get()
+    ));
+}
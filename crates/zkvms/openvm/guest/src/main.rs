@@ -1,38 +1,127 @@
-use sha2::{Digest, Sha256};
+use guest_protocol::{ErrorKind, ExecutionMode, GuestInput, ProgramHasher, conformance};
+use uplc::UplcError;
+
+#[cfg(feature = "heap-stats")]
+mod heap;
 
 openvm::entry!(main);
 
 pub fn main() {
-    // Read flat-encoded UPLC program from host
-    let program_bytes: Vec<u8> = openvm::io::read_vec();
+    // Read the payload (a program, a batch, a query, or nothing) from host
+    let payload: Vec<u8> = openvm::io::read_vec();
+
+    // Read which entrypoint to run, and how evaluation failures are handled
+    let mode = ExecutionMode::decode(&openvm::io::read_vec()).expect("Unknown execution mode");
+
+    // Read the CBOR-encoded arguments the program is applied to, if any
+    let args = guest_protocol::decode_args(&openvm::io::read_vec()).expect("Malformed arguments");
+
+    // Every entrypoint commits to its outcome with 32 bytes of public values,
+    // opening with the status word of how the run ended
+    openvm::io::reveal_bytes32(run(&GuestInput {
+        payload,
+        mode,
+        args,
+    }));
+}
+
+/// Dispatch to the entrypoint named by `mode.entrypoint()`.
+fn run(input: &GuestInput) -> [u8; 32] {
+    let payload = input.payload.as_slice();
+    match input.mode {
+        ExecutionMode::Strict | ExecutionMode::ProveFailures => {
+            evaluate(payload, &input.args, input.mode)
+        }
+        ExecutionMode::Aggregate => batch(payload),
+        ExecutionMode::BuildInfo => version_query(payload),
+        ExecutionMode::SelfTest => self_test(),
+    }
+}
+
+/// `evaluate`: evaluate a flat-encoded UPLC program applied to `args`.
+fn evaluate(program_bytes: &[u8], args: &[Vec<u8>], mode: ExecutionMode) -> [u8; 32] {
+    // Refuse oversized inputs with a provable rejection rather than a panic,
+    // in every mode, so the boundary behaves the same for every client
+    if guest_protocol::exceeds_limits(program_bytes, args) {
+        return guest_protocol::rejection_commitment(program_bytes, args);
+    }
 
     if program_bytes.is_empty() {
         panic!("No program provided");
     }
 
-    // Convert program bytes to hex string for the evaluator
-    let program_hex = hex::encode(&program_bytes);
-
     // Create evaluator and evaluate the program
     // The evaluator implementation is selected based on enabled features
     let evaluator = uplc::get_evaluator();
+    let hasher = ProgramHasher::with_program(program_bytes).with_args(args);
 
-    match evaluator.evaluate_program(&program_hex) {
-        Ok(result) => {
-            // Hash program bytes + evaluation result together.
-            // This commits the proof to BOTH the input program AND its output,
-            // so a verifier can confirm "program X produced result Y".
-            let result_str = result.to_string();
-            let mut hasher = Sha256::new();
-            hasher.update(&program_bytes);
-            hasher.update(result_str.as_bytes());
-            let commitment: [u8; 32] = hasher.finalize().into();
-
-            // Reveal the combined hash as the public output of the proof
-            openvm::io::reveal_bytes32(commitment);
+    let commitment = match evaluator.evaluate_program_bytes_with_args(program_bytes, args) {
+        // Hash program bytes, arguments and evaluation result together.
+        // This commits the proof to the input program, its inputs AND its
+        // output, so a verifier can confirm "program X on Y produced result Z".
+        Ok(result) => hasher.success_commitment(&result.to_string()),
+        // Commit to the failure category instead, so "program X is rejected"
+        // is itself a provable claim.
+        Err(e) if mode == ExecutionMode::ProveFailures => {
+            let kind = match e.downcast_ref::<UplcError>() {
+                Some(UplcError::DecodeError(_)) => ErrorKind::Decode,
+                Some(UplcError::BudgetExceeded(_)) => ErrorKind::BudgetExceeded,
+                Some(UplcError::ResultError(_)) => ErrorKind::NonConstantResult,
+                Some(UplcError::EvaluationError(_)) | None => ErrorKind::Evaluation,
+            };
+            hasher.failure_commitment(kind)
         }
         Err(e) => {
             panic!("UPLC evaluation failed: {}", e);
         }
-    }
+    };
+
+    // Print how much of the heap evaluation needed
+    #[cfg(feature = "heap-stats")]
+    heap::report();
+
+    commitment
+}
+
+/// `batch`: chain an epoch's commitments instead of running a program.
+fn batch(payload: &[u8]) -> [u8; 32] {
+    let (previous, commitments) =
+        guest_protocol::decode_aggregation(payload).expect("Malformed aggregation batch");
+    let root = guest_protocol::merkle_root(&commitments);
+    let count = commitments.len() as u64;
+    guest_protocol::epoch_commitment(&previous, &root, count)
+}
+
+/// `version-query`: reveal which evaluator and crate versions this guest was
+/// built with, so proofs can be attributed to a release.
+fn version_query(payload: &[u8]) -> [u8; 32] {
+    let index =
+        guest_protocol::decode_build_info_query(payload).expect("Malformed build info query");
+    let info = format!(
+        "evaluator={};openvm-guest={};uplc={};guest-protocol={}",
+        uplc::get_evaluator().version(),
+        env!("CARGO_PKG_VERSION"),
+        uplc::VERSION,
+        guest_protocol::VERSION,
+    );
+    guest_protocol::build_info_chunk(info.as_bytes(), index)
+}
+
+/// `self-test`: run the conformance cases through the other entrypoints,
+/// aborting on the first one that does not conform.
+fn self_test() -> [u8; 32] {
+    let public_values: Vec<[u8; 32]> = conformance::self_test_cases()
+        .into_iter()
+        .map(|case| {
+            let actual = run(&case.input);
+            assert_eq!(
+                Some(actual),
+                case.public_values,
+                "Self-test case {} failed",
+                case.name
+            );
+            actual
+        })
+        .collect();
+    guest_protocol::self_test_commitment(&public_values)
 }
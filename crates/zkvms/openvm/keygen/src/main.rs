@@ -0,0 +1,128 @@
+use std::{env, path::PathBuf};
+
+use eyre::{eyre, Result};
+use openvm_prover::{generate_agg_keys, generate_app_pk, KeygenOptions};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Keys {
+    App,
+    Agg,
+    #[cfg(feature = "evm-prove")]
+    Halo2,
+}
+
+#[derive(Debug)]
+struct Args {
+    keys: Keys,
+    config: PathBuf,
+    target_dir: Option<PathBuf>,
+    openvm_home: Option<PathBuf>,
+    app_pk: Option<PathBuf>,
+    agg_pk: Option<PathBuf>,
+    out: Option<PathBuf>,
+    keygen: KeygenOptions,
+}
+
+fn usage() -> &'static str {
+    "usage: openvm-keygen app --config <openvm.toml> --target-dir <dir>\n       \
+     openvm-keygen agg --config <openvm.toml> --openvm-home <dir> \
+     [--low-memory | --threads <n>]\n       \
+     openvm-keygen halo2 --config <openvm.toml> --app-pk <app.pk> \
+     --agg-pk <agg_stark.pk> --out <halo2.pk>    (with the evm-prove feature)"
+}
+
+fn parse_args() -> Result<Args> {
+    let mut raw_args = env::args().skip(1);
+    let keys = match raw_args.next().as_deref() {
+        Some("app") => Keys::App,
+        Some("agg") => Keys::Agg,
+        #[cfg(feature = "evm-prove")]
+        Some("halo2") => Keys::Halo2,
+        Some("--help" | "-h") => {
+            println!("{}", usage());
+            std::process::exit(0);
+        }
+        _ => return Err(eyre!(usage())),
+    };
+
+    let mut config = None;
+    let mut args = Args {
+        keys,
+        config: PathBuf::new(),
+        target_dir: None,
+        openvm_home: None,
+        app_pk: None,
+        agg_pk: None,
+        out: None,
+        keygen: KeygenOptions::default(),
+    };
+
+    while let Some(flag) = raw_args.next() {
+        let mut take_value = || {
+            raw_args
+                .next()
+                .ok_or_else(|| eyre!("missing value for {flag}"))
+        };
+
+        match flag.as_str() {
+            "--config" => config = Some(take_value()?.into()),
+            "--target-dir" => args.target_dir = Some(take_value()?.into()),
+            "--openvm-home" => args.openvm_home = Some(take_value()?.into()),
+            "--app-pk" => args.app_pk = Some(take_value()?.into()),
+            "--agg-pk" => args.agg_pk = Some(take_value()?.into()),
+            "--out" => args.out = Some(take_value()?.into()),
+            "--low-memory" => args.keygen = KeygenOptions::low_memory(),
+            "--threads" => {
+                let threads = take_value()?;
+                args.keygen.threads = match threads.parse() {
+                    Ok(threads) if threads > 0 => Some(threads),
+                    _ => return Err(eyre!("invalid --threads: {threads}")),
+                };
+            }
+            other => return Err(eyre!("unknown argument {other}\n{}", usage())),
+        }
+    }
+
+    args.config = config.ok_or_else(|| eyre!("missing --config\n{}", usage()))?;
+    Ok(args)
+}
+
+fn required<'a>(value: &'a Option<PathBuf>, flag: &str) -> Result<&'a PathBuf> {
+    value
+        .as_ref()
+        .ok_or_else(|| eyre!("missing {flag}\n{}", usage()))
+}
+
+/// Generates the guest's keys without the web backend, so that servers can
+/// build the backend against the prove-only core (without the prover's
+/// `keygen` feature) and load keys generated here:
+///
+/// - `app` writes `openvm/app.pk` and `openvm/app.vk` under `--target-dir`;
+/// - `agg` writes `agg_stark.pk` and `agg_stark.vk` under `--openvm-home`;
+/// - `halo2` writes the Halo2 proving key for EVM proof wrapping to `--out`.
+fn main() -> Result<()> {
+    let args = parse_args()?;
+
+    match args.keys {
+        Keys::App => generate_app_pk(&args.config, required(&args.target_dir, "--target-dir")?),
+        Keys::Agg => generate_agg_keys(
+            &args.config,
+            required(&args.openvm_home, "--openvm-home")?,
+            &args.keygen,
+        ),
+        #[cfg(feature = "evm-prove")]
+        Keys::Halo2 => {
+            use eyre::Context;
+            use openvm_prover::openvm_sdk::fs::write_object_to_file;
+
+            let out = required(&args.out, "--out")?;
+            let halo2_pk = openvm_prover::generate_halo2_pk(
+                &openvm_prover::load_config(&args.config)?,
+                &openvm_prover::load_app_pk(required(&args.app_pk, "--app-pk")?)?,
+                &openvm_prover::load_agg_pk(required(&args.agg_pk, "--agg-pk")?)?,
+            )?;
+            write_object_to_file(out, &halo2_pk)
+                .with_context(|| format!("failed to write Halo2 proving key: {}", out.display()))
+        }
+    }
+}
@@ -178,6 +178,7 @@ fn main() -> Result<()> {
             println!("proof kind: {:?}", envelope.proof_kind);
             println!("openvm version: {}", envelope.openvm_version);
             println!("public values: {} bytes", envelope.user_public_values.len());
+            println!("guest status: {}", guest_status(&envelope));
             println!("proof data: {} bytes", envelope.proof_data.len());
         }
         Command::VerifyEvm { key, proof } => {
@@ -191,11 +192,18 @@ fn main() -> Result<()> {
             let report = verifier.verify(&key, &proof)?;
             println!("verified: {}", report.verified);
             println!("public values: {} bytes", report.public_values_len);
+            println!("guest status: {}", guest_status(&proof));
         }
     }
     Ok(())
 }
 
+fn guest_status(envelope: &ProofEnvelope) -> &'static str {
+    envelope
+        .guest_status()
+        .map_or("unknown (no status word)", |status| status.label())
+}
+
 fn decode_artifact<'bytes, T>(bytes: &'bytes [u8], expected_frame_type: FrameType) -> Result<T>
 where
     T: serde::Deserialize<'bytes>,
@@ -5,6 +5,7 @@ extern crate alloc;
 use core::fmt;
 
 pub mod protocol;
+pub use guest_protocol::Status as GuestStatus;
 pub use protocol::*;
 
 mod halo2_compact;
@@ -2,6 +2,7 @@ use alloc::string::String;
 use alloc::vec::Vec;
 use core::fmt;
 
+use guest_protocol::Status;
 use serde::{Deserialize, Serialize};
 
 pub const FRAME_MAGIC: [u8; 2] = *b"OV";
@@ -101,6 +102,12 @@ impl ProofEnvelope {
             metadata: verification_baseline_json,
         }
     }
+
+    /// How the proven guest run ended, from the status word opening the
+    /// public values. `None` for guests older than commitment version 3.
+    pub fn guest_status(&self) -> Option<Status> {
+        Status::from_public_values(&self.user_public_values)
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -246,4 +253,21 @@ mod tests {
         let decoded: ProofEnvelope = decode_message(view.payload).unwrap();
         assert_eq!(decoded, proof);
     }
+
+    #[test]
+    fn reads_the_guest_status() {
+        let mut proof = ProofEnvelope::new_stark(
+            "v2.0".into(),
+            [7; 32],
+            [1; 32],
+            [2; 32],
+            vec![0, 0, 0, 3, 0xaa],
+            Vec::new(),
+            Vec::new(),
+        );
+        assert_eq!(proof.guest_status(), Some(Status::DecodeError));
+
+        proof.user_public_values = vec![42];
+        assert_eq!(proof.guest_status(), None);
+    }
 }
@@ -0,0 +1,103 @@
+use std::{env, fs, path::PathBuf};
+
+use openvm_wasm_verifier::{verify_stark_stats_native, VerificationStats};
+
+#[derive(Default)]
+struct Args {
+    prove_response_json: PathBuf,
+    agg_vk: PathBuf,
+    iterations: usize,
+}
+
+fn usage() -> &'static str {
+    "usage: openvm-verify-bench \
+      --prove-response-json <prove-response.json> \
+      --agg-vk <agg_stark.vk> \
+      [--iterations <n>]"
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        iterations: 5,
+        ..Args::default()
+    };
+    let mut raw_args = env::args().skip(1);
+
+    while let Some(flag) = raw_args.next() {
+        let mut take_value = || {
+            raw_args
+                .next()
+                .ok_or_else(|| format!("missing value for {flag}"))
+        };
+
+        match flag.as_str() {
+            "--prove-response-json" => args.prove_response_json = take_value()?.into(),
+            "--agg-vk" => args.agg_vk = take_value()?.into(),
+            "--iterations" => {
+                args.iterations = take_value()?
+                    .parse()
+                    .map_err(|e| format!("invalid --iterations: {e}"))?
+            }
+            "--help" | "-h" => {
+                println!("{}", usage());
+                std::process::exit(0);
+            }
+            other => return Err(format!("unknown argument {other}\n{}", usage())),
+        }
+    }
+
+    if args.prove_response_json.as_os_str().is_empty()
+        || args.agg_vk.as_os_str().is_empty()
+        || args.iterations == 0
+    {
+        return Err(usage().to_owned());
+    }
+
+    Ok(args)
+}
+
+/// Verifies the proof of a saved `/api/prove` response `--iterations` times,
+/// printing the `VerificationStats` of each run as a JSON line, then a
+/// summary line with the mean and fastest `verify_ms`.
+fn main() -> Result<(), String> {
+    let args = parse_args()?;
+
+    let response: serde_json::Value = serde_json::from_slice(
+        &fs::read(&args.prove_response_json)
+            .map_err(|e| format!("failed to read {}: {e}", args.prove_response_json.display()))?,
+    )
+    .map_err(|e| format!("failed to parse prove response JSON: {e}"))?;
+    let field = |name: &str| {
+        response
+            .get(name)
+            .map(ToString::to_string)
+            .ok_or_else(|| format!("prove response is missing {name}"))
+    };
+    let proof_json = field("stark_proof_json")?;
+    let baseline_json = field("verification_baseline_json")?;
+    let agg_vk = fs::read(&args.agg_vk)
+        .map_err(|e| format!("failed to read {}: {e}", args.agg_vk.display()))?;
+
+    let mut runs: Vec<VerificationStats> = Vec::with_capacity(args.iterations);
+    for _ in 0..args.iterations {
+        let stats = verify_stark_stats_native(&proof_json, &agg_vk, &baseline_json)?;
+        println!(
+            "{}",
+            serde_json::to_string(&stats).map_err(|e| e.to_string())?
+        );
+        if let Some(reason) = &stats.reason {
+            return Err(reason.clone());
+        }
+        runs.push(stats);
+    }
+
+    let verify_ms = runs.iter().map(|stats| stats.verify_ms);
+    let summary = serde_json::json!({
+        "iterations": runs.len(),
+        "mean_verify_ms": verify_ms.clone().sum::<f64>() / runs.len() as f64,
+        "min_verify_ms": verify_ms.fold(f64::INFINITY, f64::min),
+        "proof_json_bytes": proof_json.len(),
+    });
+    println!("{summary}");
+    Ok(())
+}
@@ -1,13 +1,57 @@
 mod error;
 mod hasher;
 mod public_values;
+mod registry;
+#[cfg(feature = "instrumentation")]
+mod stats;
 mod types;
 mod verifier;
+mod worker;
 
-use openvm_stark_backend::keygen::types::MultiStarkVerifyingKey;
+pub use guest_protocol::Status;
+use openvm_stark_backend::{keygen::types::MultiStarkVerifyingKey, p3_field::PrimeField32};
 use openvm_stark_sdk::config::baby_bear_poseidon2::BabyBearPoseidon2Config as SC;
+pub use registry::{GuestRelease, Registry};
+#[cfg(feature = "instrumentation")]
+pub use stats::{verify_stark_stats, verify_stark_stats_native, VerificationStats};
 use types::{VerificationBaseline, VerificationBaselineJson, VersionedVmStarkProof};
 use wasm_bindgen::prelude::*;
+pub use worker::handle_worker_message;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TYPES: &'static str = r#"
+/** A guest release listed in the guest registry. */
+export interface GuestRelease {
+  app_exe_commit: string;
+  guest_protocol_version: string;
+  commitment_version: number;
+  label?: string;
+}
+
+/** How the proven guest run ended, from the status word of its public values. */
+export type GuestStatus = "ok" | "eval_error" | "budget_exceeded" | "decode_error" | "internal" | "too_large";
+
+/**
+ * Result of checking a well-formed proof with `verify_stark_outcome`, or with
+ * `verify_stark_registered`, which also reports the proven guest release.
+ * `status` is null when the public values hold no status word, as for guests
+ * older than commitment version 3.
+ */
+export type VerificationOutcome =
+  | {
+      verified: true;
+      user_public_values: string;
+      status: GuestStatus | null;
+      guest?: GuestRelease;
+    }
+  | { verified: false; reason: string };
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "VerificationOutcome")]
+    pub type VerificationOutcome;
+}
 
 #[wasm_bindgen(start)]
 pub fn main() {
@@ -39,29 +83,161 @@ pub fn verify_stark(
     }
 }
 
+/// Verify an OpenVM STARK proof and return a `VerificationOutcome`: the
+/// hex-encoded user public values and the guest's status when it verifies,
+/// or the reason it was rejected. Throws for deserialization errors, like
+/// `verify_stark`.
+#[wasm_bindgen]
+pub fn verify_stark_outcome(
+    proof_json: &str,
+    agg_vk_bytes: &[u8],
+    baseline_json: &str,
+) -> Result<VerificationOutcome, JsValue> {
+    let outcome = match verify_stark_claim(proof_json, agg_vk_bytes, baseline_json) {
+        Ok(claim) => serde_json::json!({
+            "verified": true,
+            "status": claim.status().map(Status::label),
+            "user_public_values": hex::encode(claim.user_public_values),
+        }),
+        Err(error) if error.starts_with("OpenVM verification failed:") => serde_json::json!({
+            "verified": false,
+            "reason": error,
+        }),
+        Err(error) => return Err(JsValue::from_str(&error)),
+    };
+    Ok(js_sys::JSON::parse(&outcome.to_string())?.unchecked_into())
+}
+
+/// Verify an OpenVM STARK proof of a known guest release.
+///
+/// Like `verify_stark_outcome`, but the proof's `app_exe_commit` must be
+/// listed in `registry_json` (a `Registry`), and the matching release is
+/// returned as `guest`, telling how to read the public values. Proofs of
+/// guests missing from the registry are rejected, and `status` is only read
+/// from releases with commitment version 3 or later.
+#[wasm_bindgen]
+pub fn verify_stark_registered(
+    proof_json: &str,
+    agg_vk_bytes: &[u8],
+    baseline_json: &str,
+    registry_json: &str,
+) -> Result<VerificationOutcome, JsValue> {
+    let registry = Registry::from_json(registry_json).map_err(|e| JsValue::from_str(&e))?;
+    let outcome = match verify_stark_claim(proof_json, agg_vk_bytes, baseline_json) {
+        Ok(claim) => match registry.lookup(&claim.app_exe_commit) {
+            Some(guest) => serde_json::json!({
+                "verified": true,
+                "status": claim
+                    .status()
+                    .filter(|_| guest.commitment_version >= 3)
+                    .map(Status::label),
+                "user_public_values": hex::encode(claim.user_public_values),
+                "guest": guest,
+            }),
+            None => serde_json::json!({
+                "verified": false,
+                "reason": format!(
+                    "Unknown guest: app_exe_commit 0x{} is not in the guest registry",
+                    hex::encode(&claim.app_exe_commit)
+                ),
+            }),
+        },
+        Err(error) if error.starts_with("OpenVM verification failed:") => serde_json::json!({
+            "verified": false,
+            "reason": error,
+        }),
+        Err(error) => return Err(JsValue::from_str(&error)),
+    };
+    Ok(js_sys::JSON::parse(&outcome.to_string())?.unchecked_into())
+}
+
+/// Read the user public values a proof claims, without verifying it.
+///
+/// Decoding is cheap, so a UI can check the claimed commitment (see
+/// `check_commitment` in the UPLC module) while `verify_stark` runs, e.g. in
+/// a worker. The values are only proven once the proof verifies.
+///
+/// # Returns
+/// * The hex-encoded user public values, or an error if the proof does not
+///   deserialize
+#[wasm_bindgen]
+pub fn extract_public_values(proof_json: &str) -> Result<String, JsValue> {
+    decode_proof(proof_json)
+        .map(|proof| hex::encode(user_public_values(&proof)))
+        .map_err(|e| JsValue::from_str(&e))
+}
+
 pub fn verify_stark_native(
     proof_json: &str,
     agg_vk_bytes: &[u8],
     baseline_json: &str,
 ) -> Result<(), String> {
-    let proof_json: VersionedVmStarkProof = serde_json::from_str(proof_json)
-        .map_err(|e| format!("Failed to deserialize proof JSON: {}", e))?;
-    let proof: VmStarkProof = proof_json
-        .try_into()
-        .map_err(|e| format!("Failed to decode versioned proof: {}", e))?;
+    verify_stark_claim(proof_json, agg_vk_bytes, baseline_json).map(|_| ())
+}
+
+/// What a verified proof attests: its user public values, under the
+/// verification baseline it was checked against.
+pub struct VerifiedClaim {
+    /// `VerificationBaselineJson::canonical_bytes` of the baseline.
+    pub baseline: Vec<u8>,
+    /// Commitment to the guest executable the proof was checked against,
+    /// see [`Registry::lookup`].
+    pub app_exe_commit: Vec<u8>,
+    /// Bytes revealed by the guest.
+    pub user_public_values: Vec<u8>,
+}
+
+impl VerifiedClaim {
+    /// How the proven run ended, see [`Status::from_public_values`].
+    pub fn status(&self) -> Option<Status> {
+        Status::from_public_values(&self.user_public_values)
+    }
+}
+
+/// Verify an OpenVM STARK proof and return the claim it proves.
+pub fn verify_stark_claim(
+    proof_json: &str,
+    agg_vk_bytes: &[u8],
+    baseline_json: &str,
+) -> Result<VerifiedClaim, String> {
+    let proof = decode_proof(proof_json)?;
 
     let agg_vk: MultiStarkVerifyingKey<SC> = bitcode::deserialize(agg_vk_bytes)
         .map_err(|e| format!("Failed to deserialize aggregation verification key: {}", e))?;
     let baseline_json: VerificationBaselineJson = serde_json::from_str(baseline_json)
         .map_err(|e| format!("Failed to deserialize verification baseline: {}", e))?;
+    let canonical_baseline = baseline_json.canonical_bytes();
+    let app_exe_commit = baseline_json.app_exe_commit.0.to_vec();
     let baseline: VerificationBaseline = baseline_json.into();
 
     match verifier::verify_vm_stark_proof_decoded(&agg_vk, &baseline, &proof) {
-        Ok(()) => Ok(()),
+        Ok(()) => Ok(VerifiedClaim {
+            baseline: canonical_baseline,
+            app_exe_commit,
+            user_public_values: user_public_values(&proof),
+        }),
         Err(e) => Err(format!("OpenVM verification failed: {}", e)),
     }
 }
 
+fn decode_proof(proof_json: &str) -> Result<VmStarkProof, String> {
+    let proof_json: VersionedVmStarkProof = serde_json::from_str(proof_json)
+        .map_err(|e| format!("Failed to deserialize proof JSON: {}", e))?;
+    proof_json
+        .try_into()
+        .map_err(|e| format!("Failed to decode versioned proof: {}", e))
+}
+
+/// Bytes revealed by the guest, one per public value field element.
+fn user_public_values(proof: &VmStarkProof) -> Vec<u8> {
+    proof
+        .user_pvs_proof
+        .public_values
+        .iter()
+        .map(|value| value.as_canonical_u32() as u8)
+        .collect()
+}
+
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(js_namespace = console)]
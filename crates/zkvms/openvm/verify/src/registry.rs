@@ -0,0 +1,44 @@
+//! Registry of guest releases, keyed by their `app_exe_commit`.
+//!
+//! A proof verifies against the baseline shipped with it, which says which
+//! guest executable it proves but not how to read its public values. The
+//! registry maps the `app_exe_commit` of every guest release ever served to
+//! the guest protocol and commitment version it used, so archived proofs stay
+//! interpretable after guest upgrades, and proofs of unknown guests are
+//! rejected explicitly.
+
+use serde::{Deserialize, Serialize};
+
+/// One guest release.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuestRelease {
+    /// `app_exe_commit` of the release, as hex with or without `0x`.
+    pub app_exe_commit: String,
+    /// `guest_protocol::VERSION` the release was built with.
+    pub guest_protocol_version: String,
+    /// `guest_protocol::COMMITMENT_VERSION` of its public values.
+    pub commitment_version: u32,
+    /// Free-form name, e.g. the deployment's release tag.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// Known guest releases, as served at `/data/guest-registry.json`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Registry {
+    pub guests: Vec<GuestRelease>,
+}
+
+impl Registry {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| format!("Failed to parse guest registry: {}", e))
+    }
+
+    /// The release whose executable commits to `app_exe_commit`.
+    pub fn lookup(&self, app_exe_commit: &[u8]) -> Option<&GuestRelease> {
+        self.guests.iter().find(|guest| {
+            hex::decode(guest.app_exe_commit.trim().trim_start_matches("0x"))
+                .is_ok_and(|commit| commit == app_exe_commit)
+        })
+    }
+}
@@ -0,0 +1,154 @@
+//! Verification timings and proof statistics, for benchmarking the verifier.
+//!
+//! Enabled by the `instrumentation` feature. [`verify_stark_stats_native`]
+//! verifies like [`crate::verify_stark_claim`] but reports how long each
+//! phase took and what the proof contained, so verifier-side regressions
+//! show up when OpenVM is upgraded. The `openvm-verify-bench` binary runs it
+//! on a saved prove response.
+
+use openvm_stark_backend::keygen::types::MultiStarkVerifyingKey;
+use openvm_stark_sdk::config::baby_bear_poseidon2::BabyBearPoseidon2Config as SC;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    types::{VerificationBaseline, VerificationBaselineJson, VersionedVmStarkProof, VmStarkProof},
+    user_public_values, verifier,
+};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TYPES: &'static str = r#"
+/** What `verify_stark_stats` measured. Durations are in milliseconds. */
+export interface VerificationStats {
+  verified: boolean;
+  reason?: string;
+  proof_version: string;
+  proof_json_bytes: number;
+  proof_bytes: number;
+  user_pvs_proof_bytes: number;
+  agg_vk_bytes: number;
+  air_count: number;
+  public_value_count: number;
+  user_public_value_count: number;
+  decode_ms: number;
+  vk_decode_ms: number;
+  verify_ms: number;
+  total_ms: number;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "VerificationStats")]
+    pub type VerificationStatsJs;
+}
+
+/// Statistics of one verification.
+#[derive(Clone, Debug, Serialize)]
+pub struct VerificationStats {
+    pub verified: bool,
+    /// Why the proof was rejected, when it was.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub proof_version: String,
+    /// Size of the proof JSON.
+    pub proof_json_bytes: usize,
+    /// Size of the encoded STARK proof within it.
+    pub proof_bytes: usize,
+    /// Size of the encoded user public values proof within it.
+    pub user_pvs_proof_bytes: usize,
+    /// Size of the bitcode-serialized aggregation verifying key.
+    pub agg_vk_bytes: usize,
+    /// Number of AIRs with public values in the STARK proof.
+    pub air_count: usize,
+    /// Public values across all AIRs.
+    pub public_value_count: usize,
+    /// Public values revealed by the guest.
+    pub user_public_value_count: usize,
+    /// Parsing and decoding the proof.
+    pub decode_ms: f64,
+    /// Deserializing the aggregation verifying key.
+    pub vk_decode_ms: f64,
+    /// Checking the decoded proof.
+    pub verify_ms: f64,
+    pub total_ms: f64,
+}
+
+/// Verify an OpenVM STARK proof and return `VerificationStats`, whether or
+/// not it verifies. Throws for deserialization errors, like `verify_stark`.
+#[wasm_bindgen]
+pub fn verify_stark_stats(
+    proof_json: &str,
+    agg_vk_bytes: &[u8],
+    baseline_json: &str,
+) -> Result<VerificationStatsJs, JsValue> {
+    let stats = verify_stark_stats_native(proof_json, agg_vk_bytes, baseline_json)
+        .map_err(|e| JsValue::from_str(&e))?;
+    let json = serde_json::to_string(&stats).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    Ok(js_sys::JSON::parse(&json)?.unchecked_into())
+}
+
+/// Verify an OpenVM STARK proof, timing each phase. Rejected proofs are
+/// reported in the stats; only deserialization errors are returned as `Err`.
+pub fn verify_stark_stats_native(
+    proof_json: &str,
+    agg_vk_bytes: &[u8],
+    baseline_json: &str,
+) -> Result<VerificationStats, String> {
+    let start = now_ms();
+
+    let versioned: VersionedVmStarkProof = serde_json::from_str(proof_json)
+        .map_err(|e| format!("Failed to deserialize proof JSON: {}", e))?;
+    let proof_version = versioned.version.clone();
+    let proof_bytes = versioned.proof.len();
+    let user_pvs_proof_bytes = versioned.user_pvs_proof.len();
+    let proof: VmStarkProof = versioned
+        .try_into()
+        .map_err(|e| format!("Failed to decode versioned proof: {}", e))?;
+    let decoded = now_ms();
+
+    let agg_vk: MultiStarkVerifyingKey<SC> = bitcode::deserialize(agg_vk_bytes)
+        .map_err(|e| format!("Failed to deserialize aggregation verification key: {}", e))?;
+    let vk_decoded = now_ms();
+
+    let baseline: VerificationBaselineJson = serde_json::from_str(baseline_json)
+        .map_err(|e| format!("Failed to deserialize verification baseline: {}", e))?;
+    let baseline: VerificationBaseline = baseline.into();
+
+    let verify_start = now_ms();
+    let result = verifier::verify_vm_stark_proof_decoded(&agg_vk, &baseline, &proof);
+    let end = now_ms();
+
+    Ok(VerificationStats {
+        verified: result.is_ok(),
+        reason: result
+            .err()
+            .map(|e| format!("OpenVM verification failed: {}", e)),
+        proof_version,
+        proof_json_bytes: proof_json.len(),
+        proof_bytes,
+        user_pvs_proof_bytes,
+        agg_vk_bytes: agg_vk_bytes.len(),
+        air_count: proof.inner.public_values.len(),
+        public_value_count: proof.inner.public_values.iter().map(Vec::len).sum(),
+        user_public_value_count: user_public_values(&proof).len(),
+        decode_ms: decoded - start,
+        vk_decode_ms: vk_decoded - decoded,
+        verify_ms: end - verify_start,
+        total_ms: end - start,
+    })
+}
+
+// `std::time::Instant` is unsupported on wasm32-unknown-unknown.
+#[cfg(target_arch = "wasm32")]
+fn now_ms() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_ms() -> f64 {
+    use std::{sync::OnceLock, time::Instant};
+
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    EPOCH.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
@@ -151,6 +151,35 @@ pub struct VerificationBaseline {
     pub expected_def_hook_commit: Option<Digest>,
 }
 
+impl VerificationBaselineJson {
+    /// Fixed-layout encoding for committing to the baseline: every commit in
+    /// declaration order (zeroes for a missing def hook commit), then the
+    /// memory dimensions as big-endian u32s.
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.app_exe_commit.0.to_vec();
+        for vk_commit in [
+            &self.app_vk_commit,
+            &self.leaf_vk_commit,
+            &self.internal_for_leaf_vk_commit,
+            &self.internal_recursive_vk_commit,
+        ] {
+            bytes.extend_from_slice(&vk_commit.cached_commit.0);
+            bytes.extend_from_slice(&vk_commit.vk_pre_hash.0);
+        }
+        let def_hook_commit = self
+            .expected_def_hook_commit
+            .map_or([0; BN254_BYTES], |c| c.0);
+        bytes.extend_from_slice(&def_hook_commit);
+        for height in [
+            self.memory_dimensions.addr_space_height,
+            self.memory_dimensions.address_height,
+        ] {
+            bytes.extend_from_slice(&(height as u32).to_be_bytes());
+        }
+        bytes
+    }
+}
+
 impl From<VerificationBaselineJson> for VerificationBaseline {
     fn from(value: VerificationBaselineJson) -> Self {
         Self {
@@ -0,0 +1,117 @@
+//! Message protocol for running the verifier in a Web Worker.
+//!
+//! Verification takes seconds and would freeze the page on the main thread,
+//! so pages post `VerifierRequest`s to a worker that hands each one to
+//! [`handle_worker_message`] and posts back the `VerifierResponse`. The
+//! message shapes are declared here, next to the calls they map to, so the
+//! worker script stays a thin relay.
+
+use js_sys::{Object, Reflect, Uint8Array};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TYPES: &'static str = r#"
+/** A verifier call to run in a worker. `id` is echoed in the response. */
+export type VerifierRequest =
+  | { id: number; method: "extract_public_values"; proof_json: string }
+  | {
+      id: number;
+      method: "verify_stark" | "verify_stark_outcome";
+      proof_json: string;
+      agg_vk_bytes: Uint8Array;
+      baseline_json: string;
+    }
+  | {
+      id: number;
+      method: "verify_stark_registered";
+      proof_json: string;
+      agg_vk_bytes: Uint8Array;
+      baseline_json: string;
+      registry_json: string;
+    };
+
+/**
+ * The result of the `VerifierRequest` with the same `id`: what the called
+ * function returns, or the message it threw.
+ */
+export type VerifierResponse =
+  | { id: number; ok: true; value: boolean | string | VerificationOutcome }
+  | { id: number; ok: false; error: string };
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "VerifierRequest")]
+    pub type VerifierRequest;
+
+    #[wasm_bindgen(typescript_type = "VerifierResponse")]
+    pub type VerifierResponse;
+}
+
+/// Run a `VerifierRequest` and return its `VerifierResponse`. Never throws:
+/// malformed requests are answered with an error response.
+#[wasm_bindgen]
+pub fn handle_worker_message(request: VerifierRequest) -> VerifierResponse {
+    let request: JsValue = request.into();
+    let id = Reflect::get(&request, &"id".into()).unwrap_or(JsValue::NULL);
+    let (ok, key, value) = match dispatch(&request) {
+        Ok(value) => (true, "value", value),
+        Err(error) => {
+            let message = error
+                .as_string()
+                .or_else(|| error.dyn_ref::<js_sys::Error>().map(|e| e.message().into()))
+                .unwrap_or_else(|| format!("{:?}", error));
+            (false, "error", JsValue::from_str(&message))
+        }
+    };
+
+    let response = Object::new();
+    for (key, value) in [("id", id), ("ok", JsValue::from_bool(ok)), (key, value)] {
+        let _ = Reflect::set(&response, &key.into(), &value);
+    }
+    response.unchecked_into()
+}
+
+fn dispatch(request: &JsValue) -> Result<JsValue, JsValue> {
+    let method = string_field(request, "method")?;
+    let proof_json = string_field(request, "proof_json")?;
+    match method.as_str() {
+        "extract_public_values" => crate::extract_public_values(&proof_json).map(Into::into),
+        "verify_stark" => crate::verify_stark(
+            &proof_json,
+            &bytes_field(request, "agg_vk_bytes")?,
+            &string_field(request, "baseline_json")?,
+        )
+        .map(Into::into),
+        "verify_stark_outcome" => crate::verify_stark_outcome(
+            &proof_json,
+            &bytes_field(request, "agg_vk_bytes")?,
+            &string_field(request, "baseline_json")?,
+        )
+        .map(Into::into),
+        "verify_stark_registered" => crate::verify_stark_registered(
+            &proof_json,
+            &bytes_field(request, "agg_vk_bytes")?,
+            &string_field(request, "baseline_json")?,
+            &string_field(request, "registry_json")?,
+        )
+        .map(Into::into),
+        other => Err(JsValue::from_str(&format!(
+            "Unknown verifier method: {}",
+            other
+        ))),
+    }
+}
+
+fn string_field(request: &JsValue, name: &str) -> Result<String, JsValue> {
+    Reflect::get(request, &name.into())?
+        .as_string()
+        .ok_or_else(|| JsValue::from_str(&format!("Verifier request is missing `{}`", name)))
+}
+
+fn bytes_field(request: &JsValue, name: &str) -> Result<Vec<u8>, JsValue> {
+    Reflect::get(request, &name.into())?
+        .dyn_into::<Uint8Array>()
+        .map(|bytes| bytes.to_vec())
+        .map_err(|_| JsValue::from_str(&format!("Verifier request is missing `{}`", name)))
+}
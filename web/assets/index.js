@@ -1,6 +1,7 @@
 // ——— State ———
 let uplcWasm = null;
 let aikenWasm = null;
+let aikenCompilation = null;  // In-flight AikenCompilation, dropped on edit
 let openVmVerifierWasm = null;
 let aggStarkVkBytes = null;
 let starkProofJson = null;
@@ -18,6 +19,7 @@ let aikenCompiled = false;
 let compiledHex = null;  // Hex from successful Aiken compilation
 let backendStatus = 'unknown';  // 'unknown' | 'available' | 'unavailable'
 let proveAbort = null;   // AbortController for in-flight prove request
+let verifyAbort = null;  // AbortController for in-flight STARK verification
 let proveGeneration = 0; // bumped each run to detect stale callbacks
 let busy = false;        // true while an async action is running
 
@@ -44,10 +46,13 @@ const MCU_TIMING_PHASES = [
   ['verify', 'Verify'],
 ];
 
+const verifierWorker = new VerifierWorker();
+
 restoreMcuHalo2ArtifactCache();
 
 // ——— Client-side proof processing ———
 import { normalizePublicValuesHex } from './proof-utils.js';
+import { VerifierWorker } from './verify-client.js';
 import { highlightAiken } from './aiken-highlight.js';
 import { highlightUplc } from './uplc-highlight.js';
 import { config } from './config.js';
@@ -63,17 +68,58 @@ function getAggStarkVkUrls() {
   ])];
 }
 
+// Download `url` in full. When the connection drops mid-body, as it often
+// does for the multi-MB key on mobile, resume with a Range request for the
+// rest, guarded by the ETag so a key replaced meanwhile restarts the download.
+async function fetchResumable(url, attempts = 4) {
+  const chunks = [];
+  let received = 0;
+  let etag = null;
+
+  for (let attempt = 1; ; attempt++) {
+    const headers = etag && received > 0
+      ? { Range: `bytes=${received}-`, 'If-Range': etag }
+      : {};
+    const resp = await fetch(url, { headers });
+    if (resp.status !== 206) {
+      if (!resp.ok) {
+        throw new Error(`Failed to load ${url} (${resp.status})`);
+      }
+      chunks.length = 0;
+      received = 0;
+    }
+    etag = resp.headers.get('ETag') ?? etag;
+
+    try {
+      const reader = resp.body.getReader();
+      for (;;) {
+        const { done, value } = await reader.read();
+        if (done) break;
+        chunks.push(value);
+        received += value.length;
+      }
+      break;
+    } catch (error) {
+      if (attempt >= attempts || !etag) throw error;
+      console.warn(`Download of ${url} interrupted after ${received} bytes, resuming:`, error);
+    }
+  }
+
+  const bytes = new Uint8Array(received);
+  let offset = 0;
+  for (const chunk of chunks) {
+    bytes.set(chunk, offset);
+    offset += chunk.length;
+  }
+  return bytes;
+}
+
 async function loadAggStarkVk() {
   let lastError = null;
 
   for (const url of getAggStarkVkUrls()) {
     try {
-      const resp = await fetch(url);
-      if (!resp.ok) {
-        throw new Error(`Failed to load agg_stark.vk from ${url} (${resp.status})`);
-      }
-
-      return new Uint8Array(await resp.arrayBuffer());
+      return await fetchResumable(url);
     } catch (error) {
       lastError = error;
     }
@@ -99,7 +145,7 @@ async function loadAikenWasm() {
     const mod = await import('../aiken/aiken_wasm.js');
     await mod.default();
     aikenWasm = mod;
-    setStatus('aikenStatus', 'ready', 'Aiken WASM');
+    setStatus('aikenStatus', 'ready', `Aiken WASM (stdlib ${mod.stdlib_version()})`);
     document.getElementById('compileBtn').disabled = false;
   } catch (e) {
     setStatus('aikenStatus', 'error', 'Aiken WASM');
@@ -132,6 +178,7 @@ async function checkBackend() {
       backendStatus = 'available';
       setStatus('backendStatus', 'ready', 'Backend');
       hideBackendBanner();
+      loadExamples();
       updateProofUIVisibility();
       updateSteps();
     } else {
@@ -224,6 +271,7 @@ function resetFrom(step) {
   if (step <= 1) {
     // Cancel any in-flight prove request
     if (proveAbort) { proveAbort.abort(); proveAbort = null; }
+    if (verifyAbort) { verifyAbort.abort(); verifyAbort = null; }
     proveGeneration++;
     busy = false;
     lastEvalResult = null;
@@ -262,6 +310,45 @@ function hideResult(id) {
   document.getElementById(id).className = 'result-box';
 }
 
+// Fill the "Load example" menu from the backend's corpus
+async function loadExamples() {
+  const select = document.getElementById('exampleSelect');
+  if (select.options.length > 1) return;
+  try {
+    const resp = await fetch(config.apiUrl('/api/examples'));
+    if (!resp.ok) return;
+    const { examples } = await resp.json();
+    for (const example of examples) {
+      const option = document.createElement('option');
+      option.value = example.id;
+      option.textContent = example.aiken_source ? `${example.name} (Aiken)` : example.name;
+      option.title = example.description;
+      option.example = example;
+      select.appendChild(option);
+    }
+    select.hidden = examples.length === 0;
+  } catch (e) {
+    console.warn('Failed to load examples:', e);
+  }
+}
+
+function loadExample() {
+  const select = document.getElementById('exampleSelect');
+  const example = select.selectedOptions[0]?.example;
+  select.value = '';
+  if (!example) return;
+  if (example.aiken_source) {
+    switchTab('aiken');
+    aikenTA.value = example.aiken_source;
+    aikenTA.dispatchEvent(new Event('input'));
+  } else {
+    switchTab('uplcHex');
+    const input = document.getElementById('programHex');
+    input.value = example.program_hex;
+    input.dispatchEvent(new Event('input'));
+  }
+}
+
 // Get the current program hex based on active tab
 function getCurrentHex() {
   if (activeTab === 'aiken' && compiledHex) {
@@ -344,6 +431,30 @@ function toggleUplcPreview() {
 
 // ——— Aiken Compilation ———
 
+// Compiled programs persist in the Cache API by `aiken_source_hash`, so a
+// reload does not recompile unchanged sources. Unavailable outside secure
+// contexts, in which case only the wasm module's in-memory cache applies.
+const AIKEN_PROGRAM_CACHE = 'aiken-programs';
+
+async function loadPersistedProgram(hash) {
+  try {
+    const cache = await caches.open(AIKEN_PROGRAM_CACHE);
+    const response = await cache.match(`/aiken-programs/${hash}`);
+    return response ? await response.text() : null;
+  } catch (e) {
+    return null;
+  }
+}
+
+async function persistProgram(hash, hex) {
+  try {
+    const cache = await caches.open(AIKEN_PROGRAM_CACHE);
+    await cache.put(`/aiken-programs/${hash}`, new Response(hex));
+  } catch (e) {
+    // Persisting is best effort.
+  }
+}
+
 async function compileAiken() {
   if (!aikenWasm) return;
   const source = document.getElementById('aikenSource').value;
@@ -355,11 +466,28 @@ async function compileAiken() {
   btn.disabled = true;
   document.getElementById('compileBtnText').innerHTML = '<span class="spinner"></span> Compiling…';
 
-  await new Promise(resolve => setTimeout(resolve, 0));
-
+  let compilation = null;
   try {
     const t0 = performance.now();
-    const hex = aikenWasm.compile_to_uplc_hex(source);
+    const hash = aikenWasm.aiken_source_hash(source);
+    const persisted = await loadPersistedProgram(hash);
+    if (persisted) aikenWasm.remember_compiled_program(hash, persisted);
+    compilation = new aikenWasm.AikenCompilation(source);
+    aikenCompilation = compilation;
+    // Step through the compilation, yielding between steps so typing stays
+    // responsive; an edit drops the handle and aborts it.
+    while (!compilation.step()) {
+      document.getElementById('compileBtnText').innerHTML =
+        `<span class="spinner"></span> Compiling… ${Math.round(compilation.progress * 100)}%`;
+      await new Promise(resolve => setTimeout(resolve, 0));
+      if (aikenCompilation !== compilation) {
+        compilation.free();
+        return;
+      }
+    }
+    const hex = compilation.hex;
+    const cached = compilation.cached;
+    if (!cached) persistProgram(compilation.hash, hex);
     const dt = performance.now() - t0;
     compiledHex = hex;  // Store the compiled hex (don't overwrite programHex input)
     aikenCompiled = true;
@@ -369,7 +497,7 @@ async function compileAiken() {
     showResult('compileResult', 'success',
       `<div class="result-label">Compiled to UPLC</div>` +
       `<div class="result-value">${hex.length > 120 ? hex.slice(0, 120) + '…' : hex}</div>` +
-      `<div class="timing">Compiled in ${dt.toFixed(1)} ms &mdash; ${hex.length / 2} bytes flat</div>`
+      `<div class="timing">${cached ? 'Loaded from cache' : 'Compiled'} in ${dt.toFixed(1)} ms &mdash; ${hex.length / 2} bytes flat</div>`
     );
   } catch (e) {
     aikenCompiled = false;
@@ -377,9 +505,11 @@ async function compileAiken() {
     updateSteps();
     showResult('compileResult', 'error',
       `<div class="result-label">Compilation Failed</div>` +
-      `<div class="result-value">${escapeHtml(String(e))}</div>`
+      `<div class="result-value">${escapeHtml(e?.message ?? String(e))}</div>`
     );
   }
+  aikenCompilation = null;
+  compilation?.free();
   btn.disabled = false;
   document.getElementById('compileBtnText').textContent = 'Compile';
 };
@@ -472,8 +602,9 @@ async function runEvaluateAndProve() {
     if (!data.success) {
       setPipeFail(2);
       setCardFail('card2');
+      const rejected = data.outcome === 'script_error' || data.outcome === 'budget_exceeded';
       showResult('proveResult', 'error',
-        `<div class="result-label">Proof Generation Failed</div>` +
+        `<div class="result-label">${rejected ? 'Script Rejected' : 'Proof Generation Failed'}</div>` +
         `<div class="result-value">${escapeHtml(data.error || 'Unknown error')}</div>`
       );
       busy = false;
@@ -596,6 +727,7 @@ function runCommitmentCheck() {
     if (expectedResult) {
       details += `<div style="margin-bottom:6px">&#x2705; Result matches: <strong>${escapeHtml(expectedResult)}</strong></div>`;
     }
+    details += `<div style="margin-bottom:6px">Statement: ${escapeHtml(uplcWasm.describe_statement(hex, resultForCommitment))}</div>`;
 
     if (!expectedCommitment) {
       stepDone[2] = true;
@@ -638,6 +770,23 @@ function runCommitmentCheck() {
 
 // ——— Step 4: STARK Verification ———
 
+// Whether the proof claims the commitment to the evaluated program and
+// result. Instant, as it only decodes the proof; verification proves it.
+function claimedCommitmentHtml() {
+  if (!uplcWasm?.check_commitment || !openVmVerifierWasm.extract_public_values || lastEvalResult == null) {
+    return '';
+  }
+  try {
+    const claimed = openVmVerifierWasm.extract_public_values(JSON.stringify(starkProofJson));
+    return uplcWasm.check_commitment(claimed, getCurrentHex(), lastEvalResult)
+      ? `<div style="margin-bottom:6px">&#x2705; The proof claims the commitment to this program and result</div>`
+      : `<div style="margin-bottom:6px">&#x26A0;&#xFE0F; The proof claims a different commitment than this program and result</div>`;
+  } catch (e) {
+    console.warn('Failed to read the proof\'s public values:', e);
+    return '';
+  }
+}
+
 async function runStarkVerification() {
   if (!openVmVerifierWasm) {
     return showResult('starkResult', 'error', 'Verifier WASM unavailable. Reload the page or rebuild the verifier bundle.');
@@ -659,18 +808,20 @@ async function runStarkVerification() {
   setPipeActive(4);
   showResult('starkResult', 'info',
     `<div class="result-label">STARK Verification</div>` +
+    claimedCommitmentHtml() +
     `<div class="result-value">Verifying proof locally in your browser via WASM… this may take a moment.</div>`
   );
 
-  // Yield to allow UI to update
-  await new Promise(resolve => setTimeout(resolve, 50));
+  const abort = new AbortController();
+  verifyAbort = abort;
 
   try {
     const t0 = performance.now();
-    const verified = openVmVerifierWasm.verify_stark(
+    const verified = await verifyStark(
       JSON.stringify(starkProofJson),
       aggStarkVkBytes,
       JSON.stringify(starkVerificationBaselineJson),
+      abort.signal,
     );
     const dt = (performance.now() - t0) / 1000;
 
@@ -686,7 +837,7 @@ async function runStarkVerification() {
       let pvHtml = '';
       if (lastUserPublicValues) {
         pvHtml = `<div class="public-values">` +
-          `<div class="pv-label">Proof public values &mdash; SHA-256( program || result )</div>` +
+          `<div class="pv-label">Proof public values &mdash; status &Vert; SHA-256( statement )</div>` +
           `<div class="pv-value">${lastUserPublicValues}</div>` +
           `</div>`;
       }
@@ -712,6 +863,8 @@ async function runStarkVerification() {
       );
     }
   } catch (e) {
+    // Cancelled by a reset, which already cleared the result
+    if (e?.name === 'AbortError') return;
     if (lastProofDetails) {
       lastProofDetails.verifier = 'error';
       renderProofDetails();
@@ -724,10 +877,22 @@ async function runStarkVerification() {
     );
   }
 
+  if (verifyAbort === abort) verifyAbort = null;
   busy = false;
   updateSteps();
 };
 
+// Verification takes seconds, so it runs in a worker unless the browser
+// has none, in which case it blocks the main thread.
+async function verifyStark(proofJson, aggVkBytes, baselineJson, signal) {
+  if (VerifierWorker.supported()) {
+    return verifierWorker.verifyStark(proofJson, aggVkBytes, baselineJson, { signal });
+  }
+  // Yield to allow UI to update
+  await new Promise(resolve => setTimeout(resolve, 50));
+  return openVmVerifierWasm.verify_stark(proofJson, aggVkBytes, baselineJson);
+}
+
 // ——— Step 5: ZKMCU BLE Verification ———
 
 async function runMcuBleVerification() {
@@ -1737,6 +1902,11 @@ function syncScroll() {
 
 const aikenTA = document.getElementById('aikenSource');
 aikenTA.addEventListener('input', () => {
+  if (aikenCompilation) {
+    aikenCompilation = null;
+    document.getElementById('compileBtn').disabled = false;
+    document.getElementById('compileBtnText').textContent = 'Compile';
+  }
   aikenCompiled = false;
   resetFrom(1);
   syncHighlight();
@@ -1772,6 +1942,7 @@ document.getElementById('programHex').addEventListener('input', () => {
 // Button event listeners
 document.getElementById('tabBtnAiken').addEventListener('click', () => switchTab('aiken'));
 document.getElementById('tabBtnUplcHex').addEventListener('click', () => switchTab('uplcHex'));
+document.getElementById('exampleSelect').addEventListener('change', loadExample);
 document.getElementById('toggleUplcPreview').addEventListener('click', toggleUplcPreview);
 document.getElementById('compileBtn').addEventListener('click', compileAiken);
 document.getElementById('evalProveBtn').addEventListener('click', runEvaluateAndProve);
@@ -0,0 +1,76 @@
+/**
+ * Promise API over the verifier worker (see verify-worker.js).
+ *
+ * Calls are queued in a single worker and resolve with what the verifier
+ * function returns. A call can't be interrupted once running in WASM, so
+ * cancelling one terminates the worker, rejecting every call in flight with
+ * an `AbortError`; the next call starts a fresh worker.
+ */
+
+export class VerifierWorker {
+  constructor() {
+    this.worker = null;
+    this.nextId = 0;
+    this.pending = new Map();
+  }
+
+  static supported() {
+    return typeof Worker !== 'undefined';
+  }
+
+  /**
+   * Run `method` of the verifier with `args` (the request fields besides
+   * `id` and `method`). The returned promise has a `cancel()` method, and is
+   * also cancelled when `signal` aborts.
+   */
+  call(method, args, { signal } = {}) {
+    const worker = this.#start();
+    const id = this.nextId++;
+    const cancel = () => {
+      if (this.pending.has(id)) this.terminate(new DOMException('Verification cancelled', 'AbortError'));
+    };
+    const promise = new Promise((resolve, reject) => {
+      this.pending.set(id, { resolve, reject });
+      worker.postMessage({ id, method, ...args });
+    });
+    if (signal?.aborted) cancel();
+    else signal?.addEventListener('abort', cancel, { once: true });
+    promise.cancel = cancel;
+    return promise;
+  }
+
+  verifyStark(proofJson, aggVkBytes, baselineJson, options) {
+    return this.call('verify_stark', {
+      proof_json: proofJson,
+      agg_vk_bytes: aggVkBytes,
+      baseline_json: baselineJson,
+    }, options);
+  }
+
+  /** Stop the worker, rejecting calls in flight with `reason`. */
+  terminate(reason = new DOMException('Verifier worker terminated', 'AbortError')) {
+    this.worker?.terminate();
+    this.worker = null;
+    for (const { reject } of this.pending.values()) reject(reason);
+    this.pending.clear();
+  }
+
+  #start() {
+    if (this.worker) return this.worker;
+    const worker = new Worker(new URL('./verify-worker.js', import.meta.url), { type: 'module' });
+    worker.onmessage = (event) => {
+      const { id, ok, value, error } = event.data;
+      const call = this.pending.get(id);
+      if (!call) return;
+      this.pending.delete(id);
+      if (ok) call.resolve(value);
+      else call.reject(new Error(error));
+    };
+    worker.onerror = (event) => {
+      event.preventDefault();
+      this.terminate(new Error(`Verifier worker failed: ${event.message || 'unknown error'}`));
+    };
+    this.worker = worker;
+    return worker;
+  }
+}
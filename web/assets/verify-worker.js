@@ -0,0 +1,20 @@
+/**
+ * Web Worker running the OpenVM verifier off the main thread.
+ *
+ * Every message is a `VerifierRequest` from the verifier crate, answered with
+ * the `VerifierResponse` that `handle_worker_message` builds.
+ */
+
+import init, { handle_worker_message } from '../openvm-verifier/openvm_wasm_verifier.js';
+
+const ready = init();
+
+self.onmessage = async (event) => {
+  try {
+    await ready;
+  } catch (e) {
+    self.postMessage({ id: event.data?.id, ok: false, error: `Verifier WASM failed to load: ${e}` });
+    return;
+  }
+  self.postMessage(handle_worker_message(event.data));
+};
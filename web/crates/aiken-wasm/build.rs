@@ -0,0 +1,153 @@
+//! Embeds the Aiken stdlib release selected by the `stdlib-v*` features.
+//!
+//! `fetch-deps.sh` downloads every supported release under `stdlib/<tag>/`.
+//! This generates `$OUT_DIR/stdlib.rs` with the selected release's tag, its
+//! modules' sources, and an order to type-check them in, derived from their
+//! `use` imports.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Supported stdlib releases, oldest first: the feature selecting each, and
+/// its tag. Keep in sync with `STDLIB_VERSIONS` in `fetch-deps.sh`.
+const STDLIB_VERSIONS: [(&str, &str); 2] = [("stdlib-v2", "v2.2.0"), ("stdlib-v3", "v3.0.0")];
+
+/// The newest release whose feature is enabled, so `stdlib-v3` can be
+/// enabled on top of the default `stdlib-v2`.
+fn selected_version() -> &'static str {
+    STDLIB_VERSIONS
+        .iter()
+        .rev()
+        .find(|(feature, _)| {
+            let var = format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"));
+            std::env::var_os(var).is_some()
+        })
+        .map(|(_, tag)| *tag)
+        .expect("enable one of the stdlib-v* features")
+}
+
+/// Every `.ak` module under `dir`, by module name (`aiken/collection/list`).
+fn collect_modules(root: &Path, dir: &Path, modules: &mut BTreeMap<String, PathBuf>) {
+    let entries = std::fs::read_dir(dir).unwrap_or_else(|e| panic!("{}: {}", dir.display(), e));
+    for entry in entries {
+        let path = entry.expect("stdlib entry").path();
+        if path.is_dir() {
+            collect_modules(root, &path, modules);
+            continue;
+        }
+        let relative = path.strip_prefix(root).expect("module under stdlib root");
+        let Some(name) = relative.to_str().and_then(|name| name.strip_suffix(".ak")) else {
+            continue;
+        };
+        // Skip `*.test.ak` and the like, which are not importable modules.
+        if !name.contains('.') {
+            modules.insert(name.replace('\\', "/"), path);
+        }
+    }
+}
+
+/// The stdlib modules that `source` imports.
+fn imports<'a>(source: &'a str, modules: &BTreeMap<String, PathBuf>) -> BTreeSet<&'a str> {
+    source
+        .lines()
+        .filter_map(|line| line.trim_start().strip_prefix("use "))
+        .filter_map(|rest| rest.split(|c: char| c == '.' || c.is_whitespace()).next())
+        .filter(|name| modules.contains_key(*name))
+        .collect()
+}
+
+/// Module names in dependency order, ties broken alphabetically so the
+/// order is stable across builds.
+fn type_check_order(modules: &BTreeMap<String, PathBuf>) -> Vec<String> {
+    let sources: BTreeMap<&str, String> = modules
+        .iter()
+        .map(|(name, path)| {
+            let source = std::fs::read_to_string(path)
+                .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+            (name.as_str(), source)
+        })
+        .collect();
+    let mut pending: BTreeMap<&str, BTreeSet<&str>> = sources
+        .iter()
+        .map(|(name, source)| {
+            let mut deps = imports(source, modules);
+            deps.remove(name);
+            (*name, deps)
+        })
+        .collect();
+
+    let mut order = Vec::with_capacity(pending.len());
+    while let Some(next) = pending
+        .iter()
+        .find(|(_, deps)| deps.is_empty())
+        .map(|(name, _)| *name)
+    {
+        pending.remove(next);
+        for deps in pending.values_mut() {
+            deps.remove(next);
+        }
+        order.push(next.to_string());
+    }
+    if !pending.is_empty() {
+        let cycle: Vec<&str> = pending.keys().copied().collect();
+        panic!("stdlib modules import each other: {}", cycle.join(", "));
+    }
+    order
+}
+
+fn main() {
+    let tag = selected_version();
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("stdlib")
+        .join(tag);
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    let root = dir.join("lib");
+    if !root.is_dir() {
+        panic!(
+            "stdlib {} is missing from {}; run fetch-deps.sh",
+            tag,
+            dir.display()
+        );
+    }
+    let mut modules = BTreeMap::new();
+    collect_modules(&root, &root, &mut modules);
+
+    let mut generated = String::new();
+    writeln!(generated, "/// Tag of the embedded stdlib release.").unwrap();
+    writeln!(generated, "pub const VERSION: &str = {:?};", tag).unwrap();
+    writeln!(generated).unwrap();
+    writeln!(
+        generated,
+        "/// The type-checking sequence in which we must compile the modules:\n\
+         /// each after the modules it imports."
+    )
+    .unwrap();
+    writeln!(generated, "pub const MODULES_SEQUENCE: &[&str] = &[").unwrap();
+    for name in type_check_order(&modules) {
+        writeln!(generated, "    {:?},", name).unwrap();
+    }
+    writeln!(generated, "];").unwrap();
+    writeln!(generated).unwrap();
+    writeln!(
+        generated,
+        "pub fn modules() -> HashMap<&'static str, &'static str> {{"
+    )
+    .unwrap();
+    writeln!(generated, "    let mut m = HashMap::new();").unwrap();
+    for (name, path) in &modules {
+        let path = path.to_str().expect("UTF-8 stdlib path");
+        writeln!(
+            generated,
+            "    m.insert({:?}, include_str!({:?}));",
+            name, path
+        )
+        .unwrap();
+    }
+    writeln!(generated, "    m\n}}").unwrap();
+
+    let out = PathBuf::from(std::env::var_os("OUT_DIR").expect("OUT_DIR")).join("stdlib.rs");
+    std::fs::write(&out, generated).unwrap_or_else(|e| panic!("{}: {}", out.display(), e));
+}
@@ -1,21 +1,26 @@
 #!/usr/bin/env bash
-# Downloads the Aiken stdlib and fuzz library sources needed for compilation.
-# These .ak files are embedded into the WASM binary via include_str!().
+# Downloads the Aiken stdlib releases needed for compilation, under
+# stdlib/<tag>/. build.rs embeds the one the stdlib-v* features select into
+# the WASM binary via include_str!().
 set -euo pipefail
 
-STDLIB_VERSION="v2.2.0"
+# Keep in sync with STDLIB_VERSIONS in build.rs.
+STDLIB_VERSIONS=("v2.2.0" "v3.0.0")
 
 cd "$(dirname "$0")"
 
-echo "==> Fetching stdlib ${STDLIB_VERSION}…"
 rm -rf stdlib && mkdir -p stdlib
-curl -sL \
-  -H "Accept: application/vnd.github+json" \
-  -H "X-GitHub-Api-Version: 2022-11-28" \
-  "https://api.github.com/repos/aiken-lang/stdlib/tarball/${STDLIB_VERSION}" \
-  -o stdlib.tar
-tar -xf stdlib.tar --strip-components 1 -C stdlib
-rm stdlib.tar
-echo "   stdlib extracted to ./stdlib/"
+for version in "${STDLIB_VERSIONS[@]}"; do
+  echo "==> Fetching stdlib ${version}…"
+  mkdir -p "stdlib/${version}"
+  curl -sL \
+    -H "Accept: application/vnd.github+json" \
+    -H "X-GitHub-Api-Version: 2022-11-28" \
+    "https://api.github.com/repos/aiken-lang/stdlib/tarball/${version}" \
+    -o stdlib.tar
+  tar -xf stdlib.tar --strip-components 1 -C "stdlib/${version}"
+  rm stdlib.tar
+  echo "   stdlib extracted to ./stdlib/${version}/"
+done
 
 echo "==> Done. Ready to build."
@@ -0,0 +1,50 @@
+//! Compiled programs by content hash, so recompiling unchanged source is
+//! instant.
+
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+
+/// Programs kept in memory; the least recently used is evicted first.
+const CAPACITY: usize = 32;
+
+/// Content hash of compiling `source`: SHA-256 over this crate's version, the
+/// embedded stdlib release and the source, hex-encoded. Sources with equal
+/// hashes compile to the same program, so callers can persist programs by it.
+pub fn source_hash(source: &str) -> String {
+    let mut hasher = Sha256::new();
+    for part in [
+        env!("CARGO_PKG_VERSION"),
+        crate::vendor::stdlib::VERSION,
+        source,
+    ] {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part.as_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+thread_local! {
+    /// Entries by `source_hash`, most recently used last.
+    static PROGRAMS: RefCell<Vec<(String, Vec<u8>)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The program compiled for `hash`, if still cached.
+pub(crate) fn get(hash: &str) -> Option<Vec<u8>> {
+    PROGRAMS.with_borrow_mut(|programs| {
+        let index = programs.iter().position(|(entry, _)| entry == hash)?;
+        let entry = programs.remove(index);
+        let program = entry.1.clone();
+        programs.push(entry);
+        Some(program)
+    })
+}
+
+pub(crate) fn insert(hash: String, program: Vec<u8>) {
+    PROGRAMS.with_borrow_mut(|programs| {
+        programs.retain(|(entry, _)| *entry != hash);
+        if programs.len() == CAPACITY {
+            programs.remove(0);
+        }
+        programs.push((hash, program));
+    });
+}
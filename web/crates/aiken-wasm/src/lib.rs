@@ -1,9 +1,15 @@
 use wasm_bindgen::prelude::*;
 
+mod cache;
 mod vendor;
 
+pub use cache::source_hash;
+
 use aiken_lang::{
-    ast::{DataTypeKey, Definition, FunctionAccessKey, ModuleKind, Tracing, TraceLevel},
+    ast::{
+        DataTypeKey, Definition, FunctionAccessKey, ModuleKind, TraceLevel, Tracing, TypedDataType,
+        TypedFunction,
+    },
     builtins,
     expr::TypedExpr,
     gen_uplc::CodeGenerator,
@@ -14,7 +20,8 @@ use aiken_lang::{
     utils, IdGenerator,
 };
 use indexmap::IndexMap;
-use std::collections::{BTreeSet, HashMap};
+use std::collections::HashMap;
+use std::fmt;
 use uplc::ast::{DeBruijn, Program};
 
 const KIND: ModuleKind = ModuleKind::Validator;
@@ -22,144 +29,266 @@ const NAME: &str = "play";
 const PLUTUS_VERSION: PlutusVersion = PlutusVersion::V3;
 const TRACING: Tracing = Tracing::All(TraceLevel::Verbose);
 
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TYPES: &'static str = r#"
+/** Compilation step an Aiken source failed at. */
+export type AikenCompileStage = "input" | "stdlib" | "parse" | "type" | "no_test" | "codegen";
+
+/** Error thrown by `compile_to_uplc_hex` and `AikenCompilation.step`. */
+export interface AikenCompileError extends Error {
+  stage: AikenCompileStage;
+}
+
+/** A compiled program and the content hash of its source, as returned by
+ * `compile_to_uplc_hex_cached`. */
+export interface AikenCompiledProgram {
+  /** `aiken_source_hash` of the source, to persist the program by. */
+  hash: string;
+  hex: string;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "AikenCompiledProgram")]
+    pub type AikenCompiledProgram;
+}
+
 /// Initialize panic hook for better error messages in the browser console.
 #[wasm_bindgen(start)]
 pub fn init() {
     console_error_panic_hook::set_once();
 }
 
+/// Tag of the embedded Aiken stdlib release that sources can `use`, such as
+/// `v2.2.0`. Projects written against another release may not compile.
+#[wasm_bindgen]
+pub fn stdlib_version() -> String {
+    vendor::stdlib::VERSION.to_string()
+}
+
 /// Compile Aiken source code to UPLC flat-encoded hex bytes.
 ///
 /// The source must contain at least one `test`. The first test is compiled
 /// to a standalone UPLC program (no arguments needed) and returned as hex.
+/// Failures throw an `AikenCompileError` carrying the step that failed.
+///
+/// Blocks until compilation finishes; use `AikenCompilation` to keep the
+/// page responsive.
 #[wasm_bindgen]
 pub fn compile_to_uplc_hex(source: &str) -> Result<String, JsValue> {
-    if source.trim().is_empty() {
-        return Err(JsValue::from_str("Empty source code"));
+    Ok(hex::encode(compile_to_flat(source)?))
+}
+
+/// Like `compile_to_uplc_hex`, but returns an `AikenCompiledProgram` with the
+/// source's content hash. The last programs compiled are kept in memory by
+/// hash, so compiling unchanged source again returns at once.
+#[wasm_bindgen]
+pub fn compile_to_uplc_hex_cached(source: &str) -> Result<AikenCompiledProgram, JsValue> {
+    let hex = compile_to_uplc_hex(source)?;
+    let program = js_sys::Object::new();
+    // Setting a property on a freshly created object cannot fail.
+    let _ = js_sys::Reflect::set(&program, &"hash".into(), &source_hash(source).into());
+    let _ = js_sys::Reflect::set(&program, &"hex".into(), &hex.into());
+    Ok(program.unchecked_into())
+}
+
+/// Content hash of compiling `source` with this module: equal hashes compile
+/// to the same program. Use it to look up programs persisted by the page,
+/// such as in the Cache API, before compiling.
+#[wasm_bindgen]
+pub fn aiken_source_hash(source: &str) -> String {
+    source_hash(source)
+}
+
+/// Hand back a program persisted by `aiken_source_hash`, so the next
+/// compilation of that source is served from memory.
+#[wasm_bindgen]
+pub fn remember_compiled_program(hash: &str, hex: &str) -> Result<(), JsValue> {
+    let program = hex::decode(hex.trim())
+        .map_err(|e| JsValue::from_str(&format!("Hex decode error: {}", e)))?;
+    cache::insert(hash.to_string(), program);
+    Ok(())
+}
+
+/// Compile the first test of an Aiken source to flat-encoded UPLC bytes,
+/// like `compile_to_uplc_hex` but usable outside the browser.
+pub fn compile_to_flat(source: &str) -> Result<Vec<u8>, CompileError> {
+    let mut compilation = AikenCompilation::start(source)?;
+    while !compilation.advance()? {}
+    Ok(compilation.program.unwrap_or_default())
+}
+
+/// Why a compilation failed: the `AikenCompileStage` it failed at, and a
+/// message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileError {
+    pub stage: &'static str,
+    pub message: String,
+}
+
+impl CompileError {
+    fn new(stage: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            stage,
+            message: message.into(),
+        }
     }
+}
 
-    let id_gen = IdGenerator::new();
-
-    // --- Bootstrap built-in types ---
-    let mut module_types: HashMap<String, TypeInfo> = HashMap::new();
-    module_types.insert("aiken".to_string(), builtins::prelude(&id_gen));
-    module_types.insert("aiken/builtin".to_string(), builtins::plutus(&id_gen));
-
-    let mut functions = builtins::prelude_functions(&id_gen, &module_types);
-    let mut data_types = builtins::prelude_data_types(&id_gen);
-    let mut constants: IndexMap<FunctionAccessKey, TypedExpr> = IndexMap::new();
-    let mut module_sources: HashMap<String, (String, LineNumbers)> = HashMap::new();
-    let mut dependencies: BTreeSet<String> = BTreeSet::new();
-
-    // --- Load stdlib ---
-    setup_dependency(
-        "stdlib",
-        vendor::stdlib::modules(),
-        &vendor::stdlib::MODULES_SEQUENCE[..],
-        &id_gen,
-        &mut module_types,
-        &mut functions,
-        &mut constants,
-        &mut data_types,
-        &mut module_sources,
-    )
-    .map_err(|e| JsValue::from_str(&e))?;
-    dependencies.insert("stdlib".to_string());
-
-    // --- Parse user source ---
-    let (mut ast, _extra) = parser::module(source, KIND)
-        .map_err(|errs| {
-            let msgs: Vec<String> = errs.iter().map(|e| format!("{e}")).collect();
-            JsValue::from_str(&format!("Parse error(s):\n{}", msgs.join("\n")))
-        })?;
-    ast.name = NAME.to_string();
-
-    // --- Type-check ---
-    let mut warnings = vec![];
-    let package_name = format!("aiken-lang/{NAME}");
-    let ast = ast
-        .infer(
-            &id_gen,
-            KIND,
-            &package_name,
-            &module_types,
-            TRACING,
-            &mut warnings,
-            None,
-        )
-        .map_err(|e| JsValue::from_str(&format!("Type error: {e}")))?;
-
-    // Register definitions for code generation
-    module_sources.insert(
-        NAME.to_string(),
-        (source.to_string(), LineNumbers::new(source)),
-    );
-    module_types.insert(NAME.to_string(), ast.type_info.clone());
-    ast.register_definitions(&mut functions, &mut constants, &mut data_types);
-
-    // --- Find tests (zero-argument unit tests compile to standalone programs) ---
-    let tests: Vec<_> = ast
-        .definitions()
-        .filter_map(|def| match def {
-            Definition::Test(t) if t.arguments.is_empty() => Some(t),
-            _ => None,
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} error: {}", self.stage, self.message)
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+/// An `AikenCompileError`: a JS `Error` with the failing `stage`.
+impl From<CompileError> for JsValue {
+    fn from(e: CompileError) -> Self {
+        let error = js_sys::Error::new(&e.message);
+        // Setting a property on a freshly created object cannot fail.
+        let _ = js_sys::Reflect::set(&error, &"stage".into(), &e.stage.into());
+        error.into()
+    }
+}
+
+/// An Aiken compilation run in small steps, so the caller can yield to the
+/// event loop between them and abort by dropping the handle.
+///
+/// Each `step()` type-checks one stdlib module; the last one compiles the
+/// source itself. A source compiled recently is served from memory, and its
+/// first `step()` returns `true`:
+///
+/// ```js
+/// const compilation = new AikenCompilation(source);
+/// while (!compilation.step()) {
+///   await new Promise(resolve => setTimeout(resolve, 0));
+///   if (aborted) return compilation.free();
+/// }
+/// const hex = compilation.hex;
+/// ```
+#[wasm_bindgen]
+pub struct AikenCompilation {
+    source: String,
+    id_gen: IdGenerator,
+    module_types: HashMap<String, TypeInfo>,
+    functions: IndexMap<FunctionAccessKey, TypedFunction>,
+    constants: IndexMap<FunctionAccessKey, TypedExpr>,
+    data_types: IndexMap<DataTypeKey, TypedDataType>,
+    module_sources: HashMap<String, (String, LineNumbers)>,
+    stdlib: HashMap<&'static str, &'static str>,
+    /// Stdlib modules type-checked so far.
+    stdlib_checked: usize,
+    /// `source_hash` of the source.
+    hash: String,
+    /// Whether the program came from the cache.
+    cached: bool,
+    /// Compiled program, once done.
+    program: Option<Vec<u8>>,
+}
+
+#[wasm_bindgen]
+impl AikenCompilation {
+    /// Start compiling `source`. Throws an `AikenCompileError` if it is empty.
+    #[wasm_bindgen(constructor)]
+    pub fn new(source: &str) -> Result<AikenCompilation, JsValue> {
+        Ok(Self::start(source)?)
+    }
+
+    /// Run the next step; `true` once the program is compiled. Throws an
+    /// `AikenCompileError` if the step fails.
+    pub fn step(&mut self) -> Result<bool, JsValue> {
+        Ok(self.advance()?)
+    }
+
+    /// Fraction of the steps done, from 0 to 1.
+    #[wasm_bindgen(getter)]
+    pub fn progress(&self) -> f64 {
+        if self.program.is_some() {
+            return 1.0;
+        }
+        let done = self.stdlib_checked + usize::from(self.program.is_some());
+        done as f64 / (vendor::stdlib::MODULES_SEQUENCE.len() + 1) as f64
+    }
+
+    /// The compiled program as flat hex, once `step()` returned `true`.
+    #[wasm_bindgen(getter)]
+    pub fn hex(&self) -> Option<String> {
+        self.program.as_ref().map(hex::encode)
+    }
+
+    /// Content hash of the source, see `aiken_source_hash`.
+    #[wasm_bindgen(getter)]
+    pub fn hash(&self) -> String {
+        self.hash.clone()
+    }
+
+    /// Whether the program was served from memory instead of compiled.
+    #[wasm_bindgen(getter)]
+    pub fn cached(&self) -> bool {
+        self.cached
+    }
+}
+
+impl AikenCompilation {
+    fn start(source: &str) -> Result<Self, CompileError> {
+        if source.trim().is_empty() {
+            return Err(CompileError::new("input", "Empty source code"));
+        }
+
+        let hash = source_hash(source);
+        let program = cache::get(&hash);
+
+        // --- Bootstrap built-in types ---
+        let id_gen = IdGenerator::new();
+        let mut module_types: HashMap<String, TypeInfo> = HashMap::new();
+        module_types.insert("aiken".to_string(), builtins::prelude(&id_gen));
+        module_types.insert("aiken/builtin".to_string(), builtins::plutus(&id_gen));
+
+        Ok(Self {
+            source: source.to_string(),
+            functions: builtins::prelude_functions(&id_gen, &module_types),
+            data_types: builtins::prelude_data_types(&id_gen),
+            id_gen,
+            module_types,
+            constants: IndexMap::new(),
+            module_sources: HashMap::new(),
+            stdlib: vendor::stdlib::modules(),
+            stdlib_checked: 0,
+            hash,
+            cached: program.is_some(),
+            program,
         })
-        .collect();
-
-    if tests.is_empty() {
-        return Err(JsValue::from_str(
-            "No test found. Aiken source must contain at least one zero-argument `test`.\n\
-             Example:\n\
-             test my_test() {\n  \
-               1 + 1 == 2\n\
-             }",
-        ));
     }
 
-    // --- Generate UPLC for first test using generate_raw ---
-    let mut generator = CodeGenerator::new(
-        PLUTUS_VERSION,
-        utils::indexmap::as_ref_values(&functions),
-        utils::indexmap::as_ref_values(&constants),
-        utils::indexmap::as_ref_values(&data_types),
-        utils::indexmap::as_str_ref_values(&module_types),
-        utils::indexmap::as_str_ref_values(&module_sources),
-        TRACING,
-    );
-
-    let test = tests[0];
-    let program = generator.generate_raw(&test.body, &[], NAME);
-    let program: Program<DeBruijn> = program
-        .try_into()
-        .map_err(|e| JsValue::from_str(&format!("UPLC conversion error: {e:?}")))?;
-
-    // Use to_flat() for raw flat bytes, NOT to_hex() which wraps in CBOR.
-    // uplc-turbo's flat::decode() expects raw flat bytes.
-    let flat_bytes = program
-        .to_flat()
-        .map_err(|e| JsValue::from_str(&format!("Flat encoding error: {e:?}")))?;
-
-    Ok(hex::encode(flat_bytes))
-}
-
-/// Set up a dependency by parsing and type-checking its modules in order.
-fn setup_dependency(
-    context: &str,
-    modules: HashMap<&str, &str>,
-    sequence: &[&str],
-    id_gen: &IdGenerator,
-    module_types: &mut HashMap<String, TypeInfo>,
-    functions: &mut IndexMap<FunctionAccessKey, aiken_lang::ast::TypedFunction>,
-    constants: &mut IndexMap<FunctionAccessKey, TypedExpr>,
-    data_types: &mut IndexMap<DataTypeKey, aiken_lang::ast::TypedDataType>,
-    module_sources: &mut HashMap<String, (String, LineNumbers)>,
-) -> Result<(), String> {
-    for module_name in sequence {
-        let module_src = modules
-            .get(module_name)
-            .ok_or_else(|| {
-                format!("couldn't find sources for '{module_name}' when compiling {context}")
-            })?;
+    /// Run the next step; `true` once the program is compiled.
+    fn advance(&mut self) -> Result<bool, CompileError> {
+        if self.program.is_some() {
+            return Ok(true);
+        }
+        match vendor::stdlib::MODULES_SEQUENCE.get(self.stdlib_checked) {
+            Some(module_name) => {
+                self.check_dependency_module("stdlib", module_name)
+                    .map_err(|e| CompileError::new("stdlib", e))?;
+                self.stdlib_checked += 1;
+                Ok(false)
+            }
+            None => {
+                let program = self.compile_source()?;
+                cache::insert(self.hash.clone(), program.clone());
+                self.program = Some(program);
+                Ok(true)
+            }
+        }
+    }
+
+    /// Parse and type-check a dependency module, registering its definitions.
+    fn check_dependency_module(&mut self, context: &str, module_name: &str) -> Result<(), String> {
+        let module_src = self.stdlib.get(module_name).ok_or_else(|| {
+            format!("couldn't find sources for '{module_name}' when compiling {context}")
+        })?;
 
         let (mut ast, _extra) = parser::module(module_src, ModuleKind::Lib)
             .map_err(|e| format!("Parse error in {context}/{module_name}: {e:?}"))?;
@@ -169,24 +298,112 @@ fn setup_dependency(
         let mut warnings = vec![];
         let ast = ast
             .infer(
-                id_gen,
+                &self.id_gen,
                 ModuleKind::Lib,
                 module_name,
-                module_types,
+                &self.module_types,
                 Tracing::silent(),
                 &mut warnings,
                 None,
             )
             .map_err(|e| format!("Type error in {context}/{module_name}: {e}"))?;
 
-        ast.register_definitions(functions, constants, data_types);
+        ast.register_definitions(
+            &mut self.functions,
+            &mut self.constants,
+            &mut self.data_types,
+        );
 
-        module_sources.insert(
+        self.module_sources.insert(
             module_name.to_string(),
             (module_src.to_string(), LineNumbers::new(module_src)),
         );
 
-        module_types.insert(module_name.to_string(), ast.type_info);
+        self.module_types
+            .insert(module_name.to_string(), ast.type_info);
+        Ok(())
+    }
+
+    /// Parse, type-check, and generate UPLC for the first test of the source.
+    fn compile_source(&mut self) -> Result<Vec<u8>, CompileError> {
+        let source = self.source.as_str();
+
+        // --- Parse user source ---
+        let (mut ast, _extra) = parser::module(source, KIND).map_err(|errs| {
+            let msgs: Vec<String> = errs.iter().map(|e| format!("{e}")).collect();
+            CompileError::new("parse", format!("Parse error(s):\n{}", msgs.join("\n")))
+        })?;
+        ast.name = NAME.to_string();
+
+        // --- Type-check ---
+        let mut warnings = vec![];
+        let package_name = format!("aiken-lang/{NAME}");
+        let ast = ast
+            .infer(
+                &self.id_gen,
+                KIND,
+                &package_name,
+                &self.module_types,
+                TRACING,
+                &mut warnings,
+                None,
+            )
+            .map_err(|e| CompileError::new("type", format!("Type error: {e}")))?;
+
+        // Register definitions for code generation
+        self.module_sources.insert(
+            NAME.to_string(),
+            (source.to_string(), LineNumbers::new(source)),
+        );
+        self.module_types
+            .insert(NAME.to_string(), ast.type_info.clone());
+        ast.register_definitions(
+            &mut self.functions,
+            &mut self.constants,
+            &mut self.data_types,
+        );
+
+        // --- Find tests (zero-argument unit tests compile to standalone programs) ---
+        let tests: Vec<_> = ast
+            .definitions()
+            .filter_map(|def| match def {
+                Definition::Test(t) if t.arguments.is_empty() => Some(t),
+                _ => None,
+            })
+            .collect();
+
+        if tests.is_empty() {
+            return Err(CompileError::new(
+                "no_test",
+                "No test found. Aiken source must contain at least one zero-argument `test`.\n\
+                 Example:\n\
+                 test my_test() {\n  \
+                   1 + 1 == 2\n\
+                 }",
+            ));
+        }
+
+        // --- Generate UPLC for first test using generate_raw ---
+        let mut generator = CodeGenerator::new(
+            PLUTUS_VERSION,
+            utils::indexmap::as_ref_values(&self.functions),
+            utils::indexmap::as_ref_values(&self.constants),
+            utils::indexmap::as_ref_values(&self.data_types),
+            utils::indexmap::as_str_ref_values(&self.module_types),
+            utils::indexmap::as_str_ref_values(&self.module_sources),
+            TRACING,
+        );
+
+        let test = tests[0];
+        let program = generator.generate_raw(&test.body, &[], NAME);
+        let program: Program<DeBruijn> = program
+            .try_into()
+            .map_err(|e| CompileError::new("codegen", format!("UPLC conversion error: {e:?}")))?;
+
+        // Use to_flat() for raw flat bytes, NOT to_hex() which wraps in CBOR.
+        // uplc-turbo's flat::decode() expects raw flat bytes.
+        program
+            .to_flat()
+            .map_err(|e| CompileError::new("codegen", format!("Flat encoding error: {e:?}")))
     }
-    Ok(())
 }
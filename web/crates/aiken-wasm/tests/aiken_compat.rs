@@ -0,0 +1,114 @@
+//! Compiles the `tests/aiken-compat` corpus with the vendored Aiken compiler
+//! and checks each program against its golden `.json`: what this repo's
+//! evaluators return for it and, once pinned, its flat bytes and the
+//! commitment the guest reveals for it.
+//!
+//! Upgrading aiken-lang fails here when the new codegen changes what a
+//! program evaluates to, or when it changes a pinned program's bytes, which
+//! changes every commitment to it. After reviewing such a change, rerun with
+//! `AIKEN_COMPAT_BLESS=1` to pin the new programs (results are never
+//! blessed). `make aiken-compat` also runs the pinned programs in the built
+//! guest, and enables `uplc-evaluators/determinism-audit` so each evaluator
+//! must also agree with itself.
+
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use uplc_evaluators::impls::{UplcAikenEvaluator, UplcTurboEvaluator};
+use uplc_evaluators::{audited, UplcEvaluator};
+
+/// The guest's evaluator, whose rendering of results it commits to.
+const GUEST_EVALUATOR: &str = "uplc-turbo";
+
+fn corpus() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/aiken-compat");
+    let mut sources: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .expect("aiken-compat corpus")
+        .map(|entry| entry.expect("corpus entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ak"))
+        .collect();
+    sources.sort();
+    sources
+}
+
+fn evaluate(evaluator: &dyn UplcEvaluator, program: &[u8]) -> String {
+    match evaluator.evaluate_program_bytes(program) {
+        Ok(result) => result.to_string(),
+        Err(e) => format!("error: {}", e),
+    }
+}
+
+#[test]
+fn compiled_corpus_matches_goldens() {
+    let bless = std::env::var_os("AIKEN_COMPAT_BLESS").is_some();
+    let evaluators: [(&str, Box<dyn UplcEvaluator>); 2] = [
+        ("uplc-turbo", audited(Box::new(UplcTurboEvaluator::new()))),
+        ("uplc-aiken", audited(Box::new(UplcAikenEvaluator::new()))),
+    ];
+
+    let mut mismatches = Vec::new();
+    for source_path in corpus() {
+        let name = source_path
+            .file_stem()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        let golden_path = source_path.with_extension("json");
+        let mut golden: Value =
+            serde_json::from_str(&std::fs::read_to_string(&golden_path).expect("golden results"))
+                .expect("golden JSON");
+
+        let source = std::fs::read_to_string(&source_path).expect("Aiken source");
+        let program = match aiken_wasm::compile_to_flat(&source) {
+            Ok(program) => program,
+            Err(e) => {
+                mismatches.push(format!("{}: {}", name, e));
+                continue;
+            }
+        };
+
+        let mut results_match = true;
+        let mut guest_result = String::new();
+        for (evaluator_name, evaluator) in &evaluators {
+            let actual = evaluate(evaluator.as_ref(), &program);
+            let expected = golden["results"][evaluator_name].as_str();
+            if expected != Some(actual.as_str()) {
+                mismatches.push(format!(
+                    "{}: {} returned {}, expected {}",
+                    name,
+                    evaluator_name,
+                    actual,
+                    expected.unwrap_or("no golden result")
+                ));
+                results_match = false;
+            }
+            if *evaluator_name == GUEST_EVALUATOR {
+                guest_result = actual;
+            }
+        }
+        if !results_match {
+            continue;
+        }
+
+        let program_hex = hex::encode(&program);
+        let commitment = hex::encode(guest_protocol::success_commitment(&program, &guest_result));
+        if bless {
+            golden["program_hex"] = program_hex.into();
+            golden["commitment"] = commitment.into();
+            let json = serde_json::to_string_pretty(&golden).expect("golden JSON");
+            std::fs::write(&golden_path, json + "\n").expect("write golden");
+        } else if let Some(pinned) = golden["program_hex"].as_str() {
+            if pinned != program_hex {
+                mismatches.push(format!(
+                    "{}: the compiled program changed, and with it its commitment",
+                    name
+                ));
+            } else if golden["commitment"].as_str() != Some(commitment.as_str()) {
+                mismatches.push(format!(
+                    "{}: the commitment changed to {}",
+                    name, commitment
+                ));
+            }
+        }
+    }
+    assert!(mismatches.is_empty(), "{:#?}", mismatches);
+}
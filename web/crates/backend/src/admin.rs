@@ -0,0 +1,219 @@
+//! Authenticated admin endpoints for provisioning a deployment through the API.
+//!
+//! Each setup step runs in the background; its progress is polled with
+//! `GET /api/admin/setup/{step}`. Requests must carry
+//! `Authorization: Bearer <ADMIN_TOKEN>`, and the endpoints are disabled when
+//! `ADMIN_TOKEN` is unset.
+
+use crate::{
+    hash32, invalidate_stale_runtime_artifacts, keygen_options_from_env, openvm_version_tag,
+    write_version_marker, AppState, ArtifactPaths, SetupStep,
+};
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum SetupJobStatus {
+    Running,
+    Succeeded,
+    /// The step's output already existed and `force` was not set.
+    Skipped,
+    Failed,
+}
+
+/// Progress of a setup step started through the admin API.
+pub(crate) struct SetupJob {
+    status: SetupJobStatus,
+    /// Unix timestamp (seconds) at which the step was started.
+    started_at: u64,
+    /// Unix timestamp (seconds) at which the step finished.
+    finished_at: Option<u64>,
+    started: std::time::Instant,
+    duration_secs: Option<f64>,
+    error: Option<String>,
+}
+
+impl SetupJob {
+    fn started() -> Self {
+        Self {
+            status: SetupJobStatus::Running,
+            started_at: unix_now(),
+            finished_at: None,
+            started: std::time::Instant::now(),
+            duration_secs: None,
+            error: None,
+        }
+    }
+
+    fn finish(&mut self, status: SetupJobStatus, error: Option<String>) {
+        self.status = status;
+        self.finished_at = Some(unix_now());
+        self.duration_secs = Some(self.started.elapsed().as_secs_f64());
+        self.error = error;
+    }
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// JSON report for one step; steps that never ran are reported as `idle`.
+fn step_report(step: SetupStep, job: Option<&SetupJob>) -> serde_json::Value {
+    let Some(job) = job else {
+        return serde_json::json!({ "step": step, "status": "idle" });
+    };
+    serde_json::json!({
+        "step": step,
+        "status": job.status,
+        "started_at": job.started_at,
+        "finished_at": job.finished_at,
+        "elapsed_secs": job
+            .duration_secs
+            .unwrap_or_else(|| job.started.elapsed().as_secs_f64()),
+        "error": job.error,
+        // Artifacts are loaded once at startup, so regenerated ones are only
+        // served after a restart.
+        "restart_required": job.status == SetupJobStatus::Succeeded,
+    })
+}
+
+pub(crate) fn authorize(req: &HttpRequest, state: &AppState) -> Result<(), HttpResponse> {
+    let Some(expected) = state.admin_token.as_deref() else {
+        return Err(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Admin API is disabled. Set ADMIN_TOKEN to enable it."
+        })));
+    };
+    let provided = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    // Compare digests rather than the tokens themselves so the comparison
+    // time does not depend on how much of the token matched.
+    match provided {
+        Some(token) if hash32(token.as_bytes()) == hash32(expected.as_bytes()) => Ok(()),
+        _ => Err(HttpResponse::Unauthorized().json(serde_json::json!({
+            "error": "Missing or invalid admin token"
+        }))),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct RunQuery {
+    /// Rerun the step even if its output already exists.
+    #[serde(default)]
+    force: bool,
+}
+
+/// Run a single step, returning `false` if it was skipped.
+fn run_step(paths: &ArtifactPaths, step: SetupStep, force: bool) -> eyre::Result<bool> {
+    let expected_version = openvm_version_tag();
+    invalidate_stale_runtime_artifacts(paths, &expected_version)?;
+
+    if !force && step.output_path(paths).exists() {
+        return Ok(false);
+    }
+    step.run(paths, &keygen_options_from_env()?)?;
+    write_version_marker(&step.version_marker_path(paths), &expected_version)?;
+    Ok(true)
+}
+
+async fn run_setup_job(state: web::Data<AppState>, step: SetupStep, force: bool) {
+    info!("Admin setup: {}...", step.description());
+
+    let task_state = state.clone();
+    let result = web::block(move || run_step(&task_state.paths, step, force)).await;
+
+    let (status, error) = match result {
+        Ok(Ok(true)) => {
+            info!("Admin setup: {:?} done", step);
+            (SetupJobStatus::Succeeded, None)
+        }
+        Ok(Ok(false)) => {
+            info!("Admin setup: {:?} output already exists, skipped", step);
+            (SetupJobStatus::Skipped, None)
+        }
+        Ok(Err(e)) => {
+            error!("Admin setup: {:?} failed: {:?}", step, e);
+            (SetupJobStatus::Failed, Some(format!("{:?}", e)))
+        }
+        Err(e) => {
+            error!("Blocking setup task error: {}", e);
+            (
+                SetupJobStatus::Failed,
+                Some(format!("Internal error: {}", e)),
+            )
+        }
+    };
+
+    let mut jobs = state.setup_jobs.lock().expect("setup jobs lock poisoned");
+    if let Some(job) = jobs.get_mut(&step) {
+        job.finish(status, error);
+    }
+}
+
+/// POST /api/admin/setup/{step}
+///
+/// Start `build-guest`, `app-keygen`, or `agg-keygen` in the background.
+/// Returns 202 with the step's progress, or 409 while another step runs.
+pub(crate) async fn start_setup_step(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    step: web::Path<SetupStep>,
+    query: web::Query<RunQuery>,
+) -> HttpResponse {
+    if let Err(response) = authorize(&req, &state) {
+        return response;
+    }
+    let step = step.into_inner();
+
+    let report = {
+        let mut jobs = state.setup_jobs.lock().expect("setup jobs lock poisoned");
+        // Steps share the target directory and are heavy; run one at a time.
+        if let Some((running, _)) = jobs
+            .iter()
+            .find(|(_, job)| job.status == SetupJobStatus::Running)
+        {
+            return HttpResponse::Conflict().json(serde_json::json!({
+                "error": format!("Setup step {:?} is already running", running)
+            }));
+        }
+        jobs.insert(step, SetupJob::started());
+        step_report(step, jobs.get(&step))
+    };
+
+    actix_web::rt::spawn(run_setup_job(state.clone(), step, query.force));
+    HttpResponse::Accepted().json(report)
+}
+
+/// GET /api/admin/setup/{step}
+pub(crate) async fn setup_step_status(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    step: web::Path<SetupStep>,
+) -> HttpResponse {
+    if let Err(response) = authorize(&req, &state) {
+        return response;
+    }
+    let step = step.into_inner();
+    let jobs = state.setup_jobs.lock().expect("setup jobs lock poisoned");
+    HttpResponse::Ok().json(step_report(step, jobs.get(&step)))
+}
+
+/// GET /api/admin/setup
+pub(crate) async fn setup_status(req: HttpRequest, state: web::Data<AppState>) -> HttpResponse {
+    if let Err(response) = authorize(&req, &state) {
+        return response;
+    }
+    let jobs = state.setup_jobs.lock().expect("setup jobs lock poisoned");
+    let steps: Vec<_> = SetupStep::ALL
+        .into_iter()
+        .map(|step| step_report(step, jobs.get(&step)))
+        .collect();
+    HttpResponse::Ok().json(serde_json::json!({ "steps": steps }))
+}
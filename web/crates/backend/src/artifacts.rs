@@ -0,0 +1,228 @@
+//! Artifact bundles: move keygen output between machines.
+//!
+//! Aggregation keygen needs far more memory than serving proofs, so it can run
+//! on a large machine and its output be shipped to the serving hosts.
+//! `cardano-zkvms artifacts export` packs the config, vmexe, app keys, and
+//! aggregation keys into one `.tar.zst` with a manifest of their SHA256
+//! digests; `cardano-zkvms artifacts import` unpacks it to the configured
+//! artifact paths, checking every file against the manifest and writing the
+//! checksum files and version markers setup would have written.
+
+use crate::{ensure_parent, openvm_version_tag, write_version_marker, ArtifactPaths};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Version of the bundle layout, bumped on incompatible changes.
+const BUNDLE_VERSION: u32 = 1;
+/// Name of the manifest, the first entry of every bundle.
+const MANIFEST_NAME: &str = "manifest.json";
+const ZSTD_LEVEL: i32 = 3;
+
+/// What a bundle holds.
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    bundle_version: u32,
+    /// OpenVM version the artifacts were generated with.
+    openvm_version: String,
+    files: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ManifestFile {
+    name: String,
+    size: u64,
+    sha256: String,
+}
+
+/// Bundle entry names and where each lives on this host. The guest build
+/// info is optional, see `record_build_info`.
+fn bundle_files(paths: &ArtifactPaths) -> Vec<(&'static str, PathBuf, bool)> {
+    vec![
+        ("openvm.toml", paths.config_path.clone(), true),
+        ("openvm-guest.vmexe", paths.vmexe_path.clone(), true),
+        ("app.pk", paths.app_pk_path.clone(), true),
+        ("app.vk", paths.app_vk_path.clone(), true),
+        ("agg_stark.pk", paths.agg_pk_path.clone(), true),
+        ("agg_stark.vk", paths.agg_vk_path.clone(), true),
+        ("guest.build-info", paths.build_info_path(), false),
+    ]
+}
+
+/// SHA256 and size of what `reader` yields, copying it to `writer`.
+fn copy_hashed(reader: &mut impl Read, writer: &mut impl Write) -> std::io::Result<(String, u64)> {
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1 << 20];
+    let mut size = 0;
+    loop {
+        let read = reader.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+        writer.write_all(&buf[..read])?;
+        size += read as u64;
+    }
+    Ok((hex::encode(hasher.finalize()), size))
+}
+
+/// `cardano-zkvms artifacts export [FILE]`
+///
+/// Bundle the configured artifacts into `FILE`, by default
+/// `openvm-artifacts-<openvm version>.tar.zst`.
+fn cmd_export(args: &[String]) -> eyre::Result<()> {
+    let paths = ArtifactPaths::from_env(&crate::environment::Environment::from_process())?;
+    let output = args.first().map(PathBuf::from).unwrap_or_else(|| {
+        PathBuf::from(format!("openvm-artifacts-{}.tar.zst", openvm_version_tag()))
+    });
+
+    let mut files = Vec::new();
+    let mut manifest = Manifest {
+        bundle_version: BUNDLE_VERSION,
+        openvm_version: openvm_version_tag(),
+        files: Vec::new(),
+    };
+    for (name, path, required) in bundle_files(&paths) {
+        if !path.exists() {
+            if required {
+                eyre::bail!(
+                    "{} not found at {}. Run `cardano-zkvms setup` first.",
+                    name,
+                    path.display()
+                );
+            }
+            continue;
+        }
+        // Refuse to ship a truncated or corrupted key, or one the receiving
+        // host could not check.
+        openvm_prover::verify_artifact_strict(&path)?;
+        let (sha256, size) = copy_hashed(&mut std::fs::File::open(&path)?, &mut std::io::sink())?;
+        eprintln!("  {:18} {} bytes, sha256 {}", name, size, sha256);
+        manifest.files.push(ManifestFile {
+            name: name.to_string(),
+            size,
+            sha256,
+        });
+        files.push((name, path));
+    }
+
+    let partial = output.with_extension("zst.partial");
+    let encoder = zstd::Encoder::new(std::fs::File::create(&partial)?, ZSTD_LEVEL)?;
+    let mut archive = tar::Builder::new(encoder);
+    let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest_json.len() as u64);
+    header.set_mode(0o644);
+    archive.append_data(&mut header, MANIFEST_NAME, manifest_json.as_slice())?;
+    for (name, path) in files {
+        archive.append_path_with_name(&path, name)?;
+    }
+    archive.into_inner()?.finish()?.sync_all()?;
+    std::fs::rename(&partial, &output)?;
+
+    eprintln!("Exported artifacts to {}", output.display());
+    Ok(())
+}
+
+/// `cardano-zkvms artifacts import FILE`
+///
+/// Unpack a bundle to the configured artifact paths. Each file is written
+/// next to its destination, checked against the manifest, then moved into
+/// place, so a bad bundle never replaces working artifacts.
+fn cmd_import(args: &[String]) -> eyre::Result<()> {
+    let input = args
+        .first()
+        .ok_or_else(|| eyre::eyre!("Pass the artifact bundle to import"))?;
+    let paths = ArtifactPaths::from_env(&crate::environment::Environment::from_process())?;
+    let destinations = bundle_files(&paths);
+
+    let decoder = zstd::Decoder::new(std::fs::File::open(input)?)?;
+    let mut archive = tar::Archive::new(decoder);
+    let mut entries = archive.entries()?;
+
+    let manifest: Manifest = match entries.next() {
+        Some(entry) => {
+            let entry = entry?;
+            if entry.path()?.as_ref() != Path::new(MANIFEST_NAME) {
+                eyre::bail!("{} is not an artifact bundle: no manifest", input);
+            }
+            serde_json::from_reader(entry)?
+        }
+        None => eyre::bail!("{} is empty", input),
+    };
+    if manifest.bundle_version != BUNDLE_VERSION {
+        eyre::bail!(
+            "Unsupported bundle version {}, expected {}",
+            manifest.bundle_version,
+            BUNDLE_VERSION
+        );
+    }
+    if manifest.openvm_version != openvm_version_tag() {
+        eyre::bail!(
+            "Bundle holds artifacts for OpenVM {}, but this backend runs {}",
+            manifest.openvm_version,
+            openvm_version_tag()
+        );
+    }
+
+    // Unpack everything before moving anything into place.
+    let mut unpacked = Vec::new();
+    for entry in entries {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let expected = manifest
+            .files
+            .iter()
+            .find(|file| file.name == name)
+            .ok_or_else(|| eyre::eyre!("Bundle entry {} is not in its manifest", name))?;
+        let (_, destination, _) = destinations
+            .iter()
+            .find(|(known, _, _)| *known == name)
+            .ok_or_else(|| eyre::eyre!("Unknown bundle entry {}", name))?;
+
+        ensure_parent(destination)?;
+        let mut partial = destination.as_os_str().to_owned();
+        partial.push(".partial");
+        let partial = PathBuf::from(partial);
+        let (sha256, size) = copy_hashed(&mut entry, &mut std::fs::File::create(&partial)?)?;
+        if sha256 != expected.sha256 || size != expected.size {
+            std::fs::remove_file(&partial)?;
+            eyre::bail!(
+                "{} ({} bytes) does not match the manifest: the bundle is truncated or corrupted",
+                name,
+                size
+            );
+        }
+        eprintln!("  {:18} verified", name);
+        unpacked.push((name, partial, destination.clone(), sha256));
+    }
+    if let Some(missing) = manifest
+        .files
+        .iter()
+        .find(|file| !unpacked.iter().any(|(name, ..)| *name == file.name))
+    {
+        eyre::bail!("Bundle is missing {}", missing.name);
+    }
+
+    for (_, partial, destination, sha256) in unpacked {
+        // The checksum file first, so no artifact is ever in place without it.
+        openvm_prover::write_checksum(&destination, &sha256)?;
+        std::fs::rename(&partial, &destination)?;
+        eprintln!("  Installed {}", destination.display());
+    }
+    write_version_marker(&paths.target_version_path(), &manifest.openvm_version)?;
+    write_version_marker(&paths.openvm_version_path(), &manifest.openvm_version)?;
+
+    eprintln!("Imported artifacts from {}", input);
+    Ok(())
+}
+
+/// `cardano-zkvms artifacts export [FILE] | import FILE`
+pub(crate) fn cmd_artifacts(args: &[String]) -> eyre::Result<()> {
+    match args.first().map(String::as_str) {
+        Some("export") => cmd_export(&args[1..]),
+        Some("import") => cmd_import(&args[1..]),
+        _ => eyre::bail!("Usage: cardano-zkvms artifacts export [FILE] | import FILE"),
+    }
+}
@@ -0,0 +1,458 @@
+//! Epoch certificates: many stored proofs attested by one small object.
+//!
+//! When `EPOCH_INTERVAL_SECS` is set, the backend periodically collects the
+//! commitments of the proofs stored since the previous epoch, proves their
+//! Merkle root in the guest (`Aggregate` mode), chained to the previous
+//! epoch's commitment Mithril-style, and signs the result with the service
+//! key. A consumer checks an epoch's proof and signature once, then any of
+//! its commitments with a Merkle inclusion path; following `previous` back
+//! to epoch 1 covers the whole history. Windows are over the time proofs
+//! entered the store, so a replicated proof imported after its `proved_at`
+//! window closed is certified in the next epoch. Epoch proofs take a slot of
+//! the prove queue like any other proof. Requires `PROOF_STORE_DIR` and
+//! `SERVICE_SIGNING_KEY_FILE`; certificates and their proofs are kept under
+//! `<PROOF_STORE_DIR>/epochs/`.
+
+use crate::claims::SignedClaim;
+use crate::proofs::ProofStore;
+use crate::{hex32, openvm_version_tag, AppState};
+use actix_web::{web, HttpResponse};
+use ciborium::Value;
+use openvm_prover::ExecutionMode;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{error, info};
+
+/// Proofs stored in the last minute are left to the next epoch, so one still
+/// being written when the epoch closes is not skipped.
+const SETTLE_SECS: u64 = 60;
+
+/// What an epoch certificate attests, as signed by the service.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EpochClaim {
+    epoch: u64,
+    /// Unix timestamps (seconds) bounding the proofs covered, by the time
+    /// they were stored: `window_start <= stored_at < window_end`.
+    window_start: u64,
+    window_end: u64,
+    /// Commitment of the previous epoch; all zeroes for epoch 1.
+    previous: String,
+    /// Merkle root of the commitments, see `guest_protocol::merkle_root`.
+    merkle_root: String,
+    commitment_count: u64,
+    /// SHA256("epoch:" || previous || merkle_root || commitment_count): the
+    /// public value of the epoch proof.
+    epoch_commitment: String,
+    app_exe_commit: String,
+    app_vm_commit: String,
+}
+
+impl EpochClaim {
+    fn payload(&self) -> Vec<u8> {
+        let text = |value: &str| Value::Text(value.to_string());
+        let int = |value: u64| Value::Integer(value.into());
+        let bytes = |value: &str| Value::Bytes(hex::decode(value).unwrap_or_default());
+        crate::claims::cbor(&Value::Map(vec![
+            (text("epoch"), int(self.epoch)),
+            (text("window_start"), int(self.window_start)),
+            (text("window_end"), int(self.window_end)),
+            (text("previous"), bytes(&self.previous)),
+            (text("merkle_root"), bytes(&self.merkle_root)),
+            (text("commitment_count"), int(self.commitment_count)),
+            (text("epoch_commitment"), bytes(&self.epoch_commitment)),
+            (text("app_exe_commit"), text(&self.app_exe_commit)),
+            (text("app_vm_commit"), text(&self.app_vm_commit)),
+        ]))
+    }
+}
+
+/// A certified epoch, as stored and served.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct EpochCertificate {
+    #[serde(flatten)]
+    claim: EpochClaim,
+    /// The epoch's commitments, sorted, in Merkle leaf order.
+    commitments: Vec<String>,
+    openvm_version: String,
+    /// The claim fields signed with the service key as a CIP-8 `COSE_Sign1`.
+    signed_claim: SignedClaim,
+}
+
+/// STARK proof of an epoch, in the shape `/api/verify` accepts.
+#[derive(Debug, Serialize, Deserialize)]
+struct EpochProof {
+    proof_version: String,
+    stark_proof_json: serde_json::Value,
+    verification_baseline_json: openvm_prover::StarkVerificationBaselineJson,
+}
+
+/// Directory of certified epochs: `<epoch>.json` certificates and their
+/// `<epoch>.proof.json` proofs.
+#[derive(Debug, Clone)]
+pub(crate) struct EpochStore {
+    dir: PathBuf,
+}
+
+impl EpochStore {
+    /// The store and certification interval from `EPOCH_INTERVAL_SECS`, or
+    /// `None` when it is unset.
+    pub(crate) fn from_env(
+        proof_store: Option<&ProofStore>,
+        signing: bool,
+    ) -> eyre::Result<Option<(Self, Duration)>> {
+        let Some(interval) = std::env::var("EPOCH_INTERVAL_SECS")
+            .ok()
+            .filter(|value| !value.is_empty())
+        else {
+            return Ok(None);
+        };
+        let interval: u64 = interval
+            .parse()
+            .ok()
+            .filter(|secs| *secs > 0)
+            .ok_or_else(|| eyre::eyre!("Invalid EPOCH_INTERVAL_SECS: {}", interval))?;
+        let Some(proof_store) = proof_store else {
+            eyre::bail!("EPOCH_INTERVAL_SECS requires PROOF_STORE_DIR");
+        };
+        if !signing {
+            eyre::bail!("EPOCH_INTERVAL_SECS requires SERVICE_SIGNING_KEY_FILE");
+        }
+        let store = Self {
+            dir: proof_store.dir().join("epochs"),
+        };
+        Ok(Some((store, Duration::from_secs(interval))))
+    }
+
+    pub(crate) fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn certificate_path(&self, epoch: u64) -> PathBuf {
+        self.dir.join(format!("{}.json", epoch))
+    }
+
+    fn proof_path(&self, epoch: u64) -> PathBuf {
+        self.dir.join(format!("{}.proof.json", epoch))
+    }
+
+    /// Number of the last certified epoch, if any.
+    fn latest_epoch(&self) -> std::io::Result<Option<u64>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let mut latest = None;
+        for entry in entries {
+            let path = entry?.path();
+            let epoch = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".json"))
+                .and_then(|stem| stem.parse::<u64>().ok());
+            latest = latest.max(epoch);
+        }
+        Ok(latest)
+    }
+
+    fn load(&self, epoch: u64) -> std::io::Result<EpochCertificate> {
+        Ok(serde_json::from_slice(&std::fs::read(
+            self.certificate_path(epoch),
+        )?)?)
+    }
+
+    /// Write then rename, so a reader never sees a partial file. The proof
+    /// goes first: a certificate is only visible once its proof is.
+    fn save(&self, certificate: &EpochCertificate, proof: &EpochProof) -> std::io::Result<()> {
+        fn write(path: &Path, json: Vec<u8>) -> std::io::Result<()> {
+            let partial = path.with_extension("json.partial");
+            std::fs::write(&partial, json)?;
+            std::fs::rename(&partial, path)
+        }
+
+        std::fs::create_dir_all(&self.dir)?;
+        let epoch = certificate.claim.epoch;
+        write(&self.proof_path(epoch), serde_json::to_vec(proof)?)?;
+        write(
+            &self.certificate_path(epoch),
+            serde_json::to_vec_pretty(certificate)?,
+        )
+    }
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Certify the proofs stored since the last epoch. `None` when there are none
+/// yet, in which case the window stays open until the next run.
+///
+/// Blocking: call from `web::block`.
+fn certify_next_epoch(state: &AppState) -> Result<Option<EpochCertificate>, String> {
+    let (Some(store), Some(proof_store), Some(key)) =
+        (&state.epochs, &state.proof_store, &state.service_key)
+    else {
+        return Err("Epoch certificates are disabled".into());
+    };
+
+    let latest = store
+        .latest_epoch()
+        .and_then(|epoch| epoch.map(|epoch| store.load(epoch)).transpose())
+        .map_err(|e| format!("Failed to read the last epoch certificate: {}", e))?;
+    let (epoch, previous, window_start) = match &latest {
+        Some(certificate) => (
+            certificate.claim.epoch + 1,
+            hex32(&certificate.claim.epoch_commitment)?,
+            certificate.claim.window_end,
+        ),
+        None => (1, [0; 32], 0),
+    };
+    let window_end = unix_now().saturating_sub(SETTLE_SECS);
+    if window_end <= window_start {
+        return Ok(None);
+    }
+
+    let commitments = proof_store
+        .commitments_stored_between(window_start, window_end)
+        .map_err(|e| format!("Failed to read stored proofs: {}", e))?;
+    if commitments.is_empty() {
+        return Ok(None);
+    }
+    let root = guest_protocol::merkle_root(&commitments);
+    let count = commitments.len() as u64;
+    let expected = guest_protocol::epoch_commitment(&previous, &root, count);
+
+    info!(
+        "Proving epoch {} over {} commitments...",
+        epoch,
+        commitments.len()
+    );
+    let batch = guest_protocol::encode_aggregation(&previous, &commitments);
+    let (prover, _) = state.prover()?;
+    let _slot = state
+        .prove_queue
+        .acquire(&mut crate::queue::Timeline::new());
+    let proved = state
+        .retry_policy
+        .run("Epoch proving", || {
+            prover.prove_stark(&batch, ExecutionMode::Aggregate)
+        })
+        .map_err(|e| format!("Epoch proof generation failed: {}", e))?;
+    if proved.user_public_values != expected {
+        return Err(format!(
+            "Epoch proof commits to {} but the batch hashes to {}; is the vmexe out of date?",
+            hex::encode(&proved.user_public_values),
+            hex::encode(expected)
+        ));
+    }
+
+    let claim = EpochClaim {
+        epoch,
+        window_start,
+        window_end,
+        previous: hex::encode(previous),
+        merkle_root: hex::encode(root),
+        commitment_count: count,
+        epoch_commitment: hex::encode(expected),
+        app_exe_commit: proved.app_exe_commit,
+        app_vm_commit: proved.app_vm_commit,
+    };
+    let certificate = EpochCertificate {
+        signed_claim: key.sign_payload(claim.payload()),
+        claim,
+        commitments: commitments.iter().map(hex::encode).collect(),
+        openvm_version: openvm_version_tag(),
+    };
+    let proof = EpochProof {
+        proof_version: proved.proof_version,
+        stark_proof_json: proved.proof_json,
+        verification_baseline_json: proved.baseline_json,
+    };
+    store
+        .save(&certificate, &proof)
+        .map_err(|e| format!("Failed to save epoch {}: {}", epoch, e))?;
+    Ok(Some(certificate))
+}
+
+/// Certify a new epoch every `interval`, for the lifetime of the server.
+pub(crate) async fn run(state: web::Data<AppState>, interval: Duration) {
+    let mut ticks = tokio::time::interval(interval);
+    // Proving can outlast the interval; start the next epoch a full interval
+    // after it rather than immediately.
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // The first tick completes immediately.
+    ticks.tick().await;
+
+    loop {
+        ticks.tick().await;
+        let task_state = state.clone();
+        match web::block(move || certify_next_epoch(&task_state)).await {
+            Ok(Ok(Some(certificate))) => info!(
+                "Certified epoch {} ({} proofs, commitment {})",
+                certificate.claim.epoch,
+                certificate.claim.commitment_count,
+                certificate.claim.epoch_commitment
+            ),
+            Ok(Ok(None)) => info!("No new proofs to certify"),
+            Ok(Err(e)) => error!("Epoch certification failed: {}", e),
+            Err(e) => error!("Blocking epoch task error: {}", e),
+        }
+    }
+}
+
+fn epoch_error(response: &mut actix_web::HttpResponseBuilder, error: String) -> HttpResponse {
+    response.json(serde_json::json!({ "error": error }))
+}
+
+/// Read an epoch file, mapping failures to responses.
+async fn read_epoch_file<T: serde::de::DeserializeOwned>(
+    path: &Path,
+    epoch: u64,
+) -> Result<T, HttpResponse> {
+    match tokio::fs::read(path).await {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| {
+            error!("Corrupt epoch file {}: {}", path.display(), e);
+            epoch_error(
+                &mut HttpResponse::InternalServerError(),
+                format!("Epoch {} is unreadable: {}", epoch, e),
+            )
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(epoch_error(
+            &mut HttpResponse::NotFound(),
+            format!("No certificate for epoch {}", epoch),
+        )),
+        Err(e) => Err(epoch_error(
+            &mut HttpResponse::InternalServerError(),
+            format!("Failed to read epoch {}: {}", epoch, e),
+        )),
+    }
+}
+
+fn epoch_store(data: &AppState) -> Result<&EpochStore, HttpResponse> {
+    data.epochs.as_ref().ok_or_else(|| {
+        epoch_error(
+            &mut HttpResponse::NotFound(),
+            "Epoch certificates are disabled. Set EPOCH_INTERVAL_SECS to enable them.".into(),
+        )
+    })
+}
+
+/// GET /api/epochs/latest
+pub(crate) async fn latest_certificate(data: web::Data<AppState>) -> HttpResponse {
+    let store = match epoch_store(&data) {
+        Ok(store) => store.clone(),
+        Err(response) => return response,
+    };
+    let latest = web::block(move || store.latest_epoch().map(|epoch| (store, epoch))).await;
+    match latest {
+        Ok(Ok((store, Some(epoch)))) => {
+            match read_epoch_file::<EpochCertificate>(&store.certificate_path(epoch), epoch).await {
+                Ok(certificate) => HttpResponse::Ok().json(certificate),
+                Err(response) => response,
+            }
+        }
+        Ok(Ok((_, None))) => epoch_error(
+            &mut HttpResponse::NotFound(),
+            "No epoch has been certified yet".into(),
+        ),
+        Ok(Err(e)) => epoch_error(
+            &mut HttpResponse::InternalServerError(),
+            format!("Failed to list epochs: {}", e),
+        ),
+        Err(e) => epoch_error(
+            &mut HttpResponse::InternalServerError(),
+            format!("Internal error: {}", e),
+        ),
+    }
+}
+
+/// GET /api/epochs/{epoch}
+pub(crate) async fn certificate(data: web::Data<AppState>, epoch: web::Path<u64>) -> HttpResponse {
+    let epoch = epoch.into_inner();
+    let store = match epoch_store(&data) {
+        Ok(store) => store,
+        Err(response) => return response,
+    };
+    match read_epoch_file::<EpochCertificate>(&store.certificate_path(epoch), epoch).await {
+        Ok(certificate) => HttpResponse::Ok().json(certificate),
+        Err(response) => response,
+    }
+}
+
+/// GET /api/epochs/{epoch}/proof
+///
+/// The epoch's STARK proof, as accepted by `/api/verify`.
+pub(crate) async fn proof(data: web::Data<AppState>, epoch: web::Path<u64>) -> HttpResponse {
+    let epoch = epoch.into_inner();
+    let store = match epoch_store(&data) {
+        Ok(store) => store,
+        Err(response) => return response,
+    };
+    match read_epoch_file::<EpochProof>(&store.proof_path(epoch), epoch).await {
+        Ok(proof) => HttpResponse::Ok().json(proof),
+        Err(response) => response,
+    }
+}
+
+/// GET /api/epochs/{epoch}/inclusion/{commitment}
+///
+/// Merkle path from `commitment` to the epoch's `merkle_root`, leaf first.
+pub(crate) async fn inclusion(
+    data: web::Data<AppState>,
+    path: web::Path<(u64, String)>,
+) -> HttpResponse {
+    let (epoch, commitment) = path.into_inner();
+    let store = match epoch_store(&data) {
+        Ok(store) => store,
+        Err(response) => return response,
+    };
+    let Ok(leaf) = hex32(&commitment) else {
+        return epoch_error(
+            &mut HttpResponse::BadRequest(),
+            "Commitment must be 32 bytes of hex".into(),
+        );
+    };
+    let certificate: EpochCertificate =
+        match read_epoch_file(&store.certificate_path(epoch), epoch).await {
+            Ok(certificate) => certificate,
+            Err(response) => return response,
+        };
+
+    let leaves: Vec<[u8; 32]> = match certificate.commitments.iter().map(|c| hex32(c)).collect() {
+        Ok(leaves) => leaves,
+        Err(e) => {
+            return epoch_error(
+                &mut HttpResponse::InternalServerError(),
+                format!("Epoch {} holds an invalid commitment: {}", epoch, e),
+            )
+        }
+    };
+    let Some(index) = leaves.iter().position(|candidate| *candidate == leaf) else {
+        return epoch_error(
+            &mut HttpResponse::NotFound(),
+            format!("Commitment {} is not in epoch {}", hex::encode(leaf), epoch),
+        );
+    };
+    let path = guest_protocol::merkle_path(&leaves, index).unwrap_or_default();
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "epoch": epoch,
+        "commitment": hex::encode(leaf),
+        "index": index,
+        "merkle_root": &certificate.claim.merkle_root,
+        "epoch_commitment": &certificate.claim.epoch_commitment,
+        // Hash each sibling with the running node, starting from
+        // SHA256(0x00 || commitment): SHA256(0x01 || left || right).
+        "path": path
+            .iter()
+            .map(|step| serde_json::json!({
+                "sibling": hex::encode(step.sibling),
+                "position": if step.sibling_is_left { "left" } else { "right" },
+            }))
+            .collect::<Vec<_>>(),
+    }))
+}
@@ -0,0 +1,195 @@
+//! `cardano-zkvms check`: validate a deployment's configuration and
+//! artifacts without serving, for deployment pipelines to run before
+//! switching traffic to a host.
+//!
+//! It resolves every artifact path, checks each artifact's checksum and
+//! toolchain version, loads the prover, computes the guest's commits, and
+//! executes the guest on the warmup program and its self-test. It never
+//! deletes stale artifacts the way starting the server does. The report is
+//! JSON on stdout, and the exit status is non-zero if any check failed.
+
+use crate::{openvm_version_tag, read_version_marker, ArtifactPaths, WARMUP_PROGRAM_HEX};
+use guest_protocol::{conformance, GuestInput};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// One check of the deployment, and what it found.
+#[derive(Debug, Serialize)]
+struct Check {
+    name: String,
+    passed: bool,
+    detail: String,
+}
+
+/// What `cardano-zkvms check` prints.
+#[derive(Debug, Default, Serialize)]
+struct Report {
+    /// Whether every check passed.
+    ok: bool,
+    openvm_version: String,
+    /// Where each artifact was looked for.
+    paths: BTreeMap<&'static str, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    app_exe_commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    app_vm_commit: Option<String>,
+    checks: Vec<Check>,
+}
+
+impl Report {
+    fn push(&mut self, name: impl Into<String>, result: Result<String, String>) -> bool {
+        let passed = result.is_ok();
+        self.checks.push(Check {
+            name: name.into(),
+            passed,
+            detail: result.unwrap_or_else(|error| error),
+        });
+        passed
+    }
+}
+
+/// `cardano-zkvms check`
+pub(crate) fn cmd_check(args: &[String]) -> eyre::Result<()> {
+    if !args.is_empty() {
+        eyre::bail!("Usage: cardano-zkvms check");
+    }
+    let report = check(&ArtifactPaths::from_env(
+        &crate::environment::Environment::from_process(),
+    )?);
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    let failed = report.checks.iter().filter(|check| !check.passed).count();
+    if failed > 0 {
+        eyre::bail!("{} of {} checks failed", failed, report.checks.len());
+    }
+    Ok(())
+}
+
+fn check(paths: &ArtifactPaths) -> Report {
+    let expected_version = openvm_version_tag();
+    let mut report = Report {
+        openvm_version: expected_version.clone(),
+        ..Report::default()
+    };
+
+    let artifacts: [(&'static str, &Path, bool); 6] = [
+        ("openvm.toml", &paths.config_path, false),
+        ("openvm-guest.vmexe", &paths.vmexe_path, true),
+        ("app.pk", &paths.app_pk_path, true),
+        ("app.vk", &paths.app_vk_path, true),
+        ("agg_stark.pk", &paths.agg_pk_path, true),
+        ("agg_stark.vk", &paths.agg_vk_path, true),
+    ];
+    let mut complete = true;
+    for (name, path, checksummed) in artifacts {
+        report.paths.insert(name, path.display().to_string());
+        let found = if path.exists() {
+            Ok(format!("found at {}", path.display()))
+        } else {
+            Err(format!("not found at {}", path.display()))
+        };
+        complete &= report.push(format!("exists:{}", name), found);
+        if checksummed && path.exists() {
+            // A deployment must not ship an artifact without a checksum file.
+            let verified = openvm_prover::verify_artifact_strict(path)
+                .map(|()| format!("matches {}", openvm_prover::checksum_path(path).display()))
+                .map_err(|e| format!("{:#}", e));
+            complete &= report.push(format!("checksum:{}", name), verified);
+        }
+    }
+
+    for (name, marker) in [
+        ("version:app", paths.target_version_path()),
+        ("version:agg", paths.openvm_version_path()),
+    ] {
+        let version = match read_version_marker(&marker) {
+            Some(version) if version == expected_version => Ok(version),
+            Some(version) => Err(format!(
+                "generated with {}, but this backend runs {}; run setup again",
+                version, expected_version
+            )),
+            None => Err(format!("no version marker at {}", marker.display())),
+        };
+        complete &= report.push(name, version);
+    }
+
+    if !complete {
+        report.push(
+            "load",
+            Err("skipped: fix the artifacts above first".to_string()),
+        );
+        return report;
+    }
+
+    let prover = match openvm_prover::Prover::builder()
+        .config_path(&paths.config_path)
+        .exe_path(&paths.vmexe_path)
+        .app_pk_path(&paths.app_pk_path)
+        .agg_pk_path(&paths.agg_pk_path)
+        // Checked above, one check per artifact.
+        .verify_checksums(false)
+        .build()
+    {
+        Ok(prover) => {
+            report.push("load", Ok("config, vmexe and keys loaded".to_string()));
+            prover
+        }
+        Err(e) => {
+            report.push("load", Err(format!("{:#}", e)));
+            return report;
+        }
+    };
+
+    // The app key must be for the vmexe's VM: the prover cannot commit to
+    // an executable its key does not fit.
+    match prover.app_commit() {
+        Ok((app_exe_commit, app_vm_commit)) => {
+            report.push(
+                "key_exe_match",
+                Ok("the app key commits to the vmexe".to_string()),
+            );
+            report.app_exe_commit = Some(app_exe_commit);
+            report.app_vm_commit = Some(app_vm_commit);
+        }
+        Err(e) => {
+            report.push("key_exe_match", Err(format!("{:#}", e)));
+        }
+    }
+
+    let program_bytes = hex::decode(WARMUP_PROGRAM_HEX).expect("valid warmup program hex");
+    let expected = guest_protocol::success_commitment(&program_bytes, "Integer(42)");
+    let dry_run = match prover.execute(&program_bytes, openvm_prover::ExecutionMode::Strict) {
+        Ok(run) if run.public_values == expected => Ok(format!(
+            "the warmup program ran in {} cycles over {} segments",
+            run.total_cycles, run.segments
+        )),
+        Ok(run) => Err(format!(
+            "the warmup program revealed {}, expected {}",
+            hex::encode(&run.public_values),
+            hex::encode(expected)
+        )),
+        Err(e) => Err(format!("{:#}", e)),
+    };
+    report.push("dry_run", dry_run);
+
+    let self_test = conformance::cases()
+        .into_iter()
+        .find(|case| case.name == "self_test")
+        .and_then(|case| case.public_values)
+        .expect("the conformance suite has a self-test case");
+    let conforms = match prover.execute_input(&GuestInput::self_test()) {
+        Ok(run) if run.public_values == self_test => {
+            Ok("the guest passes the conformance self-test".to_string())
+        }
+        Ok(run) => Err(format!(
+            "the guest revealed {}, expected {}: it was built against another guest protocol",
+            hex::encode(&run.public_values),
+            hex::encode(self_test)
+        )),
+        Err(e) => Err(format!("{:#}", e)),
+    };
+    report.push("self_test", conforms);
+
+    report.ok = report.checks.iter().all(|check| check.passed);
+    report
+}
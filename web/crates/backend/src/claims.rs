@@ -0,0 +1,191 @@
+//! CIP-8 signed claims.
+//!
+//! With `claim_format: "cip8"`, `/api/prove` also returns the claim the proof
+//! attests to (script hash, outcome, result, commitment) signed with the
+//! service key as a CIP-8 `COSE_Sign1`, the envelope CIP-30 wallets return
+//! from `signData`, so existing Cardano wallet and metadata tooling can check
+//! it. The service key is an Ed25519 key whose hex-encoded 32-byte seed is
+//! read from the file at `SERVICE_SIGNING_KEY_FILE`; its `COSE_Key` is served
+//! at `GET /api/service-key`.
+
+use crate::AppState;
+use actix_web::{web, HttpResponse};
+use blake2::{digest::consts::U28, Blake2b, Digest};
+use ciborium::Value;
+use ed25519_dalek::{Signer, SigningKey};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// COSE algorithm identifier for EdDSA.
+const COSE_ALG_EDDSA: i64 = -8;
+/// Language tag prepended to PlutusV3 scripts when hashing them.
+const PLUTUS_V3_SCRIPT_TAG: u8 = 0x03;
+
+/// Signed claim formats a prove request can ask for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ClaimFormat {
+    /// CIP-8 `COSE_Sign1` over a CBOR claim.
+    Cip8,
+}
+
+/// What a proof attests, as signed by the service.
+pub(crate) struct Claim<'a> {
+    pub(crate) program_bytes: &'a [u8],
+    pub(crate) outcome: &'a str,
+    /// Evaluation result, for successful programs.
+    pub(crate) result: Option<&'a str>,
+    /// Failure kind, for proven failures.
+    pub(crate) error_kind: Option<&'a str>,
+    pub(crate) commitment: &'a [u8],
+    pub(crate) app_exe_commit: &'a str,
+    pub(crate) app_vm_commit: &'a str,
+}
+
+/// A CIP-8 signed claim, CBOR-encoded as hex.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct SignedClaim {
+    /// Untagged `COSE_Sign1`, as returned by CIP-30 `signData`.
+    cose_sign1: String,
+    /// `COSE_Key` of the service key that signed it.
+    cose_key: String,
+}
+
+/// Hash of the program as a PlutusV3 script: blake2b-224 of the language tag
+/// followed by the CBOR-wrapped flat bytes, as used on-chain.
+pub(crate) fn plutus_v3_script_hash(program_bytes: &[u8]) -> Vec<u8> {
+    let mut hasher = Blake2b::<U28>::new();
+    hasher.update([PLUTUS_V3_SCRIPT_TAG]);
+    hasher.update(cbor(&Value::Bytes(program_bytes.to_vec())));
+    hasher.finalize().to_vec()
+}
+
+pub(crate) fn cbor(value: &Value) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes).expect("CBOR encoding into a Vec cannot fail");
+    bytes
+}
+
+fn text(value: &str) -> Value {
+    Value::Text(value.to_string())
+}
+
+fn int(value: i64) -> Value {
+    Value::Integer(value.into())
+}
+
+impl Claim<'_> {
+    fn payload(&self) -> Vec<u8> {
+        let optional_text = |value: Option<&str>| value.map_or(Value::Null, text);
+        cbor(&Value::Map(vec![
+            (
+                text("script_hash"),
+                Value::Bytes(plutus_v3_script_hash(self.program_bytes)),
+            ),
+            (text("outcome"), text(self.outcome)),
+            (text("result"), optional_text(self.result)),
+            (text("error_kind"), optional_text(self.error_kind)),
+            (text("commitment"), Value::Bytes(self.commitment.to_vec())),
+            (text("app_exe_commit"), text(self.app_exe_commit)),
+            (text("app_vm_commit"), text(self.app_vm_commit)),
+        ]))
+    }
+}
+
+/// The service's Ed25519 key for signing claims.
+pub(crate) struct ServiceKey {
+    signing_key: SigningKey,
+}
+
+impl ServiceKey {
+    /// Load the key from the file at `SERVICE_SIGNING_KEY_FILE`, if set.
+    pub(crate) fn from_env() -> eyre::Result<Option<Self>> {
+        match crate::env_path("SERVICE_SIGNING_KEY_FILE") {
+            Some(path) => Self::load(&path).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn load(path: &Path) -> eyre::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| eyre::eyre!("Failed to read service key {}: {}", path.display(), e))?;
+        let seed: [u8; 32] = hex::decode(contents.trim())
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| {
+                eyre::eyre!(
+                    "Service key {} must hold a hex-encoded 32-byte Ed25519 seed",
+                    path.display()
+                )
+            })?;
+        Ok(Self {
+            signing_key: SigningKey::from_bytes(&seed),
+        })
+    }
+
+    /// Hex of the Ed25519 public key.
+    pub(crate) fn public_key_hex(&self) -> String {
+        hex::encode(self.signing_key.verifying_key().as_bytes())
+    }
+
+    /// `COSE_Key` for the public key (OKP, EdDSA, Ed25519).
+    fn cose_key(&self) -> Vec<u8> {
+        cbor(&Value::Map(vec![
+            (int(1), int(1)),
+            (int(3), int(COSE_ALG_EDDSA)),
+            (int(-1), int(6)),
+            (
+                int(-2),
+                Value::Bytes(self.signing_key.verifying_key().as_bytes().to_vec()),
+            ),
+        ]))
+    }
+
+    /// Sign `claim` as a CIP-8 `COSE_Sign1`.
+    pub(crate) fn sign(&self, claim: &Claim<'_>) -> SignedClaim {
+        self.sign_payload(claim.payload())
+    }
+
+    /// Sign a CBOR `payload` as a CIP-8 `COSE_Sign1`.
+    pub(crate) fn sign_payload(&self, payload: Vec<u8>) -> SignedClaim {
+        let protected = cbor(&Value::Map(vec![
+            (int(1), int(COSE_ALG_EDDSA)),
+            (
+                int(4),
+                Value::Bytes(self.signing_key.verifying_key().as_bytes().to_vec()),
+            ),
+        ]));
+        let sig_structure = cbor(&Value::Array(vec![
+            text("Signature1"),
+            Value::Bytes(protected.clone()),
+            Value::Bytes(Vec::new()),
+            Value::Bytes(payload.clone()),
+        ]));
+        let signature = self.signing_key.sign(&sig_structure);
+        let cose_sign1 = cbor(&Value::Array(vec![
+            Value::Bytes(protected),
+            Value::Map(vec![(text("hashed"), Value::Bool(false))]),
+            Value::Bytes(payload),
+            Value::Bytes(signature.to_bytes().to_vec()),
+        ]));
+        SignedClaim {
+            cose_sign1: hex::encode(cose_sign1),
+            cose_key: hex::encode(self.cose_key()),
+        }
+    }
+}
+
+/// GET /api/service-key
+///
+/// The public key signed claims are checked against.
+pub(crate) async fn service_key(data: web::Data<AppState>) -> HttpResponse {
+    match &data.service_key {
+        Some(key) => HttpResponse::Ok().json(serde_json::json!({
+            "public_key": key.public_key_hex(),
+            "cose_key": hex::encode(key.cose_key()),
+        })),
+        None => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Signed claims are disabled. Set SERVICE_SIGNING_KEY_FILE to enable them."
+        })),
+    }
+}
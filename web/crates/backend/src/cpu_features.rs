@@ -0,0 +1,106 @@
+//! The SIMD instruction sets the STARK backend uses, next to those the CPU
+//! supports.
+//!
+//! Plonky3 picks its vectorized field arithmetic at compile time from the
+//! enabled target features, not at run time. A backend built for the baseline
+//! target (SSE2 on x86_64) runs markedly slower on an AVX2 or AVX-512 CPU
+//! than one built with `make backend-build-native` (`-C target-cpu=native`).
+//! `missing` lists what the CPU supports but the binary does not use.
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct CpuFeatures {
+    arch: &'static str,
+    /// Instruction sets the binary was compiled to use.
+    compiled: Vec<&'static str>,
+    /// Instruction sets the CPU supports.
+    detected: Vec<&'static str>,
+    /// Supported by the CPU but unused by the binary.
+    missing: Vec<&'static str>,
+}
+
+impl CpuFeatures {
+    pub(crate) fn detect() -> Self {
+        let (compiled, detected) = probe();
+        let missing = detected
+            .iter()
+            .filter(|feature| !compiled.contains(feature))
+            .copied()
+            .collect();
+        Self {
+            arch: std::env::consts::ARCH,
+            compiled,
+            detected,
+            missing,
+        }
+    }
+
+    pub(crate) fn compiled(&self) -> &[&'static str] {
+        &self.compiled
+    }
+
+    pub(crate) fn missing(&self) -> &[&'static str] {
+        &self.missing
+    }
+}
+
+/// The instruction sets Plonky3 vectorizes with on x86_64, compiled in and
+/// detected.
+#[cfg(target_arch = "x86_64")]
+fn probe() -> (Vec<&'static str>, Vec<&'static str>) {
+    let mut compiled = Vec::new();
+    let mut detected = Vec::new();
+    if cfg!(target_feature = "avx2") {
+        compiled.push("avx2");
+    }
+    if cfg!(target_feature = "avx512f") {
+        compiled.push("avx512f");
+    }
+    if std::arch::is_x86_feature_detected!("avx2") {
+        detected.push("avx2");
+    }
+    if std::arch::is_x86_feature_detected!("avx512f") {
+        detected.push("avx512f");
+    }
+    (compiled, detected)
+}
+
+/// The instruction sets Plonky3 vectorizes with on aarch64, compiled in and
+/// detected.
+#[cfg(target_arch = "aarch64")]
+fn probe() -> (Vec<&'static str>, Vec<&'static str>) {
+    let mut compiled = Vec::new();
+    let mut detected = Vec::new();
+    if cfg!(target_feature = "neon") {
+        compiled.push("neon");
+    }
+    if std::arch::is_aarch64_feature_detected!("neon") {
+        detected.push("neon");
+    }
+    (compiled, detected)
+}
+
+/// Other architectures have no vectorized Plonky3 backend.
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn probe() -> (Vec<&'static str>, Vec<&'static str>) {
+    (Vec::new(), Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compiled_features_are_detected() {
+        // A binary using instructions the CPU lacks would not get this far.
+        let features = CpuFeatures::detect();
+        for feature in features.compiled() {
+            assert!(features.detected.contains(feature), "{}", feature);
+        }
+        assert!(features
+            .missing()
+            .iter()
+            .all(|feature| !features.compiled().contains(feature)));
+    }
+}
@@ -0,0 +1,163 @@
+//! Public demo mode.
+//!
+//! With `DEMO_MODE=1`, the backend can serve a public playground without
+//! unbounded compute cost: only programs whose SHA256 is listed in
+//! `DEMO_ALLOWLIST_FILE` (one hex digest per line, `#` starts a comment), or
+//! whose evaluation consumes at most `DEMO_MAX_CPU` CPU units, are proven.
+//! At most `DEMO_PROOFS_PER_HOUR` proofs (default 30) are started per hour.
+//! `/api/prove` still executes every other program and returns its
+//! commitment without a proof; `/api/prove/mcu-halo2` refuses it.
+
+use sha2::{Digest, Sha256};
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uplc_turbo::{arena::Arena, binder::DeBruijn, flat, program::Program};
+
+const RATE_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Why a program is not proven in demo mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DemoRefusal {
+    /// Neither allowlisted nor cheap enough.
+    NotAllowed { max_cpu: Option<i64> },
+    /// The hourly proof budget is spent.
+    RateLimited { proofs_per_hour: usize },
+}
+
+impl fmt::Display for DemoRefusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAllowed { max_cpu: Some(max_cpu) } => write!(
+                f,
+                "Demo mode only proves allowlisted programs and programs using at most {} CPU units",
+                max_cpu
+            ),
+            Self::NotAllowed { max_cpu: None } => {
+                write!(f, "Demo mode only proves allowlisted programs")
+            }
+            Self::RateLimited { proofs_per_hour } => write!(
+                f,
+                "Demo mode proves at most {} programs per hour, try again later",
+                proofs_per_hour
+            ),
+        }
+    }
+}
+
+/// Which programs get real proofs in demo mode.
+pub(crate) struct DemoPolicy {
+    allowlist: HashSet<[u8; 32]>,
+    max_cpu: Option<i64>,
+    proofs_per_hour: usize,
+    /// When the proofs of the last hour started, oldest first.
+    recent_proofs: Mutex<VecDeque<Instant>>,
+}
+
+impl fmt::Debug for DemoPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DemoPolicy")
+            .field("allowlisted", &self.allowlist.len())
+            .field("max_cpu", &self.max_cpu)
+            .field("proofs_per_hour", &self.proofs_per_hour)
+            .finish()
+    }
+}
+
+impl DemoPolicy {
+    /// The demo policy, when `DEMO_MODE` is set.
+    pub(crate) fn from_env() -> eyre::Result<Option<Self>> {
+        if !std::env::var("DEMO_MODE").is_ok_and(|v| v == "1" || v == "true") {
+            return Ok(None);
+        }
+        let allowlist = match crate::env_path("DEMO_ALLOWLIST_FILE") {
+            Some(path) => read_allowlist(&path)?,
+            None => HashSet::new(),
+        };
+        let max_cpu = match std::env::var("DEMO_MAX_CPU") {
+            Ok(value) if !value.is_empty() => Some(
+                value
+                    .parse()
+                    .map_err(|_| eyre::eyre!("Invalid DEMO_MAX_CPU {:?}", value))?,
+            ),
+            _ => None,
+        };
+        let proofs_per_hour = std::env::var("DEMO_PROOFS_PER_HOUR")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(30);
+        Ok(Some(Self {
+            allowlist,
+            max_cpu,
+            proofs_per_hour,
+            recent_proofs: Mutex::new(VecDeque::new()),
+        }))
+    }
+
+    /// Whether `program_bytes` may be proven now, counting it against the
+    /// hourly budget if so.
+    ///
+    /// Blocking: programs that are not allowlisted are evaluated on the host
+    /// to measure their cost, so call from `web::block`.
+    pub(crate) fn check(&self, program_bytes: &[u8]) -> Result<(), DemoRefusal> {
+        let allowlisted = self
+            .allowlist
+            .contains(&<[u8; 32]>::from(Sha256::digest(program_bytes)));
+        let cheap = || {
+            self.max_cpu
+                .zip(consumed_cpu(program_bytes))
+                .is_some_and(|(max_cpu, cpu)| cpu <= max_cpu)
+        };
+        if !allowlisted && !cheap() {
+            return Err(DemoRefusal::NotAllowed {
+                max_cpu: self.max_cpu,
+            });
+        }
+
+        let now = Instant::now();
+        let mut recent_proofs = self.recent_proofs.lock().expect("demo lock poisoned");
+        while recent_proofs
+            .front()
+            .is_some_and(|started| now.duration_since(*started) >= RATE_WINDOW)
+        {
+            recent_proofs.pop_front();
+        }
+        if recent_proofs.len() >= self.proofs_per_hour {
+            return Err(DemoRefusal::RateLimited {
+                proofs_per_hour: self.proofs_per_hour,
+            });
+        }
+        recent_proofs.push_back(now);
+        Ok(())
+    }
+}
+
+fn read_allowlist(path: &Path) -> eyre::Result<HashSet<[u8; 32]>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| eyre::eyre!("Failed to read {}: {}", path.display(), e))?;
+    contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            crate::hex32(line).map_err(|e| {
+                eyre::eyre!(
+                    "Invalid program hash {:?} in {}: {}",
+                    line,
+                    path.display(),
+                    e
+                )
+            })
+        })
+        .collect()
+}
+
+/// CPU units the program consumes when evaluated on the host, whether or not
+/// it succeeds. `None` if it does not decode.
+fn consumed_cpu(program_bytes: &[u8]) -> Option<i64> {
+    let arena = Arena::new();
+    let program: &Program<DeBruijn> = flat::decode(&arena, program_bytes).ok()?;
+    Some(program.eval(&arena).info.consumed_budget.cpu)
+}
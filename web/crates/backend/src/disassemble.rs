@@ -0,0 +1,128 @@
+//! `POST /api/disassemble`: decode a flat UPLC program without proving it, so
+//! clients without the WASM module can inspect what they are about to prove.
+
+use crate::AppState;
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uplc_turbo::{arena::Arena, binder::DeBruijn, flat, program::Program, term::Term};
+
+/// Request for POST /api/disassemble.
+#[derive(Deserialize)]
+pub(crate) struct DisassembleRequest {
+    /// Hex-encoded flat UPLC program
+    program_hex: String,
+}
+
+/// Shape of the decoded program.
+#[derive(Debug, Default, Serialize)]
+struct ProgramStats {
+    /// Size of the flat-encoded program.
+    size_bytes: usize,
+    /// Number of term nodes.
+    terms: usize,
+    /// Deepest nesting of terms, the root being at depth 1.
+    max_depth: usize,
+    /// Number of nodes of each kind (`apply`, `lambda`, `builtin`, ...).
+    term_kinds: BTreeMap<&'static str, usize>,
+}
+
+/// Response from POST /api/disassemble.
+#[derive(Serialize)]
+struct DisassembleResponse {
+    success: bool,
+    /// Human-readable program, in the same format as the browser's `hex_to_uplc`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uplc: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<ProgramStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn term_kind(term: &Term<'_, DeBruijn>) -> &'static str {
+    match term {
+        Term::Var(_) => "var",
+        Term::Lambda { .. } => "lambda",
+        Term::Apply { .. } => "apply",
+        Term::Delay(_) => "delay",
+        Term::Force(_) => "force",
+        Term::Case { .. } => "case",
+        Term::Constr { .. } => "constr",
+        Term::Constant(_) => "constant",
+        Term::Builtin(_) => "builtin",
+        Term::Error => "error",
+    }
+}
+
+/// Walk the term tree with an explicit stack, as deeply nested programs would
+/// overflow a recursive walk.
+fn program_stats(program: &Program<'_, DeBruijn>, size_bytes: usize) -> ProgramStats {
+    let mut stats = ProgramStats {
+        size_bytes,
+        ..ProgramStats::default()
+    };
+    let mut pending = vec![(program.term, 1)];
+    while let Some((term, depth)) = pending.pop() {
+        stats.terms += 1;
+        stats.max_depth = stats.max_depth.max(depth);
+        *stats.term_kinds.entry(term_kind(term)).or_default() += 1;
+
+        match term {
+            Term::Lambda { body, .. } => pending.push((*body, depth + 1)),
+            Term::Apply { function, argument } => {
+                pending.push((*function, depth + 1));
+                pending.push((*argument, depth + 1));
+            }
+            Term::Delay(inner) | Term::Force(inner) => pending.push((*inner, depth + 1)),
+            Term::Case { constr, branches } => {
+                pending.push((*constr, depth + 1));
+                pending.extend(branches.iter().map(|branch| (*branch, depth + 1)));
+            }
+            Term::Constr { fields, .. } => {
+                pending.extend(fields.iter().map(|field| (*field, depth + 1)));
+            }
+            Term::Var(_) | Term::Constant(_) | Term::Builtin(_) | Term::Error => {}
+        }
+    }
+    stats
+}
+
+fn disassemble_error(error: String) -> HttpResponse {
+    HttpResponse::BadRequest().json(DisassembleResponse {
+        success: false,
+        uplc: None,
+        stats: None,
+        error: Some(error),
+    })
+}
+
+/// POST /api/disassemble
+///
+/// Return the pretty-printed program and its stats. Nothing is evaluated.
+pub(crate) async fn disassemble(
+    data: web::Data<AppState>,
+    body: web::Json<DisassembleRequest>,
+) -> HttpResponse {
+    let program_bytes = match hex::decode(body.program_hex.trim()) {
+        Ok(bytes) => bytes,
+        Err(e) => return disassemble_error(format!("Invalid hex: {}", e)),
+    };
+    // Printing walks the program recursively, so check its shape first.
+    if let Err(e) = data.decode_limits.check_flat(&program_bytes) {
+        return disassemble_error(e.to_string());
+    }
+
+    let arena = Arena::new();
+    let program: &Program<DeBruijn> = match flat::decode(&arena, &program_bytes) {
+        Ok(program) => program,
+        Err(e) => return disassemble_error(format!("Program decode error: {:?}", e)),
+    };
+
+    HttpResponse::Ok().json(DisassembleResponse {
+        success: true,
+        uplc: Some(format!("{:#?}", program)),
+        stats: Some(program_stats(program, program_bytes.len())),
+        error: None,
+    })
+}
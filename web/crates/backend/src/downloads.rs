@@ -0,0 +1,380 @@
+//! Conditional, ranged and pre-compressed responses for large downloads:
+//! `agg_stark.vk` and proof bundles.
+//!
+//! Every response carries an `ETag` (the SHA256 of the uncompressed body), so
+//! clients revalidate with `If-None-Match` instead of downloading again, and
+//! resume an interrupted download with `Range: bytes=N-` and `If-Range`.
+//! A file may have pre-compressed siblings, `<file>.br` or `<file>.gz`,
+//! served to clients that accept the encoding, with the encoding appended
+//! to the `ETag` since their bytes differ. Ranges always address the
+//! uncompressed body, since browsers decode compressed responses before a
+//! client can count the bytes it received. A file's SHA256 is cached until
+//! its modification time or length changes.
+
+use actix_web::http::{header, StatusCode};
+use actix_web::{HttpRequest, HttpResponse};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Pre-compressed siblings, most preferred first: `Content-Encoding` and
+/// file extension.
+const ENCODINGS: [(&str, &str); 2] = [("br", "br"), ("gzip", "gz")];
+
+/// Modification time and length of a file, which change when it is
+/// rewritten.
+type FileStamp = (SystemTime, u64);
+
+/// SHA256 of the files served, as hex, with the stamp of the file it was
+/// computed from.
+static DIGESTS: Mutex<BTreeMap<PathBuf, (FileStamp, String)>> = Mutex::new(BTreeMap::new());
+
+fn file_stamp(metadata: &std::fs::Metadata) -> Option<FileStamp> {
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+fn cached_sha256(path: &Path, stamp: Option<FileStamp>) -> Option<String> {
+    let digests = DIGESTS.lock().expect("digest cache lock poisoned");
+    match (digests.get(path), stamp) {
+        (Some((cached, digest)), Some(stamp)) if *cached == stamp => Some(digest.clone()),
+        _ => None,
+    }
+}
+
+fn cache_sha256(path: &Path, stamp: Option<FileStamp>, digest: &str) {
+    if let Some(stamp) = stamp {
+        DIGESTS
+            .lock()
+            .expect("digest cache lock poisoned")
+            .insert(path.to_path_buf(), (stamp, digest.to_string()));
+    }
+}
+
+/// SHA256 of the file at `path`, as hex, read and hashed only if it changed
+/// since it was last hashed here.
+pub(crate) async fn file_sha256(path: &Path) -> std::io::Result<String> {
+    let stamp = file_stamp(&tokio::fs::metadata(path).await?);
+    if let Some(digest) = cached_sha256(path, stamp) {
+        return Ok(digest);
+    }
+    let digest = hex::encode(Sha256::digest(tokio::fs::read(path).await?));
+    cache_sha256(path, stamp, &digest);
+    Ok(digest)
+}
+
+/// A body to serve: uncompressed, with a pre-compressed variant if one
+/// exists.
+pub(crate) struct Download {
+    pub(crate) bytes: Vec<u8>,
+    /// SHA256 of `bytes`, as hex.
+    sha256: String,
+    /// `Content-Encoding` and the compressed body.
+    pub(crate) encoded: Option<(&'static str, Vec<u8>)>,
+    /// Extra headers sent with every response.
+    headers: Vec<(&'static str, String)>,
+}
+
+impl Download {
+    pub(crate) fn new(bytes: Vec<u8>) -> Self {
+        let sha256 = hex::encode(Sha256::digest(&bytes));
+        Self::hashed(bytes, sha256)
+    }
+
+    fn hashed(bytes: Vec<u8>, sha256: String) -> Self {
+        Self {
+            bytes,
+            sha256,
+            encoded: None,
+            headers: Vec::new(),
+        }
+    }
+
+    /// SHA256 of the uncompressed body, as hex.
+    pub(crate) fn sha256(&self) -> &str {
+        &self.sha256
+    }
+
+    /// Send `name: value` with every response.
+    pub(crate) fn header(mut self, name: &'static str, value: String) -> Self {
+        self.headers.push((name, value));
+        self
+    }
+
+    /// Read `path`, with the first of its pre-compressed siblings that the
+    /// request accepts. Siblings older than `path` are stale and ignored.
+    pub(crate) async fn read(path: &Path, req: &HttpRequest) -> std::io::Result<Self> {
+        let metadata = tokio::fs::metadata(path).await?;
+        let stamp = file_stamp(&metadata);
+        let modified = metadata.modified().ok();
+        let bytes = tokio::fs::read(path).await?;
+        let sha256 = match cached_sha256(path, stamp) {
+            Some(sha256) => sha256,
+            None => {
+                let sha256 = hex::encode(Sha256::digest(&bytes));
+                cache_sha256(path, stamp, &sha256);
+                sha256
+            }
+        };
+        let accepted = accepted_encodings(header_str(req, header::ACCEPT_ENCODING));
+        for (encoding, extension) in ENCODINGS {
+            if !accepted.contains(&encoding) {
+                continue;
+            }
+            let sibling = sibling(path, extension);
+            let Ok(metadata) = tokio::fs::metadata(&sibling).await else {
+                continue;
+            };
+            if modified.is_some_and(|modified| {
+                !metadata.modified().is_ok_and(|sibling| sibling >= modified)
+            }) {
+                continue;
+            }
+            if let Ok(encoded) = tokio::fs::read(&sibling).await {
+                return Ok(Self {
+                    encoded: Some((encoding, encoded)),
+                    ..Self::hashed(bytes, sha256)
+                });
+            }
+        }
+        Ok(Self::hashed(bytes, sha256))
+    }
+
+    /// The response to `req`: `304` when the client's copy is current, `206`
+    /// with the requested range, `416` for a range past the end, and the
+    /// whole body otherwise.
+    pub(crate) fn respond(
+        self,
+        req: &HttpRequest,
+        content_type: &str,
+        cache_control: &str,
+    ) -> HttpResponse {
+        // Ranges and `If-Range` address the uncompressed body, so its ETag
+        // validates them; a compressed response has an ETag of its own.
+        let identity_etag = format!("\"{}\"", self.sha256);
+        let range = match header_str(req, header::IF_RANGE) {
+            Some(validator) if validator != identity_etag => ByteRange::Full,
+            _ => byte_range(header_str(req, header::RANGE), self.bytes.len()),
+        };
+        let etag = match (&range, &self.encoded) {
+            (ByteRange::Full, Some((encoding, _))) => format!("\"{}-{}\"", self.sha256, encoding),
+            _ => identity_etag,
+        };
+        let response = |status: StatusCode| {
+            let mut builder = HttpResponse::build(status);
+            builder
+                .content_type(content_type)
+                .append_header((header::CACHE_CONTROL, cache_control))
+                .append_header((header::ETAG, etag.as_str()))
+                .append_header((header::ACCEPT_RANGES, "bytes"))
+                .append_header((header::VARY, "Accept-Encoding"));
+            for (name, value) in &self.headers {
+                builder.append_header((*name, value.as_str()));
+            }
+            builder
+        };
+
+        if etag_matches(header_str(req, header::IF_NONE_MATCH), &etag) {
+            return response(StatusCode::NOT_MODIFIED).finish();
+        }
+        match (range, self.encoded) {
+            (ByteRange::Partial(range), _) => response(StatusCode::PARTIAL_CONTENT)
+                .append_header((
+                    header::CONTENT_RANGE,
+                    format!(
+                        "bytes {}-{}/{}",
+                        range.start,
+                        range.end - 1,
+                        self.bytes.len()
+                    ),
+                ))
+                .body(self.bytes[range].to_vec()),
+            (ByteRange::Unsatisfiable, _) => response(StatusCode::RANGE_NOT_SATISFIABLE)
+                .append_header((
+                    header::CONTENT_RANGE,
+                    format!("bytes */{}", self.bytes.len()),
+                ))
+                .finish(),
+            (ByteRange::Full, Some((encoding, encoded))) => response(StatusCode::OK)
+                .append_header((header::CONTENT_ENCODING, encoding))
+                .body(encoded),
+            (ByteRange::Full, None) => response(StatusCode::OK).body(self.bytes),
+        }
+    }
+}
+
+fn header_str(req: &HttpRequest, name: header::HeaderName) -> Option<&str> {
+    req.headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+}
+
+/// `path` with `extension` appended, e.g. `agg_stark.vk.gz`.
+fn sibling(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+/// Encodings an `Accept-Encoding` header allows, ignoring those with `q=0`.
+fn accepted_encodings(header: Option<&str>) -> Vec<&str> {
+    header
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';').map(str::trim);
+            let encoding = parts.next().filter(|encoding| !encoding.is_empty())?;
+            let refused = parts.any(|param| {
+                param
+                    .strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .is_some_and(|q| q == 0.0)
+            });
+            (!refused).then_some(encoding)
+        })
+        .collect()
+}
+
+/// Whether an `If-None-Match` header lists `etag`, compared weakly.
+fn etag_matches(header: Option<&str>, etag: &str) -> bool {
+    header.is_some_and(|header| {
+        header
+            .split(',')
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag == "*" || tag == etag)
+    })
+}
+
+#[derive(Debug, PartialEq)]
+enum ByteRange {
+    Full,
+    Partial(Range<usize>),
+    Unsatisfiable,
+}
+
+/// The part of a `len`-byte body a `Range` header asks for. Malformed
+/// headers and multiple ranges are ignored, as HTTP allows, and get the
+/// full body.
+fn byte_range(header: Option<&str>, len: usize) -> ByteRange {
+    let Some(spec) = header.and_then(|header| header.trim().strip_prefix("bytes=")) else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = spec.split_once('-') else {
+        return ByteRange::Full;
+    };
+    let (start, end) = (start.trim(), end.trim());
+    let range = if start.is_empty() {
+        // `bytes=-N`: the last N bytes.
+        match end.parse::<usize>() {
+            Ok(0) => return ByteRange::Unsatisfiable,
+            Ok(suffix) => len.saturating_sub(suffix)..len,
+            Err(_) => return ByteRange::Full,
+        }
+    } else {
+        let Ok(start) = start.parse::<usize>() else {
+            return ByteRange::Full;
+        };
+        let end = match end {
+            "" => len,
+            end => match end.parse::<usize>() {
+                Ok(end) if end >= start => end.saturating_add(1).min(len),
+                _ => return ByteRange::Full,
+            },
+        };
+        start..end
+    };
+    if range.start >= len {
+        ByteRange::Unsatisfiable
+    } else {
+        ByteRange::Partial(range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_byte_ranges() {
+        assert_eq!(byte_range(None, 10), ByteRange::Full);
+        assert_eq!(byte_range(Some("bytes=2-5"), 10), ByteRange::Partial(2..6));
+        assert_eq!(byte_range(Some("bytes=4-"), 10), ByteRange::Partial(4..10));
+        assert_eq!(byte_range(Some("bytes=-3"), 10), ByteRange::Partial(7..10));
+        assert_eq!(
+            byte_range(Some("bytes=8-100"), 10),
+            ByteRange::Partial(8..10)
+        );
+        assert_eq!(byte_range(Some("bytes=10-"), 10), ByteRange::Unsatisfiable);
+        assert_eq!(byte_range(Some("bytes=-0"), 10), ByteRange::Unsatisfiable);
+        assert_eq!(byte_range(Some("bytes=5-2"), 10), ByteRange::Full);
+        assert_eq!(byte_range(Some("bytes=0-1,4-5"), 10), ByteRange::Full);
+        assert_eq!(byte_range(Some("items=0-1"), 10), ByteRange::Full);
+    }
+
+    #[test]
+    fn matches_etags_and_encodings() {
+        assert!(etag_matches(Some("\"a\", W/\"b\""), "\"b\""));
+        assert!(etag_matches(Some("*"), "\"b\""));
+        assert!(!etag_matches(Some("\"a\""), "\"b\""));
+        assert_eq!(
+            accepted_encodings(Some("gzip, deflate, br;q=0")),
+            vec!["gzip", "deflate"]
+        );
+        assert_eq!(
+            sibling(Path::new("/keys/agg_stark.vk"), "gz"),
+            PathBuf::from("/keys/agg_stark.vk.gz")
+        );
+    }
+
+    #[test]
+    fn tags_each_encoding() {
+        use actix_web::test::TestRequest;
+
+        let download = |encoded: Option<(&'static str, Vec<u8>)>| Download {
+            encoded,
+            ..Download::new(b"body".to_vec())
+        };
+        let gzipped = || download(Some(("gzip", b"compressed".to_vec())));
+        let etag = |response: HttpResponse| {
+            response.headers()[header::ETAG]
+                .to_str()
+                .expect("ETag is ASCII")
+                .to_string()
+        };
+        let request = TestRequest::default().to_http_request();
+        let identity = etag(download(None).respond(&request, "text/plain", "no-cache"));
+        let gzip = etag(gzipped().respond(&request, "text/plain", "no-cache"));
+        assert_ne!(identity, gzip);
+
+        // Ranges are served uncompressed, under the uncompressed body's ETag.
+        let ranged = TestRequest::default()
+            .insert_header((header::RANGE, "bytes=0-1"))
+            .to_http_request();
+        assert_eq!(
+            etag(gzipped().respond(&ranged, "text/plain", "no-cache")),
+            identity
+        );
+
+        let revalidate = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, gzip.as_str()))
+            .to_http_request();
+        assert_eq!(
+            gzipped()
+                .respond(&revalidate, "text/plain", "no-cache")
+                .status(),
+            StatusCode::NOT_MODIFIED
+        );
+        assert_eq!(
+            download(None)
+                .respond(&revalidate, "text/plain", "no-cache")
+                .status(),
+            StatusCode::OK
+        );
+    }
+}
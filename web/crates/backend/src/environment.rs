@@ -0,0 +1,193 @@
+//! Host environment the backend resolves its default paths from.
+//!
+//! Defaults must hold on Linux, macOS, and Windows alike: the home directory
+//! comes from the platform (`$HOME`, or the user profile on Windows) with no
+//! hard-coded fallback, the guest sources default to their location in this
+//! repository rather than a path relative to the working directory, and the
+//! workspace that holds `target/` is found by looking for its `Cargo.toml`
+//! instead of assuming how deep the guest sits in it.
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Environment variables and home directory, read once.
+pub(crate) struct Environment {
+    vars: HashMap<String, OsString>,
+    home_dir: Option<PathBuf>,
+}
+
+impl Environment {
+    pub(crate) fn from_process() -> Self {
+        Self {
+            vars: std::env::vars_os()
+                .filter_map(|(key, value)| Some((key.into_string().ok()?, value)))
+                .collect(),
+            home_dir: dirs::home_dir(),
+        }
+    }
+
+    /// A path variable, treating empty values as unset.
+    pub(crate) fn path(&self, var: &str) -> Option<PathBuf> {
+        self.vars
+            .get(var)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// Directory of the OpenVM guest workspace: `OPENVM_GUEST_DIR`, or
+    /// `crates/zkvms/openvm` of the repository the backend was built from.
+    pub(crate) fn guest_dir(&self) -> PathBuf {
+        normalize(
+            self.path("OPENVM_GUEST_DIR")
+                .unwrap_or_else(default_guest_dir),
+        )
+    }
+
+    /// Where guest builds and app keys go: `OPENVM_TARGET_DIR`, or `target`
+    /// of the outermost Cargo workspace containing `guest_dir`. Without one,
+    /// e.g. when only pre-built artifacts are deployed, `target` under the
+    /// guest dir.
+    pub(crate) fn target_dir(&self, guest_dir: &Path) -> PathBuf {
+        self.path("OPENVM_TARGET_DIR").unwrap_or_else(|| {
+            guest_dir
+                .ancestors()
+                .filter(|dir| dir.join("Cargo.toml").is_file())
+                .last()
+                .unwrap_or(guest_dir)
+                .join("target")
+        })
+    }
+
+    /// Where the aggregation keys go: `OPENVM_HOME`, or `.openvm` in the
+    /// user's home directory.
+    pub(crate) fn openvm_home(&self) -> eyre::Result<PathBuf> {
+        if let Some(openvm_home) = self.path("OPENVM_HOME") {
+            return Ok(openvm_home);
+        }
+        self.home_dir
+            .as_ref()
+            .map(|home| home.join(".openvm"))
+            .ok_or_else(|| eyre::eyre!("Cannot determine the home directory. Set OPENVM_HOME."))
+    }
+}
+
+/// `crates/zkvms/openvm` of this repository, located from the backend crate
+/// at build time.
+fn default_guest_dir() -> PathBuf {
+    ["..", "..", "..", "crates", "zkvms", "openvm"]
+        .iter()
+        .fold(PathBuf::from(env!("CARGO_MANIFEST_DIR")), |path, part| {
+            path.join(part)
+        })
+}
+
+/// Make `path` absolute and resolve `..`, so that its ancestors are real
+/// directories. Paths that do not exist are only made absolute.
+fn normalize(path: PathBuf) -> PathBuf {
+    match path.canonicalize() {
+        Ok(canonical) => strip_verbatim_prefix(canonical),
+        Err(_) => std::path::absolute(&path).unwrap_or(path),
+    }
+}
+
+/// On Windows, canonical paths carry the `\\?\` verbatim prefix, which many
+/// tools (and users reading logs) do not expect; drop it for drive paths.
+#[cfg(windows)]
+fn strip_verbatim_prefix(path: PathBuf) -> PathBuf {
+    use std::path::{Component, Prefix};
+
+    match path.components().next() {
+        Some(Component::Prefix(prefix)) if matches!(prefix.kind(), Prefix::VerbatimDisk(_)) => path
+            .to_str()
+            .and_then(|path| path.strip_prefix(r"\\?\"))
+            .map(PathBuf::from)
+            .unwrap_or(path),
+        _ => path,
+    }
+}
+
+#[cfg(not(windows))]
+fn strip_verbatim_prefix(path: PathBuf) -> PathBuf {
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::scratch_dir;
+
+    fn environment(vars: &[(&str, &Path)], home_dir: Option<&Path>) -> Environment {
+        Environment {
+            vars: vars
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.as_os_str().to_owned()))
+                .collect(),
+            home_dir: home_dir.map(Path::to_path_buf),
+        }
+    }
+
+    #[test]
+    fn empty_variables_are_unset() {
+        let env = environment(&[("OPENVM_HOME", Path::new(""))], None);
+        assert_eq!(env.path("OPENVM_HOME"), None);
+        assert!(env.openvm_home().is_err());
+    }
+
+    #[test]
+    fn openvm_home_defaults_to_the_home_directory() {
+        let home = Path::new("home").join("user");
+        let env = environment(&[], Some(&home));
+        assert_eq!(env.openvm_home().unwrap(), home.join(".openvm"));
+
+        let openvm_home = Path::new("keys");
+        let env = environment(&[("OPENVM_HOME", openvm_home)], Some(&home));
+        assert_eq!(env.openvm_home().unwrap(), openvm_home);
+    }
+
+    #[test]
+    fn default_guest_dir_is_the_repository_guest_workspace() {
+        let guest_dir = environment(&[], None).guest_dir();
+        assert!(guest_dir.is_absolute());
+        assert!(guest_dir.join("openvm.toml").is_file());
+        assert!(guest_dir.join("guest").join("Cargo.toml").is_file());
+    }
+
+    #[test]
+    fn guest_dir_resolves_parent_components() {
+        let root = scratch_dir("environment", "guest-dir");
+        let guest_dir = root.join("openvm");
+        std::fs::create_dir_all(&guest_dir).unwrap();
+        let indirect = root.join("elsewhere").join("..").join("openvm");
+        std::fs::create_dir_all(root.join("elsewhere")).unwrap();
+
+        let env = environment(&[("OPENVM_GUEST_DIR", &indirect)], None);
+        assert_eq!(env.guest_dir(), normalize(guest_dir));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn target_dir_is_in_the_outermost_workspace() {
+        let root = scratch_dir("environment", "workspace");
+        let guest_dir = root.join("crates").join("zkvms").join("openvm");
+        std::fs::create_dir_all(guest_dir.join("guest")).unwrap();
+        std::fs::write(root.join("Cargo.toml"), "[workspace]\n").unwrap();
+        std::fs::write(guest_dir.join("Cargo.toml"), "[workspace]\n").unwrap();
+
+        let env = environment(&[], None);
+        assert_eq!(env.target_dir(&guest_dir), root.join("target"));
+
+        let target_dir = root.join("elsewhere");
+        let env = environment(&[("OPENVM_TARGET_DIR", &target_dir)], None);
+        assert_eq!(env.target_dir(&guest_dir), target_dir);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn target_dir_without_a_workspace_is_under_the_guest_dir() {
+        let root = scratch_dir("environment", "no-workspace");
+        let env = environment(&[], None);
+        assert_eq!(env.target_dir(&root), root.join("target"));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,113 @@
+//! Host-side evaluation, and its comparison with what the guest committed.
+//!
+//! With `EVALUATION_CHECK` set, `/api/prove` evaluates every program on the
+//! host as well and reports in `evaluation_check` whether the host would have
+//! committed to the same outcome as the guest. A mismatch means the host and
+//! guest evaluators diverge, which users then see right away instead of when
+//! a proof is audited. Comparing commitments rather than results covers
+//! failures too, and uses the guest protocol's canonical encoding of both.
+
+use guest_protocol::ErrorKind;
+use serde::Serialize;
+use std::panic::{self, AssertUnwindSafe};
+use tracing::warn;
+use uplc::UplcError;
+
+/// How a program evaluated on the host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum HostEvaluation {
+    /// The result, formatted as the guest hashes it.
+    Success(String),
+    /// A script failure, classified like the guest does.
+    Failure(ErrorKind, String),
+    /// The evaluator panicked.
+    Panic(String),
+}
+
+/// Evaluate `program_bytes` with the host's evaluator.
+pub(crate) fn evaluate_on_host(program_bytes: &[u8]) -> HostEvaluation {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        uplc::get_evaluator().evaluate_program_bytes(program_bytes)
+    }));
+    match result {
+        Ok(Ok(result)) => HostEvaluation::Success(result.to_string()),
+        Ok(Err(e)) => {
+            let kind = match e.downcast_ref::<UplcError>() {
+                Some(UplcError::DecodeError(_)) => ErrorKind::Decode,
+                Some(UplcError::BudgetExceeded(_)) => ErrorKind::BudgetExceeded,
+                Some(UplcError::ResultError(_)) => ErrorKind::NonConstantResult,
+                Some(UplcError::EvaluationError(_)) | None => ErrorKind::Evaluation,
+            };
+            HostEvaluation::Failure(kind, e.to_string())
+        }
+        Err(payload) => HostEvaluation::Panic(
+            payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default(),
+        ),
+    }
+}
+
+/// Host evaluation of a proven program, next to the guest's commitment.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct EvaluationCheck {
+    /// Whether the host would have committed to the guest's public values.
+    matches: bool,
+    /// `success`, `failure` or `panic`.
+    host_outcome: &'static str,
+    /// The host's result, when it evaluated successfully.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    host_result: Option<String>,
+    /// The host's failure kind (`decode`, `evaluation`, ...) or panic message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    host_error: Option<String>,
+    /// What the host would have committed to, as hex. Absent on panics.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    host_commitment: Option<String>,
+}
+
+/// Compare the host's evaluation of `program_bytes` with the public values
+/// the guest revealed for it, logging mismatches.
+pub(crate) fn check(program_bytes: &[u8], public_values: &[u8]) -> EvaluationCheck {
+    let hasher = guest_protocol::ProgramHasher::with_program(program_bytes);
+    let check = match evaluate_on_host(program_bytes) {
+        HostEvaluation::Success(result) => {
+            let commitment = hasher.success_commitment(&result);
+            EvaluationCheck {
+                matches: commitment[..] == *public_values,
+                host_outcome: "success",
+                host_result: Some(result),
+                host_error: None,
+                host_commitment: Some(hex::encode(commitment)),
+            }
+        }
+        HostEvaluation::Failure(kind, _) => {
+            let commitment = hasher.failure_commitment(kind);
+            EvaluationCheck {
+                matches: commitment[..] == *public_values,
+                host_outcome: "failure",
+                host_result: None,
+                host_error: Some(kind.label().to_string()),
+                host_commitment: Some(hex::encode(commitment)),
+            }
+        }
+        HostEvaluation::Panic(message) => EvaluationCheck {
+            matches: false,
+            host_outcome: "panic",
+            host_result: None,
+            host_error: Some(message),
+            host_commitment: None,
+        },
+    };
+    if !check.matches {
+        warn!(
+            "Host and guest evaluation diverge: host {} ({:?}), guest committed {}",
+            check.host_outcome,
+            check.host_result.as_ref().or(check.host_error.as_ref()),
+            hex::encode(public_values)
+        );
+    }
+    check
+}
@@ -0,0 +1,131 @@
+//! Example programs served at `/api/examples`.
+//!
+//! The corpus lives in `corpus/`: one `<id>.json` per example with its name,
+//! description, and, for UPLC programs, the program and the outcome, result,
+//! and commitment the guest is expected to produce; Aiken examples add an
+//! `<id>.ak` source instead, compiled by the playground. Files are embedded at
+//! build time, so add new examples to `EXAMPLES` below. The playground's
+//! "Load example" menu and the e2e harness both read this list.
+
+use actix_web::HttpResponse;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Embedded examples: id, metadata, and Aiken source.
+const EXAMPLES: &[(&str, &str, Option<&str>)] = &[
+    ("answer", include_str!("../corpus/answer.json"), None),
+    ("add", include_str!("../corpus/add.json"), None),
+    ("multiply", include_str!("../corpus/multiply.json"), None),
+    (
+        "explicit-error",
+        include_str!("../corpus/explicit-error.json"),
+        None,
+    ),
+    ("identity", include_str!("../corpus/identity.json"), None),
+    ("malformed", include_str!("../corpus/malformed.json"), None),
+    (
+        "fib",
+        include_str!("../corpus/fib.json"),
+        Some(include_str!("../corpus/fib.ak")),
+    ),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Example {
+    #[serde(default)]
+    id: String,
+    name: String,
+    description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    aiken_source: Option<String>,
+    /// Hex-encoded flat program, for UPLC examples.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    program_hex: Option<String>,
+    #[serde(default)]
+    prove_failures: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expected: Option<Expected>,
+}
+
+/// What proving an example yields.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Expected {
+    outcome: crate::Outcome,
+    /// The rendered result, for successful programs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result: Option<String>,
+    /// The failure kind, for failing programs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error_kind: Option<String>,
+    commitment: String,
+}
+
+fn examples() -> &'static [Example] {
+    static PARSED: OnceLock<Vec<Example>> = OnceLock::new();
+    PARSED.get_or_init(|| {
+        EXAMPLES
+            .iter()
+            .map(|(id, metadata, aiken_source)| {
+                let mut example: Example = serde_json::from_str(metadata)
+                    .unwrap_or_else(|e| panic!("invalid example corpus/{}.json: {}", id, e));
+                example.id = id.to_string();
+                example.aiken_source = aiken_source.map(str::to_string);
+                example
+            })
+            .collect()
+    })
+}
+
+/// GET /api/examples
+pub(crate) async fn list() -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({ "examples": examples() }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn examples_are_aiken_or_uplc() {
+        for example in examples() {
+            assert_ne!(
+                example.aiken_source.is_some(),
+                example.program_hex.is_some(),
+                "{}",
+                example.id
+            );
+            assert_eq!(
+                example.program_hex.is_some(),
+                example.expected.is_some(),
+                "{}",
+                example.id
+            );
+        }
+    }
+
+    #[test]
+    fn expected_commitments_match_their_programs() {
+        for example in examples() {
+            let (Some(program_hex), Some(expected)) = (&example.program_hex, &example.expected)
+            else {
+                continue;
+            };
+            let program_bytes = hex::decode(program_hex).unwrap();
+            let commitment = match (&expected.result, &expected.error_kind) {
+                (Some(result), None) => guest_protocol::success_commitment(&program_bytes, result),
+                (None, Some(kind)) => {
+                    assert!(example.prove_failures, "{}", example.id);
+                    let kind = guest_protocol::ErrorKind::from_label(kind).unwrap();
+                    guest_protocol::failure_commitment(&program_bytes, kind)
+                }
+                _ => panic!("{} needs either a result or an error kind", example.id),
+            };
+            assert_eq!(
+                hex::encode(commitment),
+                expected.commitment,
+                "{}",
+                example.id
+            );
+        }
+    }
+}
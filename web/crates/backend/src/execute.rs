@@ -0,0 +1,253 @@
+//! `POST /api/execute` and `POST /api/evaluate`: run a program without
+//! proving it.
+//!
+//! Both keep working in degraded mode, when the backend started without the
+//! artifacts proving needs: evaluating on the host needs no artifacts at all,
+//! and executing the guest only its executable and config.
+
+use crate::evaluation_check::{evaluate_on_host, HostEvaluation};
+use crate::{
+    committed_outcome, openvm_version_tag, preflight_program, AppState, Outcome, OutcomeDetails,
+    ProveRequest,
+};
+use actix_web::{web, HttpResponse, HttpResponseBuilder};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// The guest executable and its config: enough to execute programs without
+/// the proving keys.
+pub(crate) struct Guest {
+    pub(crate) config: openvm_prover::Config,
+    pub(crate) exe: Arc<openvm_prover::Exe>,
+}
+
+/// Response from POST /api/execute.
+#[derive(Serialize)]
+struct ExecuteResponse {
+    success: bool,
+    openvm_version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    outcome: Option<Outcome>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    outcome_details: Option<OutcomeDetails>,
+    /// The public values the guest revealed, hex-encoded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    public_values_hex: Option<String>,
+    /// Label of the status word opening the public values.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<&'static str>,
+    /// Cycles, segments and trace heights of the run. Metering needs the app
+    /// proving key, so it is missing in degraded mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    execution: Option<openvm_prover::ExecutionReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_secs: Option<f64>,
+}
+
+impl ExecuteResponse {
+    fn failure(error: String, duration_secs: Option<f64>) -> Self {
+        Self {
+            success: false,
+            openvm_version: openvm_version_tag(),
+            outcome: None,
+            outcome_details: None,
+            public_values_hex: None,
+            status: None,
+            execution: None,
+            error: Some(error),
+            duration_secs,
+        }
+    }
+}
+
+/// POST /api/execute
+///
+/// Runs a program through the guest and returns the public values it
+/// reveals, without proving them. Takes the same body as `/api/prove`.
+pub(crate) async fn execute(
+    data: web::Data<AppState>,
+    body: web::Json<ProveRequest>,
+) -> HttpResponse {
+    let start = Instant::now();
+    let program_bytes = match body.program_bytes() {
+        Ok(bytes) => bytes,
+        Err(e) => return HttpResponse::BadRequest().json(ExecuteResponse::failure(e, None)),
+    };
+    if let Err(e) = preflight_program(&data.decode_limits, &program_bytes) {
+        return HttpResponse::UnprocessableEntity().json(ExecuteResponse::failure(e, None));
+    }
+    let prover = data.prover.clone();
+    let guest = data.guest.clone();
+    if prover.is_none() && guest.is_none() {
+        return HttpResponse::ServiceUnavailable().json(ExecuteResponse::failure(
+            format!(
+                "Execution is unavailable until setup provides: {}",
+                data.missing_artifacts.join(", ")
+            ),
+            None,
+        ));
+    }
+
+    let mode = body.execution_mode();
+    // With a timeout, execution runs in a worker that is killed on timeout.
+    let worker = data.worker.clone().zip(data.guest_timeouts.execute);
+    let executed_program = program_bytes.clone();
+    let result = web::block(move || match (worker, prover, guest) {
+        (Some((worker, timeout)), prover, _) => {
+            let input = openvm_prover::GuestInput {
+                payload: executed_program,
+                mode,
+                args: Vec::new(),
+            };
+            worker.execute(&input, Some(timeout)).map(|report| {
+                // Without the app key the worker does not meter either.
+                let execution = prover.is_some().then(|| report.clone());
+                (report.public_values, execution)
+            })
+        }
+        (None, Some(prover), _) => prover
+            .execute(&executed_program, mode)
+            .map(|report| (report.public_values.clone(), Some(report))),
+        (None, None, Some(guest)) => {
+            openvm_prover::execute(&guest.config, guest.exe.clone(), &executed_program, mode)
+                .map(|public_values| (public_values, None))
+        }
+        (None, None, None) => unreachable!("checked above"),
+    })
+    .await;
+    let duration = Some(start.elapsed().as_secs_f64());
+
+    let (public_values, execution) = match result {
+        Ok(Ok(executed)) => executed,
+        Ok(Err(e)) => {
+            return HttpResponse::UnprocessableEntity().json(ExecuteResponse::failure(
+                format!("Guest execution failed: {}", e),
+                duration,
+            ))
+        }
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(ExecuteResponse::failure(
+                format!("Internal error: {}", e),
+                duration,
+            ))
+        }
+    };
+    let (outcome, outcome_details) = committed_outcome(&program_bytes, &public_values);
+    HttpResponse::Ok().json(ExecuteResponse {
+        success: true,
+        openvm_version: openvm_version_tag(),
+        outcome: Some(outcome),
+        outcome_details,
+        status: guest_protocol::Status::from_public_values(&public_values)
+            .map(guest_protocol::Status::label),
+        public_values_hex: Some(hex::encode(&public_values)),
+        execution,
+        error: None,
+        duration_secs: duration,
+    })
+}
+
+/// Response from POST /api/evaluate.
+#[derive(Serialize)]
+struct EvaluateResponse {
+    success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    outcome: Option<Outcome>,
+    /// The result, formatted as the guest commits to it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_kind: Option<&'static str>,
+    /// The public values a guest proving failures would reveal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commitment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_secs: Option<f64>,
+}
+
+fn evaluate_error(mut response: HttpResponseBuilder, error: String) -> HttpResponse {
+    response.json(EvaluateResponse {
+        success: false,
+        outcome: None,
+        result: None,
+        error_kind: None,
+        commitment: None,
+        error: Some(error),
+        duration_secs: None,
+    })
+}
+
+/// POST /api/evaluate
+///
+/// Evaluates a program with the host's evaluator, outside any zkVM, and
+/// returns its result with the commitment the guest would reveal for it.
+pub(crate) async fn evaluate(
+    data: web::Data<AppState>,
+    body: web::Json<ProveRequest>,
+) -> HttpResponse {
+    let start = Instant::now();
+    let program_bytes = match body.program_bytes() {
+        Ok(bytes) => bytes,
+        Err(e) => return evaluate_error(HttpResponse::BadRequest(), e),
+    };
+    if let Err(e) = preflight_program(&data.decode_limits, &program_bytes) {
+        return evaluate_error(HttpResponse::UnprocessableEntity(), e);
+    }
+
+    let evaluated_program = program_bytes.clone();
+    let evaluation = match web::block(move || evaluate_on_host(&evaluated_program)).await {
+        Ok(evaluation) => evaluation,
+        Err(e) => {
+            return evaluate_error(
+                HttpResponse::InternalServerError(),
+                format!("Internal error: {}", e),
+            )
+        }
+    };
+    let duration_secs = Some(start.elapsed().as_secs_f64());
+
+    match evaluation {
+        HostEvaluation::Success(result) => HttpResponse::Ok().json(EvaluateResponse {
+            success: true,
+            outcome: Some(Outcome::Success),
+            commitment: Some(hex::encode(guest_protocol::success_commitment(
+                &program_bytes,
+                &result,
+            ))),
+            result: Some(result),
+            error_kind: None,
+            error: None,
+            duration_secs,
+        }),
+        HostEvaluation::Failure(kind, error) => {
+            HttpResponse::UnprocessableEntity().json(EvaluateResponse {
+                success: false,
+                outcome: Some(Outcome::from_failure(kind)),
+                result: None,
+                error_kind: Some(kind.label()),
+                commitment: Some(hex::encode(guest_protocol::failure_commitment(
+                    &program_bytes,
+                    kind,
+                ))),
+                error: Some(error),
+                duration_secs,
+            })
+        }
+        HostEvaluation::Panic(message) => {
+            HttpResponse::InternalServerError().json(EvaluateResponse {
+                success: false,
+                outcome: Some(Outcome::GuestError),
+                result: None,
+                error_kind: None,
+                commitment: None,
+                error: Some(format!("The evaluator panicked: {}", message)),
+                duration_secs,
+            })
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! The guest registry served to verifiers.
+//!
+//! With `GUEST_REGISTRY_FILE` set, every guest release this backend proves
+//! with is recorded there by its `app_exe_commit`, along with the guest
+//! protocol and commitment version its public values follow. The file is
+//! served at `/data/guest-registry.json` for the verifier's
+//! `verify_stark_registered`, so proofs archived under earlier releases stay
+//! verifiable after the guest is upgraded. Keep the file across deployments:
+//! entries are only ever added.
+
+use crate::{openvm_version_tag, AppState};
+use actix_web::{web, HttpResponse};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// Serializes updates from concurrent proofs.
+static UPDATE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Add the release with `app_exe_commit` to the registry at `path`, unless
+/// it is already listed. Best effort: failures are logged, since the proof
+/// itself is still valid.
+pub(crate) fn record(
+    path: &Path,
+    app_exe_commit: &str,
+    guest_build: Option<&BTreeMap<String, String>>,
+) {
+    let _guard = UPDATE_LOCK.lock().expect("registry lock poisoned");
+    if let Err(e) = try_record(path, app_exe_commit, guest_build) {
+        warn!("Failed to update guest registry {}: {}", path.display(), e);
+    }
+}
+
+fn try_record(
+    path: &Path,
+    app_exe_commit: &str,
+    guest_build: Option<&BTreeMap<String, String>>,
+) -> eyre::Result<()> {
+    let mut registry: serde_json::Value = match std::fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => serde_json::json!({ "guests": [] }),
+        Err(e) => return Err(e.into()),
+    };
+    let guests = registry
+        .get_mut("guests")
+        .and_then(serde_json::Value::as_array_mut)
+        .ok_or_else(|| eyre::eyre!("no guests list"))?;
+    let normalize = |commit: &str| commit.trim_start_matches("0x").to_ascii_lowercase();
+    if guests.iter().any(|guest| {
+        guest
+            .get("app_exe_commit")
+            .and_then(serde_json::Value::as_str)
+            .is_some_and(|commit| normalize(commit) == normalize(app_exe_commit))
+    }) {
+        return Ok(());
+    }
+
+    guests.push(serde_json::json!({
+        "app_exe_commit": app_exe_commit,
+        // Guests built before build info was recorded use the protocol
+        // version this backend was built with.
+        "guest_protocol_version": guest_build
+            .and_then(|build| build.get("guest-protocol"))
+            .map_or(guest_protocol::VERSION, String::as_str),
+        "commitment_version": guest_protocol::COMMITMENT_VERSION,
+        "label": format!("OpenVM {}", openvm_version_tag()),
+    }));
+    let partial = path.with_extension("json.partial");
+    std::fs::write(&partial, serde_json::to_vec_pretty(&registry)?)?;
+    std::fs::rename(&partial, path)?;
+    info!("Recorded guest {} in the guest registry", app_exe_commit);
+    Ok(())
+}
+
+/// GET /data/guest-registry.json
+pub(crate) async fn serve(data: web::Data<AppState>) -> HttpResponse {
+    let Some(path) = &data.guest_registry else {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Guest registry is disabled. Set GUEST_REGISTRY_FILE to enable it."
+        }));
+    };
+    match tokio::fs::read(path).await {
+        Ok(bytes) => HttpResponse::Ok()
+            .content_type("application/json")
+            .append_header(("Cache-Control", "no-cache"))
+            .body(bytes),
+        // No proof recorded a release yet.
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            HttpResponse::Ok().json(serde_json::json!({ "guests": [] }))
+        }
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to read the guest registry: {}", e)
+        })),
+    }
+}
@@ -0,0 +1,144 @@
+//! Optional IPFS publication, behind the `ipfs` feature.
+//!
+//! When built with `--features ipfs` and `IPFS_API_URL` points at an IPFS
+//! node's RPC API (e.g. `http://127.0.0.1:5001`), `/api/prove` pins the
+//! program bytes and the proof bundle (see `/api/proofs/{commitment}/bundle`)
+//! and returns their CIDs, so a proof can be referenced immutably from
+//! on-chain metadata. Publication is best effort: if pinning fails the proof
+//! is still served, without CIDs.
+
+use crate::proofs::{self, StoredProof};
+use serde::Serialize;
+use std::path::Path;
+use tracing::{error, info};
+
+/// CIDs of the content pinned for a proof.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(not(feature = "ipfs"), allow(dead_code))]
+pub(crate) struct IpfsCids {
+    /// Raw flat-encoded program bytes.
+    pub(crate) program: String,
+    /// The proof bundle JSON.
+    pub(crate) bundle: String,
+}
+
+/// Client for an IPFS node's RPC API.
+#[cfg(feature = "ipfs")]
+#[derive(Debug, Clone)]
+pub(crate) struct IpfsClient {
+    api_url: String,
+}
+
+#[cfg(feature = "ipfs")]
+impl IpfsClient {
+    /// Client for `IPFS_API_URL`, or `None` when it is unset.
+    pub(crate) fn from_env() -> Option<Self> {
+        let api_url = std::env::var("IPFS_API_URL")
+            .ok()
+            .filter(|url| !url.is_empty())?;
+        Some(Self {
+            api_url: api_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    pub(crate) fn api_url(&self) -> &str {
+        &self.api_url
+    }
+
+    /// Add and pin `bytes`, returning the CIDv1.
+    ///
+    /// Blocking: call from `web::block`. The client is created per call, as
+    /// reqwest's blocking client must not live on the async runtime.
+    fn add(&self, file_name: &str, bytes: Vec<u8>) -> Result<String, String> {
+        #[derive(serde::Deserialize)]
+        struct AddResponse {
+            #[serde(rename = "Hash")]
+            hash: String,
+        }
+
+        let form = reqwest::blocking::multipart::Form::new().part(
+            "file",
+            reqwest::blocking::multipart::Part::bytes(bytes).file_name(file_name.to_string()),
+        );
+        let response = reqwest::blocking::Client::new()
+            .post(format!("{}/api/v0/add", self.api_url))
+            .query(&[("pin", "true"), ("cid-version", "1")])
+            .multipart(form)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("IPFS add of {} failed: {}", file_name, e))?;
+        let added: AddResponse = response
+            .json()
+            .map_err(|e| format!("Unexpected IPFS add response for {}: {}", file_name, e))?;
+        Ok(added.hash)
+    }
+
+    /// Pin the program and its proof bundle.
+    pub(crate) fn publish(
+        &self,
+        program_bytes: &[u8],
+        bundle: &serde_json::Value,
+    ) -> Result<IpfsCids, String> {
+        let bundle_bytes = serde_json::to_vec(bundle)
+            .map_err(|e| format!("Failed to encode proof bundle: {}", e))?;
+        Ok(IpfsCids {
+            program: self.add("program.flat", program_bytes.to_vec())?,
+            bundle: self.add("bundle.json", bundle_bytes)?,
+        })
+    }
+}
+
+/// Without the `ipfs` feature no client can exist.
+#[cfg(not(feature = "ipfs"))]
+#[derive(Debug, Clone)]
+pub(crate) enum IpfsClient {}
+
+#[cfg(not(feature = "ipfs"))]
+impl IpfsClient {
+    pub(crate) fn from_env() -> Option<Self> {
+        if std::env::var("IPFS_API_URL").is_ok_and(|url| !url.is_empty()) {
+            tracing::warn!(
+                "IPFS_API_URL is set but the backend was built without the ipfs feature"
+            );
+        }
+        None
+    }
+
+    pub(crate) fn api_url(&self) -> &str {
+        match *self {}
+    }
+
+    pub(crate) fn publish(
+        &self,
+        _program_bytes: &[u8],
+        _bundle: &serde_json::Value,
+    ) -> Result<IpfsCids, String> {
+        match *self {}
+    }
+}
+
+/// Pin `stored`'s program and bundle, logging rather than returning failures.
+pub(crate) fn publish_proof(
+    client: &IpfsClient,
+    agg_vk_path: &Path,
+    program_bytes: &[u8],
+    stored: &StoredProof,
+) -> Option<IpfsCids> {
+    let published = proofs::agg_vk_sha256(agg_vk_path)
+        .and_then(|vk_sha256| proofs::bundle_json(stored, &vk_sha256))
+        .map_err(|e| format!("Failed to build proof bundle: {}", e))
+        .and_then(|bundle| client.publish(program_bytes, &bundle));
+    match published {
+        Ok(cids) => {
+            info!(
+                "Pinned program {} and proof bundle {} to IPFS",
+                cids.program, cids.bundle
+            );
+            Some(cids)
+        }
+        Err(e) => {
+            error!("IPFS publication failed: {}", e);
+            None
+        }
+    }
+}
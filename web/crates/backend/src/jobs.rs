@@ -0,0 +1,517 @@
+//! Prove jobs that survive restarts, and idempotent retries.
+//!
+//! With `JOB_STORE_DIR` set, a `/api/prove` request carrying an
+//! `Idempotency-Key` header is recorded in that directory before it is
+//! proven, and its response once it is. A retry with the same key replays
+//! the recorded response instead of proving again, or gets `409` while the
+//! job is still running; reusing a key for a different request gets `422`.
+//! Keys are scoped to the API key of the request (see `usage`).
+//!
+//! A job runs to completion even if its client disconnects. Jobs accepted
+//! but not finished when the backend stopped are proven again at startup,
+//! so a client retrying after a restart gets the result of a single proof.
+//! Responses with a server error are not recorded, so retrying them proves
+//! again. Finished jobs older than `JOB_RETENTION_SECS` (a day by default)
+//! are forgotten at startup and then every retention period, at most an hour
+//! apart, along with records a crash left partially written.
+
+use crate::response_fields::ResponseFields;
+use crate::{AppState, ProveRequest};
+use actix_web::{http::StatusCode, web, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{error, info, warn};
+
+pub(crate) const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+const MAX_KEY_LEN: usize = 255;
+
+const DEFAULT_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Longest time between two sweeps of the store.
+const MAX_SWEEP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Partially written records younger than this may still be being written,
+/// so sweeps leave them alone.
+const PARTIAL_GRACE: Duration = Duration::from_secs(60);
+
+/// A job as stored in `JOB_STORE_DIR`.
+#[derive(Debug, Serialize, Deserialize)]
+struct JobRecord {
+    /// SHA-256 of the request and its fields, so a key is not reused for
+    /// another request.
+    request_hash: String,
+    /// The request, to prove it again if the backend stops before it is done.
+    request: ProveRequest,
+    /// The `include` of the request.
+    #[serde(default)]
+    fields: ResponseFields,
+    api_key: Option<String>,
+    /// Unix seconds.
+    accepted_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    finished_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    response: Option<RecordedResponse>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct RecordedResponse {
+    status: u16,
+    body: serde_json::Value,
+}
+
+/// What a request with an idempotency key leads to.
+#[derive(Debug)]
+pub(crate) enum Claim {
+    /// A new job, to prove under this id.
+    Accepted(String),
+    /// The job already finished with this response.
+    Finished(HttpResponse),
+    /// The job is still being proven.
+    Running,
+    /// The key was used for another request.
+    KeyReused,
+    Failed(String),
+}
+
+/// Jobs in `JOB_STORE_DIR`, one JSON file each.
+#[derive(Debug)]
+pub(crate) struct JobStore {
+    dir: PathBuf,
+    retention: Duration,
+    /// Serializes claims, so two requests with the same key don't both
+    /// start a job.
+    claims: Mutex<()>,
+}
+
+impl JobStore {
+    /// The store in `JOB_STORE_DIR`, or `None` when it is unset.
+    pub(crate) fn from_env() -> eyre::Result<Option<Self>> {
+        let Some(dir) = crate::env_path("JOB_STORE_DIR") else {
+            return Ok(None);
+        };
+        let retention = match std::env::var("JOB_RETENTION_SECS") {
+            Ok(value) if !value.is_empty() => Duration::from_secs(
+                value
+                    .parse()
+                    .map_err(|_| eyre::eyre!("Invalid JOB_RETENTION_SECS: {}", value))?,
+            ),
+            _ => DEFAULT_RETENTION,
+        };
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| eyre::eyre!("Failed to create {}: {}", dir.display(), e))?;
+        Ok(Some(Self::new(dir, retention)))
+    }
+
+    fn new(dir: PathBuf, retention: Duration) -> Self {
+        Self {
+            dir,
+            retention,
+            claims: Mutex::new(()),
+        }
+    }
+
+    pub(crate) fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    fn read(&self, id: &str) -> std::io::Result<Option<JobRecord>> {
+        match std::fs::read(self.path(id)) {
+            Ok(json) => serde_json::from_slice(&json)
+                .map(Some)
+                .map_err(std::io::Error::from),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn write(&self, id: &str, record: &JobRecord) -> std::io::Result<()> {
+        let path = self.path(id);
+        // Write then rename, so a crash never leaves a partial record.
+        let partial = path.with_extension("json.partial");
+        std::fs::write(&partial, serde_json::to_vec(record)?)?;
+        std::fs::rename(&partial, path)
+    }
+
+    fn remove(&self, id: &str) {
+        if let Err(e) = std::fs::remove_file(self.path(id)) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                error!("Failed to remove job {}: {}", id, e);
+            }
+        }
+    }
+
+    /// Start the job for `key`, unless a request with that key already did.
+    pub(crate) fn claim(
+        &self,
+        key: &str,
+        api_key: Option<&str>,
+        request: &ProveRequest,
+        fields: ResponseFields,
+    ) -> Claim {
+        let id = job_id(api_key, key);
+        let request_hash = match serde_json::to_vec(&(request, fields)) {
+            Ok(json) => hex::encode(Sha256::digest(&json)),
+            Err(e) => return Claim::Failed(e.to_string()),
+        };
+        let _claims = self.claims.lock().unwrap_or_else(|e| e.into_inner());
+        match self.read(&id) {
+            Ok(Some(record)) if record.request_hash != request_hash => Claim::KeyReused,
+            Ok(Some(JobRecord {
+                response: Some(response),
+                ..
+            })) => Claim::Finished(response.replay()),
+            Ok(Some(_)) => Claim::Running,
+            Ok(None) => {
+                let record = JobRecord {
+                    request_hash,
+                    request: request.clone(),
+                    fields,
+                    api_key: api_key.map(str::to_string),
+                    accepted_at: unix_secs(),
+                    finished_at: None,
+                    response: None,
+                };
+                match self.write(&id, &record) {
+                    Ok(()) => Claim::Accepted(id),
+                    Err(e) => Claim::Failed(format!("Failed to record the job: {}", e)),
+                }
+            }
+            Err(e) => Claim::Failed(format!("Failed to read the job: {}", e)),
+        }
+    }
+
+    /// Record the response of job `id`, or forget the job if the response is
+    /// a server error, so a retry proves again.
+    fn complete(&self, id: &str, status: StatusCode, body: &[u8]) {
+        let body = serde_json::from_slice(body).ok();
+        let record = self.read(id).ok().flatten();
+        match (status.is_server_error(), body, record) {
+            (false, Some(body), Some(mut record)) => {
+                record.finished_at = Some(unix_secs());
+                record.response = Some(RecordedResponse {
+                    status: status.as_u16(),
+                    body,
+                });
+                if let Err(e) = self.write(id, &record) {
+                    error!("Failed to record the response of job {}: {}", id, e);
+                    self.remove(id);
+                }
+            }
+            _ => self.remove(id),
+        }
+    }
+
+    /// Forget finished jobs older than the retention period, unreadable
+    /// records, and stale partial records, and return the jobs that were
+    /// accepted but not finished.
+    fn sweep(&self) -> std::io::Result<Vec<(String, JobRecord)>> {
+        let expired_before = unix_secs().saturating_sub(self.retention.as_secs());
+        let mut unfinished = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if name.ends_with(".json.partial") {
+                if is_stale(&path) {
+                    warn!("Removing partially written job {}", path.display());
+                    if let Err(e) = std::fs::remove_file(&path) {
+                        error!("Failed to remove {}: {}", path.display(), e);
+                    }
+                }
+                continue;
+            }
+            let Some(id) = name.strip_suffix(".json") else {
+                continue;
+            };
+            match self.read(id) {
+                Ok(Some(record)) if record.response.is_none() => {
+                    unfinished.push((id.to_string(), record));
+                }
+                Ok(Some(record)) => {
+                    if record.finished_at.unwrap_or_default() < expired_before {
+                        self.remove(id);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    warn!("Removing unreadable job {}: {}", path.display(), e);
+                    self.remove(id);
+                }
+            }
+        }
+        Ok(unfinished)
+    }
+}
+
+impl RecordedResponse {
+    fn replay(&self) -> HttpResponse {
+        HttpResponse::build(
+            StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+        )
+        .insert_header(("Idempotent-Replayed", "true"))
+        .json(&self.body)
+    }
+}
+
+/// Whether the file at `path` was last written longer than `PARTIAL_GRACE`
+/// ago.
+fn is_stale(path: &Path) -> bool {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age >= PARTIAL_GRACE))
+}
+
+/// Jobs are stored by a digest of their keys, so keys can hold any
+/// characters and one client cannot address another's jobs.
+fn job_id(api_key: Option<&str>, key: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(api_key.unwrap_or_default().as_bytes());
+    hasher.update([0]);
+    hasher.update(key.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+fn unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// The `Idempotency-Key` of `req`, or why it is invalid.
+pub(crate) fn idempotency_key(req: &HttpRequest) -> Result<Option<&str>, String> {
+    let Some(value) = req.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+    match value.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LEN => Ok(Some(key)),
+        _ => Err(format!(
+            "{} must be 1 to {} visible ASCII characters",
+            IDEMPOTENCY_KEY_HEADER, MAX_KEY_LEN
+        )),
+    }
+}
+
+/// Prove job `id` on the server's runtime rather than in the request, so it
+/// finishes and is recorded even if the client disconnects.
+pub(crate) async fn run(
+    state: web::Data<AppState>,
+    id: String,
+    request: ProveRequest,
+    fields: ResponseFields,
+    api_key: Option<String>,
+) -> HttpResponse {
+    let task = actix_web::rt::spawn(async move {
+        let response = crate::prove_job(state.clone(), request, fields, api_key).await;
+        let Some(store) = &state.job_store else {
+            return response;
+        };
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = match actix_web::body::to_bytes(response.into_body()).await {
+            Ok(body) => body,
+            Err(e) => {
+                store.remove(&id);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": format!("Internal error: {}", e)
+                }));
+            }
+        };
+        store.complete(&id, status, &body);
+        let mut replayed = HttpResponse::build(status);
+        for (name, value) in &headers {
+            replayed.append_header((name.clone(), value.clone()));
+        }
+        replayed.body(body)
+    });
+    task.await.unwrap_or_else(|e| {
+        HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Internal error: {}", e)
+        }))
+    })
+}
+
+/// Prove again the jobs the backend accepted but did not finish before it
+/// last stopped.
+pub(crate) fn resume(state: &web::Data<AppState>) {
+    let Some(store) = &state.job_store else {
+        return;
+    };
+    let unfinished = match store.sweep() {
+        Ok(unfinished) => unfinished,
+        Err(e) => {
+            error!("Failed to read jobs in {}: {}", store.dir().display(), e);
+            return;
+        }
+    };
+    if !unfinished.is_empty() {
+        info!("Resuming {} unfinished prove jobs", unfinished.len());
+    }
+    for (id, record) in unfinished {
+        actix_web::rt::spawn(run(
+            state.clone(),
+            id,
+            record.request,
+            record.fields,
+            record.api_key,
+        ));
+    }
+}
+
+/// Sweep the store every retention period, at most an hour apart, for the
+/// lifetime of the server, so finished jobs don't pile up between restarts.
+pub(crate) async fn sweep_periodically(state: web::Data<AppState>) {
+    let Some(store) = &state.job_store else {
+        return;
+    };
+    let mut ticks = tokio::time::interval(store.retention.clamp(PARTIAL_GRACE, MAX_SWEEP_INTERVAL));
+    // The first tick completes immediately, right after `resume` swept.
+    ticks.tick().await;
+
+    loop {
+        ticks.tick().await;
+        let task_state = state.clone();
+        let swept = web::block(move || task_state.job_store.as_ref().map(JobStore::sweep)).await;
+        match swept {
+            Ok(Some(Err(e))) => error!("Failed to sweep jobs in {}: {}", store.dir().display(), e),
+            Err(e) => error!("Blocking job sweep error: {}", e),
+            Ok(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::scratch_dir;
+
+    /// A job store in an empty scratch directory for one test.
+    fn scratch_store(name: &str) -> JobStore {
+        JobStore::new(scratch_dir("jobs", name), DEFAULT_RETENTION)
+    }
+
+    fn request(program_hex: &str) -> ProveRequest {
+        serde_json::from_value(serde_json::json!({ "program_hex": program_hex })).unwrap()
+    }
+
+    fn claim(store: &JobStore, key: &str, api_key: Option<&str>, program_hex: &str) -> Claim {
+        store.claim(
+            key,
+            api_key,
+            &request(program_hex),
+            ResponseFields::default(),
+        )
+    }
+
+    fn accepted(claim: Claim) -> String {
+        match claim {
+            Claim::Accepted(id) => id,
+            other => panic!("expected a new job, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn proves_each_key_once() {
+        let store = scratch_store("once");
+        let id = accepted(claim(&store, "retry-1", None, "0101"));
+        assert!(matches!(
+            claim(&store, "retry-1", None, "0101"),
+            Claim::Running
+        ));
+        assert!(matches!(
+            claim(&store, "retry-1", None, "0202"),
+            Claim::KeyReused
+        ));
+        let without_proof = ResponseFields {
+            proof: false,
+            ..ResponseFields::default()
+        };
+        assert!(matches!(
+            store.claim("retry-1", None, &request("0101"), without_proof),
+            Claim::KeyReused
+        ));
+
+        store.complete(&id, StatusCode::OK, br#"{"success":true}"#);
+        match claim(&store, "retry-1", None, "0101") {
+            Claim::Finished(response) => assert_eq!(response.status(), StatusCode::OK),
+            other => panic!("expected the recorded response, got {:?}", other),
+        }
+        assert_eq!(
+            store.read(&id).unwrap().unwrap().response,
+            Some(RecordedResponse {
+                status: 200,
+                body: serde_json::json!({ "success": true }),
+            })
+        );
+    }
+
+    #[test]
+    fn scopes_keys_to_api_keys() {
+        let store = scratch_store("scoped");
+        let acme = accepted(claim(&store, "retry-1", Some("acme"), "0101"));
+        let globex = accepted(claim(&store, "retry-1", Some("globex"), "0202"));
+        assert_ne!(acme, globex);
+    }
+
+    #[test]
+    fn forgets_server_errors() {
+        let store = scratch_store("server-error");
+        let id = accepted(claim(&store, "retry-1", None, "0101"));
+        store.complete(
+            &id,
+            StatusCode::SERVICE_UNAVAILABLE,
+            br#"{"success":false}"#,
+        );
+        assert!(store.read(&id).unwrap().is_none());
+        accepted(claim(&store, "retry-1", None, "0101"));
+    }
+
+    #[test]
+    fn lists_unfinished_jobs() {
+        let store = scratch_store("unfinished");
+        let running = accepted(claim(&store, "running", None, "0101"));
+        let finished = accepted(claim(&store, "finished", None, "0202"));
+        store.complete(&finished, StatusCode::OK, b"{}");
+        std::fs::write(store.path("garbled"), b"{").unwrap();
+
+        let unfinished = store.sweep().unwrap();
+        assert_eq!(unfinished.len(), 1);
+        assert_eq!(unfinished[0].0, running);
+        assert_eq!(unfinished[0].1.request.program_hex.as_deref(), Some("0101"));
+        assert!(store.read(&finished).unwrap().is_some());
+        assert!(!store.path("garbled").exists());
+    }
+
+    #[test]
+    fn sweeps_expired_jobs_and_stale_partial_records() {
+        let store = JobStore::new(scratch_dir("jobs", "sweep"), Duration::ZERO);
+        let finished = accepted(claim(&store, "finished", None, "0101"));
+        store.complete(&finished, StatusCode::OK, b"{}");
+        let stale = store.dir().join("stale.json.partial");
+        let fresh = store.dir().join("fresh.json.partial");
+        std::fs::write(&stale, b"{").unwrap();
+        std::fs::write(&fresh, b"{").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&stale)
+            .unwrap()
+            .set_modified(SystemTime::now() - 2 * PARTIAL_GRACE)
+            .unwrap();
+
+        // Finished this second, so only expired once the clock moves on.
+        std::thread::sleep(Duration::from_millis(1100));
+        assert!(store.sweep().unwrap().is_empty());
+        assert!(store.read(&finished).unwrap().is_none());
+        assert!(!stale.exists());
+        assert!(fresh.exists());
+    }
+}
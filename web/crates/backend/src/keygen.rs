@@ -0,0 +1,63 @@
+//! Guest builds and key generation, for `cardano-zkvms setup` and the admin
+//! API.
+//!
+//! They need the prover's `build` and `keygen` features, which the default
+//! `build` and `setup` features enable. Built with `--no-default-features`,
+//! the backend links the prove-only core and compiles faster: it serves with
+//! keys generated by `openvm-keygen` (or imported with `cardano-zkvms
+//! artifacts import`), and the setup steps that need them fail with a
+//! pointer to the alternative. Transpiling a precompiled `OPENVM_GUEST_ELF`
+//! still works without `build`.
+
+use crate::ArtifactPaths;
+use openvm_prover::KeygenOptions;
+
+#[cfg(feature = "build")]
+pub(crate) fn build_guest(paths: &ArtifactPaths) -> eyre::Result<()> {
+    openvm_prover::build_guest(
+        &paths.manifest_path,
+        &paths.config_path,
+        &paths.target_dir,
+        &openvm_prover::BuildOptions::default(),
+    )
+}
+
+#[cfg(feature = "setup")]
+pub(crate) fn generate_app_pk(paths: &ArtifactPaths) -> eyre::Result<()> {
+    openvm_prover::generate_app_pk(&paths.config_path, &paths.target_dir)
+}
+
+#[cfg(feature = "setup")]
+pub(crate) fn generate_agg_keys(
+    paths: &ArtifactPaths,
+    options: &KeygenOptions,
+) -> eyre::Result<()> {
+    openvm_prover::generate_agg_keys(&paths.config_path, &paths.openvm_home, options)
+}
+
+#[cfg(not(feature = "build"))]
+pub(crate) fn build_guest(_paths: &ArtifactPaths) -> eyre::Result<()> {
+    eyre::bail!(
+        "This backend was built without the `build` feature, so it cannot build the guest \
+         from source; set OPENVM_GUEST_ELF to a precompiled guest instead"
+    )
+}
+
+#[cfg(not(feature = "setup"))]
+pub(crate) fn generate_app_pk(_paths: &ArtifactPaths) -> eyre::Result<()> {
+    eyre::bail!(
+        "This backend was built without the `setup` feature; generate the app keys with \
+         `openvm-keygen app`"
+    )
+}
+
+#[cfg(not(feature = "setup"))]
+pub(crate) fn generate_agg_keys(
+    _paths: &ArtifactPaths,
+    _options: &KeygenOptions,
+) -> eyre::Result<()> {
+    eyre::bail!(
+        "This backend was built without the `setup` feature; generate the aggregation keys \
+         with `openvm-keygen agg`"
+    )
+}
@@ -0,0 +1,71 @@
+//! Log output configuration.
+//!
+//! `LOG_FORMAT=json` emits one JSON object per line for log pipelines;
+//! anything else keeps the human-readable format. Submitted programs may be
+//! confidential, so they are only logged as `LOG_PROGRAMS` allows:
+//!
+//! - `hash` (default): a SHA256 prefix and the length, enough to correlate
+//!   requests and triage records without revealing the program.
+//! - `prefix`: the first bytes of the program hex, for debugging.
+//! - `off`: the length only.
+//!
+//! Credentials such as `ADMIN_TOKEN` are never logged.
+
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+
+/// Hex characters of a program logged with `LOG_PROGRAMS=prefix`.
+const PROGRAM_PREFIX_HEX_CHARS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProgramLogging {
+    Hash,
+    Prefix,
+    Off,
+}
+
+static PROGRAM_LOGGING: OnceLock<ProgramLogging> = OnceLock::new();
+
+/// Install the global subscriber from `LOG_FORMAT` and read `LOG_PROGRAMS`.
+pub(crate) fn init() {
+    if std::env::var("LOG_FORMAT").is_ok_and(|format| format == "json") {
+        tracing_subscriber::fmt().json().init();
+    } else {
+        tracing_subscriber::fmt::init();
+    }
+
+    let program_logging = match std::env::var("LOG_PROGRAMS").as_deref() {
+        Ok("prefix") => ProgramLogging::Prefix,
+        Ok("off") => ProgramLogging::Off,
+        Ok("hash") | Err(_) => ProgramLogging::Hash,
+        Ok(other) => {
+            tracing::warn!("Unknown LOG_PROGRAMS value {:?}, using hash", other);
+            ProgramLogging::Hash
+        }
+    };
+    let _ = PROGRAM_LOGGING.set(program_logging);
+}
+
+/// How a program appears in logs, as configured by `LOG_PROGRAMS`.
+pub(crate) fn program_label(program_bytes: &[u8]) -> String {
+    match PROGRAM_LOGGING
+        .get()
+        .copied()
+        .unwrap_or(ProgramLogging::Hash)
+    {
+        ProgramLogging::Hash => format!(
+            "sha256:{} ({} bytes)",
+            &hex::encode(Sha256::digest(program_bytes))[..16],
+            program_bytes.len()
+        ),
+        ProgramLogging::Prefix => {
+            let program_hex = hex::encode(program_bytes);
+            format!(
+                "{}... ({} bytes)",
+                &program_hex[..program_hex.len().min(PROGRAM_PREFIX_HEX_CHARS)],
+                program_bytes.len()
+            )
+        }
+        ProgramLogging::Off => format!("<redacted> ({} bytes)", program_bytes.len()),
+    }
+}
@@ -1,14 +1,56 @@
+mod admin;
+mod artifacts;
+mod certificates;
+mod check;
+mod claims;
+mod cpu_features;
+mod demo;
+mod disassemble;
+mod downloads;
+mod environment;
+mod evaluation_check;
+mod examples;
+mod execute;
+mod guest_registry;
+mod ipfs;
+mod jobs;
+mod keygen;
+mod logging;
+mod metadata;
+mod metrics;
+mod progress;
+mod proof_inspect;
+mod proofs;
+mod prover_options;
+mod queue;
+mod replication;
+mod response_fields;
+mod retry;
+#[cfg(test)]
+mod test_util;
+mod triage;
+mod usage;
+mod usage_stats;
+mod verify_bundle;
+mod worker;
+
 use actix_cors::Cors;
-use actix_web::{web, App, HttpResponse, HttpServer};
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpResponseBuilder, HttpServer};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use guest_protocol::ErrorKind;
 use openvm_mcu_verifier_core::{
     compact_halo2_key_from_native_payload, decode_message, encode_message, ProofEnvelope,
     ProofKind, VerifierKey, OPENVM_EVM_HALO2_PROOF_DATA_LEN,
 };
+use openvm_prover::{ProveProgress, Stage};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 use tracing::{error, info};
+use uplc::limits::DecodeLimits;
 
 const MCU_BLE_SERVICE_UUID: &str = "7b7c0001-78f1-4f9a-8b29-6f1f1d95a100";
 const MCU_BLE_CONTROL_UUID: &str = "7b7c0002-78f1-4f9a-8b29-6f1f1d95a100";
@@ -16,6 +58,15 @@ const MCU_BLE_DATA_UUID: &str = "7b7c0003-78f1-4f9a-8b29-6f1f1d95a100";
 const MCU_BLE_STATUS_UUID: &str = "7b7c0004-78f1-4f9a-8b29-6f1f1d95a100";
 const MCU_BLE_CHUNK_BYTES: usize = 180;
 
+/// Where `build_guest` and `generate_app_pk` write their outputs, relative to
+/// the target directory.
+const BUILT_VMEXE_PATH: &str = "openvm/release/openvm-guest.vmexe";
+const BUILT_APP_PK_PATH: &str = "openvm/app.pk";
+const BUILT_APP_VK_PATH: &str = "openvm/app.vk";
+
+/// Built-in program proven by the startup warmup: evaluates to the integer 42.
+const WARMUP_PROGRAM_HEX: &str = "010000481501";
+
 fn openvm_version_tag() -> String {
     format!("v{}", openvm_prover::openvm_version())
 }
@@ -51,6 +102,7 @@ fn invalidate_if_version_changed(
 
     for path in stale_paths {
         remove_if_exists(path)?;
+        remove_if_exists(&openvm_prover::checksum_path(path))?;
     }
     remove_if_exists(marker_path)?;
     Ok(())
@@ -63,26 +115,18 @@ fn write_version_marker(marker_path: &std::path::Path, version: &str) -> eyre::R
 }
 
 fn invalidate_stale_runtime_artifacts(
-    target_dir: &std::path::Path,
-    openvm_home: &std::path::Path,
+    paths: &ArtifactPaths,
     expected_version: &str,
 ) -> eyre::Result<()> {
-    let vmexe_path = target_dir.join("openvm/release/openvm-guest.vmexe");
-    let app_pk_path = target_dir.join("openvm/app.pk");
-    let target_version_path = target_dir.join("openvm/toolchain.version");
-    let agg_pk_path = openvm_home.join("agg_stark.pk");
-    let agg_vk_path = openvm_home.join("agg_stark.vk");
-    let openvm_version_path = openvm_home.join("toolchain.version");
-
     invalidate_if_version_changed(
-        &target_version_path,
+        &paths.target_version_path(),
         expected_version,
-        &[&vmexe_path, &app_pk_path],
+        &[&paths.vmexe_path, &paths.app_pk_path, &paths.app_vk_path],
     )?;
     invalidate_if_version_changed(
-        &openvm_version_path,
+        &paths.openvm_version_path(),
         expected_version,
-        &[&agg_pk_path, &agg_vk_path],
+        &[&paths.agg_pk_path, &paths.agg_vk_path],
     )?;
 
     Ok(())
@@ -96,97 +140,366 @@ fn setup_hint(guest_dir: &std::path::Path, expected_version: &str) -> String {
     )
 }
 
-/// Resolve the OpenVM home directory (~/.openvm or OPENVM_HOME).
-fn openvm_home() -> PathBuf {
-    std::env::var("OPENVM_HOME")
+/// Read a path from an environment variable, treating empty values as unset.
+fn env_path(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .filter(|value| !value.is_empty())
         .map(PathBuf::from)
-        .unwrap_or_else(|_| {
-            dirs::home_dir()
-                .unwrap_or_else(|| PathBuf::from("/root"))
-                .join(".openvm")
+}
+
+/// Locations of the OpenVM inputs and artifacts.
+///
+/// Defaults follow the repository layout: the vmexe and app key under
+/// `target/openvm` of the workspace containing the guest crate, and the
+/// aggregation keys in `~/.openvm`. Each location can be overridden from the
+/// environment (`OPENVM_CONFIG`, `OPENVM_TARGET_DIR`, `OPENVM_HOME`,
+/// `OPENVM_VMEXE`, `OPENVM_APP_PK`, `OPENVM_APP_VK`, `OPENVM_AGG_PK`,
+/// `OPENVM_AGG_VK`), so
+/// container images can mount pre-baked artifacts anywhere, without the guest
+/// sources.
+struct ArtifactPaths {
+    guest_dir: PathBuf,
+    manifest_path: PathBuf,
+    /// Precompiled guest ELF (`OPENVM_GUEST_ELF`) transpiled by the build step
+    /// instead of building the guest crate.
+    guest_elf_path: Option<PathBuf>,
+    config_path: PathBuf,
+    target_dir: PathBuf,
+    openvm_home: PathBuf,
+    vmexe_path: PathBuf,
+    app_pk_path: PathBuf,
+    app_vk_path: PathBuf,
+    agg_pk_path: PathBuf,
+    agg_vk_path: PathBuf,
+}
+
+impl ArtifactPaths {
+    /// Resolve all locations from `env`, see [`environment::Environment`].
+    fn from_env(env: &environment::Environment) -> eyre::Result<Self> {
+        // The guest sources are optional when every artifact is overridden.
+        let guest_dir = env.guest_dir();
+        let target_dir = env.target_dir(&guest_dir);
+        let openvm_home = env.openvm_home()?;
+
+        Ok(Self {
+            manifest_path: guest_dir.join("guest").join("Cargo.toml"),
+            guest_elf_path: env.path("OPENVM_GUEST_ELF"),
+            config_path: env
+                .path("OPENVM_CONFIG")
+                .unwrap_or_else(|| guest_dir.join("openvm.toml")),
+            vmexe_path: env
+                .path("OPENVM_VMEXE")
+                .unwrap_or_else(|| target_dir.join(BUILT_VMEXE_PATH)),
+            app_pk_path: env
+                .path("OPENVM_APP_PK")
+                .unwrap_or_else(|| target_dir.join(BUILT_APP_PK_PATH)),
+            app_vk_path: env
+                .path("OPENVM_APP_VK")
+                .unwrap_or_else(|| target_dir.join(BUILT_APP_VK_PATH)),
+            agg_pk_path: env
+                .path("OPENVM_AGG_PK")
+                .unwrap_or_else(|| openvm_home.join("agg_stark.pk")),
+            agg_vk_path: env
+                .path("OPENVM_AGG_VK")
+                .unwrap_or_else(|| openvm_home.join("agg_stark.vk")),
+            guest_dir,
+            target_dir,
+            openvm_home,
         })
+    }
+
+    /// Version marker for the vmexe and app key, stored next to the app key.
+    fn target_version_path(&self) -> PathBuf {
+        sibling_version_marker(&self.app_pk_path)
+    }
+
+    /// Version marker for the aggregation keys, stored next to agg_stark.pk.
+    fn openvm_version_path(&self) -> PathBuf {
+        sibling_version_marker(&self.agg_pk_path)
+    }
+
+    /// Build info of the guest vmexe, recorded next to it by setup.
+    fn build_info_path(&self) -> PathBuf {
+        self.vmexe_path.with_file_name("guest.build-info")
+    }
 }
 
-/// `cardano-zkvms setup` — one-time provisioning: build guest, keygen, agg keygen.
-fn cmd_setup() -> eyre::Result<()> {
-    let guest_dir = std::env::var("OPENVM_GUEST_DIR")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| PathBuf::from("../../crates/zkvms/openvm"));
-
-    let guest_dir = guest_dir
-        .canonicalize()
-        .expect("Cannot resolve guest directory. Set OPENVM_GUEST_DIR env var.");
-
-    let workspace_root = guest_dir
-        .parent()
-        .and_then(|p| p.parent())
-        .and_then(|p| p.parent())
-        .expect("Guest dir must be 3 levels deep from workspace root")
-        .to_path_buf();
-
-    let config_path = guest_dir.join("openvm.toml");
-    let manifest_path = guest_dir.join("guest/Cargo.toml");
-    let target_dir = workspace_root.join("target");
-    let openvm_home = openvm_home();
-    let expected_version = openvm_version_tag();
+/// Query the build info of the guest at `paths.vmexe_path` and record it, so
+/// proofs can be attributed to an evaluator release. Best effort: a guest
+/// built before build info queries existed cannot answer them.
+fn record_build_info(paths: &ArtifactPaths) {
+    let recorded = openvm_prover::load_config(&paths.config_path)
+        .and_then(|config| {
+            let exe = openvm_prover::load_exe(&paths.vmexe_path)?;
+            openvm_prover::query_build_info(&config, Arc::new(exe))
+        })
+        .and_then(|info| Ok(std::fs::write(paths.build_info_path(), info)?));
+    match recorded {
+        Ok(()) => info!(
+            "Recorded guest build info at {}",
+            paths.build_info_path().display()
+        ),
+        Err(e) => tracing::warn!("Could not record guest build info: {:#}", e),
+    }
+}
 
-    // Step 1: Build guest
-    let vmexe_path = target_dir.join("openvm/release/openvm-guest.vmexe");
-    let app_pk_path = target_dir.join("openvm/app.pk");
-    let target_version_path = target_dir.join("openvm/toolchain.version");
-    let agg_pk_path = openvm_home.join("agg_stark.pk");
-    let agg_vk_path = openvm_home.join("agg_stark.vk");
-    let openvm_version_path = openvm_home.join("toolchain.version");
+/// Parse recorded build info: `key=value` pairs separated by `;`.
+fn parse_build_info(info: &str) -> BTreeMap<String, String> {
+    info.trim()
+        .split(';')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
 
-    invalidate_if_version_changed(
-        &target_version_path,
-        &expected_version,
-        &[&vmexe_path, &app_pk_path],
-    )?;
-    invalidate_if_version_changed(
-        &openvm_version_path,
-        &expected_version,
-        &[&agg_pk_path, &agg_vk_path],
-    )?;
+fn sibling_version_marker(artifact_path: &std::path::Path) -> PathBuf {
+    artifact_path.with_file_name("toolchain.version")
+}
 
-    if vmexe_path.exists() {
-        eprintln!("[1/3] Guest vmexe already exists, skipping build");
-    } else {
-        eprintln!("[1/3] Building guest...");
-        openvm_prover::build_guest(&manifest_path, &config_path, &target_dir)?;
-        eprintln!("  Done.");
+/// Move an artifact written by the prover, and its checksum file, to its
+/// configured location.
+fn place_artifact(produced: &std::path::Path, configured: &std::path::Path) -> eyre::Result<()> {
+    if produced == configured {
+        return Ok(());
     }
+    move_file(produced, configured)?;
+    move_file(
+        &openvm_prover::checksum_path(produced),
+        &openvm_prover::checksum_path(configured),
+    )
+}
 
-    // Step 2: Generate app proving key
-    if app_pk_path.exists() {
-        eprintln!("[2/3] App proving key already exists, skipping keygen");
-    } else {
-        eprintln!("[2/3] Generating app proving key...");
-        openvm_prover::generate_app_pk(&config_path, &target_dir)?;
-        eprintln!("  Done.");
+fn move_file(from: &std::path::Path, to: &std::path::Path) -> eyre::Result<()> {
+    ensure_parent(to)?;
+    // Fall back to copying when the locations are on different filesystems.
+    if std::fs::rename(from, to).is_err() {
+        std::fs::copy(from, to)?;
+        std::fs::remove_file(from)?;
     }
+    Ok(())
+}
 
-    // Step 3: Generate aggregation keys
-    if agg_pk_path.exists() {
-        eprintln!("[3/3] Aggregation keys already exist, skipping setup");
-    } else {
-        eprintln!("[3/3] Generating aggregation keys (this may take 30+ minutes)...");
-        openvm_prover::generate_agg_keys(&config_path, &openvm_home)?;
-        eprintln!("  Done.");
+/// One provisioning step, run in order by `cardano-zkvms setup` or
+/// individually through the admin API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum SetupStep {
+    BuildGuest,
+    AppKeygen,
+    AggKeygen,
+}
+
+impl SetupStep {
+    const ALL: [Self; 3] = [Self::BuildGuest, Self::AppKeygen, Self::AggKeygen];
+
+    fn description(self) -> &'static str {
+        match self {
+            Self::BuildGuest => "Building guest",
+            Self::AppKeygen => "Generating app proving key",
+            Self::AggKeygen => "Generating aggregation keys (this may take 30+ minutes)",
+        }
+    }
+
+    /// Artifact whose presence means the step has already run.
+    fn output_path(self, paths: &ArtifactPaths) -> &std::path::Path {
+        match self {
+            Self::BuildGuest => &paths.vmexe_path,
+            Self::AppKeygen => &paths.app_pk_path,
+            Self::AggKeygen => &paths.agg_pk_path,
+        }
+    }
+
+    /// Version marker covering the artifacts the step writes.
+    fn version_marker_path(self, paths: &ArtifactPaths) -> PathBuf {
+        match self {
+            Self::BuildGuest | Self::AppKeygen => paths.target_version_path(),
+            Self::AggKeygen => paths.openvm_version_path(),
+        }
+    }
+
+    /// Run the step, then move its outputs from where the prover writes them
+    /// to the configured artifact paths.
+    fn run(
+        self,
+        paths: &ArtifactPaths,
+        keygen_options: &openvm_prover::KeygenOptions,
+    ) -> eyre::Result<()> {
+        match self {
+            Self::BuildGuest => {
+                match &paths.guest_elf_path {
+                    Some(elf_path) => openvm_prover::build_guest_from_elf(
+                        &paths.config_path,
+                        elf_path,
+                        &paths.target_dir,
+                    )?,
+                    None => keygen::build_guest(paths)?,
+                }
+                place_artifact(&paths.target_dir.join(BUILT_VMEXE_PATH), &paths.vmexe_path)?;
+                record_build_info(paths);
+                Ok(())
+            }
+            Self::AppKeygen => {
+                keygen::generate_app_pk(paths)?;
+                place_artifact(
+                    &paths.target_dir.join(BUILT_APP_PK_PATH),
+                    &paths.app_pk_path,
+                )?;
+                place_artifact(
+                    &paths.target_dir.join(BUILT_APP_VK_PATH),
+                    &paths.app_vk_path,
+                )
+            }
+            Self::AggKeygen => {
+                keygen::generate_agg_keys(paths, keygen_options)?;
+                place_artifact(&paths.openvm_home.join("agg_stark.pk"), &paths.agg_pk_path)?;
+                place_artifact(&paths.openvm_home.join("agg_stark.vk"), &paths.agg_vk_path)
+            }
+        }
+    }
+}
+
+/// Aggregation keygen options from `KEYGEN_THREADS`: the number of threads,
+/// unset for one per core.
+fn keygen_options_from_env() -> eyre::Result<openvm_prover::KeygenOptions> {
+    let threads = match std::env::var("KEYGEN_THREADS") {
+        Ok(value) if !value.is_empty() => Some(parse_keygen_threads(&value)?),
+        _ => None,
+    };
+    Ok(openvm_prover::KeygenOptions { threads })
+}
+
+fn parse_keygen_threads(value: &str) -> eyre::Result<usize> {
+    match value.parse() {
+        Ok(threads) if threads > 0 => Ok(threads),
+        _ => eyre::bail!("Invalid keygen thread count {:?}", value),
+    }
+}
+
+/// `cardano-zkvms setup [--low-memory | --keygen-threads N]` — one-time
+/// provisioning: build guest, keygen, agg keygen.
+///
+/// `--low-memory` runs aggregation keygen on a single thread so that it fits
+/// on machines with 32 GB of RAM, at the cost of a much longer run.
+fn cmd_setup(args: &[String]) -> eyre::Result<()> {
+    let mut keygen = keygen_options_from_env()?;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--low-memory" => keygen = openvm_prover::KeygenOptions::low_memory(),
+            "--keygen-threads" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| eyre::eyre!("--keygen-threads needs a thread count"))?;
+                keygen.threads = Some(parse_keygen_threads(value)?);
+            }
+            other => eyre::bail!("Unknown setup option {}", other),
+        }
+    }
+    let paths = ArtifactPaths::from_env(&environment::Environment::from_process())?;
+    let expected_version = openvm_version_tag();
+
+    invalidate_stale_runtime_artifacts(&paths, &expected_version)?;
+
+    for (index, step) in SetupStep::ALL.into_iter().enumerate() {
+        let output_path = step.output_path(&paths);
+        if output_path.exists() {
+            eprintln!(
+                "[{}/3] {} already exists, skipping",
+                index + 1,
+                output_path.display()
+            );
+        } else {
+            eprintln!("[{}/3] {}...", index + 1, step.description());
+            step.run(&paths, &keygen)?;
+            eprintln!("  Done.");
+        }
     }
 
-    write_version_marker(&target_version_path, &expected_version)?;
-    write_version_marker(&openvm_version_path, &expected_version)?;
+    // Guests built by an earlier setup have no build info recorded yet.
+    if !paths.build_info_path().exists() {
+        record_build_info(&paths);
+    }
+
+    write_version_marker(&paths.target_version_path(), &expected_version)?;
+    write_version_marker(&paths.openvm_version_path(), &expected_version)?;
 
     eprintln!("Setup complete.");
     Ok(())
 }
 
 /// Request body for /api/prove
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 struct ProveRequest {
     /// Hex-encoded flat UPLC program
-    program_hex: String,
+    #[serde(default)]
+    program_hex: Option<String>,
+    /// The same program in base64, instead of `program_hex`: a third smaller
+    /// for large scripts.
+    #[serde(default)]
+    program_b64: Option<String>,
+    /// Prove evaluation failures instead of rejecting the program: the
+    /// commitment then covers the failure kind rather than a result.
+    #[serde(default)]
+    prove_failures: bool,
+    /// Also return the claim signed by the service key in this format
+    /// (`/api/prove` only).
+    #[serde(default)]
+    claim_format: Option<claims::ClaimFormat>,
+    /// Proving parameters overriding the server defaults (`/api/prove`
+    /// only).
+    #[serde(default)]
+    prover_options: Option<prover_options::ProverOptions>,
+    /// Only prove with the guest whose `app_exe_commit` is this hex, so a
+    /// server that upgraded its guest rejects the job instead of proving
+    /// under semantics the client has not audited.
+    #[serde(default)]
+    expected_app_exe_commit: Option<String>,
+}
+
+impl ProveRequest {
+    /// The flat program, from whichever encoding the request used.
+    fn program_bytes(&self) -> Result<Vec<u8>, String> {
+        let program_bytes = match (&self.program_hex, &self.program_b64) {
+            (Some(program_hex), None) => {
+                hex::decode(program_hex.trim()).map_err(|e| format!("Invalid hex: {}", e))?
+            }
+            (None, Some(program_b64)) => BASE64
+                .decode(program_b64.trim())
+                .map_err(|e| format!("Invalid base64: {}", e))?,
+            _ => return Err("Set exactly one of program_hex and program_b64".into()),
+        };
+        if program_bytes.is_empty() {
+            return Err("Empty program".into());
+        }
+        Ok(program_bytes)
+    }
+
+    /// Check `expected_app_exe_commit` against the loaded guest's.
+    fn check_app_exe_commit(&self, loaded: &str) -> Result<(), String> {
+        let normalize = |commit: &str| {
+            let commit = commit.trim();
+            commit
+                .strip_prefix("0x")
+                .unwrap_or(commit)
+                .to_ascii_lowercase()
+        };
+        match &self.expected_app_exe_commit {
+            Some(expected) if normalize(expected) != normalize(loaded) => Err(format!(
+                "Guest mismatch: expected app_exe_commit {} but this server proves with {}",
+                expected.trim(),
+                loaded
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn execution_mode(&self) -> openvm_prover::ExecutionMode {
+        if self.prove_failures {
+            openvm_prover::ExecutionMode::ProveFailures
+        } else {
+            openvm_prover::ExecutionMode::Strict
+        }
+    }
 }
 
 /// Request body for /api/verify.
@@ -211,12 +524,20 @@ struct ProveResponse {
     /// STARK proof format version.
     #[serde(skip_serializing_if = "Option::is_none")]
     proof_version: Option<String>,
-    /// SHA256(program_bytes || result_string) as hex
+    /// What happened to the program: success, script_error, budget_exceeded
+    /// or guest_error. Absent when the request itself was invalid.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    outcome: Option<Outcome>,
+    /// Typed details for non-success outcomes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    outcome_details: Option<OutcomeDetails>,
+    /// SHA256 of the `guest_protocol::Statement` about the program's result,
+    /// or its failure kind for proven failures, as hex
     #[serde(skip_serializing_if = "Option::is_none")]
     commitment: Option<String>,
-    /// Raw STARK proof JSON: { "proof": "0x...", "user_public_values": "0x..." }
+    /// STARK proof in OpenVM's JSON format (`api_types::StarkProofJson`).
     #[serde(skip_serializing_if = "Option::is_none")]
-    stark_proof_json: Option<serde_json::Value>,
+    stark_proof_json: Option<api_types::StarkProofJson>,
     /// Version-aware verification baseline for the native verifier.
     #[serde(skip_serializing_if = "Option::is_none")]
     verification_baseline_json: Option<openvm_prover::StarkVerificationBaselineJson>,
@@ -226,12 +547,324 @@ struct ProveResponse {
     /// App VM commit hex (from app commit)
     #[serde(skip_serializing_if = "Option::is_none")]
     app_vm_commit: Option<String>,
+    /// How far the proof was taken.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proof_level: Option<prover_options::ProofLevel>,
+    /// Version of the commitment format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commitment_version: Option<u32>,
+    /// App-level proof, for `proof_level: "app"`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    app_proof_json: Option<serde_json::Value>,
+    /// The proof fields above as gzipped JSON in base64, in their place when
+    /// a compression level was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proof_gz_b64: Option<String>,
+    /// Why the program was only executed, in demo mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proof_withheld: Option<String>,
     /// Error message if failed
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<String>,
     /// Duration in seconds
     #[serde(skip_serializing_if = "Option::is_none")]
     duration_secs: Option<f64>,
+    /// Per-phase proving time and peak memory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    telemetry: Option<openvm_prover::ProveTelemetry>,
+    /// CIDs of the program and proof bundle, when pinned to IPFS
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ipfs: Option<ipfs::IpfsCids>,
+    /// The claim signed by the service key, when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signed_claim: Option<claims::SignedClaim>,
+    /// Evaluator and crate versions the guest was built with, as recorded by
+    /// setup
+    #[serde(skip_serializing_if = "Option::is_none")]
+    guest_build: Option<BTreeMap<String, String>>,
+    /// When the request was queued, started, and finished, and how long
+    /// each phase took
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeline: Option<queue::Timeline>,
+    /// The host's evaluation compared with the guest's commitment, with
+    /// `EVALUATION_CHECK`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    evaluation_check: Option<evaluation_check::EvaluationCheck>,
+    /// Cycles, segments and per-chip trace heights of the validation run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    execution: Option<openvm_prover::ExecutionReport>,
+}
+
+impl ProveResponse {
+    /// Drop the optional field groups `fields` leaves out.
+    fn retain(&mut self, fields: response_fields::ResponseFields) {
+        if !fields.proof {
+            self.stark_proof_json = None;
+            self.verification_baseline_json = None;
+            self.app_proof_json = None;
+            self.proof_gz_b64 = None;
+        }
+        if !fields.commits {
+            self.proof_version = None;
+            self.app_exe_commit = None;
+            self.app_vm_commit = None;
+            self.proof_level = None;
+            self.commitment_version = None;
+        }
+        if !fields.metadata {
+            self.duration_secs = None;
+            self.telemetry = None;
+            self.ipfs = None;
+            self.guest_build = None;
+            self.timeline = None;
+            self.evaluation_check = None;
+            self.execution = None;
+        }
+    }
+
+    /// Move the proof fields into `proof_gz_b64`, gzipped at `level`.
+    fn compress_proof(&mut self, level: u32) -> std::io::Result<()> {
+        let mut proof = serde_json::Map::new();
+        if let Some(stark_proof_json) = self.stark_proof_json.take() {
+            proof.insert(
+                "stark_proof_json".into(),
+                serde_json::to_value(stark_proof_json)?,
+            );
+        }
+        if let Some(baseline) = self.verification_baseline_json.take() {
+            proof.insert(
+                "verification_baseline_json".into(),
+                serde_json::to_value(baseline)?,
+            );
+        }
+        if let Some(app_proof_json) = self.app_proof_json.take() {
+            proof.insert("app_proof_json".into(), app_proof_json);
+        }
+        self.proof_gz_b64 = Some(proofs::compress_json(
+            &serde_json::Value::Object(proof),
+            flate2::Compression::new(level),
+        )?);
+        Ok(())
+    }
+}
+
+/// Outcome of a proving request, so clients don't have to string-match errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Outcome {
+    /// The program evaluated to a result.
+    Success,
+    /// The script rejected its input (decode or evaluation failure).
+    ScriptError,
+    /// Evaluation ran out of execution budget.
+    BudgetExceeded,
+    /// The guest or prover failed for reasons unrelated to the script.
+    GuestError,
+}
+
+impl Outcome {
+    /// The serialized name, e.g. `script_error`.
+    fn label(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::ScriptError => "script_error",
+            Self::BudgetExceeded => "budget_exceeded",
+            Self::GuestError => "guest_error",
+        }
+    }
+
+    fn from_failure(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::BudgetExceeded => Self::BudgetExceeded,
+            ErrorKind::Decode | ErrorKind::Evaluation | ErrorKind::NonConstantResult => {
+                Self::ScriptError
+            }
+        }
+    }
+}
+
+/// Pipeline stage a guest error happened in.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum PipelineStage {
+    Execute,
+    Prove,
+    /// Re-executing the guest after proving disagreed with the proof.
+    DeterminismCheck,
+}
+
+/// Typed details accompanying a non-success outcome.
+#[derive(Debug, Serialize)]
+struct OutcomeDetails {
+    /// Failure kind reported by the guest (`decode`, `evaluation`, ...).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_kind: Option<&'static str>,
+    /// Whether the returned proof attests to this outcome.
+    proven: bool,
+    /// Pipeline stage that failed, for guest errors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stage: Option<PipelineStage>,
+}
+
+impl OutcomeDetails {
+    fn script_failure(kind: ErrorKind, proven: bool) -> Self {
+        Self {
+            error_kind: Some(kind.label()),
+            proven,
+            stage: None,
+        }
+    }
+
+    /// The guest refused the input as too large, see
+    /// [`guest_protocol::rejection_commitment`].
+    fn too_large() -> Self {
+        Self {
+            error_kind: Some(guest_protocol::Status::TooLarge.label()),
+            proven: true,
+            stage: None,
+        }
+    }
+
+    fn guest_error(stage: Option<PipelineStage>) -> Self {
+        Self {
+            error_kind: None,
+            proven: false,
+            stage,
+        }
+    }
+}
+
+/// A failed prove pipeline, classified for the response.
+struct PipelineFailure {
+    outcome: Outcome,
+    details: OutcomeDetails,
+    error: String,
+}
+
+impl PipelineFailure {
+    fn guest_error(stage: Option<PipelineStage>, error: String) -> Self {
+        Self {
+            outcome: Outcome::GuestError,
+            details: OutcomeDetails::guest_error(stage),
+            error,
+        }
+    }
+}
+
+/// Classify the public values revealed by the guest for `program_bytes`.
+fn committed_outcome(
+    program_bytes: &[u8],
+    public_values: &[u8],
+) -> (Outcome, Option<OutcomeDetails>) {
+    if guest_protocol::Status::from_public_values(public_values)
+        == Some(guest_protocol::Status::TooLarge)
+    {
+        return (Outcome::ScriptError, Some(OutcomeDetails::too_large()));
+    }
+    match guest_protocol::committed_failure(program_bytes, public_values) {
+        Some(kind) => (
+            Outcome::from_failure(kind),
+            Some(OutcomeDetails::script_failure(kind, true)),
+        ),
+        None => (Outcome::Success, None),
+    }
+}
+
+/// Check a program against the decode limits before any guest work, so a
+/// crafted flat encoding cannot exhaust the prover's memory.
+///
+/// Programs that do not decode at all are left to the guest, which classifies
+/// (and in `ProveFailures` mode proves) the decode failure.
+fn preflight_program(limits: &DecodeLimits, program_bytes: &[u8]) -> Result<(), String> {
+    limits
+        .check_flat_limits(program_bytes)
+        .map_err(|e| e.to_string())
+}
+
+/// A program rejected by [`preflight_program`]; it is not proven.
+fn preflight_failure(error: String) -> PipelineFailure {
+    PipelineFailure {
+        outcome: Outcome::ScriptError,
+        details: OutcomeDetails::script_failure(ErrorKind::Decode, false),
+        error,
+    }
+}
+
+/// Decode limits for the preflight: the evaluator defaults, overridden by
+/// `UPLC_MAX_PROGRAM_BYTES`, `UPLC_MAX_TERMS`, `UPLC_MAX_DEPTH`, and
+/// `UPLC_MAX_CONSTANT_BYTES`.
+fn decode_limits_from_env() -> DecodeLimits {
+    let limit = |var: &str, default: usize| {
+        std::env::var(var)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    };
+    let defaults = DecodeLimits::DEFAULT;
+    DecodeLimits {
+        max_program_bytes: limit("UPLC_MAX_PROGRAM_BYTES", defaults.max_program_bytes),
+        max_terms: limit("UPLC_MAX_TERMS", defaults.max_terms),
+        max_depth: limit("UPLC_MAX_DEPTH", defaults.max_depth),
+        max_constant_bytes: limit("UPLC_MAX_CONSTANT_BYTES", defaults.max_constant_bytes),
+    }
+}
+
+/// How long `/api/prove` waits for guest work before giving up on it.
+#[derive(Debug, Clone, Copy)]
+struct GuestTimeouts {
+    /// Executing the guest (`EXECUTE_TIMEOUT_SECS`, default none).
+    execute: Option<Duration>,
+    /// Proving, per attempt (`PROVE_TIMEOUT_SECS`, default none).
+    prove: Option<Duration>,
+}
+
+impl GuestTimeouts {
+    fn from_env() -> eyre::Result<Self> {
+        let timeout = |var: &str| match std::env::var(var) {
+            Ok(value) if !value.is_empty() => value
+                .parse::<u64>()
+                .ok()
+                .filter(|secs| *secs > 0)
+                .map(|secs| Some(Duration::from_secs(secs)))
+                .ok_or_else(|| eyre::eyre!("Invalid {}: {}", var, value)),
+            _ => Ok(None),
+        };
+        Ok(Self {
+            execute: timeout("EXECUTE_TIMEOUT_SECS")?,
+            prove: timeout("PROVE_TIMEOUT_SECS")?,
+        })
+    }
+}
+
+/// Classify a failed guest execution.
+///
+/// In strict mode a failing script aborts the guest, so the program is
+/// re-executed with failures committed to find out whether the script or the
+/// guest itself is at fault. Timed out executions are not re-executed.
+fn execution_failure(
+    prover: &openvm_prover::Prover,
+    program_bytes: &[u8],
+    mode: openvm_prover::ExecutionMode,
+    error: eyre::Report,
+) -> PipelineFailure {
+    let timed_out = error.downcast_ref::<openvm_prover::TimedOut>().is_some();
+    let error = format!("Guest execution failed: {}", error);
+    if mode == openvm_prover::ExecutionMode::Strict && !timed_out {
+        let classified = prover
+            .execute(program_bytes, openvm_prover::ExecutionMode::ProveFailures)
+            .ok()
+            .and_then(|report| {
+                guest_protocol::committed_failure(program_bytes, &report.public_values)
+            });
+        if let Some(kind) = classified {
+            return PipelineFailure {
+                outcome: Outcome::from_failure(kind),
+                details: OutcomeDetails::script_failure(kind, false),
+                error,
+            };
+        }
+    }
+    PipelineFailure::guest_error(Some(PipelineStage::Execute), error)
 }
 
 /// Response from /api/prove/mcu-halo2.
@@ -241,6 +874,10 @@ struct McuHalo2Response {
     openvm_version: String,
     proof_kind: String,
     #[serde(skip_serializing_if = "Option::is_none")]
+    outcome: Option<Outcome>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    outcome_details: Option<OutcomeDetails>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     proof_version: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     verifier_key_b64: Option<String>,
@@ -312,29 +949,307 @@ struct VerifyResponse {
 ///
 /// All OpenVM keys and config are loaded once at startup and reused across
 /// requests. Proof generation is CPU-bound and runs via `web::block()`.
+///
+/// When artifacts are missing at startup the backend runs in degraded mode:
+/// without a prover, serving `/api/execute`, `/api/evaluate` and the setup
+/// endpoints, and reporting what is missing through `/api/ready`.
 struct AppState {
-    /// Pre-loaded OpenVM config, executable, and keys.
-    config: openvm_prover::Config,
-    exe: openvm_prover::Exe,
-    app_pk: openvm_prover::AppPk,
-    agg_pk: openvm_prover::AggPk,
-    /// OpenVM home directory (~/.openvm) — contains agg_stark.vk.
-    openvm_home: PathBuf,
+    /// Pre-loaded OpenVM config, executable, and keys, shared across requests.
+    /// `None` in degraded mode, see [`AppState::prover`].
+    prover: Option<openvm_prover::Prover>,
+    /// In degraded mode, the guest executable and config if they were found,
+    /// so programs can still be executed.
+    guest: Option<Arc<execute::Guest>>,
+    /// Artifacts that were not found at startup, as `label (path)`.
+    missing_artifacts: Vec<String>,
+    /// Artifact locations — agg_stark.vk is read from the OpenVM home directory.
+    paths: ArtifactPaths,
+    /// Result of the optional startup warmup proof, reported by /api/ready.
+    warmup: RwLock<WarmupStatus>,
+    /// Bearer token for the admin API; unset disables it.
+    admin_token: Option<String>,
+    /// Re-execute the guest after proving and check it reveals the proof's
+    /// public values (`DETERMINISM_CHECK`).
+    determinism_check: bool,
+    /// Evaluate programs on the host too and compare with the guest's
+    /// commitment (`EVALUATION_CHECK`).
+    evaluation_check: bool,
+    /// Limits checked on every program before it reaches the guest.
+    decode_limits: DecodeLimits,
+    /// How long guest execution and proving may take.
+    guest_timeouts: GuestTimeouts,
+    /// Where guest work with a timeout runs, so it is killed when it times
+    /// out; `None` without timeouts, see [`worker`].
+    worker: Option<openvm_prover::WorkerProcess>,
+    /// Where programs that crash the guest or prover are saved (`TRIAGE_DIR`).
+    triage_dir: Option<PathBuf>,
+    /// Where served proofs are kept for `/api/proofs/{commitment}/bundle`
+    /// (`PROOF_STORE_DIR`).
+    proof_store: Option<proofs::ProofStore>,
+    /// Where stored proofs are replicated to and fetched from
+    /// (`PROOF_REPLICA_DIR`, `PROOF_REPLICA_S3_BUCKET`).
+    replicator: Option<Arc<replication::Replicator>>,
+    /// IPFS node that served proofs are pinned to (`IPFS_API_URL`, `ipfs`
+    /// feature).
+    ipfs: Option<ipfs::IpfsClient>,
+    /// Anonymous prove statistics reported to `TELEMETRY_URL` (`telemetry`
+    /// feature).
+    usage_stats: Option<Arc<usage_stats::UsageStats>>,
+    /// Keys prove requests must present (`API_KEYS_FILE`); unset accepts
+    /// every request.
+    api_keys: Option<usage::ApiKeys>,
+    /// Where the resources each prove job used are logged
+    /// (`USAGE_LOG_FILE`).
+    usage_log: Option<usage::UsageLog>,
+    /// Where prove jobs with an idempotency key are recorded
+    /// (`JOB_STORE_DIR`).
+    job_store: Option<jobs::JobStore>,
+    /// SIMD instruction sets the prover uses and the CPU supports, reported
+    /// by /api/ready.
+    cpu_features: cpu_features::CpuFeatures,
+    /// Key claims are signed with (`SERVICE_SIGNING_KEY_FILE`).
+    service_key: Option<Arc<claims::ServiceKey>>,
+    /// Where epoch certificates are kept, when they are enabled
+    /// (`EPOCH_INTERVAL_SECS`).
+    epochs: Option<certificates::EpochStore>,
+    /// Retries of transient proving failures (`PROVE_RETRIES`).
+    retry_policy: retry::RetryPolicy,
+    /// Which `prover_options` requests may set (`PROOF_LEVELS`,
+    /// `MAX_PROOF_COMPRESSION_LEVEL`).
+    prover_policy: prover_options::ProverPolicy,
+    /// Which programs are proven in demo mode (`DEMO_MODE`); unset proves
+    /// every program.
+    demo: Option<Arc<demo::DemoPolicy>>,
+    /// Requests waiting to prove (`MAX_CONCURRENT_PROOFS`).
+    prove_queue: Arc<queue::ProveQueue>,
+    /// Stages of the prove requests in flight, for `/api/prove/progress`.
+    in_flight: Arc<progress::InFlight>,
+    /// Phase timings of the proofs served, for `/api/metrics`.
+    prove_metrics: Arc<metrics::ProveMetrics>,
+    /// Where guest releases are recorded for verifiers
+    /// (`GUEST_REGISTRY_FILE`).
+    guest_registry: Option<PathBuf>,
+    /// Build info of the loaded guest, when setup recorded it.
+    guest_build: Option<BTreeMap<String, String>>,
+    /// `app_exe_commit` of the loaded guest, checked against
+    /// `expected_app_exe_commit` in prove requests. `None` in degraded mode.
+    app_exe_commit: Option<String>,
+    /// Setup steps started through the admin API.
+    setup_jobs: Mutex<HashMap<SetupStep, admin::SetupJob>>,
 }
 
-/// Helper to create an error ProveResponse
-fn prove_error(error: String, commitment: Option<String>, duration: Option<f64>) -> HttpResponse {
-    HttpResponse::InternalServerError().json(ProveResponse {
+impl AppState {
+    /// The prover and its guest's `app_exe_commit`, or why proving is
+    /// unavailable in degraded mode.
+    fn prover(&self) -> Result<(&openvm_prover::Prover, &str), String> {
+        self.prover
+            .as_ref()
+            .zip(self.app_exe_commit.as_deref())
+            .ok_or_else(|| {
+                format!(
+                    "Proving is unavailable until setup provides: {}",
+                    self.missing_artifacts.join(", ")
+                )
+            })
+    }
+}
+
+/// State of the startup warmup proof.
+///
+/// Proving and verifying a tiny built-in program checks that the loaded
+/// vmexe, app key, and aggregation keys actually belong together.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum WarmupStatus {
+    /// Warmup was not requested (`WARMUP_PROOF` unset).
+    Disabled,
+    Running,
+    Passed {
+        commitment: String,
+        duration_secs: f64,
+    },
+    Failed {
+        error: String,
+        duration_secs: f64,
+    },
+}
+
+impl WarmupStatus {
+    fn is_ready(&self) -> bool {
+        matches!(self, Self::Disabled | Self::Passed { .. })
+    }
+}
+
+/// Prove and verify the built-in warmup program with the loaded artifacts.
+fn run_warmup(state: &AppState) -> Result<String, String> {
+    let program_bytes = hex::decode(WARMUP_PROGRAM_HEX).expect("valid warmup program hex");
+    let mode = openvm_prover::ExecutionMode::Strict;
+
+    let (prover, _) = state.prover()?;
+    let output = prover
+        .execute(&program_bytes, mode)
+        .map_err(|e| format!("Warmup execution failed: {}", e))?
+        .public_values;
+    let prove_result = prover
+        .prove_stark(&program_bytes, mode)
+        .map_err(|e| format!("Warmup proof generation failed: {}", e))?;
+
+    let agg_vk = openvm_prover::load_agg_vk(&state.paths.agg_vk_path)
+        .map_err(|e| format!("Failed to load agg_stark.vk: {}", e))?;
+    openvm_prover::verify_stark(
+        &agg_vk,
+        &prove_result.proof_json,
+        &prove_result.baseline_json,
+    )
+    .map_err(|e| {
+        format!(
+            "Warmup proof did not verify, keys and vmexe are likely mismatched: {}",
+            e
+        )
+    })?;
+
+    Ok(hex::encode(output))
+}
+
+/// Run the warmup proof in the background and record its result.
+async fn warmup(state: web::Data<AppState>) {
+    let started_at = std::time::Instant::now();
+    info!("Running warmup proof...");
+
+    let task_state = state.clone();
+    let result = web::block(move || run_warmup(&task_state)).await;
+    let duration_secs = started_at.elapsed().as_secs_f64();
+
+    let status = match result {
+        Ok(Ok(commitment)) => {
+            info!("Warmup proof passed in {:.1}s", duration_secs);
+            WarmupStatus::Passed {
+                commitment,
+                duration_secs,
+            }
+        }
+        Ok(Err(error)) => {
+            error!("Warmup proof failed: {}", error);
+            WarmupStatus::Failed {
+                error,
+                duration_secs,
+            }
+        }
+        Err(e) => {
+            error!("Blocking warmup task error: {}", e);
+            WarmupStatus::Failed {
+                error: format!("Internal error: {}", e),
+                duration_secs,
+            }
+        }
+    };
+    *state.warmup.write().expect("warmup lock poisoned") = status;
+}
+
+/// Save a guest or prover failure for `cardano-zkvms triage replay`, when
+/// `TRIAGE_DIR` is set (`program_bytes` is only kept in that case).
+fn record_failure(
+    state: &AppState,
+    program_bytes: Option<Vec<u8>>,
+    mode: openvm_prover::ExecutionMode,
+    endpoint: &str,
+    failure: &PipelineFailure,
+) {
+    if let (Some(triage_dir), Some(program_bytes)) = (&state.triage_dir, program_bytes) {
+        let record = triage::TriageRecord::new(
+            &program_bytes,
+            mode,
+            endpoint,
+            failure.details.stage,
+            &failure.error,
+        );
+        triage::record_failure(triage_dir, &program_bytes, record);
+    }
+}
+
+/// A ProveResponse for a request rejected before any guest work.
+fn bad_prove_request(error: String) -> HttpResponse {
+    rejected_prove_request(HttpResponse::BadRequest(), error)
+}
+
+/// A ProveResponse for a request pinned to another guest than the loaded one,
+/// or repeating a job that is still running.
+fn prove_conflict(error: String) -> HttpResponse {
+    rejected_prove_request(HttpResponse::Conflict(), error)
+}
+
+/// A ProveResponse for a request without a valid API key.
+fn prove_unauthorized(error: String) -> HttpResponse {
+    rejected_prove_request(HttpResponse::Unauthorized(), error)
+}
+
+/// A ProveResponse for a request to a backend in degraded mode.
+fn prove_unavailable(error: String) -> HttpResponse {
+    rejected_prove_request(HttpResponse::ServiceUnavailable(), error)
+}
+
+fn rejected_prove_request(mut response: HttpResponseBuilder, error: String) -> HttpResponse {
+    response.json(ProveResponse {
         success: false,
         openvm_version: openvm_version_tag(),
         proof_version: None,
+        outcome: None,
+        outcome_details: None,
         error: Some(error),
-        commitment,
+        commitment: None,
+        stark_proof_json: None,
+        verification_baseline_json: None,
+        app_exe_commit: None,
+        app_vm_commit: None,
+        proof_level: None,
+        commitment_version: None,
+        app_proof_json: None,
+        proof_gz_b64: None,
+        proof_withheld: None,
+        duration_secs: None,
+        telemetry: None,
+        ipfs: None,
+        signed_claim: None,
+        guest_build: None,
+        timeline: None,
+        evaluation_check: None,
+        execution: None,
+    })
+}
+
+/// Helper to create an error ProveResponse
+///
+/// Rejected scripts are reported as 422; guest and prover failures as 500.
+fn prove_error(failure: PipelineFailure, duration: Option<f64>) -> HttpResponse {
+    let mut response = match failure.outcome {
+        Outcome::ScriptError | Outcome::BudgetExceeded => HttpResponse::UnprocessableEntity(),
+        Outcome::Success | Outcome::GuestError => HttpResponse::InternalServerError(),
+    };
+    response.json(ProveResponse {
+        success: false,
+        openvm_version: openvm_version_tag(),
+        proof_version: None,
+        outcome: Some(failure.outcome),
+        outcome_details: Some(failure.details),
+        error: Some(failure.error),
+        commitment: None,
         stark_proof_json: None,
         verification_baseline_json: None,
         app_exe_commit: None,
         app_vm_commit: None,
+        proof_level: None,
+        commitment_version: None,
+        app_proof_json: None,
+        proof_gz_b64: None,
+        proof_withheld: None,
         duration_secs: duration,
+        telemetry: None,
+        ipfs: None,
+        signed_claim: None,
+        guest_build: None,
+        timeline: None,
+        evaluation_check: None,
+        execution: None,
     })
 }
 
@@ -348,11 +1263,17 @@ fn verify_error(error: String, duration: Option<f64>) -> HttpResponse {
     })
 }
 
-fn mcu_halo2_error(error: String, duration: Option<f64>) -> HttpResponse {
+fn mcu_halo2_error(
+    error: String,
+    stage: Option<PipelineStage>,
+    duration: Option<f64>,
+) -> HttpResponse {
     HttpResponse::InternalServerError().json(McuHalo2Response {
         success: false,
         openvm_version: openvm_version_tag(),
         proof_kind: "OpenVM Halo2/KZG".into(),
+        outcome: Some(Outcome::GuestError),
+        outcome_details: Some(OutcomeDetails::guest_error(stage)),
         proof_version: None,
         verifier_key_b64: None,
         proof_envelope_b64: None,
@@ -367,6 +1288,33 @@ fn mcu_halo2_error(error: String, duration: Option<f64>) -> HttpResponse {
     })
 }
 
+/// An MCU proof request refused in demo mode: 403 for programs that are
+/// never proven, 429 once the hourly budget is spent.
+fn mcu_halo2_demo_refusal(refusal: demo::DemoRefusal) -> HttpResponse {
+    let mut response = match refusal {
+        demo::DemoRefusal::NotAllowed { .. } => HttpResponse::Forbidden(),
+        demo::DemoRefusal::RateLimited { .. } => HttpResponse::TooManyRequests(),
+    };
+    response.json(McuHalo2Response {
+        success: false,
+        openvm_version: openvm_version_tag(),
+        proof_kind: "OpenVM Halo2/KZG".into(),
+        outcome: None,
+        outcome_details: None,
+        proof_version: None,
+        verifier_key_b64: None,
+        proof_envelope_b64: None,
+        proof_sha256: None,
+        public_values_hex: None,
+        public_values_len: None,
+        proof_data_len: None,
+        ble: Some(mcu_ble_info()),
+        proof_json: None,
+        error: Some(refusal.to_string()),
+        duration_secs: None,
+    })
+}
+
 fn mcu_ble_info() -> McuBleInfo {
     McuBleInfo {
         service_uuid: MCU_BLE_SERVICE_UUID,
@@ -424,60 +1372,148 @@ fn hex32(value: &str) -> Result<[u8; 32], String> {
 /// Pipeline (all via SDK API, no subprocess calls):
 ///   1. Execute guest (fast) → get commitment
 ///   2. Generate STARK proof (slow) → proof + commits
-async fn prove(data: web::Data<AppState>, body: web::Json<ProveRequest>) -> HttpResponse {
-    let program_hex = body.program_hex.trim().to_string();
+async fn prove(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    query: web::Query<response_fields::ProveQuery>,
+    body: web::Json<ProveRequest>,
+) -> HttpResponse {
+    let api_key = match usage::authenticate(&req, &data) {
+        Ok(api_key) => api_key,
+        Err(e) => return prove_unauthorized(e),
+    };
+    let fields = match response_fields::ResponseFields::from_query(&query) {
+        Ok(fields) => fields,
+        Err(e) => return bad_prove_request(e),
+    };
+    let body = body.into_inner();
+    let Some(store) = &data.job_store else {
+        return prove_job(data, body, fields, api_key).await;
+    };
+    let key = match jobs::idempotency_key(&req) {
+        Ok(Some(key)) => key,
+        Ok(None) => return prove_job(data, body, fields, api_key).await,
+        Err(e) => return bad_prove_request(e),
+    };
+    match store.claim(key, api_key.as_deref(), &body, fields) {
+        jobs::Claim::Accepted(id) => jobs::run(data.clone(), id, body, fields, api_key).await,
+        jobs::Claim::Finished(response) => response,
+        jobs::Claim::Running => prove_conflict(format!(
+            "A request with this {} is still being proven; retry later",
+            jobs::IDEMPOTENCY_KEY_HEADER
+        )),
+        jobs::Claim::KeyReused => rejected_prove_request(
+            HttpResponse::UnprocessableEntity(),
+            format!(
+                "This {} was already used for a different request",
+                jobs::IDEMPOTENCY_KEY_HEADER
+            ),
+        ),
+        jobs::Claim::Failed(e) => {
+            error!("Job store error: {}", e);
+            rejected_prove_request(HttpResponse::InternalServerError(), e)
+        }
+    }
+}
+
+/// Prove one `/api/prove` request, on behalf of the named API key, and
+/// respond with the requested `fields`.
+async fn prove_job(
+    data: web::Data<AppState>,
+    body: ProveRequest,
+    fields: response_fields::ResponseFields,
+    api_key: Option<String>,
+) -> HttpResponse {
+    let options = match data
+        .prover_policy
+        .resolve(body.prover_options.as_ref(), body.prove_failures)
+    {
+        Ok(options) => options,
+        Err(e) => return bad_prove_request(e),
+    };
+    let mode = options.mode;
+    // Don't compress a proof the client left out.
+    let compression_level = options.compression_level.filter(|_| fields.proof);
     let start = std::time::Instant::now();
+    let mut timeline = queue::Timeline::new();
 
-    // Validate hex
-    let program_bytes = match hex::decode(&program_hex) {
-        Ok(b) if !b.is_empty() => b,
-        Ok(_) => {
-            return HttpResponse::BadRequest().json(ProveResponse {
-                success: false,
-                openvm_version: openvm_version_tag(),
-                proof_version: None,
-                error: Some("Empty program".into()),
-                commitment: None,
-                stark_proof_json: None,
-                verification_baseline_json: None,
-                app_exe_commit: None,
-                app_vm_commit: None,
-                duration_secs: None,
-            });
-        }
-        Err(e) => {
-            return HttpResponse::BadRequest().json(ProveResponse {
-                success: false,
-                openvm_version: openvm_version_tag(),
-                proof_version: None,
-                error: Some(format!("Invalid hex: {}", e)),
-                commitment: None,
-                stark_proof_json: None,
-                verification_baseline_json: None,
-                app_exe_commit: None,
-                app_vm_commit: None,
-                duration_secs: None,
-            });
-        }
+    let program_bytes = match body.program_bytes() {
+        Ok(bytes) => bytes,
+        Err(e) => return bad_prove_request(e),
     };
+    let (prover, app_exe_commit) = match data.prover() {
+        Ok(loaded) => loaded,
+        Err(e) => return prove_unavailable(e),
+    };
+    if let Err(e) = body.check_app_exe_commit(app_exe_commit) {
+        return prove_conflict(e);
+    }
+
+    if let Err(e) = preflight_program(&data.decode_limits, &program_bytes) {
+        return prove_error(preflight_failure(e), None);
+    }
+    let claim_format = body.claim_format;
+    if claim_format.is_some() && data.service_key.is_none() {
+        return bad_prove_request(
+            "Signed claims are disabled. Set SERVICE_SIGNING_KEY_FILE to enable them.".into(),
+        );
+    }
+    if claim_format.is_some() && options.level != prover_options::ProofLevel::Aggregated {
+        return bad_prove_request("Signed claims need an aggregated proof".into());
+    }
 
     info!(
-        "Starting proof generation for program: {}...",
-        &program_hex[..program_hex.len().min(20)]
+        "Starting proof generation for program {}",
+        logging::program_label(&program_bytes)
     );
 
-    // Clone what we need for the blocking task.
-    let config = data.config.clone();
-    let exe = data.exe.clone();
-    let app_pk = data.app_pk.clone();
-    let agg_pk = data.agg_pk.clone();
+    // The prover shares its keys, so cloning it for the blocking task is cheap.
+    let prover = prover.clone();
+    let retry_policy = data.retry_policy;
+    let determinism_check = data.determinism_check;
+    let evaluation_check = data.evaluation_check;
+    let guest_timeouts = data.guest_timeouts;
+    let worker = data.worker.clone();
+    let recorded_program = data.triage_dir.as_ref().map(|_| program_bytes.clone());
+    let proof_store = data.proof_store.clone();
+    let replicator = data.replicator.clone();
+    let ipfs_client = data.ipfs.clone();
+    let agg_vk_path = data.paths.agg_vk_path.clone();
+    let service_key = data.service_key.clone();
+    let guest_build = data.guest_build.clone();
+    let demo = data.demo.clone();
+    let prove_queue = data.prove_queue.clone();
+    let in_flight = data.in_flight.clone();
+    let prove_metrics = data.prove_metrics.clone();
+    let guest_registry = data.guest_registry.clone();
+    let program_len = program_bytes.len();
+    let meter = usage::Meter::start(api_key);
 
     // Run the entire pipeline in a blocking thread (CPU-bound work).
-    let result = web::block(move || -> Result<ProveResponse, String> {
+    let result = web::block(move || -> Result<ProveResponse, PipelineFailure> {
+        let progress = Arc::new(in_flight.track(&program_bytes, Stage::Executing));
         // 1. Execute guest (fast) to validate program and get commitment
         info!("Executing guest (validation run)...");
-        let output = openvm_prover::execute(&config, &exe, &program_bytes)
-            .map_err(|e| format!("Guest execution failed: {}", e))?;
+        let execute_start = std::time::Instant::now();
+        let input = openvm_prover::GuestInput {
+            payload: program_bytes.clone(),
+            mode,
+            args: Vec::new(),
+        };
+        let execution = match (&worker, guest_timeouts.execute) {
+            (Some(worker), Some(timeout)) => worker.execute(&input, Some(timeout)),
+            _ => prover.execute(&program_bytes, mode),
+        }
+        .map_err(|e| execution_failure(&prover, &program_bytes, mode, e))?;
+        timeline.execute_secs = Some(execute_start.elapsed().as_secs_f64());
+        info!(
+            "Guest ran {} cycles in {} segments, {} trace rows",
+            execution.total_cycles,
+            execution.segments,
+            execution.total_trace_height()
+        );
+        let output = execution.public_values.clone();
+        let (outcome, outcome_details) = committed_outcome(&program_bytes, &output);
 
         let commitment_hex = if output.len() == 32 {
             Some(hex::encode(&output))
@@ -485,47 +1521,309 @@ async fn prove(data: web::Data<AppState>, body: web::Json<ProveRequest>) -> Http
             None
         };
         info!("Guest executed. Commitment: {:?}", commitment_hex);
+        let evaluation_check =
+            evaluation_check.then(|| evaluation_check::check(&program_bytes, &output));
+
+        // In demo mode, programs that may not be proven get their commitment
+        // from the execution alone.
+        if let Some(Err(refusal)) = demo.as_ref().map(|demo| demo.check(&program_bytes)) {
+            info!("Demo mode, not proving: {}", refusal);
+            return Ok(ProveResponse {
+                success: true,
+                openvm_version: openvm_version_tag(),
+                proof_version: None,
+                outcome: Some(outcome),
+                outcome_details,
+                commitment: commitment_hex,
+                stark_proof_json: None,
+                verification_baseline_json: None,
+                app_exe_commit: None,
+                app_vm_commit: None,
+                proof_level: None,
+                commitment_version: Some(guest_protocol::COMMITMENT_VERSION),
+                app_proof_json: None,
+                proof_gz_b64: None,
+                proof_withheld: Some(refusal.to_string()),
+                error: None,
+                duration_secs: Some(start.elapsed().as_secs_f64()),
+                telemetry: None,
+                ipfs: None,
+                signed_claim: None,
+                guest_build,
+                timeline: Some(timeline.finish()),
+                evaluation_check,
+                execution: Some(execution),
+            });
+        }
+
+        progress.stage(Stage::Queued);
+        let slot = prove_queue.acquire(&mut timeline);
+        let prove_start = std::time::Instant::now();
+        if options.level == prover_options::ProofLevel::App {
+            progress.stage(Stage::AppProof);
+            let mut response = prove_app_level(
+                &prover,
+                &program_bytes,
+                mode,
+                (outcome, outcome_details),
+                commitment_hex,
+                compression_level,
+                guest_build,
+                start,
+            )?;
+            timeline.prove_secs = Some(prove_start.elapsed().as_secs_f64());
+            response.timeline = Some(timeline.finish());
+            response.evaluation_check = evaluation_check;
+            response.execution = Some(execution);
+            return Ok(response);
+        }
 
         // 2. Generate STARK proof (slow — minutes)
         info!("Generating STARK proof (this may take several minutes)...");
-        let prove_result = openvm_prover::prove_stark(&exe, &app_pk, &agg_pk, &program_bytes)
-            .map_err(|e| format!("STARK proof generation failed: {}", e))?;
+        let prove_result = retry_policy
+            .run("STARK proving", || match (&worker, guest_timeouts.prove) {
+                // The worker is killed when it times out, so the queue slot
+                // is only freed once the proof has stopped.
+                (Some(worker), Some(timeout)) => {
+                    worker.prove_stark(&input, Some(timeout), &*progress)
+                }
+                _ => prover.prove_stark_with_progress(&program_bytes, mode, &*progress),
+            })
+            .map_err(|e| {
+                PipelineFailure::guest_error(
+                    Some(PipelineStage::Prove),
+                    format!("STARK proof generation failed: {}", e),
+                )
+            })?;
+        timeline.prove_secs = Some(prove_start.elapsed().as_secs_f64());
+
+        // 3. Optionally re-execute and compare against the proof's public
+        //    values, so a nondeterministic guest never serves a bad proof.
+        if determinism_check {
+            info!("Re-executing guest to check determinism...");
+            prover
+                .check_determinism(&program_bytes, mode, &prove_result)
+                .map_err(|e| {
+                    PipelineFailure::guest_error(
+                        Some(PipelineStage::DeterminismCheck),
+                        format!("Determinism check failed: {}", e),
+                    )
+                })?;
+        }
+        drop(slot);
+        if let Some(path) = &guest_registry {
+            guest_registry::record(path, &prove_result.app_exe_commit, guest_build.as_ref());
+        }
+
+        let stark_proof_json = serde_json::from_value(prove_result.proof_json).map_err(|e| {
+            PipelineFailure::guest_error(
+                Some(PipelineStage::Prove),
+                format!("Unexpected STARK proof JSON: {}", e),
+            )
+        })?;
 
         let duration = start.elapsed().as_secs_f64();
-        info!("STARK proof generated in {:.1}s", duration);
+        let telemetry = prove_result.telemetry;
+        prove_metrics.record(&telemetry);
+        info!(
+            "STARK proof generated in {:.1}s (setup {:.1}s, app proof of {} segments {:.1}s, aggregation {:.1}s, baseline {:.1}s, peak RSS {} MiB)",
+            duration,
+            telemetry.setup_secs,
+            telemetry.segments,
+            telemetry.app_proof_secs,
+            telemetry.aggregation_secs,
+            telemetry.baseline_secs,
+            telemetry
+                .peak_rss_bytes
+                .map_or_else(|| "n/a".to_string(), |bytes| (bytes >> 20).to_string()),
+        );
 
-        Ok(ProveResponse {
+        let mut response = ProveResponse {
             success: true,
             openvm_version: openvm_version_tag(),
             proof_version: Some(prove_result.proof_version),
+            outcome: Some(outcome),
+            outcome_details,
             commitment: commitment_hex,
-            stark_proof_json: Some(prove_result.proof_json),
+            stark_proof_json: Some(stark_proof_json),
             verification_baseline_json: Some(prove_result.baseline_json),
             app_exe_commit: Some(prove_result.app_exe_commit),
             app_vm_commit: Some(prove_result.app_vm_commit),
+            proof_level: Some(prover_options::ProofLevel::Aggregated),
+            commitment_version: Some(guest_protocol::COMMITMENT_VERSION),
+            app_proof_json: None,
+            proof_gz_b64: None,
+            proof_withheld: None,
             error: None,
             duration_secs: Some(duration),
-        })
+            telemetry: Some(telemetry),
+            ipfs: None,
+            signed_claim: None,
+            guest_build,
+            timeline: None,
+            evaluation_check,
+            execution: Some(execution),
+        };
+        if let (Some(claims::ClaimFormat::Cip8), Some(key)) = (claim_format, &service_key) {
+            let result = match outcome {
+                Outcome::Success => proofs::claimed_result(&program_bytes, &hex::encode(&output)),
+                _ => None,
+            };
+            response.signed_claim = Some(key.sign(&claims::Claim {
+                program_bytes: &program_bytes,
+                outcome: outcome.label(),
+                result: result.as_deref(),
+                error_kind: response
+                    .outcome_details
+                    .as_ref()
+                    .and_then(|details| details.error_kind),
+                commitment: &output,
+                app_exe_commit: response.app_exe_commit.as_deref().unwrap_or_default(),
+                app_vm_commit: response.app_vm_commit.as_deref().unwrap_or_default(),
+            }));
+        }
+        if proof_store.is_some() || ipfs_client.is_some() {
+            if let Some(mut stored) = proofs::StoredProof::from_response(&program_bytes, &response)
+            {
+                if let Some(store) = &proof_store {
+                    store.save(&mut stored);
+                    if let Some(replicator) = &replicator {
+                        replicator.replicate(stored.commitment());
+                    }
+                }
+                if let Some(client) = &ipfs_client {
+                    response.ipfs =
+                        ipfs::publish_proof(client, &agg_vk_path, &program_bytes, &stored);
+                }
+            }
+        }
+        if let Some(level) = compression_level {
+            response.compress_proof(level).map_err(compression_failure)?;
+        }
+        response.timeline = Some(timeline.finish());
+        Ok(response)
     })
     .await;
 
+    if let Some(stats) = &data.usage_stats {
+        stats.record(
+            program_len,
+            start.elapsed().as_secs_f64(),
+            matches!(result, Ok(Ok(_))),
+        );
+    }
+    if let Some(log) = &data.usage_log {
+        let (outcome, execution, telemetry) = match &result {
+            Ok(Ok(response)) => (
+                response.outcome.unwrap_or(Outcome::Success),
+                response.execution.as_ref(),
+                response.telemetry.as_ref(),
+            ),
+            Ok(Err(failure)) => (failure.outcome, None, None),
+            Err(_) => (Outcome::GuestError, None, None),
+        };
+        // A worker proves one job at a time, so only its measurements are
+        // the job's own.
+        let measured =
+            telemetry.filter(|_| data.worker.is_some() && data.guest_timeouts.prove.is_some());
+        log.record(&usage::JobUsage {
+            cpu_secs: measured.and_then(|telemetry| telemetry.cpu_secs),
+            peak_rss_bytes: measured.and_then(|telemetry| telemetry.peak_rss_bytes),
+            cycles: execution.map(|execution| execution.total_cycles),
+            ..meter.finish("prove", outcome.label(), program_len)
+        });
+    }
     match result {
-        Ok(Ok(response)) => HttpResponse::Ok().json(response),
-        Ok(Err(e)) => {
-            error!("Prove pipeline error: {}", e);
-            prove_error(e, None, Some(start.elapsed().as_secs_f64()))
+        Ok(Ok(mut response)) => {
+            response.retain(fields);
+            HttpResponse::Ok().json(response)
+        }
+        Ok(Err(failure)) => {
+            error!(
+                "Prove pipeline error ({:?}): {}",
+                failure.outcome, failure.error
+            );
+            if failure.outcome == Outcome::GuestError {
+                record_failure(&data, recorded_program, mode, "prove", &failure);
+            }
+            prove_error(failure, Some(start.elapsed().as_secs_f64()))
         }
         Err(e) => {
             error!("Blocking task error: {}", e);
-            prove_error(
-                format!("Internal error: {}", e),
-                None,
-                Some(start.elapsed().as_secs_f64()),
-            )
+            let failure = PipelineFailure::guest_error(None, format!("Internal error: {}", e));
+            record_failure(&data, recorded_program, mode, "prove", &failure);
+            prove_error(failure, Some(start.elapsed().as_secs_f64()))
         }
     }
 }
 
+/// Generate an app-level proof for a program the guest already executed,
+/// skipping aggregation.
+///
+/// The proof is not stored, pinned, or claimed: those need an aggregated
+/// proof.
+#[allow(clippy::too_many_arguments)]
+fn prove_app_level(
+    prover: &openvm_prover::Prover,
+    program_bytes: &[u8],
+    mode: openvm_prover::ExecutionMode,
+    (outcome, outcome_details): (Outcome, Option<OutcomeDetails>),
+    commitment: Option<String>,
+    compression_level: Option<u32>,
+    guest_build: Option<BTreeMap<String, String>>,
+    start: std::time::Instant,
+) -> Result<ProveResponse, PipelineFailure> {
+    info!("Generating app-level proof...");
+    let prove_failure = |e: String| PipelineFailure::guest_error(Some(PipelineStage::Prove), e);
+    let app_proof = prover
+        .prove_app(program_bytes, mode)
+        .map_err(|e| prove_failure(format!("App proof generation failed: {}", e)))?;
+    let app_proof_json = serde_json::to_value(&app_proof)
+        .map_err(|e| prove_failure(format!("Failed to serialize app proof: {}", e)))?;
+
+    let duration = start.elapsed().as_secs_f64();
+    info!("App-level proof generated in {:.1}s", duration);
+    let mut response = ProveResponse {
+        success: true,
+        openvm_version: openvm_version_tag(),
+        proof_version: None,
+        outcome: Some(outcome),
+        outcome_details,
+        commitment,
+        stark_proof_json: None,
+        verification_baseline_json: None,
+        app_exe_commit: None,
+        app_vm_commit: None,
+        proof_level: Some(prover_options::ProofLevel::App),
+        commitment_version: Some(guest_protocol::COMMITMENT_VERSION),
+        app_proof_json: Some(app_proof_json),
+        proof_gz_b64: None,
+        proof_withheld: None,
+        error: None,
+        duration_secs: Some(duration),
+        telemetry: None,
+        ipfs: None,
+        signed_claim: None,
+        guest_build,
+        timeline: None,
+        evaluation_check: None,
+        execution: None,
+    };
+    if let Some(level) = compression_level {
+        response
+            .compress_proof(level)
+            .map_err(compression_failure)?;
+    }
+    Ok(response)
+}
+
+fn compression_failure(e: std::io::Error) -> PipelineFailure {
+    PipelineFailure::guest_error(
+        Some(PipelineStage::Prove),
+        format!("Failed to compress the proof: {}", e),
+    )
+}
+
 /// POST /api/patch-envelope
 ///
 /// Decode a postcard-encoded ProofEnvelope, replace user_public_values, and
@@ -595,17 +1893,47 @@ async fn patch_envelope(body: web::Json<PatchEnvelopeRequest>) -> HttpResponse {
 ///
 /// Generate the OpenVM Halo2/KZG proof and native verifier key in the same
 /// envelope format consumed by the ESP32-S3 firmware.
-async fn prove_mcu_halo2(data: web::Data<AppState>, body: web::Json<ProveRequest>) -> HttpResponse {
-    let program_hex = body.program_hex.trim().to_string();
+async fn prove_mcu_halo2(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    body: web::Json<ProveRequest>,
+) -> HttpResponse {
+    let mode = body.execution_mode();
     let start = std::time::Instant::now();
 
-    let program_bytes = match hex::decode(&program_hex) {
-        Ok(bytes) if !bytes.is_empty() => bytes,
-        Ok(_) => {
-            return HttpResponse::BadRequest().json(McuHalo2Response {
+    let api_key = match usage::authenticate(&req, &data) {
+        Ok(api_key) => api_key,
+        Err(error) => {
+            return HttpResponse::Unauthorized().json(McuHalo2Response {
+                success: false,
+                openvm_version: openvm_version_tag(),
+                proof_kind: "OpenVM Halo2/KZG".into(),
+                outcome: None,
+                outcome_details: None,
+                proof_version: None,
+                verifier_key_b64: None,
+                proof_envelope_b64: None,
+                proof_sha256: None,
+                public_values_hex: None,
+                public_values_len: None,
+                proof_data_len: None,
+                ble: Some(mcu_ble_info()),
+                proof_json: None,
+                error: Some(error),
+                duration_secs: None,
+            });
+        }
+    };
+
+    let (prover, app_exe_commit) = match data.prover() {
+        Ok(loaded) => loaded,
+        Err(error) => {
+            return HttpResponse::ServiceUnavailable().json(McuHalo2Response {
                 success: false,
                 openvm_version: openvm_version_tag(),
                 proof_kind: "OpenVM Halo2/KZG".into(),
+                outcome: None,
+                outcome_details: None,
                 proof_version: None,
                 verifier_key_b64: None,
                 proof_envelope_b64: None,
@@ -615,15 +1943,23 @@ async fn prove_mcu_halo2(data: web::Data<AppState>, body: web::Json<ProveRequest
                 proof_data_len: None,
                 ble: Some(mcu_ble_info()),
                 proof_json: None,
-                error: Some("Empty program".into()),
+                error: Some(error),
                 duration_secs: None,
             });
         }
+    };
+    let program_bytes = match body
+        .check_app_exe_commit(app_exe_commit)
+        .and_then(|()| body.program_bytes())
+    {
+        Ok(bytes) => bytes,
         Err(error) => {
             return HttpResponse::BadRequest().json(McuHalo2Response {
                 success: false,
                 openvm_version: openvm_version_tag(),
                 proof_kind: "OpenVM Halo2/KZG".into(),
+                outcome: None,
+                outcome_details: None,
                 proof_version: None,
                 verifier_key_b64: None,
                 proof_envelope_b64: None,
@@ -633,21 +1969,72 @@ async fn prove_mcu_halo2(data: web::Data<AppState>, body: web::Json<ProveRequest
                 proof_data_len: None,
                 ble: Some(mcu_ble_info()),
                 proof_json: None,
-                error: Some(format!("Invalid hex: {error}")),
+                error: Some(error),
                 duration_secs: None,
             });
         }
     };
 
-    let exe = data.exe.clone();
-    let app_pk = data.app_pk.clone();
-    let agg_pk = data.agg_pk.clone();
+    if let Err(error) = preflight_program(&data.decode_limits, &program_bytes) {
+        return HttpResponse::UnprocessableEntity().json(McuHalo2Response {
+            success: false,
+            openvm_version: openvm_version_tag(),
+            proof_kind: "OpenVM Halo2/KZG".into(),
+            outcome: Some(Outcome::ScriptError),
+            outcome_details: Some(OutcomeDetails::script_failure(ErrorKind::Decode, false)),
+            proof_version: None,
+            verifier_key_b64: None,
+            proof_envelope_b64: None,
+            proof_sha256: None,
+            public_values_hex: None,
+            public_values_len: None,
+            proof_data_len: None,
+            ble: Some(mcu_ble_info()),
+            proof_json: None,
+            error: Some(error),
+            duration_secs: None,
+        });
+    }
+
+    if let Some(demo) = data.demo.clone() {
+        let checked_program = program_bytes.clone();
+        match web::block(move || demo.check(&checked_program)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(refusal)) => return mcu_halo2_demo_refusal(refusal),
+            Err(error) => {
+                return mcu_halo2_error(format!("Internal error: {error}"), None, None);
+            }
+        }
+    }
+
+    let prover = prover.clone();
+    let retry_policy = data.retry_policy;
+    let worker = data.worker.clone();
+    let prove_timeout = data.guest_timeouts.prove;
+    let recorded_program = data.triage_dir.as_ref().map(|_| program_bytes.clone());
+    let prove_queue = data.prove_queue.clone();
+    let program_len = program_bytes.len();
+    let meter = usage::Meter::start(api_key);
 
     let result = web::block(move || -> Result<McuHalo2Response, String> {
-        info!("Generating MCU Halo2/KZG proof for BLE transfer...");
-        let artifacts =
-            openvm_prover::evm_halo2_mcu::prove_mcu_halo2(&exe, &app_pk, &agg_pk, &program_bytes)
-                .map_err(|error| format!("MCU Halo2/KZG proof generation failed: {error}"))?;
+        let _slot = prove_queue.acquire(&mut queue::Timeline::new());
+        info!(
+            "Generating MCU Halo2/KZG proof for BLE transfer, program {}...",
+            logging::program_label(&program_bytes)
+        );
+        let artifacts = retry_policy
+            .run("MCU Halo2/KZG proving", || match (&worker, prove_timeout) {
+                (Some(worker), Some(timeout)) => worker.prove_mcu_halo2(
+                    &openvm_prover::GuestInput {
+                        payload: program_bytes.clone(),
+                        mode,
+                        args: Vec::new(),
+                    },
+                    Some(timeout),
+                ),
+                _ => openvm_prover::evm_halo2_mcu::prove_mcu_halo2(&prover, &program_bytes, mode),
+            })
+            .map_err(|error| format!("MCU Halo2/KZG proof generation failed: {error}"))?;
 
         let proof_json = artifacts.proof_json;
         let proof_version = required_json_str(&proof_json, "version")?.to_owned();
@@ -655,15 +2042,14 @@ async fn prove_mcu_halo2(data: web::Data<AppState>, body: web::Json<ProveRequest
         let app_vm_commit = hex32(required_json_str(&proof_json, "app_vm_commit")?)?;
         let user_public_values = hex_vec(required_json_str(&proof_json, "user_public_values")?)?;
         let proof_data = packed_halo2_proof_data(&proof_json)?;
+        let (outcome, outcome_details) = committed_outcome(&program_bytes, &user_public_values);
 
-        let portable_verifier_key = compact_halo2_key_from_native_payload(&artifacts.native_verifier_key)
-            .map_err(|error| format!("failed to compact verifier key for MCU: {error}"))?;
+        let portable_verifier_key =
+            compact_halo2_key_from_native_payload(&artifacts.native_verifier_key)
+                .map_err(|error| format!("failed to compact verifier key for MCU: {error}"))?;
         let key_id = hash32(&portable_verifier_key);
-        let verifier_key = VerifierKey::new(
-            ProofKind::OpenVmEvmHalo2,
-            key_id,
-            portable_verifier_key,
-        );
+        let verifier_key =
+            VerifierKey::new(ProofKind::OpenVmEvmHalo2, key_id, portable_verifier_key);
         let proof_envelope = ProofEnvelope::new_evm_halo2(
             proof_version.clone(),
             key_id,
@@ -684,6 +2070,8 @@ async fn prove_mcu_halo2(data: web::Data<AppState>, body: web::Json<ProveRequest
             success: true,
             openvm_version: openvm_version_tag(),
             proof_kind: "OpenVM Halo2/KZG".into(),
+            outcome: Some(outcome),
+            outcome_details,
             proof_version: Some(proof_version),
             verifier_key_b64: Some(BASE64.encode(verifier_key_bytes)),
             proof_envelope_b64: Some(BASE64.encode(proof_envelope_bytes)),
@@ -699,40 +2087,88 @@ async fn prove_mcu_halo2(data: web::Data<AppState>, body: web::Json<ProveRequest
     })
     .await;
 
+    if let Some(log) = &data.usage_log {
+        let outcome = match &result {
+            Ok(Ok(response)) => response.outcome.unwrap_or(Outcome::Success),
+            _ => Outcome::GuestError,
+        };
+        // The MCU prover does not report cycles or memory.
+        log.record(&meter.finish("prove/mcu-halo2", outcome.label(), program_len));
+    }
     match result {
         Ok(Ok(response)) => HttpResponse::Ok().json(response),
         Ok(Err(error)) => {
             error!("MCU Halo2/KZG pipeline error: {}", error);
-            mcu_halo2_error(error, Some(start.elapsed().as_secs_f64()))
-        }
-        Err(error) => {
-            error!("Blocking MCU Halo2/KZG task error: {}", error);
+            let failure = PipelineFailure::guest_error(Some(PipelineStage::Prove), error);
+            record_failure(&data, recorded_program, mode, "prove/mcu-halo2", &failure);
             mcu_halo2_error(
-                format!("Internal error: {error}"),
+                failure.error,
+                Some(PipelineStage::Prove),
                 Some(start.elapsed().as_secs_f64()),
             )
         }
+        Err(error) => {
+            error!("Blocking MCU Halo2/KZG task error: {}", error);
+            let failure = PipelineFailure::guest_error(None, format!("Internal error: {error}"));
+            record_failure(&data, recorded_program, mode, "prove/mcu-halo2", &failure);
+            mcu_halo2_error(failure.error, None, Some(start.elapsed().as_secs_f64()))
+        }
+    }
+}
+
+/// Serve a guest artifact for the browser executor. Not cached: the vmexe
+/// changes whenever the guest is rebuilt, and the preview must match it.
+async fn serve_guest_artifact(path: &std::path::Path, content_type: &str) -> HttpResponse {
+    match tokio::fs::read(path).await {
+        Ok(bytes) => HttpResponse::Ok()
+            .content_type(content_type)
+            .append_header(("Cache-Control", "no-cache"))
+            .append_header(("X-OpenVM-Version", openvm_version_tag()))
+            .body(bytes),
+        Err(e) => {
+            error!("Failed to read {}: {}", path.display(), e);
+            HttpResponse::NotFound().json(serde_json::json!({
+                "error": format!("{} not found. Run 'cardano-zkvms setup' on the server.", path.display())
+            }))
+        }
     }
 }
 
+/// GET /data/openvm-guest.vmexe
+///
+/// The guest executable the server proves, for the browser executor.
+async fn serve_guest_vmexe(data: web::Data<AppState>) -> HttpResponse {
+    serve_guest_artifact(&data.paths.vmexe_path, "application/octet-stream").await
+}
+
+/// GET /data/openvm.toml
+///
+/// The VM config the guest runs under, for the browser executor.
+async fn serve_guest_config(data: web::Data<AppState>) -> HttpResponse {
+    serve_guest_artifact(&data.paths.config_path, "application/toml").await
+}
+
 /// GET /data/agg_stark.vk
 ///
 /// Serve the aggregation STARK verifying key from the OpenVM home directory
-/// (~/.openvm/agg_stark.vk).
-async fn serve_agg_stark_vk(data: web::Data<AppState>) -> HttpResponse {
-    let vk_path = data.openvm_home.join("agg_stark.vk");
-    match tokio::fs::read(&vk_path).await {
-        Ok(bytes) => {
+/// (~/.openvm/agg_stark.vk), resumable and pre-compressed as described in
+/// `downloads`.
+async fn serve_agg_stark_vk(req: HttpRequest, data: web::Data<AppState>) -> HttpResponse {
+    let vk_path = &data.paths.agg_vk_path;
+    match downloads::Download::read(vk_path, &req).await {
+        Ok(download) => {
             info!(
-                "Serving agg_stark.vk: {} bytes from {}",
-                bytes.len(),
-                vk_path.display()
+                "Serving agg_stark.vk: {} bytes from {}{}",
+                download.bytes.len(),
+                vk_path.display(),
+                download
+                    .encoded
+                    .as_ref()
+                    .map_or_else(String::new, |(encoding, _)| format!(" ({})", encoding)),
             );
-            HttpResponse::Ok()
-                .content_type("application/octet-stream")
-                .append_header(("Cache-Control", "public, max-age=86400"))
-                .append_header(("X-OpenVM-Version", openvm_version_tag()))
-                .body(bytes)
+            download
+                .header("X-OpenVM-Version", openvm_version_tag())
+                .respond(&req, "application/octet-stream", "public, max-age=86400")
         }
         Err(e) => {
             error!(
@@ -750,17 +2186,51 @@ async fn serve_agg_stark_vk(data: web::Data<AppState>) -> HttpResponse {
     }
 }
 
+/// GET /data/vk/{hash}
+///
+/// `agg_stark.vk`, served only while its SHA256 is `hash`, as linked from
+/// proof bundles. The URL never serves another key, so it is cached as
+/// immutable, e.g. by a CDN; after a key rotation it serves `404`.
+async fn serve_agg_stark_vk_by_hash(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    hash: web::Path<String>,
+) -> HttpResponse {
+    let hash = hash.into_inner().to_ascii_lowercase();
+    let vk_path = &data.paths.agg_vk_path;
+    let download = match downloads::Download::read(vk_path, &req).await {
+        Ok(download) => download,
+        Err(e) => {
+            error!(
+                "Failed to read agg_stark.vk from {}: {}",
+                vk_path.display(),
+                e
+            );
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": format!("agg_stark.vk not found at {}", vk_path.display())
+            }));
+        }
+    };
+    if download.sha256() != hash {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("No verifying key with SHA256 {}", hash)
+        }));
+    }
+    download
+        .header("X-OpenVM-Version", openvm_version_tag())
+        .respond(&req, "application/octet-stream", proofs::IMMUTABLE)
+}
+
 /// POST /api/verify
 ///
 /// Verify a STARK proof using the server's native OpenVM 2.0 verifier.
 async fn verify(data: web::Data<AppState>, body: web::Json<VerifyRequest>) -> HttpResponse {
     let started_at = std::time::Instant::now();
-    let openvm_home = data.openvm_home.clone();
+    let agg_vk_path = data.paths.agg_vk_path.clone();
     let proof_json = body.stark_proof_json.clone();
     let baseline_json = body.verification_baseline_json.clone();
 
     let result = web::block(move || -> Result<(), String> {
-        let agg_vk_path = openvm_home.join("agg_stark.vk");
         let agg_vk = openvm_prover::load_agg_vk(&agg_vk_path)
             .map_err(|e| format!("Failed to load agg_stark.vk: {}", e))?;
         openvm_prover::verify_stark(&agg_vk, &proof_json, &baseline_json)
@@ -808,81 +2278,380 @@ async fn health() -> HttpResponse {
     }))
 }
 
+/// GET /api/ready
+///
+/// Returns 200 once the backend can serve proofs: the warmup proof passed or
+/// was not requested. Returns 503 while it runs or after it failed, and in
+/// degraded mode, listing the missing artifacts. Also reports the SIMD
+/// instruction sets the prover uses and the CPU supports.
+async fn ready(data: web::Data<AppState>) -> HttpResponse {
+    let warmup = data.warmup.read().expect("warmup lock poisoned").clone();
+    let degraded = data.prover.is_none();
+    let ready = warmup.is_ready() && !degraded;
+    let mut response = if ready {
+        HttpResponse::Ok()
+    } else {
+        HttpResponse::ServiceUnavailable()
+    };
+    response.json(serde_json::json!({
+        "ready": ready,
+        "degraded": degraded,
+        "missing_artifacts": data.missing_artifacts,
+        "openvm_version": openvm_version_tag(),
+        "app_exe_commit": data.app_exe_commit,
+        "cpu_features": data.cpu_features,
+        "warmup": warmup,
+    }))
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    tracing_subscriber::fmt::init();
-
-    // Dispatch: `cardano-zkvms setup` runs one-time provisioning, otherwise serve.
+    logging::init();
+
+    // Dispatch: `cardano-zkvms setup` runs one-time provisioning,
+    // `cardano-zkvms check` validates the configuration and artifacts,
+    // `cardano-zkvms metadata` prints proof metadata,
+    // `cardano-zkvms proof` inspects or compares proofs,
+    // `cardano-zkvms verify-bundle` checks a proof bundle offline,
+    // `cardano-zkvms triage replay` re-runs recorded failures,
+    // `cardano-zkvms triage minimize` shrinks a failing program,
+    // `cardano-zkvms worker` runs one guest job for the server, otherwise serve.
     let args: Vec<String> = std::env::args().collect();
     if args.len() > 1 {
         match args[1].as_str() {
             "setup" => {
-                if let Err(e) = cmd_setup() {
+                if let Err(e) = cmd_setup(&args[2..]) {
                     eprintln!("Setup failed: {:?}", e);
                     std::process::exit(1);
                 }
                 return Ok(());
             }
+            "check" => {
+                if let Err(e) = check::cmd_check(&args[2..]) {
+                    eprintln!("Check failed: {:?}", e);
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+            "metadata" => {
+                if let Err(e) = metadata::cmd_metadata(&args[2..]) {
+                    eprintln!("Metadata failed: {:?}", e);
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+            "artifacts" => {
+                if let Err(e) = artifacts::cmd_artifacts(&args[2..]) {
+                    eprintln!("Artifacts failed: {:?}", e);
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+            "proof" => {
+                if let Err(e) = proof_inspect::cmd_proof(&args[2..]) {
+                    eprintln!("Proof failed: {:?}", e);
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+            "verify-bundle" => {
+                if let Err(e) = verify_bundle::cmd_verify_bundle(&args[2..]) {
+                    eprintln!("Bundle verification failed: {:?}", e);
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+            "triage" if args.get(2).is_some_and(|command| command == "replay") => {
+                if let Err(e) = triage::cmd_replay(&args[3..]) {
+                    eprintln!("Replay failed: {:?}", e);
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+            "triage" if args.get(2).is_some_and(|command| command == "minimize") => {
+                if let Err(e) = triage::cmd_minimize(&args[3..]) {
+                    eprintln!("Minimize failed: {:?}", e);
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+            "worker" => {
+                if let Err(e) = worker::cmd_worker(&args[2..]) {
+                    eprintln!("Worker failed: {:?}", e);
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
             other => {
                 eprintln!("Unknown command: {}", other);
-                eprintln!("Usage: cardano-zkvms [setup]");
-                eprintln!("  (no args)  Start the web server");
-                eprintln!("  setup      One-time provisioning: build guest, keygen, agg keygen");
+                eprintln!(
+                    "Usage: cardano-zkvms [setup [--low-memory | --keygen-threads N] | check | artifacts export [FILE] | artifacts import FILE | metadata [OPTIONS] FILE | proof inspect FILE | proof diff A B | verify-bundle [--vk FILE] [--arg FILE]... BUNDLE | triage replay [--prove] [DIR] | triage minimize [--guest] [--matching TEXT] PROGRAM]"
+                );
+                eprintln!("  (no args)     Start the web server");
+                eprintln!("  setup         One-time provisioning: build guest, keygen, agg keygen");
+                eprintln!(
+                    "  check         Validate the configuration and artifacts, print a JSON report"
+                );
+                eprintln!(
+                    "  artifacts     Export the artifacts to a .tar.zst bundle, or import one"
+                );
+                eprintln!(
+                    "  metadata      Transaction metadata anchoring a proof: [--format json|cbor] [--label N] [--cid CID]"
+                );
+                eprintln!(
+                    "  proof         Print what a proof claims, or the fields two proofs differ in"
+                );
+                eprintln!(
+                    "  verify-bundle Verify a proof bundle offline and print what it attests"
+                );
+                eprintln!("  triage replay Re-run programs recorded in TRIAGE_DIR (or DIR)");
+                eprintln!(
+                    "  triage minimize Shrink a failing program (hex, hex file or triage record)"
+                );
+                eprintln!("  worker        Run one guest job for the server (internal)");
                 std::process::exit(2);
             }
         }
     }
 
-    // Resolve guest directory (the openvm guest crate)
-    let guest_dir = std::env::var("OPENVM_GUEST_DIR")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| {
-            // Default: assume we're run from web/backend/, guest is at ../../
-            PathBuf::from("../../")
-        })
-        .canonicalize()
-        .expect("Cannot resolve guest directory. Set OPENVM_GUEST_DIR env var.");
+    // Resolve the guest directory (the openvm guest crate) and artifact paths.
+    let paths =
+        ArtifactPaths::from_env(&environment::Environment::from_process()).unwrap_or_else(|err| {
+            error!("{}", err);
+            eprintln!("{}", err);
+            std::process::exit(1);
+        });
+    let guest_dir = paths.guest_dir.clone();
 
     let port: u16 = std::env::var("PORT")
         .ok()
         .and_then(|s| s.parse().ok())
         .unwrap_or(8080);
+    let warmup_enabled = std::env::var("WARMUP_PROOF").is_ok_and(|v| v == "1" || v == "true");
+    let admin_token = std::env::var("ADMIN_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty());
+    let determinism_check =
+        std::env::var("DETERMINISM_CHECK").is_ok_and(|v| v == "1" || v == "true");
+    let evaluation_check = std::env::var("EVALUATION_CHECK").is_ok_and(|v| v == "1" || v == "true");
+    let require_checksums =
+        std::env::var("REQUIRE_ARTIFACT_CHECKSUMS").is_ok_and(|v| v == "1" || v == "true");
+    let decode_limits = decode_limits_from_env();
+    let guest_timeouts = GuestTimeouts::from_env().unwrap_or_else(|err| {
+        error!("{}", err);
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+    let worker_process = worker::for_timeouts(&guest_timeouts).unwrap_or_else(|err| {
+        error!("Failed to locate the worker executable: {}", err);
+        eprintln!("Failed to locate the worker executable: {}", err);
+        std::process::exit(1);
+    });
+    if let Some(worker) = worker_process.clone() {
+        // Load a worker's keys now rather than on the first timed job.
+        std::thread::spawn(move || {
+            if let Err(err) = worker.warm() {
+                error!("Failed to start a worker: {:#}", err);
+            }
+        });
+    }
+    let retry_policy = retry::RetryPolicy::from_env().unwrap_or_else(|err| {
+        error!("{}", err);
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+    let prover_policy = prover_options::ProverPolicy::from_env().unwrap_or_else(|err| {
+        error!("{}", err);
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+    let demo = demo::DemoPolicy::from_env()
+        .unwrap_or_else(|err| {
+            error!("{}", err);
+            eprintln!("{}", err);
+            std::process::exit(1);
+        })
+        .map(Arc::new);
+    let prove_queue = Arc::new(queue::ProveQueue::from_env().unwrap_or_else(|err| {
+        error!("{}", err);
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }));
+    let guest_registry = env_path("GUEST_REGISTRY_FILE");
+    let triage_dir = env_path("TRIAGE_DIR");
+    let proof_store = env_path("PROOF_STORE_DIR").map(proofs::ProofStore::new);
+    if let Some(store) = &proof_store {
+        match store.ensure_indexed() {
+            Ok(0) => {}
+            Ok(count) => info!("Indexed {} stored proofs", count),
+            Err(e) => error!("Failed to index stored proofs: {}", e),
+        }
+    }
+    let replicator = replication::Replicator::from_env(proof_store.as_ref())
+        .unwrap_or_else(|err| {
+            error!("{}", err);
+            eprintln!("{}", err);
+            std::process::exit(1);
+        })
+        .map(Arc::new);
+    let ipfs = ipfs::IpfsClient::from_env();
+    let usage_stats = usage_stats::UsageStats::from_env()
+        .unwrap_or_else(|err| {
+            error!("{}", err);
+            eprintln!("{}", err);
+            std::process::exit(1);
+        })
+        .map(Arc::new);
+    let api_keys = usage::ApiKeys::from_env().unwrap_or_else(|err| {
+        error!("{}", err);
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+    let usage_log = usage::UsageLog::from_env();
+    let job_store = jobs::JobStore::from_env().unwrap_or_else(|err| {
+        error!("{}", err);
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+    let service_key = claims::ServiceKey::from_env()
+        .unwrap_or_else(|err| {
+            error!("{}", err);
+            eprintln!("{}", err);
+            std::process::exit(1);
+        })
+        .map(Arc::new);
+    let (epochs, epoch_interval) =
+        certificates::EpochStore::from_env(proof_store.as_ref(), service_key.is_some())
+            .unwrap_or_else(|err| {
+                error!("{}", err);
+                eprintln!("{}", err);
+                std::process::exit(1);
+            })
+            .unzip();
+
+    let cpu_features = cpu_features::CpuFeatures::detect();
 
     info!("OpenVM Web Backend starting");
     info!("  Guest dir:       {}", guest_dir.display());
     info!("  Port:            {}", port);
+    info!("  Warmup proof:    {}", warmup_enabled);
+    info!("  Admin API:       {}", admin_token.is_some());
+    info!("  Determinism:     {}", determinism_check);
+    info!("  Eval check:      {}", evaluation_check);
+    info!("  Decode limits:   {:?}", decode_limits);
+    info!("  Guest timeouts:  {:?}", guest_timeouts);
+    info!("  Prove retries:   {:?}", retry_policy);
+    info!("  Prover policy:   {:?}", prover_policy);
+    info!("  Demo mode:       {:?}", demo);
+    info!("  Prove queue:     {:?}", prove_queue);
+    info!("  Guest registry:  {:?}", guest_registry);
+    info!(
+        "  Triage dir:      {}",
+        triage_dir
+            .as_ref()
+            .map_or_else(|| "disabled".to_string(), |dir| dir.display().to_string())
+    );
+    info!(
+        "  Proof store:     {}",
+        env_path("PROOF_STORE_DIR")
+            .map_or_else(|| "disabled".to_string(), |dir| dir.display().to_string())
+    );
+    info!(
+        "  Replication:     {}",
+        replicator.as_ref().map_or_else(
+            || "disabled".to_string(),
+            |replicator| replicator.locations().join(", ")
+        )
+    );
+    info!(
+        "  IPFS:            {}",
+        ipfs.as_ref().map_or("disabled", |client| client.api_url())
+    );
+    info!(
+        "  Telemetry:       {}",
+        usage_stats.as_ref().map_or_else(
+            || "disabled".to_string(),
+            |stats| format!(
+                "every {}s to {}",
+                stats.interval().as_secs(),
+                stats.endpoint()
+            )
+        )
+    );
+    info!(
+        "  API keys:        {}",
+        api_keys
+            .as_ref()
+            .map_or_else(|| "disabled".to_string(), |keys| keys.len().to_string())
+    );
+    info!(
+        "  Usage log:       {}",
+        usage_log.as_ref().map_or_else(
+            || "disabled".to_string(),
+            |log| log.path().display().to_string()
+        )
+    );
+    info!(
+        "  Job store:       {}",
+        job_store.as_ref().map_or_else(
+            || "disabled".to_string(),
+            |store| store.dir().display().to_string()
+        )
+    );
+    info!("  CPU features:    {:?}", cpu_features.compiled());
+    if !cpu_features.missing().is_empty() {
+        tracing::warn!(
+            "The prover does not use {:?}, which this CPU supports; build with \
+             `make backend-build-native` for faster proofs",
+            cpu_features.missing()
+        );
+    }
+    info!(
+        "  Service key:     {}",
+        service_key
+            .as_ref()
+            .map_or_else(|| "disabled".to_string(), |key| key.public_key_hex())
+    );
+    info!(
+        "  Epochs:          {}",
+        epochs.as_ref().zip(epoch_interval).map_or_else(
+            || "disabled".to_string(),
+            |(store, interval)| format!(
+                "every {}s to {}",
+                interval.as_secs(),
+                store.dir().display()
+            )
+        )
+    );
 
-    let workspace_root = guest_dir
-        .parent()
-        .and_then(|p| p.parent())
-        .and_then(|p| p.parent())
-        .expect("Guest dir must be 3 levels deep from workspace root (e.g. crates/zkvms/openvm)")
-        .to_path_buf();
-
-    // Resolve all paths
-    let target_dir = workspace_root.join("target");
-    let vmexe_path = target_dir.join("openvm/release/openvm-guest.vmexe");
-    let pk_path = target_dir.join("openvm/app.pk");
-    let config_path = guest_dir.join("openvm.toml");
-    let openvm_home = openvm_home();
-    let agg_pk_path = openvm_home.join("agg_stark.pk");
+    let vmexe_path = &paths.vmexe_path;
+    let pk_path = &paths.app_pk_path;
+    let config_path = &paths.config_path;
+    let agg_pk_path = &paths.agg_pk_path;
     let expected_version = openvm_version_tag();
 
-    invalidate_stale_runtime_artifacts(&target_dir, &openvm_home, &expected_version)
+    invalidate_stale_runtime_artifacts(&paths, &expected_version)
         .expect("Failed to invalidate stale OpenVM artifacts");
 
-    info!("  Workspace root:  {}", workspace_root.display());
-    info!("  Target dir:      {}", target_dir.display());
-    info!("  OpenVM home:     {}", openvm_home.display());
+    info!("  Target dir:      {}", paths.target_dir.display());
+    info!("  OpenVM home:     {}", paths.openvm_home.display());
+
+    let build_info = std::fs::read_to_string(paths.build_info_path()).ok();
+    info!(
+        "  Guest build:     {}",
+        build_info
+            .as_deref()
+            .map_or("not recorded, run setup", str::trim)
+    );
+    let guest_build = build_info.as_deref().map(parse_build_info);
 
     // Pre-flight: check for critical files
-    let agg_vk_path = openvm_home.join("agg_stark.vk");
     let checks: &[(&str, &std::path::Path)] = &[
-        ("Guest vmexe", &vmexe_path),
-        ("Proving key", &pk_path),
-        ("OpenVM config", &config_path),
-        ("Agg STARK PK", &agg_pk_path),
-        ("Agg STARK VK", &agg_vk_path),
+        ("Guest vmexe", vmexe_path),
+        ("Proving key", pk_path),
+        ("OpenVM config", config_path),
+        ("Agg STARK PK", agg_pk_path),
+        ("Agg STARK VK", &paths.agg_vk_path),
     ];
     for (label, path) in checks {
         if path.exists() {
@@ -897,78 +2666,214 @@ async fn main() -> std::io::Result<()> {
         .filter(|(_, path)| !path.exists())
         .map(|(label, path)| format!("{} ({})", label, path.display()))
         .collect();
-    if !missing_paths.is_empty() {
-        let hint = setup_hint(&guest_dir, &expected_version);
-        error!("{} Missing: {}", hint, missing_paths.join(", "));
-        eprintln!("{}", hint);
-        eprintln!("Missing artifacts: {}", missing_paths.join(", "));
-        std::process::exit(1);
-    }
-
-    // Load all OpenVM artifacts at startup
-    info!("Loading OpenVM artifacts...");
-    let config =
-        openvm_prover::load_config(&config_path).expect("Failed to load openvm.toml config");
-    let exe = openvm_prover::load_exe(&vmexe_path).expect("Failed to load guest vmexe");
-    let app_pk = openvm_prover::load_app_pk(&pk_path).unwrap_or_else(|err| {
-        let hint = setup_hint(&guest_dir, &expected_version);
-        error!(
-            "Failed to load app proving key from {}: {}. {}",
-            pk_path.display(),
-            err,
-            hint
-        );
-        eprintln!(
-            "Failed to load app proving key from {}: {}",
-            pk_path.display(),
-            err
+    let (prover, guest, app_exe_commit) = if missing_paths.is_empty() {
+        // Load all OpenVM artifacts at startup
+        info!("Loading OpenVM artifacts...");
+        // The MCU endpoint's root and Halo2 keys, when configured, are loaded
+        // here once rather than per request.
+        let builder = openvm_prover::Prover::builder()
+            .config_path(config_path)
+            .exe_path(vmexe_path)
+            .app_pk_path(pk_path)
+            .agg_pk_path(agg_pk_path)
+            .require_checksums(require_checksums);
+        let prover = openvm_prover::evm_halo2_mcu::with_configured_keys(builder)
+            .build()
+            .unwrap_or_else(|err| {
+                let hint = setup_hint(&guest_dir, &expected_version);
+                error!("Failed to load the OpenVM artifacts: {:?}. {}", err, hint);
+                eprintln!("Failed to load the OpenVM artifacts: {:?}", err);
+                eprintln!("{}", hint);
+                std::process::exit(1);
+            });
+        let (app_exe_commit, _) = prover
+            .app_commit()
+            .expect("Failed to compute the guest's app commit");
+        info!(
+            "All artifacts loaded. Guest app_exe_commit: {}",
+            app_exe_commit
         );
-        eprintln!("{}", hint);
-        std::process::exit(1);
-    });
-    let agg_pk = openvm_prover::load_agg_pk(&agg_pk_path).unwrap_or_else(|err| {
+        (Some(prover), None, Some(app_exe_commit))
+    } else {
+        // Serve what the artifacts that are there allow, rather than
+        // nothing, so a partially provisioned deployment can be diagnosed
+        // and finished through the admin API.
         let hint = setup_hint(&guest_dir, &expected_version);
-        error!(
-            "Failed to load aggregation proving key from {}: {}. {}",
-            agg_pk_path.display(),
-            err,
-            hint
+        tracing::warn!(
+            "{} Missing: {}. Starting in degraded mode: proving is disabled.",
+            hint,
+            missing_paths.join(", ")
         );
-        eprintln!(
-            "Failed to load aggregation proving key from {}: {}",
-            agg_pk_path.display(),
-            err
+        let guest = if config_path.exists() && vmexe_path.exists() {
+            match (
+                openvm_prover::load_config(config_path),
+                openvm_prover::load_exe(vmexe_path),
+            ) {
+                (Ok(config), Ok(exe)) => Some(execute::Guest {
+                    config,
+                    exe: Arc::new(exe),
+                }),
+                (Err(err), _) | (_, Err(err)) => {
+                    tracing::warn!("Failed to load the guest: {:?}", err);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        info!(
+            "  Execution:       {}",
+            if guest.is_some() {
+                "enabled"
+            } else {
+                "disabled"
+            }
         );
-        eprintln!("{}", hint);
-        std::process::exit(1);
-    });
-    info!("All artifacts loaded.");
+        (None, guest.map(Arc::new), None)
+    };
+    let degraded = prover.is_none();
 
     let state = web::Data::new(AppState {
-        config,
-        exe,
-        app_pk,
-        agg_pk,
-        openvm_home,
+        prover,
+        guest,
+        missing_artifacts: missing_paths,
+        paths,
+        warmup: RwLock::new(if warmup_enabled && !degraded {
+            WarmupStatus::Running
+        } else {
+            WarmupStatus::Disabled
+        }),
+        admin_token,
+        determinism_check,
+        evaluation_check,
+        decode_limits,
+        guest_timeouts,
+        worker: worker_process,
+        triage_dir,
+        proof_store,
+        replicator,
+        ipfs,
+        usage_stats: usage_stats.clone(),
+        api_keys,
+        usage_log,
+        job_store,
+        cpu_features,
+        service_key,
+        epochs,
+        retry_policy,
+        prover_policy,
+        demo,
+        prove_queue,
+        in_flight: Arc::default(),
+        prove_metrics: Arc::default(),
+        guest_registry,
+        guest_build,
+        app_exe_commit,
+        setup_jobs: Mutex::new(HashMap::new()),
     });
 
+    if warmup_enabled && !degraded {
+        actix_web::rt::spawn(warmup(state.clone()));
+    }
+    if let Some(interval) = epoch_interval.filter(|_| !degraded) {
+        actix_web::rt::spawn(certificates::run(state.clone(), interval));
+    }
+    if let Some(stats) = usage_stats {
+        actix_web::rt::spawn(usage_stats::run(stats));
+    }
+    jobs::resume(&state);
+    if state.job_store.is_some() {
+        actix_web::rt::spawn(jobs::sweep_periodically(state.clone()));
+    }
+
     HttpServer::new(move || {
         let cors = Cors::default()
             .allow_any_origin()
             .allow_any_method()
-            .allow_any_header();
+            .allow_any_header()
+            // For resuming downloads, see `downloads`.
+            .expose_headers([
+                actix_web::http::header::ETAG,
+                actix_web::http::header::CONTENT_RANGE,
+                actix_web::http::header::ACCEPT_RANGES,
+            ]);
 
         App::new()
             .wrap(cors)
             .app_data(state.clone())
             .app_data(web::JsonConfig::default().limit(10 * 1024 * 1024)) // 10 MB JSON limit
             .route("/api/health", web::get().to(health))
+            .route("/api/ready", web::get().to(ready))
             .route("/api/prove", web::post().to(prove))
+            .route("/api/prove/progress", web::get().to(progress::progress))
             .route("/api/prove/mcu-halo2", web::post().to(prove_mcu_halo2))
             .route("/api/patch-envelope", web::post().to(patch_envelope))
             .route("/api/verify", web::post().to(verify))
+            .route("/api/execute", web::post().to(execute::execute))
+            .route("/api/evaluate", web::post().to(execute::evaluate))
+            .route("/api/disassemble", web::post().to(disassemble::disassemble))
+            .route("/api/examples", web::get().to(examples::list))
+            .route("/api/service-key", web::get().to(claims::service_key))
+            .route("/api/proofs", web::get().to(proofs::list_proofs))
+            .route(
+                "/api/proofs/{commitment}/bundle",
+                web::get().to(proofs::proof_bundle),
+            )
+            .route(
+                "/api/proofs/{commitment}/bundle",
+                web::head().to(proofs::proof_bundle),
+            )
+            .route(
+                "/api/proofs/by-hash/{hash}",
+                web::get().to(proofs::proof_bundle_by_hash),
+            )
+            .route(
+                "/api/proofs/by-hash/{hash}",
+                web::head().to(proofs::proof_bundle_by_hash),
+            )
+            .route("/api/replication", web::get().to(replication::status))
+            .route("/api/usage", web::get().to(usage::usage))
+            .route("/api/metrics", web::get().to(metrics::metrics))
+            .route(
+                "/api/epochs/latest",
+                web::get().to(certificates::latest_certificate),
+            )
+            .route(
+                "/api/epochs/{epoch}",
+                web::get().to(certificates::certificate),
+            )
+            .route(
+                "/api/epochs/{epoch}/proof",
+                web::get().to(certificates::proof),
+            )
+            .route(
+                "/api/epochs/{epoch}/inclusion/{commitment}",
+                web::get().to(certificates::inclusion),
+            )
+            .route("/api/admin/setup", web::get().to(admin::setup_status))
+            .route(
+                "/api/admin/setup/{step}",
+                web::get().to(admin::setup_step_status),
+            )
+            .route(
+                "/api/admin/setup/{step}",
+                web::post().to(admin::start_setup_step),
+            )
             // Serve agg_stark.vk from ~/.openvm/ (generated by `cardano-zkvms setup`)
             .route("/data/agg_stark.vk", web::get().to(serve_agg_stark_vk))
+            .route("/data/agg_stark.vk", web::head().to(serve_agg_stark_vk))
+            .route("/data/vk/{hash}", web::get().to(serve_agg_stark_vk_by_hash))
+            .route(
+                "/data/vk/{hash}",
+                web::head().to(serve_agg_stark_vk_by_hash),
+            )
+            .route(
+                "/data/guest-registry.json",
+                web::get().to(guest_registry::serve),
+            )
+            // The guest and its config, for the browser's execution-only preview
+            .route("/data/openvm-guest.vmexe", web::get().to(serve_guest_vmexe))
+            .route("/data/openvm.toml", web::get().to(serve_guest_config))
     })
     .bind(("0.0.0.0", port))?
     .run()
@@ -0,0 +1,191 @@
+//! Cardano transaction metadata anchoring a proof on L1.
+//!
+//! `cardano-zkvms metadata` turns a `/api/prove` response or a proof bundle
+//! into transaction metadata holding the commitment, the app commits, and the
+//! bundle CID when the proof was pinned to IPFS. The output is either the
+//! `cardano-cli --json-metadata-detailed-schema` JSON or the raw metadata
+//! CBOR, so existing submission tooling can attach it to a transaction.
+
+use crate::hex32;
+use ciborium::Value;
+use std::io::Read;
+
+/// Metadata label used unless `--label` is given. It is not registered in
+/// CIP-10; deployments that need one can pass their own.
+const DEFAULT_METADATA_LABEL: u64 = 8413;
+/// Version of the metadata layout, bumped on incompatible changes.
+const METADATA_VERSION: i64 = 1;
+/// Cardano caps metadata strings and bytestrings at 64 bytes.
+const MAX_METADATA_CHUNK: usize = 64;
+
+/// What gets anchored on-chain for a proof.
+pub(crate) struct ProofAnchor {
+    commitment: [u8; 32],
+    app_exe_commit: [u8; 32],
+    app_vm_commit: [u8; 32],
+    /// CID of the pinned proof bundle.
+    bundle_cid: Option<String>,
+}
+
+/// A metadata value, rendered to either output format.
+enum Metadatum {
+    Int(i64),
+    Bytes(Vec<u8>),
+    Text(String),
+}
+
+impl Metadatum {
+    fn detailed_json(&self) -> serde_json::Value {
+        match self {
+            Self::Int(value) => serde_json::json!({ "int": value }),
+            Self::Bytes(bytes) => serde_json::json!({ "bytes": hex::encode(bytes) }),
+            Self::Text(text) => serde_json::json!({ "string": text }),
+        }
+    }
+
+    fn cbor(&self) -> Value {
+        match self {
+            Self::Int(value) => Value::Integer((*value).into()),
+            Self::Bytes(bytes) => Value::Bytes(bytes.clone()),
+            Self::Text(text) => Value::Text(text.clone()),
+        }
+    }
+}
+
+impl ProofAnchor {
+    /// Read the anchor from a `/api/prove` response or a proof bundle, which
+    /// nests the app commits under `proof`.
+    pub(crate) fn from_json(
+        value: &serde_json::Value,
+        bundle_cid: Option<String>,
+    ) -> eyre::Result<Self> {
+        let field = |key: &str| {
+            value
+                .get(key)
+                .or_else(|| value.get("proof").and_then(|proof| proof.get(key)))
+                .and_then(|field| field.as_str())
+                .ok_or_else(|| eyre::eyre!("Missing `{}` in the proof JSON", key))
+        };
+        let commit = |key: &str| {
+            field(key)
+                .and_then(|hex| hex32(hex).map_err(|e| eyre::eyre!("Invalid `{}`: {}", key, e)))
+        };
+
+        let bundle_cid = bundle_cid.or_else(|| {
+            value
+                .pointer("/ipfs/bundle")
+                .and_then(|cid| cid.as_str())
+                .map(str::to_string)
+        });
+        if let Some(cid) = &bundle_cid {
+            if cid.len() > MAX_METADATA_CHUNK {
+                eyre::bail!(
+                    "Bundle CID {} is longer than {} bytes",
+                    cid,
+                    MAX_METADATA_CHUNK
+                );
+            }
+        }
+
+        Ok(Self {
+            commitment: commit("commitment")?,
+            app_exe_commit: commit("app_exe_commit")?,
+            app_vm_commit: commit("app_vm_commit")?,
+            bundle_cid,
+        })
+    }
+
+    fn entries(&self) -> Vec<(&'static str, Metadatum)> {
+        let mut entries = vec![
+            ("v", Metadatum::Int(METADATA_VERSION)),
+            ("commitment", Metadatum::Bytes(self.commitment.to_vec())),
+            (
+                "app_exe_commit",
+                Metadatum::Bytes(self.app_exe_commit.to_vec()),
+            ),
+            (
+                "app_vm_commit",
+                Metadatum::Bytes(self.app_vm_commit.to_vec()),
+            ),
+        ];
+        if let Some(cid) = &self.bundle_cid {
+            entries.push(("bundle", Metadatum::Text(cid.clone())));
+        }
+        entries
+    }
+
+    /// Metadata in `cardano-cli`'s detailed JSON schema.
+    pub(crate) fn detailed_json(&self, label: u64) -> serde_json::Value {
+        let map: Vec<_> = self
+            .entries()
+            .into_iter()
+            .map(|(key, value)| {
+                serde_json::json!({ "k": { "string": key }, "v": value.detailed_json() })
+            })
+            .collect();
+        serde_json::json!({ label.to_string(): { "map": map } })
+    }
+
+    /// Metadata as CBOR: `{ label: { key: value, ... } }`.
+    pub(crate) fn cbor(&self, label: u64) -> Vec<u8> {
+        let map = self
+            .entries()
+            .into_iter()
+            .map(|(key, value)| (Value::Text(key.to_string()), value.cbor()))
+            .collect();
+        let metadata = Value::Map(vec![(Value::Integer(label.into()), Value::Map(map))]);
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&metadata, &mut bytes).expect("CBOR encoding into a Vec cannot fail");
+        bytes
+    }
+}
+
+/// `cardano-zkvms metadata [--format json|cbor] [--label N] [--cid CID] FILE`
+///
+/// Print the metadata for the proof JSON in `FILE` (`-` for stdin): detailed
+/// schema JSON by default, or hex-encoded CBOR.
+pub(crate) fn cmd_metadata(args: &[String]) -> eyre::Result<()> {
+    let mut format = "json".to_string();
+    let mut label = DEFAULT_METADATA_LABEL;
+    let mut bundle_cid = None;
+    let mut input = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| {
+            args.next()
+                .cloned()
+                .ok_or_else(|| eyre::eyre!("{} needs a value", flag))
+        };
+        match arg.as_str() {
+            "--format" => format = value("--format")?,
+            "--label" => {
+                label = value("--label")?
+                    .parse()
+                    .map_err(|e| eyre::eyre!("Invalid --label: {}", e))?
+            }
+            "--cid" => bundle_cid = Some(value("--cid")?),
+            other => input = Some(other.to_string()),
+        }
+    }
+    let input = input.ok_or_else(|| eyre::eyre!("Pass the proof JSON file, or - for stdin"))?;
+
+    let json = if input == "-" {
+        let mut json = String::new();
+        std::io::stdin().read_to_string(&mut json)?;
+        json
+    } else {
+        std::fs::read_to_string(&input)?
+    };
+    let anchor = ProofAnchor::from_json(&serde_json::from_str(&json)?, bundle_cid)?;
+
+    match format.as_str() {
+        "json" => println!(
+            "{}",
+            serde_json::to_string_pretty(&anchor.detailed_json(label))?
+        ),
+        "cbor" => println!("{}", hex::encode(anchor.cbor(label))),
+        other => eyre::bail!("Unknown --format {}, expected json or cbor", other),
+    }
+    Ok(())
+}
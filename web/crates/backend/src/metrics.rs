@@ -0,0 +1,120 @@
+//! `GET /api/metrics`: proving telemetry in the Prometheus text format.
+//!
+//! Every STARK proof served by `/api/prove` adds the time it spent in each
+//! phase and how many continuation segments it had, so dashboards can tell
+//! app proving from aggregation and see how both grow with segments. Only
+//! sums and counts are kept, as summaries without quantiles; rates and
+//! averages are left to the scraper.
+
+use crate::AppState;
+use actix_web::{web, HttpResponse};
+use openvm_prover::ProveTelemetry;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+/// Totals of the STARK proofs generated since the server started.
+#[derive(Debug, Default)]
+pub(crate) struct ProveMetrics {
+    totals: Mutex<Totals>,
+}
+
+#[derive(Debug, Default)]
+struct Totals {
+    proofs: u64,
+    setup_secs: f64,
+    app_proof_secs: f64,
+    aggregation_secs: f64,
+    baseline_secs: f64,
+    segments: u64,
+}
+
+impl ProveMetrics {
+    pub(crate) fn record(&self, telemetry: &ProveTelemetry) {
+        let mut totals = self.totals.lock().expect("metrics lock poisoned");
+        totals.proofs += 1;
+        totals.setup_secs += telemetry.setup_secs;
+        totals.app_proof_secs += telemetry.app_proof_secs;
+        totals.aggregation_secs += telemetry.aggregation_secs;
+        totals.baseline_secs += telemetry.baseline_secs;
+        totals.segments += telemetry.segments as u64;
+    }
+
+    fn render(&self) -> String {
+        let totals = self.totals.lock().expect("metrics lock poisoned");
+        let mut text = String::new();
+        text.push_str(
+            "# HELP openvm_prove_phase_seconds Time STARK proofs spent in each phase.\n\
+             # TYPE openvm_prove_phase_seconds summary\n",
+        );
+        for (phase, secs) in [
+            ("setup", totals.setup_secs),
+            ("app_proof", totals.app_proof_secs),
+            ("aggregation", totals.aggregation_secs),
+            ("baseline", totals.baseline_secs),
+        ] {
+            let _ = writeln!(
+                text,
+                "openvm_prove_phase_seconds_sum{{phase=\"{}\"}} {}",
+                phase, secs
+            );
+            let _ = writeln!(
+                text,
+                "openvm_prove_phase_seconds_count{{phase=\"{}\"}} {}",
+                phase, totals.proofs
+            );
+        }
+        text.push_str(
+            "# HELP openvm_prove_segments Continuation segments per STARK proof.\n\
+             # TYPE openvm_prove_segments summary\n",
+        );
+        let _ = writeln!(text, "openvm_prove_segments_sum {}", totals.segments);
+        let _ = writeln!(text, "openvm_prove_segments_count {}", totals.proofs);
+        text
+    }
+}
+
+/// GET /api/metrics
+pub(crate) async fn metrics(data: web::Data<AppState>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(data.prove_metrics.render())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sums_phases_and_segments() {
+        let metrics = ProveMetrics::default();
+        for (app_proof_secs, segments) in [(2.0, 3), (4.5, 5)] {
+            metrics.record(&ProveTelemetry {
+                setup_secs: 1.0,
+                app_proof_secs,
+                aggregation_secs: 10.0,
+                segments,
+                baseline_secs: 0.25,
+                cpu_secs: None,
+                peak_rss_bytes: None,
+            });
+        }
+
+        let text = metrics.render();
+        for line in [
+            "openvm_prove_phase_seconds_sum{phase=\"setup\"} 2",
+            "openvm_prove_phase_seconds_sum{phase=\"app_proof\"} 6.5",
+            "openvm_prove_phase_seconds_sum{phase=\"aggregation\"} 20",
+            "openvm_prove_phase_seconds_sum{phase=\"baseline\"} 0.5",
+            "openvm_prove_phase_seconds_count{phase=\"aggregation\"} 2",
+            "openvm_prove_segments_sum 8",
+            "openvm_prove_segments_count 2",
+        ] {
+            assert!(
+                text.lines().any(|l| l == line),
+                "{} missing from\n{}",
+                line,
+                text
+            );
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! Progress of the prove requests in flight, for `GET /api/prove/progress`.
+//!
+//! `/api/prove` only answers once the proof is ready, minutes later. Until
+//! then clients can poll this endpoint with the SHA256 of their program to
+//! see which stage its proof has reached and for how long: executing,
+//! queued for a proving slot, proving the app segments, aggregating them
+//! (segment by segment), or finishing.
+
+use crate::AppState;
+use actix_web::{web, HttpResponse};
+use openvm_prover::{ProveProgress, Stage};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// The prove requests in flight.
+#[derive(Debug, Default)]
+pub(crate) struct InFlight {
+    requests: Mutex<InFlightState>,
+}
+
+#[derive(Debug, Default)]
+struct InFlightState {
+    next_id: u64,
+    requests: BTreeMap<u64, Request>,
+}
+
+#[derive(Debug)]
+struct Request {
+    program_sha256: String,
+    stage: Stage,
+    segment: Option<SegmentProgress>,
+    accepted: Instant,
+    stage_entered: Instant,
+}
+
+/// A request's entry in [`InFlight`], removed when dropped. Reports its
+/// request's stages.
+pub(crate) struct Tracked {
+    in_flight: Arc<InFlight>,
+    id: u64,
+}
+
+impl InFlight {
+    /// Track a request for `program_bytes`, starting at `stage`.
+    pub(crate) fn track(self: &Arc<Self>, program_bytes: &[u8], stage: Stage) -> Tracked {
+        let now = Instant::now();
+        let mut state = self.requests.lock().expect("in-flight lock poisoned");
+        let id = state.next_id;
+        state.next_id += 1;
+        state.requests.insert(
+            id,
+            Request {
+                program_sha256: hex::encode(Sha256::digest(program_bytes)),
+                stage,
+                segment: None,
+                accepted: now,
+                stage_entered: now,
+            },
+        );
+        Tracked {
+            in_flight: self.clone(),
+            id,
+        }
+    }
+
+    /// The requests for the program with SHA256 `program_sha256`, or every
+    /// request, oldest first.
+    fn list(&self, program_sha256: Option<&str>) -> Vec<RequestProgress> {
+        let state = self.requests.lock().expect("in-flight lock poisoned");
+        state
+            .requests
+            .values()
+            .filter(|request| program_sha256.is_none_or(|sha256| request.program_sha256 == sha256))
+            .map(|request| RequestProgress {
+                program_sha256: request.program_sha256.clone(),
+                stage: request.stage,
+                segment: request.segment,
+                elapsed_secs: request.accepted.elapsed().as_secs_f64(),
+                stage_secs: request.stage_entered.elapsed().as_secs_f64(),
+            })
+            .collect()
+    }
+}
+
+impl Tracked {
+    fn update(&self, update: impl FnOnce(&mut Request)) {
+        let mut state = self
+            .in_flight
+            .requests
+            .lock()
+            .expect("in-flight lock poisoned");
+        if let Some(request) = state.requests.get_mut(&self.id) {
+            update(request);
+        }
+    }
+}
+
+impl ProveProgress for Tracked {
+    fn stage(&self, stage: Stage) {
+        self.update(|request| {
+            request.stage = stage;
+            request.segment = None;
+            request.stage_entered = Instant::now();
+        });
+    }
+
+    fn segment(&self, index: usize, total: usize) {
+        self.update(|request| request.segment = Some(SegmentProgress { index, total }));
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        let mut state = self
+            .in_flight
+            .requests
+            .lock()
+            .expect("in-flight lock poisoned");
+        state.requests.remove(&self.id);
+    }
+}
+
+/// The segment a stage is working on, counted from 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+struct SegmentProgress {
+    index: usize,
+    total: usize,
+}
+
+/// A request in flight as listed by `GET /api/prove/progress`.
+#[derive(Debug, Serialize)]
+struct RequestProgress {
+    program_sha256: String,
+    stage: Stage,
+    /// Within the stage, when it reports segments.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    segment: Option<SegmentProgress>,
+    /// Since the request was accepted.
+    elapsed_secs: f64,
+    /// Since the current stage started.
+    stage_secs: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ProgressQuery {
+    program_sha256: Option<String>,
+}
+
+/// GET /api/prove/progress?program_sha256=...
+///
+/// The prove requests in flight for the program with that SHA256 (hex), or
+/// every request in flight, oldest first.
+pub(crate) async fn progress(
+    data: web::Data<AppState>,
+    query: web::Query<ProgressQuery>,
+) -> HttpResponse {
+    let program_sha256 = query
+        .program_sha256
+        .as_deref()
+        .map(|sha256| sha256.trim().trim_start_matches("0x").to_ascii_lowercase());
+    HttpResponse::Ok().json(serde_json::json!({
+        "requests": data.in_flight.list(program_sha256.as_deref()),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_stages_until_dropped() {
+        let in_flight = Arc::new(InFlight::default());
+        let program_sha256 = hex::encode(Sha256::digest(b"program"));
+        let tracked = in_flight.track(b"program", Stage::Executing);
+        let other = in_flight.track(b"other", Stage::Queued);
+        tracked.stage(Stage::AppProof);
+
+        let listed = in_flight.list(Some(&program_sha256));
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].stage, Stage::AppProof);
+        assert_eq!(listed[0].segment, None);
+        assert_eq!(in_flight.list(None).len(), 2);
+
+        tracked.stage(Stage::Aggregating);
+        tracked.segment(2, 5);
+        let listed = in_flight.list(Some(&program_sha256));
+        assert_eq!(listed[0].stage, Stage::Aggregating);
+        assert_eq!(
+            listed[0].segment,
+            Some(SegmentProgress { index: 2, total: 5 })
+        );
+        // A new stage starts without a segment.
+        tracked.stage(Stage::Finishing);
+        assert_eq!(in_flight.list(Some(&program_sha256))[0].segment, None);
+
+        drop(tracked);
+        assert!(in_flight.list(Some(&program_sha256)).is_empty());
+        drop(other);
+        assert!(in_flight.list(None).is_empty());
+    }
+}
@@ -0,0 +1,290 @@
+//! `cardano-zkvms proof inspect` and `cardano-zkvms proof diff`: what a proof
+//! claims, for debugging "verification failed" reports.
+//!
+//! Both read a `/api/prove` response, a proof bundle, a
+//! `/api/prove/mcu-halo2` response, or a raw MCU proof envelope, and
+//! summarize it: versions, app commits, the claimed commitment next to the
+//! public values the proof actually commits to, and sizes. Most verification
+//! failures show up as a field that differs from a proof that verifies, or
+//! as a commitment that does not match the public values.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use flate2::read::GzDecoder;
+use openvm_mcu_verifier_core::{decode_message, ProofEnvelope};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// A proof's metadata, as named fields in display order.
+#[derive(Debug, Default, PartialEq)]
+struct ProofSummary(Vec<(&'static str, String)>);
+
+impl ProofSummary {
+    fn push(&mut self, name: &'static str, value: impl ToString) {
+        self.0.push((name, value.to_string()));
+    }
+
+    /// Add a string field of `value` at `pointer`, if present.
+    fn push_str(&mut self, name: &'static str, value: &Value, pointer: &str) {
+        if let Some(field) = value.pointer(pointer) {
+            match field {
+                Value::String(text) => self.push(name, text),
+                Value::Null => {}
+                other => self.push(name, other),
+            }
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Record the public values a proof commits to, and the guest status
+    /// they open with.
+    fn push_public_values(&mut self, public_values: &[u8]) {
+        self.push("public_values", hex::encode(public_values));
+        if let Some(status) = guest_protocol::Status::from_public_values(public_values) {
+            self.push("status", status.label());
+        }
+    }
+
+    /// Record whether the bundle's encoded statement hashes to its
+    /// commitment.
+    fn push_statement_check(&mut self, bundle: &Value) {
+        let Some(statement_hex) = bundle.pointer("/statement/hex").and_then(Value::as_str) else {
+            return;
+        };
+        let commitment = hex::decode(statement_hex)
+            .ok()
+            .and_then(|bytes| guest_protocol::Statement::decode(&bytes))
+            .map(|statement| hex::encode(statement.commitment()));
+        self.push(
+            "statement_matches",
+            commitment.is_some() && commitment.as_deref() == self.get("commitment"),
+        );
+    }
+
+    /// Record whether the claimed commitment is what the proof commits to.
+    fn push_commitment_check(&mut self) {
+        if let (Some(commitment), Some(public_values)) =
+            (self.get("commitment"), self.get("public_values"))
+        {
+            let matches = commitment.trim_start_matches("0x") == public_values;
+            self.push("commitment_matches", matches);
+        }
+    }
+}
+
+/// Fields that differ between `a` and `b`, with `-` for missing ones.
+fn diff_rows<'a>(a: &'a ProofSummary, b: &'a ProofSummary) -> Vec<(&'a str, &'a str, &'a str)> {
+    let mut names: Vec<&str> = a.0.iter().map(|(name, _)| *name).collect();
+    for (name, _) in &b.0 {
+        if !names.contains(name) {
+            names.push(name);
+        }
+    }
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let (left, right) = (a.get(name), b.get(name));
+            (left != right).then(|| (name, left.unwrap_or("-"), right.unwrap_or("-")))
+        })
+        .collect()
+}
+
+/// Decode the gzip+base64 proof objects of compressed responses and bundles.
+pub(crate) fn decompress_json(data: &str) -> eyre::Result<Value> {
+    let compressed = BASE64.decode(data.trim())?;
+    Ok(serde_json::from_reader(GzDecoder::new(&compressed[..]))?)
+}
+
+/// Length in bytes of a hex string field.
+fn hex_len(value: &Value, pointer: &str) -> Option<usize> {
+    value.pointer(pointer)?.as_str().map(|hex| hex.len() / 2)
+}
+
+/// Summarize the STARK proof and baseline in `proof`, an object with
+/// `stark_proof_json` and `verification_baseline_json`.
+fn summarize_stark_proof(summary: &mut ProofSummary, proof: &Value) {
+    if let Some(stark_proof_json) = proof.get("stark_proof_json") {
+        summary.push_str("stark_proof_version", stark_proof_json, "/version");
+        match openvm_prover::stark_proof_public_values(stark_proof_json) {
+            Ok(public_values) => summary.push_public_values(&public_values),
+            Err(e) => summary.push("public_values", format!("undecodable ({})", e)),
+        }
+        if let Some(len) = hex_len(stark_proof_json, "/proof") {
+            summary.push("stark_proof_bytes", len);
+        }
+        if let Some(len) = hex_len(stark_proof_json, "/user_pvs_proof") {
+            summary.push("user_pvs_proof_bytes", len);
+        }
+    }
+    if let Some(baseline) = proof.get("verification_baseline_json") {
+        summary.push(
+            "baseline_sha256",
+            hex::encode(Sha256::digest(baseline.to_string())),
+        );
+    }
+    if let Some(app_proof_json) = proof.get("app_proof_json") {
+        summary.push("app_proof_json_bytes", app_proof_json.to_string().len());
+    }
+}
+
+fn summarize_envelope(summary: &mut ProofSummary, envelope: &ProofEnvelope) {
+    summary.push("envelope_protocol_version", envelope.protocol_version);
+    summary.push("proof_kind", format!("{:?}", envelope.proof_kind));
+    summary.push("openvm_version", &envelope.openvm_version);
+    summary.push("verifier_key_id", hex::encode(envelope.verifier_key_id));
+    summary.push("app_exe_commit", hex::encode(envelope.app_exe_commit));
+    summary.push("app_vm_commit", hex::encode(envelope.app_vm_commit));
+    summary.push_public_values(&envelope.user_public_values);
+    summary.push("proof_data_bytes", envelope.proof_data.len());
+    summary.push("metadata_bytes", envelope.metadata.len());
+}
+
+fn decode_envelope(bytes: &[u8]) -> eyre::Result<ProofEnvelope> {
+    decode_message(bytes).map_err(|e| eyre::eyre!("Invalid proof envelope: {}", e))
+}
+
+/// Summarize a proof file's contents.
+fn summarize(bytes: &[u8]) -> eyre::Result<ProofSummary> {
+    let mut summary = ProofSummary::default();
+    let Ok(value) = serde_json::from_slice::<Value>(bytes) else {
+        summary.push("format", "mcu envelope");
+        summarize_envelope(&mut summary, &decode_envelope(bytes)?);
+        return Ok(summary);
+    };
+
+    if value.get("bundle_version").is_some() {
+        summary.push("format", "proof bundle");
+        summary.push_str("bundle_version", &value, "/bundle_version");
+        summary.push_str("openvm_version", &value, "/metadata/openvm_version");
+        summary.push_str("proof_version", &value, "/proof/version");
+        summary.push_str("outcome", &value, "/claim/outcome");
+        summary.push_str("error_kind", &value, "/claim/error_kind");
+        summary.push_str("statement", &value, "/statement/text");
+        summary.push_str("commitment", &value, "/commitment");
+        summary.push_statement_check(&value);
+        summary.push_str("app_exe_commit", &value, "/proof/app_exe_commit");
+        summary.push_str("app_vm_commit", &value, "/proof/app_vm_commit");
+        summary.push_str("program_sha256", &value, "/program/sha256");
+        summary.push_str("verifying_key_sha256", &value, "/verifying_key/sha256");
+        if let Some(data) = value.pointer("/proof/data").and_then(Value::as_str) {
+            summarize_stark_proof(&mut summary, &decompress_json(data)?);
+        }
+    } else if let Some(envelope) = value.get("proof_envelope_b64").and_then(Value::as_str) {
+        summary.push("format", "mcu-halo2 response");
+        summary.push_str("outcome", &value, "/outcome");
+        summary.push_str("proof_sha256", &value, "/proof_sha256");
+        summarize_envelope(&mut summary, &decode_envelope(&BASE64.decode(envelope)?)?);
+    } else {
+        summary.push("format", "prove response");
+        summary.push_str("openvm_version", &value, "/openvm_version");
+        summary.push_str("proof_version", &value, "/proof_version");
+        summary.push_str("proof_level", &value, "/proof_level");
+        summary.push_str("commitment_version", &value, "/commitment_version");
+        summary.push_str("outcome", &value, "/outcome");
+        summary.push_str("error_kind", &value, "/outcome_details/error_kind");
+        summary.push_str("commitment", &value, "/commitment");
+        summary.push_str("app_exe_commit", &value, "/app_exe_commit");
+        summary.push_str("app_vm_commit", &value, "/app_vm_commit");
+        match value.get("proof_gz_b64").and_then(Value::as_str) {
+            Some(data) => summarize_stark_proof(&mut summary, &decompress_json(data)?),
+            None => summarize_stark_proof(&mut summary, &value),
+        }
+    }
+    summary.push_commitment_check();
+    Ok(summary)
+}
+
+fn read_summary(path: &str) -> eyre::Result<ProofSummary> {
+    let bytes = std::fs::read(path).map_err(|e| eyre::eyre!("Failed to read {}: {}", path, e))?;
+    summarize(&bytes).map_err(|e| eyre::eyre!("Failed to inspect {}: {}", path, e))
+}
+
+/// `cardano-zkvms proof inspect FILE` and `cardano-zkvms proof diff A B`.
+///
+/// `inspect` prints one `field: value` line per field of the proof in FILE.
+/// `diff` prints the fields that differ between two proofs, as
+/// `field: A-value | B-value`, and fails when there are any.
+pub(crate) fn cmd_proof(args: &[String]) -> eyre::Result<()> {
+    match args {
+        [command, file] if command == "inspect" => {
+            let summary = read_summary(file)?;
+            let width = summary.0.iter().map(|(name, _)| name.len() + 1).max();
+            for (name, value) in &summary.0 {
+                let label = format!("{}:", name);
+                println!("{:width$} {}", label, value, width = width.unwrap_or(0));
+            }
+            Ok(())
+        }
+        [command, a, b] if command == "diff" => {
+            let (a, b) = (read_summary(a)?, read_summary(b)?);
+            let rows = diff_rows(&a, &b);
+            if rows.is_empty() {
+                println!("No differences");
+                return Ok(());
+            }
+            for (name, left, right) in &rows {
+                println!("{}: {} | {}", name, left, right);
+            }
+            eyre::bail!("The proofs differ in {} field(s)", rows.len())
+        }
+        _ => eyre::bail!("Usage: cardano-zkvms proof inspect FILE | diff A B"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_prove_responses_without_proofs() {
+        let response = serde_json::json!({
+            "success": true,
+            "openvm_version": "v2.0.0-beta.2",
+            "outcome": "success",
+            "commitment": "ab".repeat(32),
+            "app_exe_commit": "0x01",
+            "proof_withheld": "demo mode",
+        });
+        let summary = summarize(response.to_string().as_bytes()).unwrap();
+        assert_eq!(summary.get("format"), Some("prove response"));
+        assert_eq!(summary.get("openvm_version"), Some("v2.0.0-beta.2"));
+        assert_eq!(summary.get("app_exe_commit"), Some("0x01"));
+        assert_eq!(summary.get("public_values"), None);
+        assert_eq!(summary.get("commitment_matches"), None);
+    }
+
+    #[test]
+    fn reads_the_guest_status() {
+        let mut summary = ProofSummary::default();
+        summary.push_public_values(&[0, 0, 0, 1, 0xaa]);
+        assert_eq!(summary.get("public_values"), Some("00000001aa"));
+        assert_eq!(summary.get("status"), Some("eval_error"));
+    }
+
+    #[test]
+    fn diffs_changed_and_missing_fields() {
+        let mut a = ProofSummary::default();
+        a.push("openvm_version", "v2.0.0-beta.2");
+        a.push("commitment", "aa");
+        a.push("error_kind", "decode");
+        let mut b = ProofSummary::default();
+        b.push("openvm_version", "v2.0.0-beta.2");
+        b.push("commitment", "bb");
+        b.push("proof_level", "app");
+
+        assert_eq!(
+            diff_rows(&a, &b),
+            vec![
+                ("commitment", "aa", "bb"),
+                ("error_kind", "decode", "-"),
+                ("proof_level", "-", "app"),
+            ]
+        );
+        assert!(diff_rows(&a, &a).is_empty());
+    }
+}
@@ -0,0 +1,842 @@
+//! Proof store, `GET /api/proofs`, and `GET /api/proofs/{commitment}/bundle`.
+//!
+//! When `PROOF_STORE_DIR` is set, every STARK proof served by `/api/prove` is
+//! also saved there under its commitment. The bundle endpoint returns
+//! everything a standalone "verify this proof" page needs in one download, so
+//! third parties can verify a proof without calling the prove API.
+//!
+//! Proofs are also indexed by the PlutusV3 script hash of their program and
+//! by their `app_exe_commit`, as empty marker files under
+//! `index/<key>/<value>/<commitment>`, so `/api/proofs` can search the store
+//! without reading every proof. The `proved_at` index orders proofs by the
+//! time they were proved, so listing the most recent proofs reads only those.
+//! The `stored_at` index orders them by the time they entered this store,
+//! which for a replicated proof is when it was imported, so epoch windows
+//! certify every proof once however old it is. Stores created before an index
+//! existed are indexed on startup.
+
+use crate::downloads::Download;
+use crate::{openvm_version_tag, AppState, Outcome, ProveResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use flate2::{write::GzEncoder, Compression};
+use guest_protocol::ErrorKind;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{error, info};
+
+/// Version of the bundle layout, bumped on incompatible changes.
+pub(crate) const BUNDLE_VERSION: u32 = 1;
+
+/// Directory of the secondary indexes, inside the store.
+const INDEX_DIR: &str = "index";
+
+/// Most proofs one `/api/proofs` query returns.
+const MAX_SEARCH_RESULTS: usize = 1000;
+
+/// Secondary indexes over the store.
+#[derive(Debug, Clone, Copy)]
+enum IndexKey {
+    ScriptHash,
+    AppExeCommit,
+    /// SHA256 of the stored proof file, for `/api/proofs/by-hash/{hash}`.
+    ContentHash,
+    /// `proved_at`, zero-padded so that entry names sort by time.
+    ProvedAt,
+    /// When the proof was stored here, as `ProvedAt`.
+    StoredAt,
+}
+
+impl IndexKey {
+    const ALL: [Self; 5] = [
+        Self::ScriptHash,
+        Self::AppExeCommit,
+        Self::ContentHash,
+        Self::ProvedAt,
+        Self::StoredAt,
+    ];
+
+    fn dir_name(self) -> &'static str {
+        match self {
+            Self::ScriptHash => "script_hash",
+            Self::AppExeCommit => "app_exe_commit",
+            Self::ContentHash => "content_hash",
+            Self::ProvedAt => "proved_at",
+            Self::StoredAt => "stored_at",
+        }
+    }
+}
+
+/// Entry name of a Unix timestamp in the `proved_at` and `stored_at`
+/// indexes.
+fn time_value(secs: u64) -> String {
+    format!("{:020}", secs)
+}
+
+/// `Cache-Control` of content-addressed URLs, whose content never changes.
+pub(crate) const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// Normalize a hex value from a query or a stored proof to an index entry
+/// name: lowercase without `0x`, and nothing but hex so it is a safe path.
+fn index_value(value: &str) -> Option<String> {
+    let value = value.trim();
+    let value = value
+        .strip_prefix("0x")
+        .unwrap_or(value)
+        .to_ascii_lowercase();
+    let valid =
+        !value.is_empty() && value.len() <= 64 && value.bytes().all(|b| b.is_ascii_hexdigit());
+    valid.then_some(value)
+}
+
+/// A proof saved to the store, keyed by its commitment.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct StoredProof {
+    commitment: String,
+    /// Hex-encoded flat UPLC program
+    program_hex: String,
+    outcome: Outcome,
+    /// Failure kind, for proven failures.
+    error_kind: Option<String>,
+    /// Evaluation result the commitment covers, for successful programs.
+    result: Option<String>,
+    proof_version: String,
+    stark_proof_json: api_types::StarkProofJson,
+    verification_baseline_json: openvm_prover::StarkVerificationBaselineJson,
+    app_exe_commit: String,
+    app_vm_commit: String,
+    openvm_version: String,
+    /// Unix timestamp (seconds) at which the proof was generated.
+    proved_at: u64,
+    /// Evaluator and crate versions of the guest that produced the proof.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    guest_build: Option<BTreeMap<String, String>>,
+    /// SHA256 of the stored file, as hex: set when it is written or read.
+    /// Re-proving a commitment stores a new file, so the hash addresses this
+    /// proof and no other.
+    #[serde(skip)]
+    content_hash: String,
+    /// Unix timestamp (seconds) at which this store received the proof: when
+    /// it was imported for a replicated proof, `proved_at` otherwise.
+    #[serde(skip)]
+    stored_at: u64,
+}
+
+impl StoredProof {
+    pub(crate) fn commitment(&self) -> &str {
+        &self.commitment
+    }
+
+    /// PlutusV3 script hash of the program, as hex.
+    fn script_hash(&self) -> Option<String> {
+        let program_bytes = hex::decode(&self.program_hex).ok()?;
+        Some(hex::encode(crate::claims::plutus_v3_script_hash(
+            &program_bytes,
+        )))
+    }
+
+    /// Index entries of this proof.
+    fn index_values(&self) -> Vec<(IndexKey, String)> {
+        IndexKey::ALL
+            .into_iter()
+            .filter_map(|key| {
+                let value = match key {
+                    IndexKey::ScriptHash => self.script_hash()?,
+                    IndexKey::AppExeCommit => index_value(&self.app_exe_commit)?,
+                    IndexKey::ContentHash => index_value(&self.content_hash)?,
+                    IndexKey::ProvedAt => time_value(self.proved_at),
+                    IndexKey::StoredAt => time_value(self.stored_at),
+                };
+                Some((key, value))
+            })
+            .collect()
+    }
+
+    /// The statement the commitment covers, rebuilt from the claim.
+    fn statement(&self) -> Option<guest_protocol::Statement> {
+        let program_bytes = hex::decode(&self.program_hex).ok()?;
+        let program = Sha256::digest(&program_bytes).into();
+        match (&self.result, &self.error_kind) {
+            (Some(result), _) => Some(guest_protocol::Statement::success(program, result)),
+            (None, Some(kind)) => Some(guest_protocol::Statement::failure(
+                program,
+                ErrorKind::from_label(kind)?,
+            )),
+            (None, None) => None,
+        }
+    }
+
+    fn summary(&self) -> ProofSummary {
+        ProofSummary {
+            bundle_url: format!("/api/proofs/{}/bundle", self.commitment),
+            immutable_bundle_url: format!("/api/proofs/by-hash/{}", self.content_hash),
+            commitment: self.commitment.clone(),
+            script_hash: self.script_hash().unwrap_or_default(),
+            outcome: self.outcome,
+            error_kind: self.error_kind.clone(),
+            result: self.result.clone(),
+            app_exe_commit: self.app_exe_commit.clone(),
+            openvm_version: self.openvm_version.clone(),
+            proved_at: self.proved_at,
+        }
+    }
+
+    /// Collect what the bundle needs from a successful prove response;
+    /// `None` if the response carries no proof.
+    pub(crate) fn from_response(program_bytes: &[u8], response: &ProveResponse) -> Option<Self> {
+        let commitment = response.commitment.clone()?;
+        let outcome = response.outcome?;
+        let result = match outcome {
+            Outcome::Success => claimed_result(program_bytes, &commitment),
+            _ => None,
+        };
+        let proved_at = unix_now();
+        Some(Self {
+            commitment,
+            program_hex: hex::encode(program_bytes),
+            outcome,
+            error_kind: response
+                .outcome_details
+                .as_ref()
+                .and_then(|details| details.error_kind)
+                .map(str::to_string),
+            result,
+            proof_version: response.proof_version.clone()?,
+            stark_proof_json: response.stark_proof_json.clone()?,
+            verification_baseline_json: response.verification_baseline_json.clone()?,
+            app_exe_commit: response.app_exe_commit.clone()?,
+            app_vm_commit: response.app_vm_commit.clone()?,
+            openvm_version: response.openvm_version.clone(),
+            proved_at,
+            guest_build: response.guest_build.clone(),
+            content_hash: String::new(),
+            stored_at: proved_at,
+        })
+    }
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Re-evaluate the program on the host to recover the result string the
+/// guest hashed, keeping it only if it reproduces the commitment.
+pub(crate) fn claimed_result(program_bytes: &[u8], commitment_hex: &str) -> Option<String> {
+    let result = uplc::get_evaluator()
+        .evaluate_program_bytes(program_bytes)
+        .ok()?
+        .to_string();
+    let commitment = guest_protocol::success_commitment(program_bytes, &result);
+    (hex::encode(commitment) == commitment_hex).then_some(result)
+}
+
+/// Directory of stored proofs, one `<commitment>.json` file each.
+#[derive(Debug, Clone)]
+pub(crate) struct ProofStore {
+    dir: PathBuf,
+}
+
+impl ProofStore {
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub(crate) fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path of a proof; `None` unless `commitment` is 32 bytes of lowercase
+    /// hex, so request paths can never escape the store.
+    fn path(&self, commitment: &str) -> Option<PathBuf> {
+        let valid = commitment.len() == 64
+            && commitment
+                .bytes()
+                .all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'));
+        valid.then(|| self.dir.join(format!("{}.json", commitment)))
+    }
+
+    /// Save a proof. Best effort: failures are logged, and the proof is still
+    /// served to the client that requested it.
+    pub(crate) fn save(&self, proof: &mut StoredProof) {
+        let Some(path) = self.path(&proof.commitment) else {
+            return;
+        };
+        let written = serde_json::to_vec(&*proof)
+            .map_err(std::io::Error::from)
+            .and_then(|json| {
+                proof.content_hash = hex::encode(Sha256::digest(&json));
+                self.write(&path, &json)
+            });
+        match written {
+            Ok(()) => info!("Stored proof at {}", path.display()),
+            Err(e) => {
+                error!("Failed to store proof at {}: {}", path.display(), e);
+                return;
+            }
+        }
+        if let Err(e) = self.index(proof) {
+            error!("Failed to index proof {}: {}", proof.commitment, e);
+        }
+    }
+
+    /// Store a proof file replicated from another backend, byte for byte,
+    /// and index it as stored now. `None` unless it is a readable proof of
+    /// `commitment`. Importing the file already stored changes nothing.
+    ///
+    /// Blocking: call from `web::block`.
+    pub(crate) fn import(
+        &self,
+        commitment: &str,
+        bytes: &[u8],
+    ) -> std::io::Result<Option<StoredProof>> {
+        let Some(path) = self.path(commitment) else {
+            return Ok(None);
+        };
+        let stored = match serde_json::from_slice::<StoredProof>(bytes) {
+            Ok(stored) if stored.commitment == commitment => StoredProof {
+                content_hash: hex::encode(Sha256::digest(bytes)),
+                stored_at: unix_now(),
+                ..stored
+            },
+            _ => return Ok(None),
+        };
+        if self.file(commitment)?.is_some_and(|file| file == bytes) {
+            return Ok(Some(stored));
+        }
+        self.write(&path, bytes)?;
+        self.index(&stored)?;
+        Ok(Some(stored))
+    }
+
+    /// The stored file of the proof of `commitment`, as saved.
+    pub(crate) fn file(&self, commitment: &str) -> std::io::Result<Option<Vec<u8>>> {
+        let Some(path) = self.path(commitment) else {
+            return Ok(None);
+        };
+        match std::fs::read(path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn write(&self, path: &Path, json: &[u8]) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        // Write then rename, so a bundle never reads a partial proof.
+        let partial = path.with_extension("json.partial");
+        std::fs::write(&partial, json)?;
+        std::fs::rename(&partial, path)
+    }
+
+    fn index(&self, proof: &StoredProof) -> std::io::Result<()> {
+        for (key, value) in proof.index_values() {
+            let dir = self.dir.join(INDEX_DIR).join(key.dir_name()).join(value);
+            std::fs::create_dir_all(&dir)?;
+            std::fs::write(dir.join(&proof.commitment), [])?;
+        }
+        Ok(())
+    }
+
+    /// Index every stored proof, unless every index already exists.
+    /// Returns how many proofs were indexed.
+    ///
+    /// Blocking: call at startup or from `web::block`.
+    pub(crate) fn ensure_indexed(&self) -> std::io::Result<usize> {
+        let index_dir = |key: IndexKey| self.dir.join(INDEX_DIR).join(key.dir_name());
+        if IndexKey::ALL.into_iter().all(|key| index_dir(key).exists()) {
+            return Ok(0);
+        }
+        let proofs = self.stored_proofs()?;
+        for proof in &proofs {
+            self.index(proof)?;
+        }
+        // Mark empty indexes as built too.
+        for key in IndexKey::ALL {
+            std::fs::create_dir_all(index_dir(key))?;
+        }
+        Ok(proofs.len())
+    }
+
+    /// Every readable stored proof. Unreadable proofs are logged and skipped.
+    fn stored_proofs(&self) -> std::io::Result<Vec<StoredProof>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut proofs = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if !path.extension().is_some_and(|ext| ext == "json") {
+                continue;
+            }
+            if let Some(stored) = self.read(&path)? {
+                proofs.push(stored);
+            }
+        }
+        Ok(proofs)
+    }
+
+    /// Read a stored proof; `None` if it is missing or corrupt. Its
+    /// `stored_at` is its `proved_at`: only the index records imports.
+    fn read(&self, path: &Path) -> std::io::Result<Option<StoredProof>> {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        match serde_json::from_slice::<StoredProof>(&bytes) {
+            Ok(stored) => Ok(Some(StoredProof {
+                content_hash: hex::encode(Sha256::digest(&bytes)),
+                stored_at: stored.proved_at,
+                ..stored
+            })),
+            Err(e) => {
+                error!("Skipping corrupt stored proof {}: {}", path.display(), e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Commitments listed under `value` in the `key` index.
+    fn indexed(&self, key: IndexKey, value: &str) -> std::io::Result<BTreeSet<String>> {
+        list_dir(&self.dir.join(INDEX_DIR).join(key.dir_name()).join(value))
+    }
+
+    /// Values with entries in the `key` index.
+    fn indexed_values(&self, key: IndexKey) -> std::io::Result<BTreeSet<String>> {
+        list_dir(&self.dir.join(INDEX_DIR).join(key.dir_name()))
+    }
+
+    /// Stored proofs matching every filter, most recent first.
+    ///
+    /// Blocking: call from `web::block`.
+    fn search(&self, filters: &ProofFilters) -> std::io::Result<Vec<ProofSummary>> {
+        let mut matches = Vec::new();
+        if let Some(commitment) = &filters.commitment {
+            matches.push(BTreeSet::from([commitment.clone()]));
+        }
+        for (key, value) in &filters.indexed {
+            matches.push(self.indexed(*key, value)?);
+        }
+
+        let mut proofs = Vec::new();
+        match matches
+            .into_iter()
+            .reduce(|matches, filter| matches.intersection(&filter).cloned().collect())
+        {
+            Some(commitments) => {
+                for commitment in commitments {
+                    if let Some(stored) = self.read_commitment(&commitment)? {
+                        proofs.push(stored);
+                    }
+                }
+            }
+            // Walk the `proved_at` index from the most recent proof, reading
+            // only the proofs returned.
+            None => {
+                'index: for proved_at in self.indexed_values(IndexKey::ProvedAt)?.iter().rev() {
+                    for commitment in self.indexed(IndexKey::ProvedAt, proved_at)? {
+                        if proofs.len() == filters.limit {
+                            break 'index;
+                        }
+                        // The index keeps the entries of overwritten proofs, so
+                        // check that the file is still the proof of that time.
+                        if let Some(stored) = self.read_commitment(&commitment)? {
+                            if time_value(stored.proved_at) == *proved_at {
+                                proofs.push(stored);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        proofs.sort_by(|a, b| {
+            b.proved_at
+                .cmp(&a.proved_at)
+                .then_with(|| a.commitment.cmp(&b.commitment))
+        });
+        Ok(proofs
+            .iter()
+            .take(filters.limit)
+            .map(StoredProof::summary)
+            .collect())
+    }
+
+    /// Read the stored proof of `commitment`, as `read`.
+    fn read_commitment(&self, commitment: &str) -> std::io::Result<Option<StoredProof>> {
+        match self.path(commitment) {
+            Some(path) => self.read(&path),
+            None => Ok(None),
+        }
+    }
+
+    /// The stored proof whose file has SHA256 `content_hash`, if it is
+    /// still stored.
+    ///
+    /// Blocking: call from `web::block`.
+    fn by_content_hash(&self, content_hash: &str) -> std::io::Result<Option<StoredProof>> {
+        for commitment in self.indexed(IndexKey::ContentHash, content_hash)? {
+            // The index keeps entries of overwritten proofs, so check that
+            // the file still has this hash.
+            if let Some(stored) = self.read_commitment(&commitment)? {
+                if stored.content_hash == content_hash {
+                    return Ok(Some(stored));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Commitments of the proofs stored here with `start <= stored_at < end`,
+    /// sorted, from the `stored_at` index alone. A commitment stored again
+    /// counts in the windows of both proofs.
+    pub(crate) fn commitments_stored_between(
+        &self,
+        start: u64,
+        end: u64,
+    ) -> std::io::Result<Vec<[u8; 32]>> {
+        let mut commitments = Vec::new();
+        let window = time_value(start)..time_value(end);
+        for stored_at in self.indexed_values(IndexKey::StoredAt)?.range(window) {
+            for commitment in self.indexed(IndexKey::StoredAt, stored_at)? {
+                if let Ok(commitment) = crate::hex32(&commitment) {
+                    commitments.push(commitment);
+                }
+            }
+        }
+        commitments.sort_unstable();
+        commitments.dedup();
+        Ok(commitments)
+    }
+}
+
+/// Names of the entries of `dir`, empty if it does not exist.
+fn list_dir(dir: &Path) -> std::io::Result<BTreeSet<String>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeSet::new()),
+        Err(e) => return Err(e),
+    };
+    entries
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+        .collect()
+}
+
+/// SHA256 of the aggregation verifying key, as hex.
+pub(crate) fn agg_vk_sha256(agg_vk_path: &Path) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut std::fs::File::open(agg_vk_path)?, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Gzip then base64-encode a JSON value.
+pub(crate) fn compress_json(
+    value: &serde_json::Value,
+    level: Compression,
+) -> std::io::Result<String> {
+    let mut encoder = GzEncoder::new(Vec::new(), level);
+    serde_json::to_writer(&mut encoder, value)?;
+    encoder.flush()?;
+    Ok(BASE64.encode(encoder.finish()?))
+}
+
+/// Everything a standalone verifier page needs for `stored`, verified against
+/// the `agg_stark.vk` with SHA256 `vk_sha256`.
+pub(crate) fn bundle_json(
+    stored: &StoredProof,
+    vk_sha256: &str,
+) -> std::io::Result<serde_json::Value> {
+    let proof = serde_json::json!({
+        "stark_proof_json": &stored.stark_proof_json,
+        "verification_baseline_json": &stored.verification_baseline_json,
+    });
+    let compressed_proof = compress_json(&proof, Compression::default())?;
+    let program_bytes = hex::decode(&stored.program_hex).unwrap_or_default();
+
+    Ok(serde_json::json!({
+        "bundle_version": BUNDLE_VERSION,
+        "commitment": &stored.commitment,
+        "program": {
+            "sha256": hex::encode(Sha256::digest(&program_bytes)),
+            "hex": &stored.program_hex,
+        },
+        // What the proof attests: the commitment is SHA256 of the
+        // statement, whose canonical encoding is `statement.hex`.
+        "claim": {
+            "outcome": stored.outcome,
+            "result": &stored.result,
+            "error_kind": &stored.error_kind,
+        },
+        "statement": stored.statement().map(|statement| serde_json::json!({
+            "hex": hex::encode(statement.encode()),
+            "text": statement.to_string(),
+        })),
+        "proof": {
+            "version": &stored.proof_version,
+            "app_exe_commit": &stored.app_exe_commit,
+            "app_vm_commit": &stored.app_vm_commit,
+            // gzip of {"stark_proof_json", "verification_baseline_json"}.
+            "encoding": "gzip+base64",
+            "data": compressed_proof,
+        },
+        "verifying_key": {
+            "url": "/data/agg_stark.vk",
+            "immutable_url": format!("/data/vk/{}", vk_sha256),
+            "sha256": vk_sha256,
+        },
+        "metadata": {
+            "openvm_version": &stored.openvm_version,
+            "served_by_openvm_version": openvm_version_tag(),
+            "proved_at": stored.proved_at,
+            "guest_build": &stored.guest_build,
+        },
+    }))
+}
+
+fn bundle_error(response: &mut actix_web::HttpResponseBuilder, error: String) -> HttpResponse {
+    response.json(serde_json::json!({ "error": error }))
+}
+
+/// GET /api/proofs/{commitment}/bundle
+///
+/// The stored proof, gzip-compressed, with the claim it attests to and a
+/// reference to the aggregation verifying key it verifies against. Served
+/// with an `ETag` and `Range` support, see `downloads`.
+pub(crate) async fn proof_bundle(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    commitment: web::Path<String>,
+) -> HttpResponse {
+    let Some(store) = &data.proof_store else {
+        return bundle_error(
+            &mut HttpResponse::NotFound(),
+            "Proof store is disabled. Set PROOF_STORE_DIR to enable it.".into(),
+        );
+    };
+    let commitment = commitment.into_inner().to_ascii_lowercase();
+    let Some(path) = store.path(&commitment) else {
+        return bundle_error(
+            &mut HttpResponse::BadRequest(),
+            "Commitment must be 32 bytes of hex".into(),
+        );
+    };
+
+    let stored = match tokio::fs::read(&path).await {
+        Ok(bytes) => match serde_json::from_slice::<StoredProof>(&bytes) {
+            Ok(stored) => StoredProof {
+                content_hash: hex::encode(Sha256::digest(&bytes)),
+                ..stored
+            },
+            Err(e) => {
+                error!("Corrupt stored proof {}: {}", path.display(), e);
+                return bundle_error(
+                    &mut HttpResponse::InternalServerError(),
+                    format!("Stored proof is unreadable: {}", e),
+                );
+            }
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            match fetch_replica(&data, &commitment).await {
+                Some(stored) => stored,
+                None => {
+                    return bundle_error(
+                        &mut HttpResponse::NotFound(),
+                        format!("No stored proof for commitment {}", commitment),
+                    );
+                }
+            }
+        }
+        Err(e) => {
+            return bundle_error(
+                &mut HttpResponse::InternalServerError(),
+                format!("Failed to read stored proof: {}", e),
+            );
+        }
+    };
+    serve_bundle(&req, &data, &stored, "no-cache").await
+}
+
+/// The proof of `commitment` from the replication backends, if one has it.
+async fn fetch_replica(data: &AppState, commitment: &str) -> Option<StoredProof> {
+    let replicator = data.replicator.clone()?;
+    let commitment = commitment.to_string();
+    web::block(move || replicator.fetch(&commitment))
+        .await
+        .ok()
+        .flatten()
+}
+
+/// GET /api/proofs/by-hash/{hash}
+///
+/// The bundle of the stored proof whose file has SHA256 `hash`, as listed
+/// in `immutable_bundle_url`. The URL only ever serves that proof, so it is
+/// cached as immutable, e.g. by a CDN; once the proof is overwritten the URL
+/// serves `404` rather than another proof.
+pub(crate) async fn proof_bundle_by_hash(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    hash: web::Path<String>,
+) -> HttpResponse {
+    let Some(store) = data.proof_store.clone() else {
+        return bundle_error(
+            &mut HttpResponse::NotFound(),
+            "Proof store is disabled. Set PROOF_STORE_DIR to enable it.".into(),
+        );
+    };
+    let hash = hash.into_inner().to_ascii_lowercase();
+    if hash.len() != 64 || !hash.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return bundle_error(
+            &mut HttpResponse::BadRequest(),
+            "Hash must be 32 bytes of hex".into(),
+        );
+    }
+
+    let lookup = hash.clone();
+    match web::block(move || store.by_content_hash(&lookup)).await {
+        Ok(Ok(Some(stored))) => serve_bundle(&req, &data, &stored, IMMUTABLE).await,
+        Ok(Ok(None)) => bundle_error(
+            &mut HttpResponse::NotFound(),
+            format!("No stored proof with hash {}", hash),
+        ),
+        Ok(Err(e)) => bundle_error(
+            &mut HttpResponse::InternalServerError(),
+            format!("Failed to read stored proof: {}", e),
+        ),
+        Err(e) => bundle_error(
+            &mut HttpResponse::InternalServerError(),
+            format!("Failed to read stored proof: {}", e),
+        ),
+    }
+}
+
+/// The bundle of `stored`, with `cache_control`.
+async fn serve_bundle(
+    req: &HttpRequest,
+    data: &AppState,
+    stored: &StoredProof,
+    cache_control: &str,
+) -> HttpResponse {
+    let vk_sha256 = match crate::downloads::file_sha256(&data.paths.agg_vk_path).await {
+        Ok(sha256) => sha256,
+        Err(e) => {
+            return bundle_error(
+                &mut HttpResponse::InternalServerError(),
+                format!("Failed to read agg_stark.vk: {}", e),
+            );
+        }
+    };
+
+    match bundle_json(stored, &vk_sha256).and_then(|bundle| Ok(serde_json::to_vec(&bundle)?)) {
+        Ok(bundle) => Download::new(bundle).respond(req, "application/json", cache_control),
+        Err(e) => bundle_error(
+            &mut HttpResponse::InternalServerError(),
+            format!("Failed to encode bundle: {}", e),
+        ),
+    }
+}
+
+/// Filters of `GET /api/proofs`; proofs must match all of them.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ProofQuery {
+    commitment: Option<String>,
+    /// PlutusV3 script hash (blake2b-224) of the program.
+    script_hash: Option<String>,
+    app_exe_commit: Option<String>,
+    /// Most proofs returned (default 100, at most 1000).
+    limit: Option<usize>,
+}
+
+/// A `ProofQuery` with its values normalized to index entry names.
+#[derive(Debug)]
+struct ProofFilters {
+    commitment: Option<String>,
+    indexed: Vec<(IndexKey, String)>,
+    limit: usize,
+}
+
+impl ProofQuery {
+    /// The filters of this query; an error if a value is not valid hex.
+    fn filters(&self) -> Result<ProofFilters, String> {
+        let commitment = match &self.commitment {
+            Some(commitment) => Some(
+                index_value(commitment)
+                    .filter(|commitment| commitment.len() == 64)
+                    .ok_or("commitment must be 32 bytes of hex")?,
+            ),
+            None => None,
+        };
+        let mut indexed = Vec::new();
+        for (key, value) in [
+            (IndexKey::ScriptHash, &self.script_hash),
+            (IndexKey::AppExeCommit, &self.app_exe_commit),
+        ] {
+            if let Some(value) = value {
+                let value =
+                    index_value(value).ok_or_else(|| format!("{} must be hex", key.dir_name()))?;
+                indexed.push((key, value));
+            }
+        }
+        Ok(ProofFilters {
+            commitment,
+            indexed,
+            limit: self.limit.unwrap_or(100).min(MAX_SEARCH_RESULTS),
+        })
+    }
+}
+
+/// A stored proof as listed by `GET /api/proofs`; the proof itself is in
+/// its bundle.
+#[derive(Debug, Serialize)]
+struct ProofSummary {
+    commitment: String,
+    script_hash: String,
+    outcome: Outcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<String>,
+    app_exe_commit: String,
+    openvm_version: String,
+    proved_at: u64,
+    bundle_url: String,
+    /// The bundle at a URL that only ever serves this proof.
+    immutable_bundle_url: String,
+}
+
+/// GET /api/proofs?script_hash=...&app_exe_commit=...&commitment=...&limit=...
+///
+/// Stored proofs matching every given filter, most recent first; without
+/// filters, the most recent proofs.
+pub(crate) async fn list_proofs(
+    data: web::Data<AppState>,
+    query: web::Query<ProofQuery>,
+) -> HttpResponse {
+    let Some(store) = data.proof_store.clone() else {
+        return bundle_error(
+            &mut HttpResponse::NotFound(),
+            "Proof store is disabled. Set PROOF_STORE_DIR to enable it.".into(),
+        );
+    };
+    let filters = match query.filters() {
+        Ok(filters) => filters,
+        Err(e) => return bundle_error(&mut HttpResponse::BadRequest(), e),
+    };
+    match web::block(move || store.search(&filters)).await {
+        Ok(Ok(proofs)) => HttpResponse::Ok().json(serde_json::json!({ "proofs": proofs })),
+        Ok(Err(e)) => bundle_error(
+            &mut HttpResponse::InternalServerError(),
+            format!("Failed to search the proof store: {}", e),
+        ),
+        Err(e) => bundle_error(
+            &mut HttpResponse::InternalServerError(),
+            format!("Internal error: {}", e),
+        ),
+    }
+}
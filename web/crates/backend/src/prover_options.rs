@@ -0,0 +1,159 @@
+//! Per-request proving parameters.
+//!
+//! A `/api/prove` request may carry a `prover_options` object choosing how
+//! far the proof is taken (app-level STARKs for fast iteration, or the full
+//! aggregation for production proofs), whether the proof is returned gzipped
+//! and at which level, what the guest reveals, and which commitment format is
+//! expected. Options are checked against the server policy before any guest
+//! work: `PROOF_LEVELS` lists the levels this server proves (default
+//! `aggregated,app`) and `MAX_PROOF_COMPRESSION_LEVEL` caps the gzip level
+//! (default 9).
+
+use serde::{Deserialize, Serialize};
+
+/// How far a proof is taken.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ProofLevel {
+    /// The full aggregation: one STARK checked against `agg_stark.vk`.
+    #[default]
+    Aggregated,
+    /// App-level proofs only, one STARK per segment, checked against the app
+    /// verifying key. Much faster, but not accepted by the browser verifier.
+    App,
+}
+
+impl ProofLevel {
+    const ALL: [Self; 2] = [Self::Aggregated, Self::App];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Aggregated => "aggregated",
+            Self::App => "app",
+        }
+    }
+}
+
+/// What the guest reveals about the program's evaluation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RevealMode {
+    /// Only successful results; failing programs are rejected.
+    Result,
+    /// The outcome, including the failure kind of failing programs (the same
+    /// as `prove_failures`).
+    Outcome,
+}
+
+/// The `prover_options` of a prove request; unset fields keep the defaults.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub(crate) struct ProverOptions {
+    #[serde(default)]
+    level: Option<ProofLevel>,
+    /// Gzip level (0-9) of the proof; unset returns it as plain JSON.
+    #[serde(default)]
+    compression_level: Option<u32>,
+    #[serde(default)]
+    reveal: Option<RevealMode>,
+    /// Commitment format the client expects, see
+    /// `guest_protocol::COMMITMENT_VERSION`.
+    #[serde(default)]
+    commitment_version: Option<u32>,
+}
+
+/// Proving parameters of one request, once checked against the policy.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ResolvedOptions {
+    pub(crate) level: ProofLevel,
+    pub(crate) compression_level: Option<u32>,
+    pub(crate) mode: openvm_prover::ExecutionMode,
+}
+
+/// Which prover options this server accepts.
+#[derive(Debug, Clone)]
+pub(crate) struct ProverPolicy {
+    levels: Vec<ProofLevel>,
+    max_compression_level: u32,
+}
+
+impl ProverPolicy {
+    pub(crate) fn from_env() -> eyre::Result<Self> {
+        let levels = match std::env::var("PROOF_LEVELS") {
+            Ok(value) if !value.trim().is_empty() => value
+                .split(',')
+                .map(|label| {
+                    let label = label.trim();
+                    ProofLevel::ALL
+                        .into_iter()
+                        .find(|level| level.label() == label)
+                        .ok_or_else(|| {
+                            eyre::eyre!("Unknown proof level {:?} in PROOF_LEVELS", label)
+                        })
+                })
+                .collect::<eyre::Result<_>>()?,
+            _ => ProofLevel::ALL.to_vec(),
+        };
+        let max_compression_level = std::env::var("MAX_PROOF_COMPRESSION_LEVEL")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(9)
+            .min(9);
+        Ok(Self {
+            levels,
+            max_compression_level,
+        })
+    }
+
+    /// Check `options` against the policy. `prove_failures` is the request's
+    /// own flag, which `reveal` must agree with.
+    pub(crate) fn resolve(
+        &self,
+        options: Option<&ProverOptions>,
+        prove_failures: bool,
+    ) -> Result<ResolvedOptions, String> {
+        let default = ProverOptions::default();
+        let options = options.unwrap_or(&default);
+
+        let level = options.level.unwrap_or_default();
+        if !self.levels.contains(&level) {
+            return Err(format!(
+                "Proof level {} is disabled on this server",
+                level.label()
+            ));
+        }
+        if let Some(compression_level) = options.compression_level {
+            if compression_level > self.max_compression_level {
+                return Err(format!(
+                    "Compression level {} exceeds this server's maximum of {}",
+                    compression_level, self.max_compression_level
+                ));
+            }
+        }
+        let prove_failures = match (options.reveal, prove_failures) {
+            (Some(RevealMode::Result), true) => {
+                return Err("reveal \"result\" contradicts prove_failures".into());
+            }
+            (Some(reveal), _) => reveal == RevealMode::Outcome,
+            (None, prove_failures) => prove_failures,
+        };
+        if let Some(version) = options.commitment_version {
+            if version != guest_protocol::COMMITMENT_VERSION {
+                return Err(format!(
+                    "Commitment version {} is not supported, this server produces version {}",
+                    version,
+                    guest_protocol::COMMITMENT_VERSION
+                ));
+            }
+        }
+
+        Ok(ResolvedOptions {
+            level,
+            compression_level: options.compression_level,
+            mode: if prove_failures {
+                openvm_prover::ExecutionMode::ProveFailures
+            } else {
+                openvm_prover::ExecutionMode::Strict
+            },
+        })
+    }
+}
@@ -0,0 +1,240 @@
+//! Proving queue and request timelines.
+//!
+//! A proof holds tens of GB while it runs, so `MAX_CONCURRENT_PROOFS` limits
+//! how many run at once (unset or 0: no limit; anything but a number fails
+//! startup); further requests wait for a slot in arrival order. Successful
+//! prove responses carry a `timeline` of when the request was accepted, when
+//! proving started and finished, how its queue position evolved, and how long
+//! each phase took, so clients and dashboards can tell queue latency from
+//! proving latency.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Requests waiting for a proving slot.
+#[derive(Debug)]
+pub(crate) struct ProveQueue {
+    /// `None` lets every request prove at once.
+    max_running: Option<usize>,
+    state: Mutex<QueueState>,
+    changed: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+    next_ticket: u64,
+    /// Tickets of waiting requests, first in line first.
+    waiting: VecDeque<u64>,
+    running: usize,
+}
+
+/// A proving slot, released when dropped.
+pub(crate) struct ProveSlot<'a> {
+    queue: &'a ProveQueue,
+}
+
+impl Drop for ProveSlot<'_> {
+    fn drop(&mut self) {
+        let mut state = self.queue.state.lock().expect("queue lock poisoned");
+        state.running -= 1;
+        self.queue.changed.notify_all();
+    }
+}
+
+impl ProveQueue {
+    pub(crate) fn from_env() -> eyre::Result<Self> {
+        let value = std::env::var("MAX_CONCURRENT_PROOFS").ok();
+        Ok(Self::new(max_running(value.as_deref())?))
+    }
+
+    fn new(max_running: Option<usize>) -> Self {
+        Self {
+            max_running,
+            state: Mutex::default(),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// Wait for a proving slot, recording each queue position in `timeline`.
+    ///
+    /// Blocking, so call from `web::block`.
+    pub(crate) fn acquire(&self, timeline: &mut Timeline) -> ProveSlot<'_> {
+        let mut state = self.state.lock().expect("queue lock poisoned");
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+        state.waiting.push_back(ticket);
+        let waiting_since = Instant::now();
+        loop {
+            let position = state
+                .waiting
+                .iter()
+                .position(|&waiting| waiting == ticket)
+                .expect("waiting ticket is queued");
+            timeline.record_position(position);
+            if position == 0 && self.max_running.is_none_or(|max| state.running < max) {
+                state.waiting.pop_front();
+                state.running += 1;
+                // Everyone behind moved up one place.
+                self.changed.notify_all();
+                break;
+            }
+            state = self.changed.wait(state).expect("queue lock poisoned");
+        }
+        timeline.queue_secs = Some(waiting_since.elapsed().as_secs_f64());
+        timeline.started_at = Some(unix_millis(SystemTime::now()));
+        ProveSlot { queue: self }
+    }
+}
+
+/// The limit a `MAX_CONCURRENT_PROOFS` value sets, `None` for no limit.
+fn max_running(value: Option<&str>) -> eyre::Result<Option<usize>> {
+    match value.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(value) => value
+            .parse::<usize>()
+            .map(|max_running| (max_running > 0).then_some(max_running))
+            .map_err(|_| eyre::eyre!("Invalid MAX_CONCURRENT_PROOFS: {}", value)),
+    }
+}
+
+/// When a prove request went through each stage. Times are Unix
+/// milliseconds, durations seconds.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Timeline {
+    /// When the request was accepted.
+    pub(crate) queued_at: u64,
+    /// When proving started, after waiting for a slot.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) started_at: Option<u64>,
+    /// When the response was ready.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) finished_at: Option<u64>,
+    /// Every position the request held in the queue, `0` being next.
+    pub(crate) queue_positions: Vec<QueuePosition>,
+    /// Guest execution before proving.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) execute_secs: Option<f64>,
+    /// Waiting for a proving slot.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) queue_secs: Option<f64>,
+    /// Proving, including retries.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) prove_secs: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub(crate) struct QueuePosition {
+    pub(crate) at: u64,
+    pub(crate) position: usize,
+}
+
+impl Timeline {
+    pub(crate) fn new() -> Self {
+        Self {
+            queued_at: unix_millis(SystemTime::now()),
+            started_at: None,
+            finished_at: None,
+            queue_positions: Vec::new(),
+            execute_secs: None,
+            queue_secs: None,
+            prove_secs: None,
+        }
+    }
+
+    fn record_position(&mut self, position: usize) {
+        if self.queue_positions.last().map(|last| last.position) != Some(position) {
+            self.queue_positions.push(QueuePosition {
+                at: unix_millis(SystemTime::now()),
+                position,
+            });
+        }
+    }
+
+    pub(crate) fn finish(mut self) -> Self {
+        self.finished_at = Some(unix_millis(SystemTime::now()));
+        self
+    }
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{mpsc, Arc};
+    use std::time::Duration;
+
+    #[test]
+    fn parses_the_limit() {
+        assert_eq!(max_running(None).unwrap(), None);
+        assert_eq!(max_running(Some("")).unwrap(), None);
+        assert_eq!(max_running(Some("0")).unwrap(), None);
+        assert_eq!(max_running(Some(" 2 ")).unwrap(), Some(2));
+        assert!(max_running(Some("two")).is_err());
+        assert!(max_running(Some("-1")).is_err());
+    }
+
+    #[test]
+    fn releases_slots_and_wakes_waiters_in_order() {
+        let queue = Arc::new(ProveQueue::new(Some(1)));
+        let slot = queue.acquire(&mut Timeline::new());
+
+        let (sender, started) = mpsc::channel();
+        let waiters: Vec<_> = (0..2)
+            .map(|waiter| {
+                let handle = {
+                    let queue = queue.clone();
+                    let sender = sender.clone();
+                    std::thread::spawn(move || {
+                        let mut timeline = Timeline::new();
+                        let _slot = queue.acquire(&mut timeline);
+                        sender.send(waiter).unwrap();
+                        timeline
+                    })
+                };
+                // Queue the waiters one after the other, so their order is known.
+                while queue.state.lock().unwrap().waiting.len() <= waiter {
+                    std::thread::yield_now();
+                }
+                handle
+            })
+            .collect();
+
+        assert!(started.recv_timeout(Duration::from_millis(100)).is_err());
+        drop(slot);
+        assert_eq!(started.recv_timeout(Duration::from_secs(10)), Ok(0));
+        assert_eq!(started.recv_timeout(Duration::from_secs(10)), Ok(1));
+
+        let positions: Vec<Vec<usize>> = waiters
+            .into_iter()
+            .map(|waiter| {
+                let timeline = waiter.join().unwrap();
+                assert!(timeline.started_at.is_some());
+                timeline
+                    .queue_positions
+                    .iter()
+                    .map(|position| position.position)
+                    .collect()
+            })
+            .collect();
+        assert_eq!(positions, [vec![0], vec![1, 0]]);
+        assert_eq!(queue.state.lock().unwrap().running, 0);
+    }
+
+    #[test]
+    fn never_waits_without_a_limit() {
+        let queue = ProveQueue::new(None);
+        let slots: Vec<_> = (0..3)
+            .map(|_| queue.acquire(&mut Timeline::new()))
+            .collect();
+        assert_eq!(queue.state.lock().unwrap().running, 3);
+        drop(slots);
+        assert_eq!(queue.state.lock().unwrap().running, 0);
+    }
+}
@@ -0,0 +1,445 @@
+//! Replication of stored proofs to other storage, so proofs generated in one
+//! region can be served by backends in others.
+//!
+//! Every proof saved to `PROOF_STORE_DIR` is queued for replication to the
+//! configured [`ProofBackend`]s: `PROOF_REPLICA_DIR`, a directory such as a
+//! network mount, and `PROOF_REPLICA_S3_BUCKET`, an S3 bucket (`s3`
+//! feature). Replication runs on its own thread, so `/api/prove` never waits
+//! for it, and failed uploads are retried with backoff. A bundle requested
+//! for a commitment missing from the local store is fetched from the
+//! backends and stored locally first. Each upload, failure and fetch is
+//! logged and kept as a [`ReplicationEvent`] for `GET /api/replication`.
+
+use crate::proofs::{ProofStore, StoredProof};
+use crate::AppState;
+use actix_web::{web, HttpResponse};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+#[cfg(feature = "s3")]
+mod s3;
+
+/// Events kept for `GET /api/replication`, oldest dropped first.
+const MAX_EVENTS: usize = 100;
+
+/// Storage proofs are replicated to. Proof files are stored byte for byte
+/// under `<commitment>.json`.
+pub(crate) trait ProofBackend: Send + Sync + fmt::Debug {
+    /// Where the backend stores proofs, for logs and events.
+    fn location(&self) -> String;
+
+    /// Store the file `name`, replacing any previous version.
+    ///
+    /// Blocking: called on the replication thread or from `web::block`.
+    fn put(&self, name: &str, bytes: &[u8]) -> Result<(), String>;
+
+    /// The file `name`, or `None` if the backend has none.
+    ///
+    /// Blocking: called on the replication thread or from `web::block`.
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>, String>;
+}
+
+/// A directory, typically a network file system shared between regions.
+#[derive(Debug)]
+struct DirectoryBackend {
+    dir: PathBuf,
+}
+
+impl ProofBackend for DirectoryBackend {
+    fn location(&self) -> String {
+        self.dir.display().to_string()
+    }
+
+    fn put(&self, name: &str, bytes: &[u8]) -> Result<(), String> {
+        let path = self.dir.join(name);
+        std::fs::create_dir_all(&self.dir)
+            .and_then(|()| {
+                // Write then rename, so readers never see a partial proof.
+                let partial = path.with_extension("json.partial");
+                std::fs::write(&partial, bytes)?;
+                std::fs::rename(&partial, &path)
+            })
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>, String> {
+        let path = self.dir.join(name);
+        match std::fs::read(&path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum EventKind {
+    /// A stored proof was uploaded to the backend.
+    Replicated,
+    /// Uploading failed after every retry.
+    Failed,
+    /// A proof missing locally was fetched from the backend.
+    Fetched,
+}
+
+/// Something replication did with one proof and one backend.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ReplicationEvent {
+    kind: EventKind,
+    commitment: String,
+    backend: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// Unix timestamp (seconds).
+    at: u64,
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Replicates the proof store to its backends.
+pub(crate) struct Replicator {
+    store: ProofStore,
+    backends: Vec<Arc<dyn ProofBackend>>,
+    /// Attempts after the first failed upload.
+    retries: u32,
+    jobs: mpsc::Sender<Job>,
+    /// Proofs queued or being uploaded.
+    pending: Arc<AtomicUsize>,
+    events: Arc<Mutex<VecDeque<ReplicationEvent>>>,
+}
+
+impl fmt::Debug for Replicator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Replicator")
+            .field("backends", &self.backends)
+            .field("retries", &self.retries)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Replicator {
+    /// Replicator of `proof_store` to the backends configured by
+    /// `PROOF_REPLICA_DIR` and `PROOF_REPLICA_S3_BUCKET`, or `None` when
+    /// neither is set. Failed uploads are retried `PROOF_REPLICATION_RETRIES`
+    /// times (default 3).
+    pub(crate) fn from_env(proof_store: Option<&ProofStore>) -> eyre::Result<Option<Self>> {
+        let mut backends: Vec<Arc<dyn ProofBackend>> = Vec::new();
+        if let Some(dir) = std::env::var_os("PROOF_REPLICA_DIR").filter(|dir| !dir.is_empty()) {
+            backends.push(Arc::new(DirectoryBackend { dir: dir.into() }));
+        }
+        #[cfg(feature = "s3")]
+        if let Some(bucket) = s3::S3Backend::from_env()? {
+            backends.push(Arc::new(bucket));
+        }
+        #[cfg(not(feature = "s3"))]
+        if std::env::var("PROOF_REPLICA_S3_BUCKET").is_ok_and(|bucket| !bucket.is_empty()) {
+            warn!(
+                "PROOF_REPLICA_S3_BUCKET is set but the backend was built without the s3 feature"
+            );
+        }
+        if backends.is_empty() {
+            return Ok(None);
+        }
+        let Some(proof_store) = proof_store else {
+            eyre::bail!("Proof replication requires PROOF_STORE_DIR");
+        };
+        let retries = match std::env::var("PROOF_REPLICATION_RETRIES") {
+            Ok(value) if !value.is_empty() => value
+                .parse()
+                .map_err(|_| eyre::eyre!("Invalid PROOF_REPLICATION_RETRIES: {}", value))?,
+            _ => 3,
+        };
+        Self::new(proof_store.clone(), backends, retries).map(Some)
+    }
+
+    fn new(
+        store: ProofStore,
+        backends: Vec<Arc<dyn ProofBackend>>,
+        retries: u32,
+    ) -> eyre::Result<Self> {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        std::thread::Builder::new()
+            .name("proof-replication".into())
+            .spawn(move || {
+                for job in queue {
+                    job();
+                }
+            })
+            .map_err(|e| eyre::eyre!("Failed to start the replication thread: {}", e))?;
+        Ok(Self {
+            store,
+            backends,
+            retries,
+            jobs,
+            pending: Arc::new(AtomicUsize::new(0)),
+            events: Arc::new(Mutex::new(VecDeque::new())),
+        })
+    }
+
+    /// Where proofs are replicated to.
+    pub(crate) fn locations(&self) -> Vec<String> {
+        self.backends
+            .iter()
+            .map(|backend| backend.location())
+            .collect()
+    }
+
+    /// Queue the stored proof of `commitment` for upload to every backend.
+    pub(crate) fn replicate(&self, commitment: &str) {
+        let store = self.store.clone();
+        let backends = self.backends.clone();
+        let retries = self.retries;
+        let pending = self.pending.clone();
+        let events = self.events.clone();
+        let commitment = commitment.to_string();
+        pending.fetch_add(1, Ordering::Relaxed);
+        let job: Job = Box::new(move || {
+            match store.file(&commitment) {
+                Ok(Some(bytes)) => {
+                    for backend in &backends {
+                        let uploaded = upload(backend.as_ref(), &commitment, &bytes, retries);
+                        record(&events, &commitment, backend.as_ref(), uploaded);
+                    }
+                }
+                Ok(None) => warn!("Stored proof {} vanished before replication", commitment),
+                Err(e) => error!("Failed to read stored proof {}: {}", commitment, e),
+            }
+            pending.fetch_sub(1, Ordering::Relaxed);
+        });
+        // The thread only exits once the replicator, and with it this
+        // sender, is gone.
+        self.jobs
+            .send(job)
+            .expect("replication thread outlives its replicator");
+    }
+
+    /// Fetch the proof of `commitment` from the first backend that has it,
+    /// and store it locally. `None` if no backend has a readable copy.
+    ///
+    /// Blocking: call from `web::block`.
+    pub(crate) fn fetch(&self, commitment: &str) -> Option<StoredProof> {
+        let name = format!("{}.json", commitment);
+        for backend in &self.backends {
+            let bytes = match backend.get(&name) {
+                Ok(Some(bytes)) => bytes,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Failed to fetch proof {}: {}", commitment, e);
+                    continue;
+                }
+            };
+            match self.store.import(commitment, &bytes) {
+                Ok(Some(stored)) => {
+                    info!("Fetched proof {} from {}", commitment, backend.location());
+                    self.push(ReplicationEvent::new(
+                        EventKind::Fetched,
+                        commitment,
+                        backend.as_ref(),
+                        None,
+                    ));
+                    return Some(stored);
+                }
+                Ok(None) => warn!(
+                    "Ignoring unreadable proof {} in {}",
+                    commitment,
+                    backend.location()
+                ),
+                Err(e) => {
+                    error!("Failed to store fetched proof {}: {}", commitment, e);
+                    return None;
+                }
+            }
+        }
+        None
+    }
+
+    fn push(&self, event: ReplicationEvent) {
+        push_event(&self.events, event);
+    }
+}
+
+impl ReplicationEvent {
+    fn new(
+        kind: EventKind,
+        commitment: &str,
+        backend: &dyn ProofBackend,
+        error: Option<String>,
+    ) -> Self {
+        Self {
+            kind,
+            commitment: commitment.to_string(),
+            backend: backend.location(),
+            error,
+            at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+        }
+    }
+}
+
+/// Upload a proof, retrying failures after 1, 2, 4... seconds, at most a
+/// minute apart.
+fn upload(
+    backend: &dyn ProofBackend,
+    commitment: &str,
+    bytes: &[u8],
+    retries: u32,
+) -> Result<(), String> {
+    let name = format!("{}.json", commitment);
+    let mut attempt = 0;
+    loop {
+        match backend.put(&name, bytes) {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt < retries => {
+                let delay = Duration::from_secs(1 << attempt.min(6)).min(Duration::from_secs(60));
+                attempt += 1;
+                warn!(
+                    "Replicating proof {} to {} failed, retry {}/{} in {:?}: {}",
+                    commitment,
+                    backend.location(),
+                    attempt,
+                    retries,
+                    delay,
+                    e
+                );
+                std::thread::sleep(delay);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn record(
+    events: &Mutex<VecDeque<ReplicationEvent>>,
+    commitment: &str,
+    backend: &dyn ProofBackend,
+    uploaded: Result<(), String>,
+) {
+    let event = match uploaded {
+        Ok(()) => {
+            info!("Replicated proof {} to {}", commitment, backend.location());
+            ReplicationEvent::new(EventKind::Replicated, commitment, backend, None)
+        }
+        Err(e) => {
+            error!(
+                "Failed to replicate proof {} to {}: {}",
+                commitment,
+                backend.location(),
+                e
+            );
+            ReplicationEvent::new(EventKind::Failed, commitment, backend, Some(e))
+        }
+    };
+    push_event(events, event);
+}
+
+fn push_event(events: &Mutex<VecDeque<ReplicationEvent>>, event: ReplicationEvent) {
+    let mut events = events.lock().expect("replication events poisoned");
+    if events.len() == MAX_EVENTS {
+        events.pop_front();
+    }
+    events.push_back(event);
+}
+
+/// GET /api/replication
+///
+/// Where proofs are replicated to, how many are waiting, and the most
+/// recent replication events, newest first.
+pub(crate) async fn status(data: web::Data<AppState>) -> HttpResponse {
+    let Some(replicator) = &data.replicator else {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Proof replication is disabled. Set PROOF_REPLICA_DIR or PROOF_REPLICA_S3_BUCKET to enable it."
+        }));
+    };
+    let events: Vec<ReplicationEvent> = replicator
+        .events
+        .lock()
+        .expect("replication events poisoned")
+        .iter()
+        .rev()
+        .cloned()
+        .collect();
+    HttpResponse::Ok().json(serde_json::json!({
+        "backends": replicator.locations(),
+        "pending": replicator.pending.load(Ordering::Relaxed),
+        "events": events,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::scratch_dir;
+
+    #[derive(Debug)]
+    struct FlakyBackend {
+        failures: Mutex<u32>,
+    }
+
+    impl ProofBackend for FlakyBackend {
+        fn location(&self) -> String {
+            "flaky".into()
+        }
+
+        fn put(&self, _name: &str, _bytes: &[u8]) -> Result<(), String> {
+            let mut failures = self.failures.lock().unwrap();
+            if *failures == 0 {
+                return Ok(());
+            }
+            *failures -= 1;
+            Err("unavailable".into())
+        }
+
+        fn get(&self, _name: &str) -> Result<Option<Vec<u8>>, String> {
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn directory_backend_round_trips() {
+        let backend = DirectoryBackend {
+            dir: scratch_dir("replication", "directory"),
+        };
+        assert_eq!(backend.get("a.json"), Ok(None));
+        backend.put("a.json", b"proof").unwrap();
+        assert_eq!(backend.get("a.json"), Ok(Some(b"proof".to_vec())));
+        let _ = std::fs::remove_dir_all(&backend.dir);
+    }
+
+    #[test]
+    fn upload_retries_until_out_of_attempts() {
+        let backend = FlakyBackend {
+            failures: Mutex::new(1),
+        };
+        assert_eq!(upload(&backend, "ab", b"proof", 1), Ok(()));
+        *backend.failures.lock().unwrap() = 1;
+        assert_eq!(
+            upload(&backend, "ab", b"proof", 0),
+            Err("unavailable".to_string())
+        );
+    }
+
+    #[test]
+    fn keeps_the_latest_events() {
+        let events = Mutex::new(VecDeque::new());
+        let backend = FlakyBackend {
+            failures: Mutex::new(0),
+        };
+        for i in 0..MAX_EVENTS + 1 {
+            record(&events, &i.to_string(), &backend, Ok(()));
+        }
+        let events = events.into_inner().unwrap();
+        assert_eq!(events.len(), MAX_EVENTS);
+        assert_eq!(
+            events.front().map(|event| event.commitment.as_str()),
+            Some("1")
+        );
+    }
+}
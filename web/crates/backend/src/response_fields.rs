@@ -0,0 +1,114 @@
+//! Which optional field groups a `/api/prove` response carries, chosen with
+//! `?include=proof,commits,metadata`.
+//!
+//! Clients that only need the outcome and commitment leave out `proof` and
+//! skip downloading a multi-MB proof they will never verify. The proof is
+//! still stored and pinned as usual, so it can be fetched later from
+//! `/api/proofs/{commitment}/bundle`. Without `include` every group is
+//! returned.
+
+use serde::{Deserialize, Serialize};
+
+/// Query of `/api/prove`.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct ProveQuery {
+    /// Comma-separated field groups; unset includes them all.
+    #[serde(default)]
+    include: Option<String>,
+}
+
+/// The optional field groups of a prove response. `success`, `outcome`,
+/// `outcome_details`, `commitment`, `error`, `proof_withheld` and
+/// `signed_claim` are always returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ResponseFields {
+    /// The proof itself, plain or compressed, and its verification baseline.
+    pub(crate) proof: bool,
+    /// The guest commits, proof version and level, and commitment version.
+    pub(crate) commits: bool,
+    /// Durations, telemetry, timeline, execution report, evaluation check,
+    /// guest build and IPFS CIDs.
+    pub(crate) metadata: bool,
+}
+
+impl Default for ResponseFields {
+    fn default() -> Self {
+        Self {
+            proof: true,
+            commits: true,
+            metadata: true,
+        }
+    }
+}
+
+impl ResponseFields {
+    const GROUPS: [&'static str; 3] = ["proof", "commits", "metadata"];
+
+    /// The groups listed in `query`, every group if it lists none.
+    pub(crate) fn from_query(query: &ProveQuery) -> Result<Self, String> {
+        let Some(include) = &query.include else {
+            return Ok(Self::default());
+        };
+        let mut fields = Self {
+            proof: false,
+            commits: false,
+            metadata: false,
+        };
+        for group in include
+            .split(',')
+            .map(str::trim)
+            .filter(|group| !group.is_empty())
+        {
+            match group {
+                "proof" => fields.proof = true,
+                "commits" => fields.commits = true,
+                "metadata" => fields.metadata = true,
+                other => {
+                    return Err(format!(
+                        "Unknown include {:?}, expected some of: {}",
+                        other,
+                        Self::GROUPS.join(", ")
+                    ))
+                }
+            }
+        }
+        Ok(fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(include: Option<&str>) -> Result<ResponseFields, String> {
+        ResponseFields::from_query(&ProveQuery {
+            include: include.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn includes_every_group_by_default() {
+        assert_eq!(parse(None), Ok(ResponseFields::default()));
+    }
+
+    #[test]
+    fn includes_listed_groups() {
+        assert_eq!(
+            parse(Some("commits, metadata")),
+            Ok(ResponseFields {
+                proof: false,
+                commits: true,
+                metadata: true,
+            })
+        );
+        assert_eq!(
+            parse(Some("")),
+            Ok(ResponseFields {
+                proof: false,
+                commits: false,
+                metadata: false,
+            })
+        );
+        assert!(parse(Some("proof,signature")).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+//! Bounded retries for transient prover failures.
+//!
+//! Proving takes minutes, so a failure caused by the host rather than the
+//! program (memory pressure, exhausted file descriptors, an interrupted
+//! syscall) is retried with exponential backoff and full jitter instead of
+//! failing the request. Failures that come from the program or the keys are
+//! deterministic and are never retried. Configured with `PROVE_RETRIES`
+//! (extra attempts, default 2; 0 disables retries), `PROVE_RETRY_BASE_MS`
+//! (default 1000), and `PROVE_RETRY_MAX_MS` (default 30000); a value that is
+//! not a number fails startup.
+
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use tracing::warn;
+
+/// Error messages of host resource failures reported as plain text, e.g. by
+/// allocators or worker pools.
+const TRANSIENT_MESSAGES: &[&str] = &[
+    "out of memory",
+    "cannot allocate memory",
+    "memory allocation",
+    "resource temporarily unavailable",
+    "too many open files",
+];
+
+/// How failed proving attempts are retried.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RetryPolicy {
+    /// Attempts after the first one.
+    retries: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub(crate) fn from_env() -> eyre::Result<Self> {
+        let var = |name: &str, default: u64| match std::env::var(name) {
+            Ok(value) if !value.trim().is_empty() => value
+                .trim()
+                .parse()
+                .map_err(|_| eyre::eyre!("Invalid {}: {}", name, value)),
+            _ => Ok(default),
+        };
+        Ok(Self {
+            retries: u32::try_from(var("PROVE_RETRIES", 2)?)
+                .map_err(|_| eyre::eyre!("PROVE_RETRIES is too large"))?,
+            base_delay: Duration::from_millis(var("PROVE_RETRY_BASE_MS", 1000)?),
+            max_delay: Duration::from_millis(var("PROVE_RETRY_MAX_MS", 30_000)?),
+        })
+    }
+
+    /// Delay before retry `attempt` (1-based): uniformly random up to
+    /// `base_delay * 2^(attempt - 1)`, capped at `max_delay`.
+    fn delay(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(1 << (attempt - 1).min(16))
+            .min(self.max_delay);
+        // RandomState is seeded randomly per instance; enough for jitter.
+        let random = std::collections::hash_map::RandomState::new()
+            .build_hasher()
+            .finish();
+        ceiling.mul_f64(random as f64 / u64::MAX as f64)
+    }
+
+    /// Run `operation`, retrying transient failures.
+    ///
+    /// Blocking: sleeps between attempts, so call from `web::block`.
+    pub(crate) fn run<T>(
+        &self,
+        label: &str,
+        mut operation: impl FnMut() -> eyre::Result<T>,
+    ) -> eyre::Result<T> {
+        let mut attempt = 0;
+        loop {
+            match operation() {
+                Err(e) if attempt < self.retries && is_transient(&e) => {
+                    attempt += 1;
+                    let delay = self.delay(attempt);
+                    warn!(
+                        "{} failed with a transient error, retry {}/{} in {:.1}s: {:#}",
+                        label,
+                        attempt,
+                        self.retries,
+                        delay.as_secs_f64(),
+                        e
+                    );
+                    std::thread::sleep(delay);
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Whether `error` comes from the host rather than the program or the keys,
+/// so that the same attempt may succeed later.
+fn is_transient(error: &eyre::Report) -> bool {
+    error.chain().any(|cause| {
+        if let Some(io) = cause.downcast_ref::<std::io::Error>() {
+            use std::io::ErrorKind;
+            if matches!(
+                io.kind(),
+                ErrorKind::OutOfMemory
+                    | ErrorKind::Interrupted
+                    | ErrorKind::WouldBlock
+                    | ErrorKind::TimedOut
+            ) {
+                return true;
+            }
+        }
+        let message = cause.to_string().to_ascii_lowercase();
+        TRANSIENT_MESSAGES
+            .iter()
+            .any(|transient| message.contains(transient))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A policy that retries `retries` times without waiting.
+    fn immediate(retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            retries,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn retries_transient_failures_until_they_pass() {
+        let mut attempts = 0;
+        let result = immediate(2).run("test", || {
+            attempts += 1;
+            if attempts < 3 {
+                Err(eyre::eyre!("Cannot allocate memory"))
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[test]
+    fn gives_up_after_the_last_retry() {
+        let mut attempts = 0;
+        let result: eyre::Result<()> = immediate(2).run("test", || {
+            attempts += 1;
+            Err(std::io::Error::from(std::io::ErrorKind::Interrupted).into())
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 3);
+    }
+
+    #[test]
+    fn never_retries_deterministic_failures() {
+        let mut attempts = 0;
+        let result: eyre::Result<()> = immediate(2).run("test", || {
+            attempts += 1;
+            Err(eyre::eyre!("Script evaluation failed"))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn caps_the_backoff() {
+        let policy = RetryPolicy {
+            retries: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(250),
+        };
+        for (attempt, ceiling) in [(1, 100), (2, 200), (3, 250), (20, 250)] {
+            assert!(policy.delay(attempt) <= Duration::from_millis(ceiling));
+        }
+    }
+}
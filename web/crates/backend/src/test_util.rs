@@ -0,0 +1,17 @@
+//! Helpers shared by the unit tests.
+
+use std::path::PathBuf;
+
+/// An empty scratch directory for test `name` of `module`, so tests running
+/// in parallel never share one.
+pub(crate) fn scratch_dir(module: &str, name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "cardano-zkvms-{}-{}-{}",
+        module,
+        std::process::id(),
+        name
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,184 @@
+//! Replay corpus: programs that crashed the guest or the prover.
+//!
+//! When `TRIAGE_DIR` is set, every guest or prover failure is saved there as
+//! `<sha256 of the program>-<mode>.json`, holding the program and the error.
+//! `cardano-zkvms triage replay` re-runs the saved programs locally, so a
+//! production failure becomes a reproducible test case. Script failures
+//! (decode errors, evaluation errors, budget exhaustion) are expected outcomes
+//! and are not recorded. `cardano-zkvms triage minimize` shrinks a failing
+//! program, recorded or not, to a small reproducer.
+
+use crate::{openvm_version_tag, ArtifactPaths, PipelineStage};
+use openvm_prover::ExecutionMode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{error, info};
+
+mod minimize;
+
+pub(crate) use minimize::cmd_minimize;
+
+/// A failing program, as saved to the triage directory.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct TriageRecord {
+    /// Hex-encoded flat UPLC program
+    program_hex: String,
+    /// Whether the program ran in `ProveFailures` mode.
+    prove_failures: bool,
+    /// Endpoint that hit the failure (`prove`, `prove/mcu-halo2`).
+    endpoint: String,
+    /// Pipeline stage that failed, when known.
+    stage: Option<PipelineStage>,
+    error: String,
+    openvm_version: String,
+    /// Unix timestamp (seconds) of the failure.
+    recorded_at: u64,
+}
+
+impl TriageRecord {
+    pub(crate) fn new(
+        program_bytes: &[u8],
+        mode: ExecutionMode,
+        endpoint: &str,
+        stage: Option<PipelineStage>,
+        error: &str,
+    ) -> Self {
+        Self {
+            program_hex: hex::encode(program_bytes),
+            prove_failures: mode == ExecutionMode::ProveFailures,
+            endpoint: endpoint.to_string(),
+            stage,
+            error: error.to_string(),
+            openvm_version: openvm_version_tag(),
+            recorded_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn mode(&self) -> ExecutionMode {
+        if self.prove_failures {
+            ExecutionMode::ProveFailures
+        } else {
+            ExecutionMode::Strict
+        }
+    }
+
+    /// Content-addressed file name, so repeated failures of the same program
+    /// overwrite a single record with the latest error.
+    fn file_name(&self, program_bytes: &[u8]) -> String {
+        let mode = if self.prove_failures {
+            "prove-failures"
+        } else {
+            "strict"
+        };
+        format!(
+            "{}-{}.json",
+            hex::encode(Sha256::digest(program_bytes)),
+            mode
+        )
+    }
+}
+
+/// Save a failure to `triage_dir`. Best effort: a failure to record is logged
+/// and otherwise ignored, so it never masks the original error.
+pub(crate) fn record_failure(triage_dir: &Path, program_bytes: &[u8], record: TriageRecord) {
+    let path = triage_dir.join(record.file_name(program_bytes));
+    let written = std::fs::create_dir_all(triage_dir).and_then(|()| {
+        let json = serde_json::to_vec_pretty(&record)?;
+        // Write then rename, so replay never reads a partial record.
+        let partial = path.with_extension("json.partial");
+        std::fs::write(&partial, json)?;
+        std::fs::rename(&partial, &path)
+    });
+    match written {
+        Ok(()) => info!("Recorded failing program to {}", path.display()),
+        Err(e) => error!(
+            "Failed to record failing program to {}: {}",
+            path.display(),
+            e
+        ),
+    }
+}
+
+/// `cardano-zkvms triage replay [--prove] [DIR]` — re-run recorded failures.
+///
+/// Programs are executed with the local artifacts; `--prove` also generates a
+/// STARK proof, for failures that only happen while proving. Fails if any
+/// recorded program still fails.
+pub(crate) fn cmd_replay(args: &[String]) -> eyre::Result<()> {
+    let prove = args.iter().any(|arg| arg == "--prove");
+    let triage_dir = args
+        .iter()
+        .find(|arg| !arg.starts_with("--"))
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("TRIAGE_DIR").map(PathBuf::from))
+        .ok_or_else(|| eyre::eyre!("Pass the triage directory or set TRIAGE_DIR"))?;
+
+    let mut records: Vec<PathBuf> = std::fs::read_dir(&triage_dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    records.retain(|path| path.extension().is_some_and(|ext| ext == "json"));
+    records.sort();
+    if records.is_empty() {
+        eprintln!("No recorded failures in {}", triage_dir.display());
+        return Ok(());
+    }
+
+    let paths = ArtifactPaths::from_env(&crate::environment::Environment::from_process())?;
+    let config = openvm_prover::load_config(&paths.config_path)?;
+    let exe = Arc::new(openvm_prover::load_exe(&paths.vmexe_path)?);
+    let prover = if prove {
+        Some(
+            openvm_prover::Prover::builder()
+                .config(config.clone())
+                .exe(exe.clone())
+                .app_pk_path(&paths.app_pk_path)
+                .agg_pk_path(&paths.agg_pk_path)
+                .build()?,
+        )
+    } else {
+        None
+    };
+
+    let mut still_failing = 0;
+    for (index, path) in records.iter().enumerate() {
+        let record: TriageRecord = serde_json::from_slice(&std::fs::read(path)?)?;
+        let program_bytes = hex::decode(&record.program_hex)?;
+        eprintln!(
+            "[{}/{}] {} (recorded on OpenVM {}: {})",
+            index + 1,
+            records.len(),
+            path.display(),
+            record.openvm_version,
+            record.error
+        );
+
+        let result = match &prover {
+            Some(prover) => prover
+                .prove_stark(&program_bytes, record.mode())
+                .map(|result| result.user_public_values),
+            None => openvm_prover::execute(&config, exe.clone(), &program_bytes, record.mode()),
+        };
+        match result {
+            Ok(output) => eprintln!("  Passes now, commitment {}", hex::encode(output)),
+            Err(e) => {
+                still_failing += 1;
+                eprintln!("  Still fails: {:?}", e);
+            }
+        }
+    }
+
+    if still_failing > 0 {
+        eyre::bail!(
+            "{} of {} recorded programs still fail",
+            still_failing,
+            records.len()
+        );
+    }
+    eprintln!("All {} recorded programs pass.", records.len());
+    Ok(())
+}
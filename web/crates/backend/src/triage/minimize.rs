@@ -0,0 +1,305 @@
+//! `cardano-zkvms triage minimize`: shrink a failing program to a small
+//! reproducer.
+//!
+//! Greedy hierarchical delta debugging: walking the term tree from the root,
+//! each term is replaced by one of its children (a lambda by its body, an
+//! application by its function or argument, ...) as long as the program
+//! still fails the same way, until no replacement does. Larger subterms come
+//! first, so most of the program is usually gone after a few steps.
+
+use super::TriageRecord;
+use crate::evaluation_check::{evaluate_on_host, HostEvaluation};
+use crate::ArtifactPaths;
+use guest_protocol::ErrorKind;
+use openvm_prover::ExecutionMode;
+use std::path::Path;
+use std::sync::Arc;
+use uplc_turbo::{arena::Arena, binder::DeBruijn, flat, program::Program, term::Term};
+
+/// How a program fails. Two failures match when their kinds are equal and,
+/// with `--matching`, both messages contain the given text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Failure {
+    /// Failed on the host with this kind, see the guest's `ErrorKind`.
+    Host(ErrorKind),
+    /// The evaluator panicked on the host.
+    HostPanic,
+    /// Guest execution failed.
+    Guest,
+}
+
+/// Evaluate `program` on the host; `None` if it succeeds.
+fn host_failure(program: &[u8]) -> Option<(Failure, String)> {
+    match evaluate_on_host(program) {
+        HostEvaluation::Success(_) => None,
+        HostEvaluation::Failure(kind, message) => Some((Failure::Host(kind), message)),
+        HostEvaluation::Panic(message) => Some((Failure::HostPanic, message)),
+    }
+}
+
+/// Children of `term`, in the order [`with_child`] indexes them.
+fn children<'a>(term: &'a Term<'a, DeBruijn>) -> Vec<&'a Term<'a, DeBruijn>> {
+    match term {
+        Term::Lambda { body, .. } => vec![*body],
+        Term::Apply { function, argument } => vec![*function, *argument],
+        Term::Delay(inner) | Term::Force(inner) => vec![*inner],
+        Term::Case { constr, branches } => std::iter::once(*constr)
+            .chain(branches.iter().copied())
+            .collect(),
+        Term::Constr { fields, .. } => fields.to_vec(),
+        Term::Var(_) | Term::Constant(_) | Term::Builtin(_) | Term::Error => Vec::new(),
+    }
+}
+
+/// Copy of `term` with its `index`th child replaced by `child`.
+fn with_child<'a>(
+    arena: &'a Arena,
+    term: &'a Term<'a, DeBruijn>,
+    index: usize,
+    child: &'a Term<'a, DeBruijn>,
+) -> &'a Term<'a, DeBruijn> {
+    // Slices of terms are copied into the arena, which never drops them;
+    // each candidate has its own arena, so this only lasts one candidate.
+    let replace_in =
+        |terms: &[&'a Term<'a, DeBruijn>], index: usize| -> &'a [&'a Term<'a, DeBruijn>] {
+            let mut terms = terms.to_vec();
+            terms[index] = child;
+            let terms: &'a Vec<_> = arena.alloc(terms);
+            terms.as_slice()
+        };
+    let term = match *term {
+        Term::Lambda { parameter, .. } => Term::Lambda {
+            parameter,
+            body: child,
+        },
+        Term::Apply { function, .. } if index == 1 => Term::Apply {
+            function,
+            argument: child,
+        },
+        Term::Apply { argument, .. } => Term::Apply {
+            function: child,
+            argument,
+        },
+        Term::Delay(_) => Term::Delay(child),
+        Term::Force(_) => Term::Force(child),
+        Term::Case { branches, .. } if index == 0 => Term::Case {
+            constr: child,
+            branches,
+        },
+        Term::Case { constr, branches } => Term::Case {
+            constr,
+            branches: replace_in(branches, index - 1),
+        },
+        Term::Constr { tag, fields } => Term::Constr {
+            tag,
+            fields: replace_in(fields, index),
+        },
+        Term::Var(_) | Term::Constant(_) | Term::Builtin(_) | Term::Error => {
+            unreachable!("terms without children have nothing to replace")
+        }
+    };
+    arena.alloc(term)
+}
+
+/// Copy of `term` with the subterm at `path` (child indexes from `term`)
+/// replaced by `replacement`.
+fn replace_at<'a>(
+    arena: &'a Arena,
+    term: &'a Term<'a, DeBruijn>,
+    path: &[usize],
+    replacement: &'a Term<'a, DeBruijn>,
+) -> &'a Term<'a, DeBruijn> {
+    match path.split_first() {
+        None => replacement,
+        Some((&index, rest)) => {
+            let child = replace_at(arena, children(term)[index], rest, replacement);
+            with_child(arena, term, index, child)
+        }
+    }
+}
+
+fn decode<'a>(arena: &'a Arena, program: &[u8]) -> eyre::Result<&'a Program<'a, DeBruijn>> {
+    flat::decode(arena, program).map_err(|e| eyre::eyre!("Program decode error: {:?}", e))
+}
+
+/// Paths of every term of `program`, parents before their children.
+fn term_paths(program: &[u8]) -> eyre::Result<Vec<Vec<usize>>> {
+    let arena = Arena::new();
+    let program = decode(&arena, program)?;
+    let mut paths = Vec::new();
+    let mut pending = vec![(program.term, Vec::new())];
+    while let Some((term, path)) = pending.pop() {
+        // Pushed in reverse, so children are visited in order.
+        for (index, child) in children(term).into_iter().enumerate().rev() {
+            let mut child_path = path.clone();
+            child_path.push(index);
+            pending.push((child, child_path));
+        }
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// Every program obtained by replacing the term at `path` by a child.
+fn reductions(program: &[u8], path: &[usize]) -> eyre::Result<Vec<Vec<u8>>> {
+    let arena = Arena::new();
+    let decoded = decode(&arena, program)?;
+    let mut target = decoded.term;
+    for &index in path {
+        target = children(target)[index];
+    }
+    children(target)
+        .into_iter()
+        .map(|child| {
+            let term = replace_at(&arena, decoded.term, path, child);
+            let reduced = Program {
+                version: decoded.version,
+                term,
+            };
+            flat::encode(&reduced).map_err(|e| eyre::eyre!("Program encode error: {:?}", e))
+        })
+        .collect()
+}
+
+/// Shrink `program` while `still_fails` holds, returning the smallest
+/// program found. Each accepted step removes at least one term.
+pub(crate) fn minimize(
+    program: &[u8],
+    mut still_fails: impl FnMut(&[u8]) -> bool,
+) -> eyre::Result<Vec<u8>> {
+    let mut current = program.to_vec();
+    'shrink: loop {
+        for path in term_paths(&current)? {
+            for candidate in reductions(&current, &path)? {
+                if still_fails(&candidate) {
+                    current = candidate;
+                    continue 'shrink;
+                }
+            }
+        }
+        return Ok(current);
+    }
+}
+
+/// The program to minimize: hex, a file holding hex, or a triage record.
+fn read_program(arg: &str) -> eyre::Result<Vec<u8>> {
+    let path = Path::new(arg);
+    let hex = if path.extension().is_some_and(|ext| ext == "json") {
+        let record: TriageRecord = serde_json::from_slice(&std::fs::read(path)?)?;
+        record.program_hex
+    } else if path.is_file() {
+        std::fs::read_to_string(path)?
+    } else {
+        arg.to_string()
+    };
+    Ok(hex::decode(hex.trim())?)
+}
+
+/// `cardano-zkvms triage minimize [--guest] [--matching TEXT] PROGRAM` —
+/// print a minimal program that fails like PROGRAM.
+///
+/// PROGRAM is hex, a file holding hex, or a record from the triage
+/// directory. Failures are reproduced by host evaluation, or with `--guest`
+/// by executing the guest with the local artifacts, for bugs the host does
+/// not show. They match when they have the same kind; `--matching` also
+/// requires the error to contain TEXT, so the program does not shrink into a
+/// different bug of the same kind.
+pub(crate) fn cmd_minimize(args: &[String]) -> eyre::Result<()> {
+    let mut guest = false;
+    let mut matching = None;
+    let mut program = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--guest" => guest = true,
+            "--matching" => {
+                matching = Some(
+                    args.next()
+                        .ok_or_else(|| eyre::eyre!("--matching needs a value"))?
+                        .clone(),
+                )
+            }
+            other if program.is_none() && !other.starts_with("--") => {
+                program = Some(read_program(other)?)
+            }
+            other => eyre::bail!("Unexpected argument: {}", other),
+        }
+    }
+    let program =
+        program.ok_or_else(|| eyre::eyre!("Pass the failing program, as hex or a file"))?;
+    let matches = |message: &str| {
+        matching
+            .as_deref()
+            .is_none_or(|text| message.contains(text))
+    };
+
+    let mut fails: Box<dyn FnMut(&[u8]) -> Option<(Failure, String)>> = if guest {
+        let paths = ArtifactPaths::from_env(&crate::environment::Environment::from_process())?;
+        let config = openvm_prover::load_config(&paths.config_path)?;
+        let exe = Arc::new(openvm_prover::load_exe(&paths.vmexe_path)?);
+        Box::new(move |program: &[u8]| {
+            openvm_prover::execute(&config, exe.clone(), program, ExecutionMode::Strict)
+                .err()
+                .map(|e| (Failure::Guest, format!("{:?}", e)))
+        })
+    } else {
+        Box::new(host_failure)
+    };
+
+    let failure = match fails(&program) {
+        Some((failure, message)) if matches(&message) => {
+            eprintln!("Program fails with {:?}: {}", failure, message);
+            failure
+        }
+        Some((_, message)) => eyre::bail!(
+            "The program fails, but not with {:?}: {}",
+            matching.unwrap_or_default(),
+            message
+        ),
+        None => eyre::bail!("The program does not fail"),
+    };
+    if failure == Failure::Host(ErrorKind::Decode) {
+        eyre::bail!("The program does not decode, so it has no terms to shrink");
+    }
+
+    let mut tried = 0;
+    let minimal = minimize(&program, |candidate| {
+        tried += 1;
+        fails(candidate).is_some_and(|(kind, message)| kind == failure && matches(&message))
+    })?;
+    eprintln!(
+        "Shrunk {} bytes to {} after trying {} candidates",
+        program.len(),
+        minimal.len(),
+        tried
+    );
+    println!("{}", hex::encode(minimal));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shrinks_to_the_failing_term() {
+        // [[(builtin addInteger) (con integer 1)] [(lam x (error)) (con integer 2)]]
+        let program = hex::decode("010000337009001193240081").unwrap();
+        let expected = host_failure(&program).unwrap().0;
+        assert_eq!(expected, Failure::Host(ErrorKind::Evaluation));
+
+        let minimal = minimize(&program, |candidate| {
+            host_failure(candidate).is_some_and(|(failure, _)| failure == expected)
+        })
+        .unwrap();
+        // (error)
+        assert_eq!(hex::encode(minimal), "01000061");
+    }
+
+    #[test]
+    fn keeps_programs_that_cannot_shrink() {
+        let program = hex::decode("01000061").unwrap();
+        let minimal = minimize(&program, |_| true).unwrap();
+        assert_eq!(minimal, program);
+    }
+}
@@ -0,0 +1,457 @@
+//! Per-job resource accounting, for operators billing proving by API key.
+//!
+//! With `API_KEYS_FILE` set, `/api/prove` and `/api/prove/mcu-halo2` need an
+//! `X-Api-Key` header holding one of the file's keys, and each job is
+//! attributed to that key's name. With `USAGE_LOG_FILE` set, every prove job
+//! that passes validation appends a [`JobUsage`] line to that file: its
+//! duration, CPU time, peak memory and guest cycles. `GET /api/usage`, behind
+//! the admin token, sums the log per key or exports its jobs as CSV.
+//!
+//! CPU time and peak memory are only recorded for STARK proofs run in a
+//! worker process (`PROVE_TIMEOUT_SECS`), which proves one job at a time and
+//! measures it on its own. In the server process, jobs share the counters
+//! with whatever else runs, so those fields are left unset.
+
+use crate::AppState;
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tracing::error;
+
+pub(crate) const API_KEY_HEADER: &str = "X-Api-Key";
+
+/// What jobs without an API key are totalled under.
+const ANONYMOUS: &str = "anonymous";
+
+/// API keys from `API_KEYS_FILE`, one `<name> <key>` pair per line, by the
+/// SHA-256 of the key so lookups don't depend on how much of a key matched.
+#[derive(Debug)]
+pub(crate) struct ApiKeys(HashMap<[u8; 32], String>);
+
+impl ApiKeys {
+    /// The keys in `API_KEYS_FILE`, or `None` when it is unset.
+    pub(crate) fn from_env() -> eyre::Result<Option<Self>> {
+        let Some(path) = crate::env_path("API_KEYS_FILE") else {
+            return Ok(None);
+        };
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|e| eyre::eyre!("Failed to read {}: {}", path.display(), e))?;
+        Self::parse(&contents)
+            .map(Some)
+            .map_err(|e| eyre::eyre!("Invalid {}: {}", path.display(), e))
+    }
+
+    fn parse(contents: &str) -> eyre::Result<Self> {
+        let mut keys = HashMap::new();
+        for line in contents
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default().trim())
+            .filter(|line| !line.is_empty())
+        {
+            let mut fields = line.split_whitespace();
+            let (Some(name), Some(key), None) = (fields.next(), fields.next(), fields.next())
+            else {
+                eyre::bail!("expected `<name> <key>`, got {:?}", line);
+            };
+            if name == ANONYMOUS {
+                eyre::bail!("{:?} is reserved for jobs without a key", ANONYMOUS);
+            }
+            if keys.insert(key_digest(key), name.to_string()).is_some() {
+                eyre::bail!("the key of {:?} is listed twice", name);
+            }
+        }
+        Ok(Self(keys))
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The name of `key`, if it is listed.
+    fn name(&self, key: &str) -> Option<&str> {
+        self.0.get(&key_digest(key)).map(String::as_str)
+    }
+}
+
+fn key_digest(key: &str) -> [u8; 32] {
+    Sha256::digest(key.as_bytes()).into()
+}
+
+/// The name of the API key `req` presents, `None` when API keys are
+/// disabled, or why the request is refused.
+pub(crate) fn authenticate(req: &HttpRequest, state: &AppState) -> Result<Option<String>, String> {
+    let Some(keys) = &state.api_keys else {
+        return Ok(None);
+    };
+    let provided = req
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok());
+    match provided.and_then(|key| keys.name(key)) {
+        Some(name) => Ok(Some(name.to_string())),
+        None => Err(format!("Missing or invalid {} header", API_KEY_HEADER)),
+    }
+}
+
+/// Resources one prove job used, as recorded in `USAGE_LOG_FILE`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct JobUsage {
+    /// When the job finished, in seconds since the Unix epoch.
+    finished_at: u64,
+    /// Name of the API key the job was requested with.
+    api_key: Option<String>,
+    /// The endpoint, e.g. `prove` or `prove/mcu-halo2`.
+    endpoint: String,
+    /// The job's outcome, e.g. `success` or `guest_error`.
+    outcome: String,
+    program_bytes: usize,
+    /// Wall-clock time, including waiting in the prove queue.
+    duration_secs: f64,
+    /// CPU time of the proof, when a worker measured it on its own.
+    pub(crate) cpu_secs: Option<f64>,
+    /// Peak resident set size of the proof's worker while proving.
+    pub(crate) peak_rss_bytes: Option<u64>,
+    /// Cycles the guest ran, when the job executed it.
+    pub(crate) cycles: Option<u64>,
+}
+
+/// Measures a job from when its request is accepted.
+pub(crate) struct Meter {
+    api_key: Option<String>,
+    started: Instant,
+}
+
+impl Meter {
+    pub(crate) fn start(api_key: Option<String>) -> Self {
+        Self {
+            api_key,
+            started: Instant::now(),
+        }
+    }
+
+    /// The job's usage, finishing now. CPU time, peak memory and cycles are
+    /// left for the caller, who knows whether the job measured them.
+    pub(crate) fn finish(self, endpoint: &str, outcome: &str, program_bytes: usize) -> JobUsage {
+        JobUsage {
+            finished_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+            api_key: self.api_key,
+            endpoint: endpoint.to_string(),
+            outcome: outcome.to_string(),
+            program_bytes,
+            duration_secs: self.started.elapsed().as_secs_f64(),
+            cpu_secs: None,
+            peak_rss_bytes: None,
+            cycles: None,
+        }
+    }
+}
+
+impl JobUsage {
+    fn key_name(&self) -> &str {
+        self.api_key.as_deref().unwrap_or(ANONYMOUS)
+    }
+}
+
+/// The append-only job log in `USAGE_LOG_FILE`, one JSON [`JobUsage`] per
+/// line.
+#[derive(Debug)]
+pub(crate) struct UsageLog {
+    path: PathBuf,
+    /// Serializes appends, so concurrent jobs don't interleave lines.
+    append: Mutex<()>,
+}
+
+impl UsageLog {
+    /// The log at `USAGE_LOG_FILE`, or `None` when it is unset.
+    pub(crate) fn from_env() -> Option<Self> {
+        crate::env_path("USAGE_LOG_FILE").map(|path| Self {
+            path,
+            append: Mutex::new(()),
+        })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append `job` to the log. Failures are logged rather than failing the
+    /// request that already ran.
+    pub(crate) fn record(&self, job: &JobUsage) {
+        let _append = self.append.lock().unwrap_or_else(|e| e.into_inner());
+        let append = || -> eyre::Result<()> {
+            let line = serde_json::to_string(job)?;
+            crate::ensure_parent(&self.path)?;
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+            writeln!(file, "{}", line)?;
+            Ok(())
+        };
+        if let Err(e) = append() {
+            error!("Failed to record usage in {}: {}", self.path.display(), e);
+        }
+    }
+
+    /// Every job in the log. Lines that don't parse, such as one cut short
+    /// by a crash, are skipped.
+    fn read(&self) -> std::io::Result<Vec<JobUsage>> {
+        let contents = match std::fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        Ok(contents
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct UsageQuery {
+    /// Only jobs of this API key name.
+    api_key: Option<String>,
+    /// Only jobs finished at or after this Unix time.
+    since: Option<u64>,
+    /// Only jobs finished before this Unix time.
+    until: Option<u64>,
+    /// `json` (the default) for totals per key, `csv` for every job.
+    format: Option<String>,
+}
+
+impl UsageQuery {
+    fn matches(&self, job: &JobUsage) -> bool {
+        self.api_key
+            .as_deref()
+            .is_none_or(|name| name == job.key_name())
+            && self.since.is_none_or(|since| job.finished_at >= since)
+            && self.until.is_none_or(|until| job.finished_at < until)
+    }
+}
+
+/// Usage of one API key.
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct Totals {
+    jobs: u64,
+    failures: u64,
+    duration_secs: f64,
+    /// CPU time of the jobs that measured it.
+    cpu_secs: f64,
+    cycles: u64,
+    /// The largest peak resident set size of any job.
+    max_peak_rss_bytes: Option<u64>,
+}
+
+fn totals(jobs: &[JobUsage]) -> BTreeMap<&str, Totals> {
+    let mut totals = BTreeMap::<&str, Totals>::new();
+    for job in jobs {
+        let key = totals.entry(job.key_name()).or_default();
+        key.jobs += 1;
+        if job.outcome != "success" {
+            key.failures += 1;
+        }
+        key.duration_secs += job.duration_secs;
+        key.cpu_secs += job.cpu_secs.unwrap_or_default();
+        key.cycles += job.cycles.unwrap_or_default();
+        key.max_peak_rss_bytes = key.max_peak_rss_bytes.max(job.peak_rss_bytes);
+    }
+    totals
+}
+
+const CSV_HEADER: &str = "finished_at,api_key,endpoint,outcome,program_bytes,duration_secs,cpu_secs,peak_rss_bytes,cycles";
+
+fn to_csv(jobs: &[JobUsage]) -> String {
+    fn optional<T: ToString>(value: Option<T>) -> String {
+        value.map(|value| value.to_string()).unwrap_or_default()
+    }
+
+    let mut csv = format!("{}\n", CSV_HEADER);
+    for job in jobs {
+        let row = [
+            job.finished_at.to_string(),
+            csv_field(job.key_name()),
+            csv_field(&job.endpoint),
+            csv_field(&job.outcome),
+            job.program_bytes.to_string(),
+            format!("{:.3}", job.duration_secs),
+            optional(job.cpu_secs.map(|secs| format!("{:.2}", secs))),
+            optional(job.peak_rss_bytes),
+            optional(job.cycles),
+        ];
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Quote `value` if it contains a separator, a quote or a line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// GET /api/usage
+///
+/// Resources used by prove jobs, filtered by `api_key`, `since` and `until`:
+/// totals per API key as JSON, or every job as CSV with `format=csv`. Needs
+/// the admin token.
+pub(crate) async fn usage(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    query: web::Query<UsageQuery>,
+) -> HttpResponse {
+    if let Err(response) = crate::admin::authorize(&req, &state) {
+        return response;
+    }
+    let Some(log) = &state.usage_log else {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Usage accounting is disabled. Set USAGE_LOG_FILE to enable it."
+        }));
+    };
+    let csv = match query.format.as_deref() {
+        None | Some("json") => false,
+        Some("csv") => true,
+        Some(other) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("Unknown format {:?}, expected json or csv", other)
+            }))
+        }
+    };
+
+    let jobs: Vec<JobUsage> = match log.read() {
+        Ok(jobs) => jobs.into_iter().filter(|job| query.matches(job)).collect(),
+        Err(e) => {
+            error!("Failed to read {}: {}", log.path().display(), e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to read the usage log: {}", e)
+            }));
+        }
+    };
+    if csv {
+        return HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .insert_header((
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"usage.csv\"",
+            ))
+            .body(to_csv(&jobs));
+    }
+    HttpResponse::Ok().json(serde_json::json!({
+        "since": query.since,
+        "until": query.until,
+        "jobs": jobs.len(),
+        "keys": totals(&jobs),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(api_key: Option<&str>, finished_at: u64, outcome: &str) -> JobUsage {
+        JobUsage {
+            finished_at,
+            api_key: api_key.map(str::to_string),
+            endpoint: "prove".into(),
+            outcome: outcome.into(),
+            program_bytes: 64,
+            duration_secs: 2.5,
+            cpu_secs: Some(10.0),
+            peak_rss_bytes: Some(1 << 30),
+            cycles: Some(1_000),
+        }
+    }
+
+    #[test]
+    fn parses_api_keys() {
+        let keys =
+            ApiKeys::parse("# billing\nacme s3cret\n\nglobex  other-key # staging\n").unwrap();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys.name("s3cret"), Some("acme"));
+        assert_eq!(keys.name("other-key"), Some("globex"));
+        assert_eq!(keys.name("s3cre"), None);
+
+        assert!(ApiKeys::parse("acme").is_err());
+        assert!(ApiKeys::parse("acme key extra").is_err());
+        assert!(ApiKeys::parse("acme key\nglobex key").is_err());
+        assert!(ApiKeys::parse("anonymous key").is_err());
+    }
+
+    #[test]
+    fn totals_jobs_per_key() {
+        let mut failed = job(Some("acme"), 20, "guest_error");
+        failed.peak_rss_bytes = Some(2 << 30);
+        failed.cycles = None;
+        let jobs = [
+            job(Some("acme"), 10, "success"),
+            failed,
+            job(None, 30, "success"),
+        ];
+
+        let totals = totals(&jobs);
+        assert_eq!(
+            totals["acme"],
+            Totals {
+                jobs: 2,
+                failures: 1,
+                duration_secs: 5.0,
+                cpu_secs: 20.0,
+                cycles: 1_000,
+                max_peak_rss_bytes: Some(2 << 30),
+            }
+        );
+        assert_eq!(totals[ANONYMOUS].jobs, 1);
+    }
+
+    #[test]
+    fn filters_jobs() {
+        let query = UsageQuery {
+            api_key: Some("acme".into()),
+            since: Some(10),
+            until: Some(20),
+            ..UsageQuery::default()
+        };
+        assert!(query.matches(&job(Some("acme"), 10, "success")));
+        assert!(!query.matches(&job(Some("acme"), 20, "success")));
+        assert!(!query.matches(&job(Some("globex"), 15, "success")));
+        assert!(!query.matches(&job(None, 15, "success")));
+    }
+
+    #[test]
+    fn exports_csv() {
+        let mut unmetered = job(Some("acme, inc"), 2, "guest_error");
+        (
+            unmetered.cpu_secs,
+            unmetered.peak_rss_bytes,
+            unmetered.cycles,
+        ) = (None, None, None);
+        let csv = to_csv(&[job(None, 1, "success"), unmetered]);
+        assert_eq!(
+            csv,
+            format!(
+                "{}\n\
+                 1,anonymous,prove,success,64,2.500,10.00,1073741824,1000\n\
+                 2,\"acme, inc\",prove,guest_error,64,2.500,,,\n",
+                CSV_HEADER
+            )
+        );
+    }
+
+    #[test]
+    fn round_trips_log_lines() {
+        let recorded = job(Some("acme"), 1, "success");
+        let line = serde_json::to_string(&recorded).unwrap();
+        assert_eq!(serde_json::from_str::<JobUsage>(&line).unwrap(), recorded);
+    }
+}
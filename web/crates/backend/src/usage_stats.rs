@@ -0,0 +1,212 @@
+//! Opt-in anonymous usage statistics, behind the `telemetry` feature.
+//!
+//! When built with `--features telemetry` and `TELEMETRY_URL` is set, the
+//! backend counts `/api/prove` requests and POSTs the aggregate to that URL
+//! every `TELEMETRY_INTERVAL_SECS` (a day by default): how many proofs
+//! succeeded and failed, how long they took, how large the programs were in
+//! coarse buckets, the backend, OpenVM and evaluator versions, and the SIMD
+//! instruction sets the prover uses and the CPU supports. Nothing identifies
+//! a program, a client or the server; periods without requests are not
+//! reported. This tells maintainers which workloads are worth optimizing.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Upper bounds of the program size buckets, in bytes.
+const SIZE_BUCKETS: [(usize, &str); 5] = [
+    (256, "<=256B"),
+    (1024, "<=1KiB"),
+    (4 * 1024, "<=4KiB"),
+    (16 * 1024, "<=16KiB"),
+    (64 * 1024, "<=64KiB"),
+];
+
+fn size_bucket(len: usize) -> &'static str {
+    SIZE_BUCKETS
+        .iter()
+        .find(|(limit, _)| len <= *limit)
+        .map_or(">64KiB", |(_, label)| label)
+}
+
+/// Statistics of the prove requests since the last report.
+#[derive(Debug, Default, Serialize)]
+struct Counters {
+    proofs: u64,
+    failures: u64,
+    /// Summed and longest request durations, in seconds.
+    total_secs: f64,
+    max_secs: f64,
+    /// Requests per program size bucket.
+    program_sizes: BTreeMap<&'static str, u64>,
+}
+
+/// What is sent to `TELEMETRY_URL`.
+#[derive(Debug, Serialize)]
+struct Report {
+    backend_version: &'static str,
+    openvm_version: String,
+    evaluator: String,
+    cpu_features: crate::cpu_features::CpuFeatures,
+    period_secs: u64,
+    #[serde(flatten)]
+    counters: Counters,
+}
+
+/// Usage statistics collected for `TELEMETRY_URL`.
+#[derive(Debug)]
+pub(crate) struct UsageStats {
+    endpoint: String,
+    interval: Duration,
+    /// Start of the current period, and its counters.
+    period: Mutex<(Instant, Counters)>,
+}
+
+impl UsageStats {
+    /// Statistics reported to `TELEMETRY_URL`, or `None` when it is unset.
+    pub(crate) fn from_env() -> eyre::Result<Option<Self>> {
+        let Some(endpoint) = std::env::var("TELEMETRY_URL")
+            .ok()
+            .filter(|url| !url.is_empty())
+        else {
+            return Ok(None);
+        };
+        if cfg!(not(feature = "telemetry")) {
+            warn!("TELEMETRY_URL is set but the backend was built without the telemetry feature");
+            return Ok(None);
+        }
+        let interval = match std::env::var("TELEMETRY_INTERVAL_SECS") {
+            Ok(value) if !value.is_empty() => value
+                .parse()
+                .ok()
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs)
+                .ok_or_else(|| eyre::eyre!("Invalid TELEMETRY_INTERVAL_SECS: {}", value))?,
+            _ => DEFAULT_INTERVAL,
+        };
+        Ok(Some(Self {
+            endpoint,
+            interval,
+            period: Mutex::new((Instant::now(), Counters::default())),
+        }))
+    }
+
+    pub(crate) fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    pub(crate) fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Count a prove request for a program of `program_len` bytes.
+    pub(crate) fn record(&self, program_len: usize, duration_secs: f64, succeeded: bool) {
+        let mut period = self.period.lock().expect("usage stats lock poisoned");
+        let counters = &mut period.1;
+        if succeeded {
+            counters.proofs += 1;
+        } else {
+            counters.failures += 1;
+        }
+        counters.total_secs += duration_secs;
+        counters.max_secs = counters.max_secs.max(duration_secs);
+        *counters
+            .program_sizes
+            .entry(size_bucket(program_len))
+            .or_default() += 1;
+    }
+
+    /// The report for the period so far, which starts a new one. `None` if
+    /// there were no requests.
+    fn take_report(&self) -> Option<Report> {
+        let mut period = self.period.lock().expect("usage stats lock poisoned");
+        let (started, counters) =
+            std::mem::replace(&mut *period, (Instant::now(), Counters::default()));
+        (counters.proofs + counters.failures > 0).then(|| Report {
+            backend_version: env!("CARGO_PKG_VERSION"),
+            openvm_version: crate::openvm_version_tag(),
+            evaluator: uplc::get_evaluator().version().to_string(),
+            cpu_features: crate::cpu_features::CpuFeatures::detect(),
+            period_secs: started.elapsed().as_secs(),
+            counters,
+        })
+    }
+}
+
+#[cfg(feature = "telemetry")]
+fn send(endpoint: &str, report: &Report) -> Result<(), String> {
+    reqwest::blocking::Client::new()
+        .post(endpoint)
+        .json(report)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Without the `telemetry` feature no statistics are collected to send.
+#[cfg(not(feature = "telemetry"))]
+fn send(_endpoint: &str, _report: &Report) -> Result<(), String> {
+    Err("built without the telemetry feature".into())
+}
+
+/// Report the statistics every interval, for the lifetime of the server.
+/// A report that fails to send is dropped rather than retried.
+pub(crate) async fn run(stats: Arc<UsageStats>) {
+    let mut ticks = tokio::time::interval(stats.interval);
+    // The first tick completes immediately.
+    ticks.tick().await;
+
+    loop {
+        ticks.tick().await;
+        let Some(report) = stats.take_report() else {
+            continue;
+        };
+        let task_stats = stats.clone();
+        // reqwest's blocking client must not live on the async runtime.
+        match actix_web::web::block(move || send(&task_stats.endpoint, &report)).await {
+            Ok(Ok(())) => info!("Sent usage statistics to {}", stats.endpoint),
+            Ok(Err(e)) => warn!("Failed to send usage statistics: {}", e),
+            Err(e) => warn!("Blocking usage statistics task error: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_program_sizes() {
+        assert_eq!(size_bucket(0), "<=256B");
+        assert_eq!(size_bucket(256), "<=256B");
+        assert_eq!(size_bucket(257), "<=1KiB");
+        assert_eq!(size_bucket(64 * 1024), "<=64KiB");
+        assert_eq!(size_bucket(64 * 1024 + 1), ">64KiB");
+    }
+
+    #[test]
+    fn reports_each_period_once() {
+        let stats = UsageStats {
+            endpoint: String::new(),
+            interval: DEFAULT_INTERVAL,
+            period: Mutex::new((Instant::now(), Counters::default())),
+        };
+        assert!(stats.take_report().is_none());
+
+        stats.record(100, 2.0, true);
+        stats.record(2000, 5.0, false);
+        let report = stats.take_report().unwrap();
+        assert_eq!((report.counters.proofs, report.counters.failures), (1, 1));
+        assert_eq!(report.counters.total_secs, 7.0);
+        assert_eq!(report.counters.max_secs, 5.0);
+        assert_eq!(report.counters.program_sizes["<=256B"], 1);
+        assert_eq!(report.counters.program_sizes["<=4KiB"], 1);
+
+        assert!(stats.take_report().is_none());
+    }
+}
@@ -0,0 +1,383 @@
+//! `cardano-zkvms verify-bundle`: check a proof bundle from
+//! `/api/proofs/{commitment}/bundle` end to end, without a backend.
+//!
+//! Every check runs locally: the program's SHA-256, the commitment
+//! recomputed from the program, the arguments given with `--arg` and the
+//! claim, the bundle's encoded statement against the claim, the public
+//! values the proof commits to, the verifying key's fingerprint, and the
+//! STARK proof itself.
+//! The bundle attests its claim only if all of them pass.
+
+use crate::proof_inspect::decompress_json;
+use crate::proofs::{agg_vk_sha256, BUNDLE_VERSION};
+use guest_protocol::{ErrorKind, Statement};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// One check of a bundle, and what it found.
+#[derive(Debug)]
+struct Check {
+    name: &'static str,
+    passed: bool,
+    detail: String,
+}
+
+#[derive(Debug, Default)]
+struct Checks(Vec<Check>);
+
+impl Checks {
+    fn push(&mut self, name: &'static str, passed: bool, detail: impl Into<String>) {
+        self.0.push(Check {
+            name,
+            passed,
+            detail: detail.into(),
+        });
+    }
+
+    fn failed(&self) -> usize {
+        self.0.iter().filter(|check| !check.passed).count()
+    }
+
+    fn passed(&self, name: &str) -> bool {
+        self.0
+            .iter()
+            .any(|check| check.name == name && check.passed)
+    }
+}
+
+fn str_field<'a>(bundle: &'a Value, pointer: &str) -> Option<&'a str> {
+    bundle.pointer(pointer).and_then(Value::as_str)
+}
+
+fn required<'a>(bundle: &'a Value, pointer: &str) -> eyre::Result<&'a str> {
+    str_field(bundle, pointer).ok_or_else(|| eyre::eyre!("The bundle has no {}", pointer))
+}
+
+/// The statement the bundle claims about `program` applied to the
+/// arguments with hashes `args`, or why it cannot be rebuilt.
+fn claimed_statement(
+    bundle: &Value,
+    program: &[u8],
+    args: &[[u8; 32]],
+) -> Result<Statement, String> {
+    let program = Sha256::digest(program).into();
+    let statement = match str_field(bundle, "/claim/outcome") {
+        Some("success") => str_field(bundle, "/claim/result")
+            .map(|result| Statement::success(program, result))
+            .ok_or_else(|| "the claim has no result to recompute it from".to_string()),
+        Some(_) => {
+            let label = str_field(bundle, "/claim/error_kind")
+                .ok_or_else(|| "the claim has no error kind to recompute it from".to_string())?;
+            let kind = ErrorKind::from_label(label)
+                .ok_or_else(|| format!("unknown error kind {}", label))?;
+            Ok(Statement::failure(program, kind))
+        }
+        None => Err("the bundle has no claim".to_string()),
+    };
+    statement.map(|statement| statement.with_args(args.to_vec()))
+}
+
+/// Check the arguments given with `--arg` against those the bundle's
+/// statement lists, if it has one.
+fn check_args(bundle: &Value, args: &[[u8; 32]], checks: &mut Checks) {
+    let Some(statement) = str_field(bundle, "/statement/hex")
+        .and_then(|statement_hex| hex::decode(statement_hex).ok())
+        .and_then(|bytes| Statement::decode(&bytes))
+    else {
+        return;
+    };
+    if statement.args.is_empty() && args.is_empty() {
+        return;
+    }
+    let detail = if statement.args == args {
+        format!("{} given, matching the statement", args.len())
+    } else if args.is_empty() {
+        format!(
+            "the statement lists {}, give their CBOR with --arg",
+            statement.args.len()
+        )
+    } else {
+        format!(
+            "{} given, the statement lists {}: {}",
+            args.len(),
+            statement.args.len(),
+            statement
+                .args
+                .iter()
+                .map(hex::encode)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+    checks.push("args", statement.args == args, detail);
+}
+
+/// Check the bundle's encoded statement, if it has one, against the claim.
+fn check_statement(bundle: &Value, claimed: &Statement, checks: &mut Checks) {
+    let Some(statement_hex) = str_field(bundle, "/statement/hex") else {
+        return;
+    };
+    match hex::decode(statement_hex)
+        .ok()
+        .and_then(|bytes| Statement::decode(&bytes))
+    {
+        Some(statement) if statement == *claimed => {
+            checks.push("statement", true, "matches the claim")
+        }
+        Some(statement) => checks.push("statement", false, format!("{} encoded", statement)),
+        None => checks.push("statement", false, "undecodable"),
+    }
+}
+
+/// Check the program, the arguments with hashes `args` and the claim
+/// against the commitment.
+fn check_claim(bundle: &Value, args: &[[u8; 32]], checks: &mut Checks) -> eyre::Result<()> {
+    let program = hex::decode(required(bundle, "/program/hex")?)
+        .map_err(|e| eyre::eyre!("Invalid program hex: {}", e))?;
+    let program_sha256 = hex::encode(Sha256::digest(&program));
+    checks.push(
+        "program_sha256",
+        str_field(bundle, "/program/sha256") == Some(program_sha256.as_str()),
+        format!("{} ({} bytes)", program_sha256, program.len()),
+    );
+
+    check_args(bundle, args, checks);
+    let commitment = required(bundle, "/commitment")?;
+    match claimed_statement(bundle, &program, args) {
+        Ok(statement) => {
+            check_statement(bundle, &statement, checks);
+            let recomputed = hex::encode(statement.commitment());
+            let detail = if recomputed == commitment {
+                format!("{} recomputed from the program, args and claim", commitment)
+            } else {
+                format!("{} claimed, {} recomputed", commitment, recomputed)
+            };
+            checks.push("commitment", recomputed == commitment, detail);
+        }
+        Err(e) => checks.push("commitment", false, e),
+    }
+    Ok(())
+}
+
+/// Check the STARK proof, its public values and the verifying key.
+fn check_proof(bundle: &Value, agg_vk_path: &Path, checks: &mut Checks) -> eyre::Result<()> {
+    let proof = decompress_json(required(bundle, "/proof/data")?)?;
+    let stark_proof_json = proof
+        .get("stark_proof_json")
+        .ok_or_else(|| eyre::eyre!("The bundle's proof has no stark_proof_json"))?;
+
+    let commitment = required(bundle, "/commitment")?;
+    match openvm_prover::stark_proof_public_values(stark_proof_json) {
+        Ok(public_values) => {
+            let status = guest_protocol::Status::from_public_values(&public_values)
+                .map_or("unknown", guest_protocol::Status::label);
+            let public_values = hex::encode(public_values);
+            checks.push(
+                "public_values",
+                public_values == commitment,
+                format!(
+                    "{} committed by the proof (status {})",
+                    public_values, status
+                ),
+            );
+        }
+        Err(e) => checks.push("public_values", false, format!("undecodable ({})", e)),
+    }
+
+    let vk_sha256 = agg_vk_sha256(agg_vk_path)
+        .map_err(|e| eyre::eyre!("Failed to read {}: {}", agg_vk_path.display(), e))?;
+    checks.push(
+        "verifying_key_sha256",
+        str_field(bundle, "/verifying_key/sha256") == Some(vk_sha256.as_str()),
+        format!("{} ({})", vk_sha256, agg_vk_path.display()),
+    );
+
+    let baseline: openvm_prover::StarkVerificationBaselineJson = serde_json::from_value(
+        proof
+            .get("verification_baseline_json")
+            .cloned()
+            .unwrap_or_default(),
+    )
+    .map_err(|e| eyre::eyre!("Invalid verification baseline: {}", e))?;
+    let agg_vk = openvm_prover::load_agg_vk(agg_vk_path)?;
+    match openvm_prover::verify_stark(&agg_vk, stark_proof_json, &baseline) {
+        Ok(()) => checks.push("stark_proof", true, "verified"),
+        Err(e) => checks.push("stark_proof", false, e.to_string()),
+    }
+    Ok(())
+}
+
+/// What a bundle that passed every check attests.
+fn attestation(bundle: &Value, args: &[[u8; 32]]) -> String {
+    let statement = hex::decode(str_field(bundle, "/program/hex").unwrap_or_default())
+        .map_err(|e| e.to_string())
+        .and_then(|program| claimed_statement(bundle, &program, args));
+    let claim = match statement {
+        Ok(statement) => statement.to_string(),
+        Err(_) => "an unknown statement".to_string(),
+    };
+    format!(
+        "Proved: {}, by the guest with app_exe_commit {} (OpenVM {}).",
+        claim,
+        str_field(bundle, "/proof/app_exe_commit").unwrap_or("-"),
+        str_field(bundle, "/metadata/openvm_version").unwrap_or("-"),
+    )
+}
+
+/// The [`guest_protocol::argument_hash`] of an argument file: CBOR
+/// `PlutusData`, as hex text or raw bytes.
+fn argument_file_hash(path: &str) -> eyre::Result<[u8; 32]> {
+    let bytes = std::fs::read(path).map_err(|e| eyre::eyre!("Failed to read {}: {}", path, e))?;
+    let cbor = std::str::from_utf8(&bytes)
+        .ok()
+        .and_then(|text| hex::decode(text.trim()).ok())
+        .unwrap_or(bytes);
+    Ok(guest_protocol::argument_hash(&cbor))
+}
+
+/// `cardano-zkvms verify-bundle [--vk agg_stark.vk] [--arg FILE]... BUNDLE`.
+///
+/// Verifies BUNDLE against `--vk`, or the `agg_stark.vk` the backend would
+/// load, and prints each check. Each `--arg` is the CBOR of an argument the
+/// program was applied to, in order. Fails unless every check passes.
+pub(crate) fn cmd_verify_bundle(args: &[String]) -> eyre::Result<()> {
+    const USAGE: &str =
+        "Usage: cardano-zkvms verify-bundle [--vk agg_stark.vk] [--arg FILE]... BUNDLE";
+    let mut agg_vk_path = None;
+    let mut arg_hashes = Vec::new();
+    let mut rest = args;
+    let file = loop {
+        match rest {
+            [flag, vk, tail @ ..] if flag == "--vk" => {
+                agg_vk_path = Some(PathBuf::from(vk));
+                rest = tail;
+            }
+            [flag, arg, tail @ ..] if flag == "--arg" => {
+                arg_hashes.push(argument_file_hash(arg)?);
+                rest = tail;
+            }
+            [file] if !file.starts_with("--") => break file,
+            _ => eyre::bail!(USAGE),
+        }
+    };
+    let agg_vk_path = match agg_vk_path {
+        Some(path) => path,
+        None => {
+            crate::ArtifactPaths::from_env(&crate::environment::Environment::from_process())?
+                .agg_vk_path
+        }
+    };
+    let bytes = std::fs::read(file).map_err(|e| eyre::eyre!("Failed to read {}: {}", file, e))?;
+    let bundle: Value =
+        serde_json::from_slice(&bytes).map_err(|e| eyre::eyre!("Invalid bundle: {}", e))?;
+    let version = bundle.get("bundle_version").and_then(Value::as_u64);
+    if version != Some(u64::from(BUNDLE_VERSION)) {
+        eyre::bail!(
+            "Unsupported bundle version {:?}, expected {}",
+            version,
+            BUNDLE_VERSION
+        );
+    }
+
+    let mut checks = Checks::default();
+    check_claim(&bundle, &arg_hashes, &mut checks)?;
+    check_proof(&bundle, &agg_vk_path, &mut checks)?;
+
+    for check in &checks.0 {
+        let mark = if check.passed { "ok  " } else { "FAIL" };
+        println!("{} {}: {}", mark, check.name, check.detail);
+    }
+    match checks.failed() {
+        0 => {
+            println!();
+            println!("{}", attestation(&bundle, &arg_hashes));
+            Ok(())
+        }
+        failed if checks.passed("stark_proof") => eyre::bail!(
+            "{} of {} checks failed: the proof verifies, but not for this claim",
+            failed,
+            checks.0.len()
+        ),
+        failed => eyre::bail!("{} of {} checks failed", failed, checks.0.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle(program: &[u8], result: &str) -> Value {
+        serde_json::json!({
+            "bundle_version": BUNDLE_VERSION,
+            "commitment": hex::encode(guest_protocol::success_commitment(program, "Integer(42)")),
+            "program": {
+                "sha256": hex::encode(Sha256::digest(program)),
+                "hex": hex::encode(program),
+            },
+            "claim": { "outcome": "success", "result": result, "error_kind": null },
+            "statement": {
+                "hex": hex::encode(Statement::success(Sha256::digest(program).into(), "Integer(42)").encode()),
+            },
+        })
+    }
+
+    #[test]
+    fn recomputes_the_commitment_from_the_claim() {
+        let mut checks = Checks::default();
+        check_claim(&bundle(b"program", "Integer(42)"), &[], &mut checks).unwrap();
+        assert_eq!(checks.failed(), 0, "{:?}", checks);
+
+        let mut checks = Checks::default();
+        check_claim(&bundle(b"program", "Integer(43)"), &[], &mut checks).unwrap();
+        assert!(checks.passed("program_sha256"));
+        assert!(!checks.passed("commitment"));
+        assert!(!checks.passed("statement"));
+    }
+
+    #[test]
+    fn recomputes_failure_commitments() {
+        let program = b"program";
+        let bundle = serde_json::json!({
+            "claim": { "outcome": "script_error", "result": null, "error_kind": "decode" },
+        });
+        assert_eq!(
+            claimed_statement(&bundle, program, &[]).map(|statement| statement.commitment()),
+            Ok(guest_protocol::failure_commitment(
+                program,
+                ErrorKind::Decode
+            ))
+        );
+    }
+    #[test]
+    fn recomputes_commitments_over_arguments() {
+        let program = b"program";
+        let args = [b"\x01".to_vec(), b"\x02".to_vec()];
+        let statement = guest_protocol::ProgramHasher::with_program(program)
+            .with_args(&args)
+            .success("Integer(42)");
+        let bundle = serde_json::json!({
+            "commitment": hex::encode(statement.commitment()),
+            "program": {
+                "sha256": hex::encode(Sha256::digest(program)),
+                "hex": hex::encode(program),
+            },
+            "claim": { "outcome": "success", "result": "Integer(42)", "error_kind": null },
+            "statement": { "hex": hex::encode(statement.encode()) },
+        });
+        let hashes = args
+            .iter()
+            .map(|arg| guest_protocol::argument_hash(arg))
+            .collect::<Vec<_>>();
+
+        let mut checks = Checks::default();
+        check_claim(&bundle, &hashes, &mut checks).unwrap();
+        assert_eq!(checks.failed(), 0, "{:?}", checks);
+        assert!(checks.passed("args"));
+
+        let mut checks = Checks::default();
+        check_claim(&bundle, &[], &mut checks).unwrap();
+        assert!(!checks.passed("args"));
+        assert!(!checks.passed("commitment"));
+    }
+}
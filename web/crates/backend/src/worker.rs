@@ -0,0 +1,51 @@
+//! `cardano-zkvms worker`: runs guest jobs for the server in a process of
+//! its own, see `openvm_prover::WorkerProcess`.
+//!
+//! With `EXECUTE_TIMEOUT_SECS` or `PROVE_TIMEOUT_SECS` set, the server
+//! executes and proves in such workers and kills the ones that time out, so
+//! a pathological program stops using memory and cores the moment its
+//! request fails, and a queue slot is only freed once its job is gone. The
+//! worker resolves the artifacts from the same environment as the server.
+//!
+//! A worker loads the guest and every key the server has once, then serves
+//! jobs until it is killed, so timeouts do not count key loading. Each
+//! worker keeps a second copy of those keys resident next to the server's,
+//! tens of GB, even between jobs, and one runs per job that overlapped with
+//! others (`MAX_CONCURRENT_PROOFS`). The server starts one at startup.
+
+use openvm_prover::{Prover, WorkerProcess};
+
+use crate::{environment, ArtifactPaths, GuestTimeouts};
+
+/// Workers for the server's guest jobs, when a timeout needs them.
+pub(crate) fn for_timeouts(timeouts: &GuestTimeouts) -> std::io::Result<Option<WorkerProcess>> {
+    if timeouts.execute.is_none() && timeouts.prove.is_none() {
+        return Ok(None);
+    }
+    let program = std::env::current_exe()?;
+    Ok(Some(WorkerProcess::new(program, ["worker"])))
+}
+
+pub(crate) fn cmd_worker(_args: &[String]) -> eyre::Result<()> {
+    let paths = ArtifactPaths::from_env(&environment::Environment::from_process())?;
+    openvm_prover::serve_worker(|| load(&paths))
+}
+
+/// A prover with every key the server has, for any job.
+fn load(paths: &ArtifactPaths) -> eyre::Result<Prover> {
+    let builder = Prover::builder()
+        .config_path(&paths.config_path)
+        .exe_path(&paths.vmexe_path)
+        // The server checked the artifacts' checksums when it loaded them.
+        .verify_checksums(false);
+    if !paths.app_pk_path.exists() {
+        // In degraded mode the server only executes, and without metering.
+        return builder.metering(false).build();
+    }
+    let builder = builder.app_pk_path(&paths.app_pk_path);
+    if !paths.agg_pk_path.exists() {
+        return builder.build();
+    }
+    openvm_prover::evm_halo2_mcu::with_configured_keys(builder.agg_pk_path(&paths.agg_pk_path))
+        .build()
+}
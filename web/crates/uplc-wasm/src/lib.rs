@@ -1,7 +1,58 @@
+use guest_protocol::ErrorKind;
+use uplc::limits::DecodeLimits;
 use wasm_bindgen::prelude::*;
-use sha2::{Digest, Sha256};
 use uplc_turbo::{arena::Arena, binder::DeBruijn, flat};
 
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TYPES: &'static str = r#"
+/** Why a program fails, as committed to by the OpenVM guest. */
+export type FailureKind = "decode" | "evaluation" | "budget_exceeded" | "non_constant_result";
+
+/** Execution budget consumed by an evaluation. */
+export interface ExBudget {
+  cpu: number;
+  mem: number;
+}
+
+/** Outcome of one evaluator in `evaluate_uplc_compare`. */
+export interface EvaluatorRun {
+  /** Rendered result constant, when evaluation succeeded. */
+  result: string | null;
+  error_kind: FailureKind | null;
+  error: string | null;
+  /** Consumed budget, when the program decoded. */
+  budget: ExBudget | null;
+}
+
+/** A constant embedded in a program, as listed by `extract_constants`. */
+export interface UplcConstant {
+  /** Pre-order index of the constant's term. */
+  index: number;
+  /** Steps from the root term, e.g. `["apply.function", "lambda.body"]`. */
+  path: string[];
+  type: "integer" | "bytestring" | "string" | "bool" | "unit" | "data" | "other";
+  /** Hex for bytestrings, the debug rendering otherwise. */
+  value: string;
+}
+
+/** Side-by-side evaluation with uplc-turbo and uplc-aiken. */
+export interface UplcCompareReport {
+  turbo: EvaluatorRun;
+  aiken: EvaluatorRun;
+  outcomes_match: boolean;
+  budgets_match: boolean;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "UplcCompareReport")]
+    pub type UplcCompareReport;
+
+    #[wasm_bindgen(typescript_type = "UplcConstant[]")]
+    pub type UplcConstants;
+}
+
 /// Initialize panic hook for better error messages in the browser console.
 #[wasm_bindgen(start)]
 pub fn init() {
@@ -15,12 +66,14 @@ pub fn init() {
 pub fn evaluate_uplc(program_hex: &str) -> Result<String, JsValue> {
     let program_bytes = hex::decode(program_hex.trim())
         .map_err(|e| JsValue::from_str(&format!("Hex decode error: {}", e)))?;
+    DecodeLimits::DEFAULT
+        .check_flat(&program_bytes)
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
 
     let arena = Arena::new();
 
-    let program: &uplc_turbo::program::Program<DeBruijn> =
-        flat::decode(&arena, &program_bytes)
-            .map_err(|e| JsValue::from_str(&format!("Program decode error: {:?}", e)))?;
+    let program: &uplc_turbo::program::Program<DeBruijn> = flat::decode(&arena, &program_bytes)
+        .map_err(|e| JsValue::from_str(&format!("Program decode error: {:?}", e)))?;
 
     let eval_result = program.eval(&arena);
 
@@ -36,21 +89,201 @@ pub fn evaluate_uplc(program_hex: &str) -> Result<String, JsValue> {
     Ok(result_str)
 }
 
-/// Compute SHA256(program_bytes || result_string) — the same commitment
-/// that the OpenVM guest reveals as public output.
+/// Compute the commitment to the statement that the program evaluated to
+/// `result_str` — the same public values that the OpenVM guest reveals.
 ///
-/// Returns the 64-char hex digest.
+/// Returns 64 hex chars: the 4-byte `Ok` status word, then the first 28
+/// bytes of the statement's SHA256.
 #[wasm_bindgen]
 pub fn compute_commitment(program_hex: &str, result_str: &str) -> Result<String, JsValue> {
     let program_bytes = hex::decode(program_hex.trim())
         .map_err(|e| JsValue::from_str(&format!("Hex decode error: {}", e)))?;
 
-    let mut hasher = Sha256::new();
-    hasher.update(&program_bytes);
-    hasher.update(result_str.as_bytes());
-    let hash = hasher.finalize();
+    Ok(hex::encode(guest_protocol::success_commitment(
+        &program_bytes,
+        result_str,
+    )))
+}
+
+/// Compute the commitment to the statement that the program failed with
+/// `kind` — the public values the OpenVM guest reveals when proving that a
+/// program fails.
+///
+/// `kind` is one of `decode`, `evaluation`, `budget_exceeded`, or
+/// `non_constant_result`. Returns 64 hex chars: the 4-byte status word of
+/// `kind`, then the first 28 bytes of the statement's SHA256.
+#[wasm_bindgen]
+pub fn compute_failure_commitment(program_hex: &str, kind: &str) -> Result<String, JsValue> {
+    let program_bytes = hex::decode(program_hex.trim())
+        .map_err(|e| JsValue::from_str(&format!("Hex decode error: {}", e)))?;
+    let kind = ErrorKind::from_label(kind)
+        .ok_or_else(|| JsValue::from_str(&format!("Unknown failure kind: {}", kind)))?;
+
+    Ok(hex::encode(guest_protocol::failure_commitment(
+        &program_bytes,
+        kind,
+    )))
+}
+
+/// The statement behind `compute_commitment(program_hex, result_str)`, as
+/// text, e.g. `program 5f3a… with no args evaluated to Integer(42) under the
+/// default cost model within the default budget`.
+#[wasm_bindgen]
+pub fn describe_statement(program_hex: &str, result_str: &str) -> Result<String, JsValue> {
+    let program_bytes = hex::decode(program_hex.trim())
+        .map_err(|e| JsValue::from_str(&format!("Hex decode error: {}", e)))?;
+    let program = guest_protocol::ProgramHasher::with_program(&program_bytes).program_hash();
+
+    Ok(guest_protocol::Statement::success(program, result_str).to_string())
+}
+
+/// Hash of a script argument (datum, redeemer or script context), given as
+/// hex CBOR `PlutusData` exactly as sent to the guest: the value a statement
+/// lists for each argument the program was applied to.
+#[wasm_bindgen]
+pub fn argument_hash(cbor_hex: &str) -> Result<String, JsValue> {
+    let cbor = hex::decode(cbor_hex.trim())
+        .map_err(|e| JsValue::from_str(&format!("Hex decode error: {}", e)))?;
+
+    Ok(hex::encode(guest_protocol::argument_hash(&cbor)))
+}
+
+/// Like `describe_statement`, for `program_hex` applied to the hex CBOR
+/// `args_hex` in order.
+#[wasm_bindgen]
+pub fn describe_statement_with_args(
+    program_hex: &str,
+    args_hex: Vec<String>,
+    result_str: &str,
+) -> Result<String, JsValue> {
+    let program_bytes = hex::decode(program_hex.trim())
+        .map_err(|e| JsValue::from_str(&format!("Hex decode error: {}", e)))?;
+    let args = decode_args(&args_hex)?;
+
+    Ok(guest_protocol::ProgramHasher::with_program(&program_bytes)
+        .with_args(&args)
+        .success(result_str)
+        .to_string())
+}
+
+/// Like `check_commitment`, for `program_hex` applied to the hex CBOR
+/// `args_hex` in order.
+#[wasm_bindgen]
+pub fn check_commitment_with_args(
+    public_values_hex: &str,
+    program_hex: &str,
+    args_hex: Vec<String>,
+    expected_result: &str,
+) -> Result<bool, JsValue> {
+    let public_values = hex::decode(public_values_hex.trim())
+        .map_err(|e| JsValue::from_str(&format!("Hex decode error: {}", e)))?;
+    let program_bytes = hex::decode(program_hex.trim())
+        .map_err(|e| JsValue::from_str(&format!("Hex decode error: {}", e)))?;
+    let args = decode_args(&args_hex)?;
+
+    Ok(guest_protocol::ProgramHasher::with_program(&program_bytes)
+        .with_args(&args)
+        .success_commitment(expected_result)
+        == *public_values)
+}
+
+fn decode_args(args_hex: &[String]) -> Result<Vec<Vec<u8>>, JsValue> {
+    args_hex
+        .iter()
+        .map(|arg| {
+            hex::decode(arg.trim())
+                .map_err(|e| JsValue::from_str(&format!("Argument hex decode error: {}", e)))
+        })
+        .collect()
+}
+
+/// Decode a canonically encoded statement, e.g. `statement.hex` of a proof
+/// bundle, and return it as text.
+#[wasm_bindgen]
+pub fn decode_statement(statement_hex: &str) -> Result<String, JsValue> {
+    let bytes = hex::decode(statement_hex.trim())
+        .map_err(|e| JsValue::from_str(&format!("Hex decode error: {}", e)))?;
+    guest_protocol::Statement::decode(&bytes)
+        .map(|statement| statement.to_string())
+        .ok_or_else(|| JsValue::from_str("Not a canonically encoded statement"))
+}
+
+/// Whether `public_values_hex`, e.g. from `extract_public_values` of the
+/// verifier module, is the commitment to `program_hex` evaluating to
+/// `expected_result`.
+///
+/// Only compares hashes, so it answers instantly; the claim is proven once
+/// the STARK proof itself verifies.
+#[wasm_bindgen]
+pub fn check_commitment(
+    public_values_hex: &str,
+    program_hex: &str,
+    expected_result: &str,
+) -> Result<bool, JsValue> {
+    let public_values = hex::decode(public_values_hex.trim())
+        .map_err(|e| JsValue::from_str(&format!("Hex decode error: {}", e)))?;
+    let program_bytes = hex::decode(program_hex.trim())
+        .map_err(|e| JsValue::from_str(&format!("Hex decode error: {}", e)))?;
+
+    Ok(guest_protocol::success_commitment(&program_bytes, expected_result) == *public_values)
+}
+
+/// Guest input evaluating `program_hex`, committing to failures too if
+/// `prove_failures` is set.
+///
+/// Returns the versioned `GuestInput` encoding: the exact bytes a host writes
+/// to the guest, so integrators never lay them out by hand.
+#[wasm_bindgen]
+pub fn guest_input_program(program_hex: &str, prove_failures: bool) -> Result<Vec<u8>, JsValue> {
+    let program_bytes = hex::decode(program_hex.trim())
+        .map_err(|e| JsValue::from_str(&format!("Hex decode error: {}", e)))?;
+
+    Ok(guest_protocol::GuestInput::program(&program_bytes, prove_failures).encode())
+}
+
+/// Guest input evaluating `program_hex` applied to the hex CBOR `PlutusData`
+/// `args_hex` in order, e.g. datum, redeemer and script context.
+#[wasm_bindgen]
+pub fn guest_input_program_with_args(
+    program_hex: &str,
+    args_hex: Vec<String>,
+    prove_failures: bool,
+) -> Result<Vec<u8>, JsValue> {
+    let program_bytes = hex::decode(program_hex.trim())
+        .map_err(|e| JsValue::from_str(&format!("Hex decode error: {}", e)))?;
+    let args = decode_args(&args_hex)?;
+
+    let input =
+        guest_protocol::GuestInput::program_with_args(&program_bytes, &args, prove_failures);
+    Ok(input.encode())
+}
+
+/// Guest input chaining the epoch `commitments_hex` to the previous epoch
+/// commitment `previous_hex` (all zeroes for the first epoch).
+#[wasm_bindgen]
+pub fn guest_input_aggregation(
+    previous_hex: &str,
+    commitments_hex: Vec<String>,
+) -> Result<Vec<u8>, JsValue> {
+    let commitment = |value: &str| -> Result<[u8; 32], JsValue> {
+        hex::decode(value.trim())
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| JsValue::from_str(&format!("Not a 32-byte hex commitment: {}", value)))
+    };
+    let previous = commitment(previous_hex)?;
+    let commitments = commitments_hex
+        .iter()
+        .map(|value| commitment(value))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(guest_protocol::GuestInput::aggregation(&previous, &commitments).encode())
+}
 
-    Ok(hex::encode(hash))
+/// Guest input revealing chunk `index` of the guest's build info.
+#[wasm_bindgen]
+pub fn guest_input_build_info(index: u32) -> Vec<u8> {
+    guest_protocol::GuestInput::build_info(index).encode()
 }
 
 /// Convert a hex-encoded flat UPLC program to human-readable form.
@@ -60,12 +293,212 @@ pub fn compute_commitment(program_hex: &str, result_str: &str) -> Result<String,
 pub fn hex_to_uplc(program_hex: &str) -> Result<String, JsValue> {
     let program_bytes = hex::decode(program_hex.trim())
         .map_err(|e| JsValue::from_str(&format!("Hex decode error: {}", e)))?;
+    DecodeLimits::DEFAULT
+        .check_flat(&program_bytes)
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
 
     let arena = Arena::new();
 
-    let program: &uplc_turbo::program::Program<DeBruijn> =
-        flat::decode(&arena, &program_bytes)
-            .map_err(|e| JsValue::from_str(&format!("Program decode error: {:?}", e)))?;
+    let program: &uplc_turbo::program::Program<DeBruijn> = flat::decode(&arena, &program_bytes)
+        .map_err(|e| JsValue::from_str(&format!("Program decode error: {:?}", e)))?;
 
     Ok(format!("{:#?}", program))
 }
+
+/// Whether two hex-encoded programs are the same modulo binder names and
+/// encoding. Either may be raw flat or wrapped in CBOR bytestrings, as in
+/// on-chain scripts and blueprints.
+#[wasm_bindgen]
+pub fn programs_equal(hex_a: &str, hex_b: &str) -> Result<bool, JsValue> {
+    uplc::equivalence::programs_equal(hex_a, hex_b).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Outcome of one evaluator in `evaluate_uplc_compare`.
+struct EvaluatorRun {
+    /// Rendered result constant, or the failure kind and message.
+    result: Result<String, (ErrorKind, String)>,
+    /// Consumed (cpu, mem), when the program decoded.
+    budget: Option<(i64, i64)>,
+}
+
+impl EvaluatorRun {
+    fn decode_error(message: String) -> Self {
+        Self {
+            result: Err((ErrorKind::Decode, message)),
+            budget: None,
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let (result, error_kind, error) = match &self.result {
+            Ok(result) => (Some(result.as_str()), None, None),
+            Err((kind, message)) => (None, Some(kind.label()), Some(message.as_str())),
+        };
+        serde_json::json!({
+            "result": result,
+            "error_kind": error_kind,
+            "error": error,
+            "budget": self.budget.map(|(cpu, mem)| serde_json::json!({ "cpu": cpu, "mem": mem })),
+        })
+    }
+}
+
+fn run_turbo(program_bytes: &[u8]) -> EvaluatorRun {
+    let arena = Arena::new();
+    let decoded: Result<&uplc_turbo::program::Program<DeBruijn>, _> =
+        flat::decode(&arena, program_bytes);
+    let program = match decoded {
+        Ok(program) => program,
+        Err(e) => return EvaluatorRun::decode_error(format!("Program decode error: {:?}", e)),
+    };
+
+    let eval_result = program.eval(&arena);
+    let budget = &eval_result.info.consumed_budget;
+    let budget = Some((budget.cpu, budget.mem));
+    let result = match eval_result.term {
+        Ok(uplc_turbo::term::Term::Constant(c)) => Ok(format!("{:?}", c)),
+        Ok(other) => Err((
+            ErrorKind::NonConstantResult,
+            format!("Evaluation result is not a constant: {:?}", other),
+        )),
+        Err(e @ uplc_turbo::machine::MachineError::OutOfExError(_)) => {
+            Err((ErrorKind::BudgetExceeded, format!("{:?}", e)))
+        }
+        Err(e) => Err((ErrorKind::Evaluation, format!("Evaluation error: {:?}", e))),
+    };
+    EvaluatorRun { result, budget }
+}
+
+fn run_aiken(program_bytes: &[u8]) -> EvaluatorRun {
+    use uplc_aiken::ast::{DeBruijn, Program, Term};
+    use uplc_aiken::machine::{cost_model::ExBudget, Error as MachineError};
+
+    let program = match Program::<DeBruijn>::from_flat(program_bytes) {
+        Ok(program) => program,
+        Err(e) => return EvaluatorRun::decode_error(format!("Program decode error: {:?}", e)),
+    };
+
+    let eval_result = program.eval(ExBudget::default());
+    let cost = eval_result.cost();
+    let result = match eval_result.result() {
+        Ok(Term::Constant(c)) => Ok(c.to_pretty()),
+        Ok(other) => Err((
+            ErrorKind::NonConstantResult,
+            format!("Evaluation result is not a constant: {}", other.to_pretty()),
+        )),
+        Err(e @ MachineError::OutOfExError(_)) => {
+            Err((ErrorKind::BudgetExceeded, format!("{:?}", e)))
+        }
+        Err(e) => Err((ErrorKind::Evaluation, format!("Evaluation error: {:?}", e))),
+    };
+    EvaluatorRun {
+        result,
+        budget: Some((cost.cpu, cost.mem)),
+    }
+}
+
+/// Evaluate a program with both uplc-turbo and uplc-aiken and return a
+/// `UplcCompareReport` of their results and budgets. Results are rendered the
+/// way each backend commits to them (turbo's debug form, aiken's pretty
+/// form), so they are shown side by side rather than compared;
+/// `outcomes_match` compares success and failure kinds.
+#[wasm_bindgen]
+pub fn evaluate_uplc_compare(program_hex: &str) -> Result<UplcCompareReport, JsValue> {
+    let program_bytes = hex::decode(program_hex.trim())
+        .map_err(|e| JsValue::from_str(&format!("Hex decode error: {}", e)))?;
+    DecodeLimits::DEFAULT
+        .check_flat(&program_bytes)
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+    let turbo = run_turbo(&program_bytes);
+    let aiken = run_aiken(&program_bytes);
+
+    let outcome = |run: &EvaluatorRun| run.result.as_ref().err().map(|(kind, _)| *kind);
+    let report = serde_json::json!({
+        "turbo": turbo.to_json(),
+        "aiken": aiken.to_json(),
+        "outcomes_match": outcome(&turbo) == outcome(&aiken),
+        "budgets_match": turbo.budget == aiken.budget,
+    });
+    Ok(js_sys::JSON::parse(&report.to_string())?.unchecked_into())
+}
+
+/// List every constant embedded in a hex-encoded flat UPLC program, in
+/// pre-order, as `UplcConstant`s, so the exact parameters a compiled
+/// validator carries can be audited before proving or deploying it.
+#[wasm_bindgen]
+pub fn extract_constants(program_hex: &str) -> Result<UplcConstants, JsValue> {
+    use uplc_turbo::{constant::Constant, term::Term};
+
+    let program_bytes = hex::decode(program_hex.trim())
+        .map_err(|e| JsValue::from_str(&format!("Hex decode error: {}", e)))?;
+    DecodeLimits::DEFAULT
+        .check_flat(&program_bytes)
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+    let arena = Arena::new();
+
+    let program: &uplc_turbo::program::Program<DeBruijn> = flat::decode(&arena, &program_bytes)
+        .map_err(|e| JsValue::from_str(&format!("Program decode error: {:?}", e)))?;
+
+    // Each visited term records its parent and the step leading to it, so
+    // paths are only built for constants. Children are pushed in reverse so
+    // they pop in order.
+    let mut steps: Vec<Option<(usize, String)>> = Vec::new();
+    let mut pending = vec![(program.term, None)];
+    let mut constants = Vec::new();
+    while let Some((term, step)) = pending.pop() {
+        let index = steps.len();
+        steps.push(step);
+        let child = |step: String| Some((index, step));
+
+        match term {
+            Term::Lambda { body, .. } => pending.push((*body, child("lambda.body".into()))),
+            Term::Apply { function, argument } => {
+                pending.push((*argument, child("apply.argument".into())));
+                pending.push((*function, child("apply.function".into())));
+            }
+            Term::Delay(inner) => pending.push((*inner, child("delay".into()))),
+            Term::Force(inner) => pending.push((*inner, child("force".into()))),
+            Term::Case { constr, branches } => {
+                for (i, branch) in branches.iter().enumerate().rev() {
+                    pending.push((*branch, child(format!("case.branch[{}]", i))));
+                }
+                pending.push((*constr, child("case.constr".into())));
+            }
+            Term::Constr { fields, .. } => {
+                for (i, field) in fields.iter().enumerate().rev() {
+                    pending.push((*field, child(format!("constr.field[{}]", i))));
+                }
+            }
+            Term::Constant(constant) => {
+                let (kind, value) = match constant {
+                    Constant::Integer(_) => ("integer", format!("{:?}", constant)),
+                    Constant::ByteString(bytes) => ("bytestring", hex::encode(bytes)),
+                    Constant::String(_) => ("string", format!("{:?}", constant)),
+                    Constant::Boolean(_) => ("bool", format!("{:?}", constant)),
+                    Constant::Unit => ("unit", format!("{:?}", constant)),
+                    Constant::Data(_) => ("data", format!("{:?}", constant)),
+                    _ => ("other", format!("{:?}", constant)),
+                };
+                let mut path = Vec::new();
+                let mut node = index;
+                while let Some((parent, step)) = &steps[node] {
+                    path.push(step.clone());
+                    node = *parent;
+                }
+                path.reverse();
+                constants.push(serde_json::json!({
+                    "index": index,
+                    "path": path,
+                    "type": kind,
+                    "value": value,
+                }));
+            }
+            Term::Var(_) | Term::Builtin(_) | Term::Error => {}
+        }
+    }
+
+    let constants = serde_json::Value::Array(constants).to_string();
+    Ok(js_sys::JSON::parse(&constants)?.unchecked_into())
+}
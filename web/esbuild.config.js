@@ -61,7 +61,8 @@ const copyHtmlPlugin = {
 };
 
 esbuild.build({
-  entryPoints: ['assets/index.js'],
+  // The verifier worker is loaded by URL from verify-client.js
+  entryPoints: ['assets/index.js', 'assets/verify-worker.js'],
   bundle: true,
   outdir: 'dist',
   outbase: '.',
@@ -8,10 +8,14 @@
  *
  * Usage:
  *   cd web && node test-verify.mjs
+ *
+ * Set EXAMPLES=1 to also prove every UPLC program of /api/examples and check
+ * its outcome and commitment against the corpus.
  */
 
 const BACKEND_URL = (process.env.BACKEND_URL || "http://127.0.0.1:8080").replace(/\/$/, "");
 const PROGRAM_HEX = process.env.PROGRAM_HEX || "010000481501";
+const EXAMPLES = process.env.EXAMPLES === "1";
 
 function backendFetchHint(error) {
   const code = error?.cause?.code;
@@ -122,10 +126,45 @@ async function main() {
     process.exit(1);
   }
 
-  // 5. Summary
+  // 5. Optionally check the example corpus
+  if (EXAMPLES && !(await checkExamples())) {
+    process.exit(1);
+  }
+
+  // 6. Summary
   console.log("\n" + "=".repeat(60));
   console.log("  DONE");
   console.log("=".repeat(60) + "\n");
 }
 
+async function checkExamples() {
+  console.log("\n" + "=".repeat(60));
+  console.log("  Examples: /api/examples");
+  console.log("=".repeat(60));
+
+  const { json } = await fetchJson(`${BACKEND_URL}/api/examples`, undefined, "Listing examples");
+  let passed = true;
+  for (const example of json.examples.filter(example => example.program_hex)) {
+    const { json: prove } = await fetchJson(
+      `${BACKEND_URL}/api/prove`,
+      {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({
+          program_hex: example.program_hex,
+          prove_failures: example.prove_failures,
+        }),
+      },
+      `Proving example ${example.id}`,
+    );
+    const ok =
+      prove.success &&
+      prove.outcome === example.expected.outcome &&
+      prove.commitment === example.expected.commitment;
+    console.log(`  ${example.id.padEnd(16)}${ok ? "PASS" : `FAIL (${prove.error || prove.outcome})`}`);
+    passed &&= ok;
+  }
+  return passed;
+}
+
 main().catch(e => { console.error("Fatal:", e); process.exit(1); });